@@ -1,17 +1,69 @@
 // App state for the Gaucho Tracker
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use audio::AudioConnector;
-use project::model::Track;
-use sequencer::{Sequencer, TriggerEvent};
+use audio::{AudioConfig, AudioConnector, HeadlessAudioConnector, SampleEffect};
+use audio::render::STEPS_PER_BAR;
+use project::model::{Pattern, Project, Track};
+use sequencer::{Sequencer, StepGates, Subdivision, TriggerEvent};
 use core::{EventBus, TrackerEvent, SharedEventBus};
 use log::{debug, info, error};
 
+pub mod osc;
+mod script;
+use osc::{OscCommand, OscServer};
+
+/// How the app produces sound
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AudioMode {
+    /// Play through an output device
+    #[default]
+    Hardware,
+    /// Record triggers in memory without opening a device
+    Headless,
+    /// No audio at all
+    Disabled,
+}
+
+/// Everything about one track, gathered in a single lookup
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackInfo {
+    pub name: String,
+    pub sample: String,
+    pub volume: f32,
+    pub muted: bool,
+    pub soloed: bool,
+    pub tuning_semitones: f32,
+    pub pan: f32,
+    pub effect_count: usize,
+    pub color: Option<String>,
+}
+
+/// Rectangle of the pattern, bounds inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternRegion {
+    pub start_track: usize,
+    pub end_track: usize,
+    pub start_step: usize,
+    pub end_step: usize,
+}
+
 /// Represents the state of the application
 pub struct AppState {
     /// Step pattern data for all tracks - [track][step]
     pub steps: Vec<Vec<bool>>,
+    /// Velocity of each step (0.0 to 1.0) - [track][step]
+    pub velocities: Vec<Vec<f32>>,
+    /// Chance of each step playing (0.0 to 1.0) - [track][step]
+    pub probabilities: Vec<Vec<f32>>,
+    /// Length multiplier of each step (0.5 = half time, 2.0 = double) - [track][step]
+    pub step_lengths: Vec<Vec<f32>>,
+    /// How many times each step fires (1 = once) - [track][step]
+    pub step_retrigger_count: Vec<Vec<u32>>,
+    /// Time between a step's repeats in ms (0 = spread over the step) - [track][step]
+    pub step_retrigger_interval_ms: Vec<Vec<f32>>,
     
     /// Currently selected track (for UI)
     pub selected_track: usize,
@@ -29,24 +81,127 @@ pub struct AppState {
     pub trigger_events: Vec<TriggerEvent>,
     /// Current BPM
     pub bpm: u32,
+    /// Swing amount applied to odd steps (0.0 to 1.0)
+    pub swing: f32,
+    /// Note value of one step
+    pub subdivision: Subdivision,
+    /// Fixed per-step timing offsets played instead of swing
+    groove_template: Option<Vec<f32>>,
+    /// Grooves shipped with the project, by name
+    available_grooves: HashMap<String, Vec<f32>>,
+    /// Name of the groove being played, if it came from `available_grooves`
+    active_groove: Option<String>,
+    /// Gain applied on top of every track's volume
+    master_volume: f32,
     /// Audio connector for sample playback
     pub audio: Option<AudioConnector>,
+    /// Trigger log used instead of `audio` in headless mode
+    pub headless_audio: Option<HeadlessAudioConnector>,
+    /// Audio mode used when the sequencer is initialized with audio
+    audio_mode: AudioMode,
+    /// Start playback once the sequencer is first initialized
+    trigger_on_load: bool,
     /// Path to the sample directory
     sample_dir: PathBuf,
+    /// Output device name, or None for the system default
+    audio_device: Option<String>,
     /// Track configurations
     tracks: Vec<Track>,
+    /// Semitones each track is tuned by, on top of its pitch
+    track_tuning: Vec<f32>,
+    /// Effect chain of each track, saved to `patterns/effects.json`
+    pub effects: HashMap<usize, Vec<SampleEffect>>,
+    /// Tracks whose steps are skipped during playback
+    muted_tracks: HashSet<usize>,
+    /// Tracks being soloed; while any are, the audio thread silences the rest
+    soloed_tracks: HashSet<usize>,
+    /// Steps that only fire when another track is active: (track, step) -> (required track, step offset)
+    step_gates: StepGates,
+    /// Triggers delivered by the sequencer listener, waiting for the next event pass
+    pending_triggers: Arc<Mutex<Vec<TriggerEvent>>>,
+    /// How often each step has fired - [track][step], grown as steps fire
+    step_hit_counts: Arc<Mutex<Vec<Vec<u32>>>>,
     /// Shared event bus for component communication
     event_bus: SharedEventBus,
+    /// Whether track hits are written into the pattern as they are played
+    pub record_mode: bool,
+    /// Whether visual selection mode is active
+    pub selection_mode: bool,
+    /// Anchor of the selection rectangle (track, step)
+    pub selection_start: (usize, usize),
+    /// Moving end of the selection rectangle (track, step)
+    pub selection_end: (usize, usize),
+    /// Region copied from the pattern - [track][step]
+    pub clipboard: Option<Vec<Vec<bool>>>,
+    /// Region copied with `copy_steps_to_clipboard` and its steps - [track][step]
+    pub steps_clipboard: Option<(PatternRegion, Vec<Vec<bool>>)>,
+    /// Edits that can be undone, most recent last
+    pub undo_stack: Vec<UndoEntry>,
+    /// Steps cut off by shrinking the pattern, restored if it grows again - [track][step]
+    truncated_steps: Vec<Vec<bool>>,
+    /// Listener for remote control over OSC, if enabled
+    osc_server: Option<OscServer>,
+    /// Every pattern of the project, indexed by id. The current one is edited through
+    /// `steps`, so its entry here is only refreshed when another pattern is selected.
+    patterns: Vec<Pattern>,
+    /// Index of the pattern held in `steps`
+    current_pattern: u32,
+}
+
+/// An edit that `undo` can revert
+#[derive(Debug, Clone, PartialEq)]
+pub enum UndoEntry {
+    /// The pattern before a step edit - [track][step]
+    Steps(Vec<Vec<bool>>),
+    /// A track reorder; the track that was at index `i` moved to `order[i]`
+    Reorder(Vec<usize>),
 }
 
+/// Maximum number of edits kept for undo
+const MAX_UNDO_STATES: usize = 64;
+
+/// Largest pattern length accepted by `set_step_count`
+pub const MAX_STEP_COUNT: usize = 128;
+
+/// Most patterns a project can hold
+pub const MAX_PATTERNS: usize = 256;
+
+/// Loudest master volume accepted by `set_master_volume`, about +6dB
+pub const MAX_MASTER_VOLUME: f32 = 2.0;
+
+/// Largest pitch shift accepted by `set_track_pitch`, in either direction
+pub const MAX_PITCH_SEMITONES: f32 = 24.0;
+
 impl AppState {
     /// Creates a new AppState with empty pattern data
     pub fn new(num_tracks: usize, num_steps: usize) -> Self {
         // Create event bus for component communication
         let event_bus = Arc::new(EventBus::new());
         
+        // Count every step that fires, for the heat map
+        let step_hit_counts: Arc<Mutex<Vec<Vec<u32>>>> = Arc::new(Mutex::new(Vec::new()));
+        let hit_counts_listener = Arc::clone(&step_hit_counts);
+        event_bus.subscribe(move |event| {
+            if let TrackerEvent::StepTriggered(track_idx, step_idx) = *event {
+                let mut counts = hit_counts_listener.lock().unwrap();
+                if counts.len() <= track_idx {
+                    counts.resize(track_idx + 1, Vec::new());
+                }
+                let row = &mut counts[track_idx];
+                if row.len() <= step_idx {
+                    row.resize(step_idx + 1, 0);
+                }
+                row[step_idx] += 1;
+            }
+        });
+        
         Self {
             steps: vec![vec![false; num_steps]; num_tracks],
+            velocities: vec![vec![1.0; num_steps]; num_tracks],
+            probabilities: vec![vec![1.0; num_steps]; num_tracks],
+            step_lengths: vec![vec![1.0; num_steps]; num_tracks],
+            step_retrigger_count: vec![vec![1; num_steps]; num_tracks],
+            step_retrigger_interval_ms: vec![vec![0.0; num_steps]; num_tracks],
             selected_track: 0,
             selected_step: 0,
             track_names: vec![],
@@ -55,24 +210,111 @@ impl AppState {
             sequencer: None, // Will be initialized after pattern data is loaded
             trigger_events: Vec::new(),
             bpm: 120, // Default BPM
+            swing: 0.0,
+            subdivision: Subdivision::default(),
+            groove_template: None,
+            available_grooves: HashMap::new(),
+            active_groove: None,
+            master_volume: 1.0,
             audio: None, // Will be initialized later
+            headless_audio: None,
+            audio_mode: AudioMode::Hardware,
+            trigger_on_load: false,
             sample_dir: PathBuf::from("samples"), // Default sample directory
+            audio_device: None,
             tracks: Vec::new(),
+            track_tuning: Vec::new(),
+            effects: HashMap::new(),
+            muted_tracks: HashSet::new(),
+            soloed_tracks: HashSet::new(),
+            step_gates: StepGates::new(),
+            pending_triggers: Arc::new(Mutex::new(Vec::new())),
+            step_hit_counts,
             event_bus,
+            record_mode: false,
+            selection_mode: false,
+            selection_start: (0, 0),
+            selection_end: (0, 0),
+            clipboard: None,
+            steps_clipboard: None,
+            undo_stack: Vec::new(),
+            truncated_steps: vec![Vec::new(); num_tracks],
+            osc_server: None,
+            patterns: vec![Pattern::new(0, vec![vec![false; num_steps]; num_tracks])],
+            current_pattern: 0,
         }
     }
     
+    /// Use the patterns of a loaded project, editing the first one.
+    /// Patterns are renumbered by position so ids match their index.
+    pub fn with_patterns(mut self, patterns: Vec<Pattern>) -> Self {
+        if patterns.is_empty() {
+            return self;
+        }
+        self.patterns = patterns.into_iter()
+            .enumerate()
+            .map(|(idx, pattern)| Pattern::new(idx as u32, pattern.steps))
+            .collect();
+        self.current_pattern = 0;
+        self.steps = self.patterns[0].steps.clone();
+        self.truncated_steps = vec![Vec::new(); self.steps.len()];
+        self.sync_step_rows();
+        self
+    }
+    
     /// Initialize the app with project data, including sample dir
     pub fn with_sample_dir(mut self, sample_dir: impl AsRef<Path>) -> Self {
         self.sample_dir = sample_dir.as_ref().to_path_buf();
         self
     }
     
+    /// Start playing as soon as `initialize_sequencer` first succeeds
+    pub fn with_auto_start(mut self, auto_start: bool) -> Self {
+        self.trigger_on_load = auto_start;
+        self
+    }
+    
+    /// Play audio through a specific output device instead of the default
+    pub fn with_audio_device(mut self, device_name: Option<String>) -> Self {
+        self.audio_device = device_name;
+        self
+    }
+    
+    /// Start at a given tempo
+    pub fn with_bpm(mut self, bpm: u32) -> Self {
+        self.bpm = bpm;
+        self
+    }
+    
+    /// Use `steps` as the pattern being edited, one row per track
+    pub fn with_steps(mut self, steps: Vec<Vec<bool>>) -> Self {
+        self.patterns[self.current_pattern as usize].steps = steps.clone();
+        self.truncated_steps = vec![Vec::new(); steps.len()];
+        self.steps = steps;
+        self.sync_step_rows();
+        self
+    }
+    
+    /// Lengthen or shorten every track to `num_steps` steps, new steps off
+    pub fn with_num_steps(mut self, num_steps: usize) -> Self {
+        for row in &mut self.steps {
+            row.resize(num_steps, false);
+        }
+        self.patterns[self.current_pattern as usize].steps = self.steps.clone();
+        self.sync_step_rows();
+        self
+    }
+    
     /// Initialize the app with track data
     pub fn with_tracks(mut self, tracks: Vec<Track>) -> Self {
         // Save the track names before moving the tracks vector
         let track_names: Vec<String> = tracks.iter().map(|t| t.name.clone()).collect();
         
+        self.muted_tracks = tracks.iter().enumerate()
+            .filter(|(_, track)| track.muted)
+            .map(|(idx, _)| idx)
+            .collect();
+        self.track_tuning = vec![0.0; tracks.len()];
         self.tracks = tracks;
         self.track_names = track_names;
         self
@@ -80,6 +322,7 @@ impl AppState {
 
     /// Toggle the currently selected step
     pub fn toggle_step(&mut self) {
+        self.push_undo();
         let val = &mut self.steps[self.selected_track][self.selected_step];
         *val = !*val;
         
@@ -87,7 +330,43 @@ impl AppState {
         self.event_bus.emit(TrackerEvent::PatternChanged);
         
         // After toggling a step, we need to update the sequencer pattern
-        if let Some(_) = &self.sequencer {
+        self.refresh_sequencer();
+    }
+
+    /// Toggle a step anywhere in the pattern
+    pub fn toggle_step_at(&mut self, track_idx: usize, step_idx: usize) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_step(track_idx, step_idx, !self.step_at(track_idx, step_idx))
+    }
+
+    /// Whether a step is active; false outside the pattern
+    pub fn step_at(&self, track_idx: usize, step_idx: usize) -> bool {
+        self.steps.get(track_idx)
+            .and_then(|row| row.get(step_idx))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Set a step anywhere in the pattern without moving the cursor.
+    /// Setting a step to the value it already has is not recorded for undo.
+    pub fn set_step(&mut self, track_idx: usize, step_idx: usize, value: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let current = *self.steps.get(track_idx)
+            .ok_or_else(|| format!("Track index {} out of bounds", track_idx))?
+            .get(step_idx)
+            .ok_or_else(|| format!("Step index {} out of bounds", step_idx))?;
+        if current == value {
+            return Ok(());
+        }
+        self.push_undo();
+        self.steps[track_idx][step_idx] = value;
+
+        self.event_bus.emit(TrackerEvent::PatternChanged);
+        self.refresh_sequencer();
+        Ok(())
+    }
+
+    /// Recreate the sequencer so it plays the current pattern data
+    fn refresh_sequencer(&mut self) {
+        if self.sequencer.is_some() {
             // Recreate sequencer with updated pattern
             let was_playing = self.is_playing;
             let bpm = if let Some(seq) = &self.sequencer {
@@ -104,7 +383,7 @@ impl AppState {
             }
             
             // We need to preserve audio connection when recreating the sequencer
-            let has_audio = self.audio.is_some();
+            let has_audio = self.audio.is_some() || self.headless_audio.is_some();
             
             // Clean up existing audio thread if any
             if has_audio {
@@ -115,8 +394,8 @@ impl AppState {
             if let Err(err) = self.initialize_sequencer(has_audio) {
                 debug!("Error reinitializing sequencer with audio: {}", err);
                 // Fallback to just reinitializing the sequencer without audio
-                let event_bus_clone = Arc::clone(&self.event_bus); 
-                self.sequencer = Some(Sequencer::new_with_event_bus(bpm, self.steps.clone(), event_bus_clone));
+                self.bpm = bpm;
+                let _ = self.initialize_sequencer(false);
             }
             
             // Resume if it was playing
@@ -133,24 +412,666 @@ impl AppState {
         if self.selected_step > 0 {
             self.selected_step -= 1;
         }
+        self.extend_selection();
     }
     
     pub fn move_cursor_right(&mut self) {
         if self.selected_step + 1 < self.steps[0].len() {
             self.selected_step += 1;
         }
+        self.extend_selection();
+    }
+    
+    pub fn move_cursor_up(&mut self) {
+        if self.selected_track > 0 {
+            self.selected_track -= 1;
+        }
+        self.extend_selection();
+    }
+    
+    pub fn move_cursor_down(&mut self) {
+        if self.selected_track + 1 < self.steps.len() {
+            self.selected_track += 1;
+        }
+        self.extend_selection();
+    }
+
+    /// Enter visual selection mode anchored at the cursor
+    pub fn enter_selection_mode(&mut self) {
+        self.selection_mode = true;
+        self.selection_start = (self.selected_track, self.selected_step);
+        self.selection_end = self.selection_start;
+    }
+
+    /// Leave visual selection mode without copying
+    pub fn exit_selection_mode(&mut self) {
+        self.selection_mode = false;
+    }
+
+    /// Move the end of the selection to the cursor while selecting
+    fn extend_selection(&mut self) {
+        if self.selection_mode {
+            self.selection_end = (self.selected_track, self.selected_step);
+        }
+    }
+
+    /// Get the selection rectangle as ((first_track, first_step), (last_track, last_step))
+    pub fn selection_bounds(&self) -> ((usize, usize), (usize, usize)) {
+        let (start_track, start_step) = self.selection_start;
+        let (end_track, end_step) = self.selection_end;
+        (
+            (start_track.min(end_track), start_step.min(end_step)),
+            (start_track.max(end_track), start_step.max(end_step)),
+        )
+    }
+
+    /// Check if a cell is inside the active selection
+    pub fn is_selected(&self, track_idx: usize, step_idx: usize) -> bool {
+        if !self.selection_mode {
+            return false;
+        }
+        let ((first_track, first_step), (last_track, last_step)) = self.selection_bounds();
+        (first_track..=last_track).contains(&track_idx) && (first_step..=last_step).contains(&step_idx)
+    }
+
+    /// Copy the selected region to the clipboard and leave selection mode
+    pub fn copy_selection(&mut self) {
+        if !self.selection_mode {
+            return;
+        }
+        let ((first_track, first_step), (last_track, last_step)) = self.selection_bounds();
+        let region = self.region_steps(PatternRegion {
+            start_track: first_track,
+            end_track: last_track,
+            start_step: first_step,
+            end_step: last_step,
+        });
+        debug!("Copied {}x{} region to clipboard", region.len(), last_step - first_step + 1);
+        self.clipboard = Some(region);
+        self.selection_mode = false;
+    }
+
+    /// Paste the clipboard at the cursor, clipping to the pattern bounds
+    pub fn paste_clipboard(&mut self) {
+        let region = match &self.clipboard {
+            Some(region) => region.clone(),
+            None => return,
+        };
+        self.push_undo();
+        self.write_region(self.selected_track, self.selected_step, &region);
+
+        self.event_bus.emit(TrackerEvent::PatternChanged);
+        self.refresh_sequencer();
+    }
+
+    /// Copy the steps of a region of any tracks to the region clipboard
+    pub fn copy_steps_to_clipboard(&mut self, region: PatternRegion) -> Result<(), Box<dyn std::error::Error>> {
+        if region.start_track > region.end_track || region.start_step > region.end_step {
+            return Err("Region start must not be after its end".into());
+        }
+        if region.end_track >= self.steps.len() {
+            return Err(format!("Track index {} out of bounds", region.end_track).into());
+        }
+        if region.end_step >= self.num_steps() {
+            return Err(format!("Step index {} out of bounds", region.end_step).into());
+        }
+        let steps = self.region_steps(region);
+        debug!("Copied {}x{} region to clipboard", steps.len(), region.end_step - region.start_step + 1);
+        self.steps_clipboard = Some((region, steps));
+        Ok(())
+    }
+
+    /// Write the region clipboard with its top-left corner at (dest_track, dest_step),
+    /// clipping to the pattern bounds. Does nothing if the clipboard is empty.
+    pub fn paste_steps_from_clipboard(&mut self, dest_track: usize, dest_step: usize) {
+        let steps = match &self.steps_clipboard {
+            Some((_, steps)) => steps.clone(),
+            None => return,
+        };
+        self.push_undo();
+        self.write_region(dest_track, dest_step, &steps);
+
+        self.event_bus.emit(TrackerEvent::PatternChanged);
+        self.refresh_sequencer();
+    }
+
+    /// Steps inside a region, which must be within the pattern - [track][step]
+    fn region_steps(&self, region: PatternRegion) -> Vec<Vec<bool>> {
+        self.steps[region.start_track..=region.end_track]
+            .iter()
+            .map(|track| track[region.start_step..=region.end_step].to_vec())
+            .collect()
+    }
+
+    /// Overwrite steps from (dest_track, dest_step) on, dropping what falls outside the pattern
+    fn write_region(&mut self, dest_track: usize, dest_step: usize, region: &[Vec<bool>]) {
+        for (row_offset, row) in region.iter().enumerate() {
+            let track_idx = dest_track + row_offset;
+            if track_idx >= self.steps.len() {
+                break;
+            }
+            for (col_offset, &value) in row.iter().enumerate() {
+                let step_idx = dest_step + col_offset;
+                if step_idx >= self.steps[track_idx].len() {
+                    break;
+                }
+                self.steps[track_idx][step_idx] = value;
+            }
+        }
+    }
+
+    /// Save the current pattern so the next edit can be undone
+    fn push_undo(&mut self) {
+        self.push_undo_entry(UndoEntry::Steps(self.steps.clone()));
+    }
+
+    fn push_undo_entry(&mut self, entry: UndoEntry) {
+        if self.undo_stack.len() == MAX_UNDO_STATES {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(entry);
+    }
+
+    /// Revert the last undoable edit: restore the pattern saved before a step edit,
+    /// or put reordered tracks back. Returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(UndoEntry::Steps(steps)) => {
+                self.steps = steps;
+                self.sync_step_rows();
+                self.event_bus.emit(TrackerEvent::PatternChanged);
+                self.refresh_sequencer();
+            }
+            Some(UndoEntry::Reorder(order)) => {
+                let mut original = vec![0; order.len()];
+                for (idx, &moved_to) in order.iter().enumerate() {
+                    original[moved_to] = idx;
+                }
+                self.apply_track_order(|idx| original[idx]);
+            }
+            None => return false,
+        }
+        true
+    }
+
+    /// Change the pattern length, keeping existing steps.
+    /// Steps cut off by shrinking come back if the pattern grows again.
+    pub fn set_step_count(&mut self, new_count: usize) -> Result<(), Box<dyn std::error::Error>> {
+        if !new_count.is_power_of_two() || new_count > MAX_STEP_COUNT {
+            return Err(format!("Step count must be a power of 2 between 1 and {}, got {}", MAX_STEP_COUNT, new_count).into());
+        }
+        if new_count == self.num_steps() {
+            return Ok(());
+        }
+        
+        info!("Changing step count from {} to {}", self.num_steps(), new_count);
+        self.push_undo();
+        self.truncated_steps.resize(self.steps.len(), Vec::new());
+        for (row, truncated) in self.steps.iter_mut().zip(self.truncated_steps.iter_mut()) {
+            if new_count < row.len() {
+                let mut removed = row.split_off(new_count);
+                removed.append(truncated);
+                *truncated = removed;
+            } else {
+                let restored = (new_count - row.len()).min(truncated.len());
+                row.extend(truncated.drain(..restored));
+                row.resize(new_count, false);
+            }
+        }
+        self.sync_step_rows();
+        self.selected_step = self.selected_step.min(new_count - 1);
+        self.selection_mode = false;
+        
+        self.event_bus.emit(TrackerEvent::PatternChanged);
+        let steps = self.steps.clone();
+        if let Some(sequencer) = &mut self.sequencer {
+            sequencer.set_pattern(steps)?;
+        }
+        Ok(())
+    }
+    
+    /// Change the note value of a step, keeping the pattern's timing: going finer spreads
+    /// steps out with empty ones in between, going coarser keeps only the steps on the new grid
+    pub fn set_subdivision(&mut self, subdivision: Subdivision) -> Result<(), Box<dyn std::error::Error>> {
+        if subdivision == self.subdivision {
+            return Ok(());
+        }
+        let (from, to) = (self.subdivision.factor() as usize, subdivision.factor() as usize);
+        let new_count = self.num_steps() * to / from;
+        if new_count == 0 || new_count > MAX_STEP_COUNT {
+            return Err(format!("Step count must be between 1 and {}, got {}", MAX_STEP_COUNT, new_count).into());
+        }
+        
+        info!("Changing subdivision to 1/{} with {} steps", subdivision.note_value(), new_count);
+        for row in &mut self.steps {
+            *row = resample_steps(row, from, to, false);
+        }
+        for pattern in &mut self.patterns {
+            for row in &mut pattern.steps {
+                *row = resample_steps(row, from, to, false);
+            }
+            pattern.length = pattern.length * to / from;
+        }
+        for rows in [&mut self.velocities, &mut self.probabilities, &mut self.step_lengths] {
+            for row in rows.iter_mut() {
+                *row = resample_steps(row, from, to, 1.0);
+            }
+        }
+        for row in &mut self.step_retrigger_interval_ms {
+            *row = resample_steps(row, from, to, 0.0);
+        }
+        for row in &mut self.step_retrigger_count {
+            *row = resample_steps(row, from, to, 1);
+        }
+        self.step_gates = std::mem::take(&mut self.step_gates)
+            .into_iter()
+            .filter(|&((_, step), _)| (step * to).is_multiple_of(from))
+            .map(|((track, step), (required, offset))| ((track, step * to / from), (required, offset * to as i32 / from as i32)))
+            .collect();
+        self.truncated_steps = vec![Vec::new(); self.steps.len()];
+        // Snapshots hold the old step count
+        self.undo_stack.clear();
+        self.selected_step = (self.selected_step * to / from).min(new_count - 1);
+        self.selection_mode = false;
+        self.subdivision = subdivision;
+        
+        self.event_bus.emit(TrackerEvent::SubdivisionChanged(subdivision.note_value()));
+        self.event_bus.emit(TrackerEvent::PatternChanged);
+        self.refresh_sequencer();
+        Ok(())
+    }
+    
+    /// Resize the per-step velocity and probability rows to match the pattern
+    fn sync_step_rows(&mut self) {
+        for rows in [&mut self.velocities, &mut self.probabilities, &mut self.step_lengths] {
+            rows.resize(self.steps.len(), Vec::new());
+            for (row, steps) in rows.iter_mut().zip(&self.steps) {
+                row.resize(steps.len(), 1.0);
+            }
+        }
+        self.step_retrigger_count.resize(self.steps.len(), Vec::new());
+        self.step_retrigger_interval_ms.resize(self.steps.len(), Vec::new());
+        for (track_idx, steps) in self.steps.iter().enumerate() {
+            self.step_retrigger_count[track_idx].resize(steps.len(), 1);
+            self.step_retrigger_interval_ms[track_idx].resize(steps.len(), 0.0);
+        }
+    }
+
+    /// Replace the pattern with one imported from a MIDI file, using the
+    /// General MIDI drum map. Notes for tracks the project doesn't have are dropped.
+    pub fn import_midi_pattern(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let pattern = project::import_midi_pattern(path, self.bpm, &project::default_drum_map())?;
+        let num_steps = pattern.steps.first().map_or(16, Vec::len).min(MAX_STEP_COUNT);
+
+        self.push_undo();
+        let mut imported = pattern.steps.into_iter();
+        for row in self.steps.iter_mut() {
+            *row = imported.next().unwrap_or_default();
+            row.resize(num_steps, false);
+        }
+        self.truncated_steps = vec![Vec::new(); self.steps.len()];
+        self.sync_step_rows();
+        self.selected_step = self.selected_step.min(num_steps - 1);
+        self.selection_mode = false;
+
+        info!("Imported MIDI pattern with {} steps from {:?}", num_steps, path);
+        self.event_bus.emit(TrackerEvent::PatternChanged);
+        self.refresh_sequencer();
+        Ok(())
+    }
+
+    /// Set how long a step lasts relative to a normal step (0.5 = half, 2.0 = double)
+    pub fn set_step_length(&mut self, track_idx: usize, step_idx: usize, length: f32) -> Result<(), Box<dyn std::error::Error>> {
+        if !length.is_finite() || length <= 0.0 {
+            return Err(format!("Step length must be positive, got {}", length).into());
+        }
+        let row = self.step_lengths.get_mut(track_idx)
+            .ok_or_else(|| format!("Track index {} out of bounds", track_idx))?;
+        let slot = row.get_mut(step_idx)
+            .ok_or_else(|| format!("Step index {} out of bounds", step_idx))?;
+        *slot = length;
+        
+        if let Some(sequencer) = &self.sequencer {
+            sequencer.set_step_lengths(track_idx, row.clone())?;
+        }
+        self.event_bus.emit(TrackerEvent::PatternChanged);
+        Ok(())
+    }
+
+    /// Make a step fire `count` times, `interval_ms` apart, for flams and rolls.
+    /// An interval of 0 spreads the repeats evenly over the step.
+    pub fn set_step_retrigger(&mut self, track_idx: usize, step_idx: usize, count: u32, interval_ms: f32) -> Result<(), Box<dyn std::error::Error>> {
+        if count == 0 {
+            return Err("Retrigger count must be at least 1".into());
+        }
+        if !interval_ms.is_finite() || interval_ms < 0.0 {
+            return Err(format!("Retrigger interval must not be negative, got {}", interval_ms).into());
+        }
+        let (Some(counts), Some(intervals)) = (self.step_retrigger_count.get_mut(track_idx), self.step_retrigger_interval_ms.get_mut(track_idx)) else {
+            return Err(format!("Track index {} out of bounds", track_idx).into());
+        };
+        let (Some(count_slot), Some(interval_slot)) = (counts.get_mut(step_idx), intervals.get_mut(step_idx)) else {
+            return Err(format!("Step index {} out of bounds", step_idx).into());
+        };
+        *count_slot = count;
+        *interval_slot = interval_ms;
+        
+        if let Some(sequencer) = &self.sequencer {
+            sequencer.set_step_retrigger(track_idx, step_idx, count, interval_ms);
+        }
+        self.event_bus.emit(TrackerEvent::PatternChanged);
+        Ok(())
+    }
+
+    /// Whether a track is muted
+    pub fn is_track_muted(&self, track_idx: usize) -> bool {
+        self.muted_tracks.contains(&track_idx)
+    }
+
+    /// Mute or unmute a track without touching its steps
+    pub fn set_track_muted(&mut self, track_idx: usize, muted: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if track_idx >= self.steps.len() {
+            return Err(format!("Track index {} out of bounds", track_idx).into());
+        }
+        if muted {
+            self.muted_tracks.insert(track_idx);
+        } else {
+            self.muted_tracks.remove(&track_idx);
+        }
+        if let Some(track) = self.tracks.get_mut(track_idx) {
+            track.muted = muted;
+        }
+        info!("Track {} {}", track_idx, if muted { "muted" } else { "unmuted" });
+        
+        // The sequencer stops triggering the track; the audio thread also ignores stray triggers
+        if let Some(sequencer) = &self.sequencer {
+            sequencer.set_track_muted(track_idx, muted);
+        }
+        if let Some(audio) = &self.audio {
+            audio.set_track_muted(track_idx, muted)?;
+        }
+        Ok(())
+    }
+
+    /// Cut off whatever a track is playing; other tracks keep sounding
+    pub fn stop_track(&mut self, track_idx: usize) -> Result<(), Box<dyn std::error::Error>> {
+        if track_idx >= self.steps.len() {
+            return Err(format!("Track index {} out of bounds", track_idx).into());
+        }
+        if let Some(audio) = &self.audio {
+            audio.stop_track(track_idx)?;
+        }
+        Ok(())
+    }
+
+    /// Whether a track is soloed
+    pub fn is_track_soloed(&self, track_idx: usize) -> bool {
+        self.soloed_tracks.contains(&track_idx)
+    }
+
+    /// Solo a track. Other tracks keep triggering, but the audio thread
+    /// drops their triggers, so nothing leaks through before the change lands.
+    pub fn solo_track(&mut self, track_idx: usize) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_track_soloed(track_idx, true)
+    }
+
+    /// Stop soloing a track; with no soloed tracks left, every unmuted track plays
+    pub fn unsolo_track(&mut self, track_idx: usize) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_track_soloed(track_idx, false)
+    }
+
+    fn set_track_soloed(&mut self, track_idx: usize, soloed: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if track_idx >= self.steps.len() {
+            return Err(format!("Track index {} out of bounds", track_idx).into());
+        }
+        if soloed {
+            self.soloed_tracks.insert(track_idx);
+        } else {
+            self.soloed_tracks.remove(&track_idx);
+        }
+        info!("Track {} {}", track_idx, if soloed { "soloed" } else { "unsoloed" });
+        
+        self.event_bus.emit(TrackerEvent::TrackSoloChanged(track_idx, soloed));
+        if let Some(audio) = &self.audio {
+            if soloed {
+                audio.solo_track(track_idx)?;
+            } else {
+                audio.unsolo_track(track_idx)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Make a step fire only if `required_track` has an active, unmuted step
+    /// `required_step_offset` steps away (wrapping around the pattern)
+    pub fn set_step_gate(&mut self, track_idx: usize, step_idx: usize, required_track: usize, required_step_offset: i32) -> Result<(), Box<dyn std::error::Error>> {
+        if track_idx >= self.steps.len() || required_track >= self.steps.len() {
+            return Err(format!("Track index {} out of bounds", track_idx.max(required_track)).into());
+        }
+        if step_idx >= self.num_steps() {
+            return Err(format!("Step index {} out of bounds", step_idx).into());
+        }
+        let gate = (required_track, required_step_offset);
+        self.step_gates.insert((track_idx, step_idx), gate);
+        info!("Track {} step {} now follows track {} at offset {}", track_idx, step_idx, required_track, required_step_offset);
+        
+        if let Some(sequencer) = &self.sequencer {
+            sequencer.set_step_gate(track_idx, step_idx, Some(gate));
+        }
+        Ok(())
+    }
+    
+    /// Remove a step's gate so it fires whenever it's active
+    pub fn clear_step_gate(&mut self, track_idx: usize, step_idx: usize) {
+        if self.step_gates.remove(&(track_idx, step_idx)).is_some() {
+            if let Some(sequencer) = &self.sequencer {
+                sequencer.set_step_gate(track_idx, step_idx, None);
+            }
+        }
+    }
+    
+    /// Get the gate of a step as (required track, step offset)
+    pub fn get_step_gate(&self, track_idx: usize, step_idx: usize) -> Option<(usize, i32)> {
+        self.step_gates.get(&(track_idx, step_idx)).copied()
+    }
+    
+    /// Flip a track's mute state
+    pub fn toggle_track_mute(&mut self, track_idx: usize) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_track_muted(track_idx, !self.is_track_muted(track_idx))
+    }
+
+    /// The pattern the sequencer is playing, read from the sequencer itself.
+    /// Mutes are applied separately, so muted tracks keep their steps; None if the sequencer isn't initialized.
+    pub fn get_live_pattern(&self) -> Option<Vec<Vec<bool>>> {
+        self.sequencer.as_ref().map(Sequencer::get_pattern)
+    }
+
+    /// Flip every step of a track
+    pub fn invert_track(&mut self, track_idx: usize) {
+        let Some(row) = self.steps.get(track_idx) else {
+            return;
+        };
+        let inverted = row.iter().map(|&step| !step).collect();
+        let _ = self.bulk_set_pattern(track_idx, inverted);
+    }
+
+    /// Flip every step of every track
+    pub fn invert_pattern(&mut self) {
+        let inverted = Pattern::new(0, self.steps.clone()).invert().steps;
+        let _ = self.bulk_set_all_tracks(inverted);
+    }
+
+    /// Play a track's steps backwards. This reverses the pattern, not the sample audio.
+    pub fn reverse_pattern_time(&mut self, track_idx: usize) {
+        if track_idx >= self.steps.len() {
+            return;
+        }
+        let reversed = Pattern::new(0, self.steps.clone()).reversed_track(track_idx);
+        let _ = self.bulk_set_pattern(track_idx, reversed);
+    }
+
+    /// Play every track's steps backwards
+    pub fn reverse_all_tracks(&mut self) {
+        let pattern = Pattern::new(0, self.steps.clone());
+        let reversed = (0..self.steps.len()).map(|track_idx| pattern.reversed_track(track_idx)).collect();
+        let _ = self.bulk_set_all_tracks(reversed);
+    }
+
+    /// Replace every step of a track at once, as a single undoable edit
+    pub fn bulk_set_pattern(&mut self, track_idx: usize, steps: Vec<bool>) -> Result<(), &'static str> {
+        if track_idx >= self.steps.len() {
+            return Err("Track index out of bounds");
+        }
+        if steps.len() != self.num_steps() {
+            return Err("Step count does not match the pattern length");
+        }
+        self.push_undo();
+        self.steps[track_idx] = steps;
+
+        self.event_bus.emit(TrackerEvent::PatternChanged);
+        self.refresh_sequencer();
+        Ok(())
+    }
+
+    /// Replace the whole pattern at once, as a single undoable edit.
+    /// The track count and pattern length must stay the same.
+    pub fn bulk_set_all_tracks(&mut self, steps: Vec<Vec<bool>>) -> Result<(), &'static str> {
+        if steps.len() != self.steps.len() {
+            return Err("Track count does not match the pattern");
+        }
+        let num_steps = self.num_steps();
+        if steps.iter().any(|row| row.len() != num_steps) {
+            return Err("Step count does not match the pattern length");
+        }
+        self.push_undo();
+        self.steps = steps;
+
+        self.event_bus.emit(TrackerEvent::PatternChanged);
+        self.refresh_sequencer();
+        Ok(())
+    }
+
+    /// Number of patterns in the project
+    pub fn pattern_count(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Index of the pattern being edited
+    pub fn current_pattern(&self) -> u32 {
+        self.current_pattern
+    }
+
+    /// A copy of a pattern; for the current pattern this includes unsaved edits
+    pub fn pattern(&self, idx: u32) -> Option<Pattern> {
+        if idx == self.current_pattern {
+            return Some(Pattern::new(idx, self.steps.clone()));
+        }
+        self.patterns.get(idx as usize).cloned()
+    }
+
+    /// Append an all-off pattern with `steps` steps per track and return its id
+    pub fn new_empty_pattern(&mut self, steps: usize) -> u32 {
+        let id = self.patterns.len() as u32;
+        let steps = steps.clamp(1, MAX_STEP_COUNT);
+        self.patterns.push(Pattern::new(id, vec![vec![false; steps]; self.num_tracks()]));
+        id
+    }
+
+    /// Copy pattern `src_idx` into slot `dst_idx`, adding empty patterns up to it if needed.
+    /// If `dst_idx` is the current pattern, the copy replaces what is being edited.
+    pub fn clone_pattern(&mut self, src_idx: u32, dst_idx: u32) -> Result<(), &'static str> {
+        let source = self.pattern(src_idx).ok_or("Source pattern does not exist")?;
+        if dst_idx as usize >= MAX_PATTERNS {
+            return Err("Destination pattern index out of range");
+        }
+        while self.patterns.len() <= dst_idx as usize {
+            self.new_empty_pattern(source.length);
+        }
+
+        if dst_idx == self.current_pattern {
+            self.push_undo();
+            self.steps = source.steps.clone();
+            self.truncated_steps = vec![Vec::new(); self.steps.len()];
+            self.sync_step_rows();
+            self.selected_step = self.selected_step.min(self.num_steps().saturating_sub(1));
+            self.refresh_sequencer();
+        }
+        self.patterns[dst_idx as usize] = Pattern::new(dst_idx, source.steps);
+
+        info!("Cloned pattern {} into {}", src_idx, dst_idx);
+        self.event_bus.emit(TrackerEvent::PatternLoaded(dst_idx));
+        Ok(())
+    }
+
+    /// Switch editing and playback to another pattern, keeping the current one's edits
+    pub fn select_pattern(&mut self, idx: u32) -> Result<(), &'static str> {
+        if idx == self.current_pattern {
+            return Ok(());
+        }
+        let steps = self.patterns.get(idx as usize).ok_or("Pattern does not exist")?.steps.clone();
+        self.patterns[self.current_pattern as usize] = Pattern::new(self.current_pattern, std::mem::replace(&mut self.steps, steps));
+        self.current_pattern = idx;
+
+        // Undo history and cut-off steps belong to the pattern that was being edited
+        self.undo_stack.clear();
+        self.truncated_steps = vec![Vec::new(); self.steps.len()];
+        self.sync_step_rows();
+        self.selected_step = self.selected_step.min(self.num_steps().saturating_sub(1));
+        self.selection_mode = false;
+
+        info!("Editing pattern {}", idx);
+        self.event_bus.emit(TrackerEvent::PatternLoaded(idx));
+        self.refresh_sequencer();
+        Ok(())
+    }
+
+    /// The current pattern drawn as text, one row per track, e.g. `Kick       |X...|X...|X...|X...|`.
+    /// The selected step is shown in lowercase.
+    pub fn export_pattern_as_ascii_art(&self) -> String {
+        let mut art = String::new();
+        for (track_idx, row) in self.steps.iter().enumerate() {
+            let name = self.track_names.get(track_idx).map(String::as_str).unwrap_or("");
+            art.push_str(&format!("{:<10.10} |", name));
+            for (step_idx, &active) in row.iter().enumerate() {
+                let selected = (track_idx, step_idx) == (self.selected_track, self.selected_step);
+                art.push(match (active, selected) {
+                    (true, true) => 'x',
+                    (true, false) => 'X',
+                    (false, _) => '.',
+                });
+                if step_idx % 4 == 3 || step_idx + 1 == row.len() {
+                    art.push('|');
+                }
+            }
+            art.push('\n');
+        }
+        art
     }
-    
-    pub fn move_cursor_up(&mut self) {
-        if self.selected_track > 0 {
-            self.selected_track -= 1;
-        }
+
+    /// Write the current pattern to the log
+    pub fn log_pattern(&self) {
+        info!("Pattern {}:\n{}", self.current_pattern, self.export_pattern_as_ascii_art());
     }
-    
-    pub fn move_cursor_down(&mut self) {
-        if self.selected_track + 1 < self.steps.len() {
-            self.selected_track += 1;
+
+    /// The current pattern as compact JSON, in the format of `patterns/NNN.json`
+    pub fn get_pattern_as_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&Pattern::new(0, self.steps.clone()))
+    }
+
+    /// Replace the current pattern with one given as JSON.
+    /// The pattern must have the same number of tracks and steps as the current one.
+    pub fn set_pattern_from_json(&mut self, json: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let pattern = Pattern::from_json(json)?;
+        pattern.validate_length()?;
+        if pattern.steps.len() != self.num_tracks() {
+            return Err(format!("Pattern has {} tracks, expected {}", pattern.steps.len(), self.num_tracks()).into());
         }
+        if pattern.length != self.num_steps() {
+            return Err(format!("Pattern has {} steps, expected {}", pattern.length, self.num_steps()).into());
+        }
+
+        self.bulk_set_all_tracks(pattern.steps)?;
+        Ok(())
     }
 
     /// Start or stop the sequencer
@@ -179,11 +1100,11 @@ impl AppState {
             // Get the current step from the sequencer
             self.current_step = sequencer.current_step();
             
-            // Process legacy trigger events for backward compatibility
-            let events = sequencer.tick();
+            // Collect triggers pushed by the sequencer listener since the last pass
+            let events: Vec<TriggerEvent> = self.pending_triggers.lock().unwrap().drain(..).collect();
             if !events.is_empty() {
-                debug!("Got {} legacy trigger events", events.len());
-                self.trigger_events = events.clone();
+                debug!("Got {} trigger events", events.len());
+                self.trigger_events = events;
                 
                 // Store trigger events for UI or other purposes,
                 // but don't directly trigger audio - that now happens via the event bus
@@ -194,6 +1115,78 @@ impl AppState {
         }
     }
 
+    /// Start listening for OSC messages on a UDP port; port 0 picks a free port.
+    /// Received messages are applied by `process_osc_messages`.
+    pub fn enable_osc(&mut self, port: u16) -> Result<(), Box<dyn std::error::Error>> {
+        // Drop any previous listener first so its port is free again
+        self.osc_server = None;
+        self.osc_server = Some(OscServer::start(port)?);
+        Ok(())
+    }
+
+    /// Port the OSC listener is bound to, if OSC is enabled
+    pub fn osc_port(&self) -> Option<u16> {
+        self.osc_server.as_ref().map(|server| server.local_addr().port())
+    }
+
+    /// Apply the OSC commands received since the last call; returns how many were applied
+    pub fn process_osc_messages(&mut self) -> usize {
+        let Some(server) = &self.osc_server else {
+            return 0;
+        };
+        let commands = server.drain();
+        let count = commands.len();
+        for command in commands {
+            debug!("Applying OSC command {:?}", command);
+            let result = match command {
+                OscCommand::ToggleStep(track_idx, step_idx) => self.toggle_step_at(track_idx, step_idx),
+                OscCommand::SetBpm(bpm) => {
+                    self.set_bpm(bpm);
+                    Ok(())
+                },
+                OscCommand::TogglePlayback => {
+                    self.toggle_playback();
+                    Ok(())
+                },
+                OscCommand::SetTrackVolume(track_idx, volume) => self.set_track_volume(track_idx, volume),
+            };
+            if let Err(e) = result {
+                error!("OSC command {:?} failed: {}", command, e);
+            }
+        }
+        count
+    }
+
+    /// Fractional playback position, from 0.0 up to `num_steps()`.
+    /// Moves smoothly between steps while playing.
+    pub fn get_playback_progress(&self) -> f32 {
+        match &self.sequencer {
+            Some(sequencer) if self.is_playing => self.current_step as f32 + sequencer.step_progress(),
+            _ => self.current_step as f32,
+        }
+    }
+
+    /// Start or stop writing track hits into the pattern
+    pub fn toggle_record_mode(&mut self) {
+        self.record_mode = !self.record_mode;
+        info!("Record mode {}", if self.record_mode { "on" } else { "off" });
+    }
+
+    /// Record a hit on a track at the playback position, rounded to the nearest step.
+    /// Returns the step that was set; the cursor doesn't move.
+    pub fn record_step(&mut self, track_idx: usize) -> Result<usize, Box<dyn std::error::Error>> {
+        if !self.record_mode {
+            return Err("Record mode is off".into());
+        }
+        let num_steps = self.num_steps();
+        if num_steps == 0 {
+            return Err("Pattern has no steps".into());
+        }
+        let step_idx = self.get_playback_progress().round() as usize % num_steps;
+        self.set_step(track_idx, step_idx, true)?;
+        Ok(step_idx)
+    }
+
     /// Set the BPM (tempo) for the sequencer
     pub fn set_bpm(&mut self, bpm: u32) {
         self.bpm = bpm;
@@ -214,7 +1207,7 @@ impl AppState {
         }
         
         // Check if audio is initialized
-        if self.audio.is_none() {
+        if self.audio.is_none() && self.headless_audio.is_none() {
             return Err("Audio not initialized".into());
         }
         
@@ -225,36 +1218,182 @@ impl AppState {
         Ok(())
     }
 
+    /// How often each step has fired since the counts were last reset - [track][step].
+    /// Rows cover every track and step, with zeros for steps that never fired.
+    pub fn step_hit_counts(&self) -> Vec<Vec<u32>> {
+        let counts = self.step_hit_counts.lock().unwrap();
+        (0..self.num_tracks())
+            .map(|track_idx| {
+                let row = counts.get(track_idx).map_or(&[][..], Vec::as_slice);
+                (0..self.num_steps()).map(|step_idx| row.get(step_idx).copied().unwrap_or(0)).collect()
+            })
+            .collect()
+    }
+    
+    /// Forget how often steps have fired
+    pub fn reset_step_hit_counts(&self) {
+        self.step_hit_counts.lock().unwrap().clear();
+    }
+    
+    /// Per-step timing offsets of the current groove, in fractions of a step.
+    /// Without a groove template this is the straight grid with swing on odd steps.
+    pub fn compute_groove_template(&self) -> Vec<f32> {
+        match &self.groove_template {
+            Some(offsets) => offsets.clone(),
+            None => (0..self.num_steps())
+                .map(|step| if step % 2 == 1 { self.swing.clamp(0.0, 1.0) * 0.5 } else { 0.0 })
+                .collect(),
+        }
+    }
+    
+    /// Play steps at fixed offsets from the grid instead of swing; an empty template goes back to swing
+    pub fn apply_groove_template(&mut self, offsets: Vec<f32>) {
+        if let Some(sequencer) = &self.sequencer {
+            sequencer.set_groove_template(offsets.clone());
+        }
+        info!("Groove template set for {} steps", offsets.len());
+        self.groove_template = if offsets.is_empty() { None } else { Some(offsets) };
+        self.active_groove = None;
+    }
+
+    /// Load the grooves in the project's `grooves/` folder, replacing those loaded before
+    pub fn load_grooves(&mut self, folder: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        self.available_grooves = project::load_grooves(folder)?;
+        Ok(())
+    }
+
+    /// Names of the loaded grooves, sorted
+    pub fn groove_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.available_grooves.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Name of the loaded groove being played, if any
+    pub fn active_groove(&self) -> Option<&str> {
+        self.active_groove.as_deref()
+    }
+
+    /// Play one of the loaded grooves
+    pub fn apply_groove(&mut self, name: &str) -> Result<(), &'static str> {
+        let offsets = self.available_grooves.get(name).ok_or("No groove with that name")?.clone();
+        self.apply_groove_template(offsets);
+        self.active_groove = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Play the loaded groove after the active one, by name; returns its name
+    pub fn cycle_groove(&mut self) -> Option<String> {
+        let names = self.groove_names();
+        let next = match self.active_groove.as_ref().and_then(|active| names.iter().position(|name| name == active)) {
+            Some(idx) => names.get(idx + 1).unwrap_or(&names[0]),
+            None => names.first()?,
+        };
+        self.apply_groove(next).ok()?;
+        Some(next.clone())
+    }
+    
     /// Initialize the sequencer with the current pattern data and BPM
     /// If with_audio is true, also initialize and connect the audio system
     pub fn initialize_sequencer(&mut self, with_audio: bool) -> Result<(), Box<dyn std::error::Error>> {
+        // Edits rebuild the sequencer through refresh_sequencer; only the first setup warms up audio
+        let first_setup = self.sequencer.is_none();
+        
         // Create the sequencer with our shared event bus
         let event_bus_clone = Arc::clone(&self.event_bus);
-        self.sequencer = Some(Sequencer::new_with_event_bus(self.bpm, self.steps.clone(), event_bus_clone));
+        let sequencer = Sequencer::new_with_event_bus(self.bpm, self.steps.clone(), event_bus_clone);
+        sequencer.set_swing(self.swing);
+        sequencer.set_subdivision(self.subdivision);
+        if let Some(offsets) = &self.groove_template {
+            sequencer.set_groove_template(offsets.clone());
+        }
+        for &track_idx in &self.muted_tracks {
+            sequencer.set_track_muted(track_idx, true);
+        }
+        for (&(track_idx, step_idx), &gate) in &self.step_gates {
+            sequencer.set_step_gate(track_idx, step_idx, Some(gate));
+        }
+        for (track_idx, lengths) in self.step_lengths.iter().enumerate() {
+            sequencer.set_step_lengths(track_idx, lengths.clone())?;
+        }
+        for (track_idx, (counts, intervals)) in self.step_retrigger_count.iter().zip(&self.step_retrigger_interval_ms).enumerate() {
+            for (step_idx, (&count, &interval_ms)) in counts.iter().zip(intervals).enumerate() {
+                if count > 1 {
+                    sequencer.set_step_retrigger(track_idx, step_idx, count, interval_ms);
+                }
+            }
+        }
+        
+        // Queue triggers for process_sequencer_events instead of polling the sequencer
+        self.pending_triggers.lock().unwrap().clear();
+        let pending = Arc::clone(&self.pending_triggers);
+        sequencer.add_listener(Box::new(move |event| {
+            pending.lock().unwrap().push(event);
+        }));
+        self.sequencer = Some(sequencer);
         
         // Initialize audio if requested
         if with_audio {
             // Initialize audio system if it hasn't been initialized yet
-            if self.audio.is_none() {
-                self.initialize_audio()?;
+            if self.audio.is_none() && self.headless_audio.is_none() {
+                self.initialize_audio(self.audio_mode, None)?;
+                if let Some(audio) = self.audio.as_ref().filter(|_| first_setup) {
+                    audio.warm_up()?;
+                }
             }
-            
-            // Connect audio to sequencer
-            self.connect_audio_to_sequencer()?;
+            if self.audio_mode != AudioMode::Disabled {
+                // Connect audio to sequencer
+                self.connect_audio_to_sequencer()?;
+            }
+        }
+        
+        // Only the first sequencer starts by itself; later ones keep the current play state
+        if std::mem::take(&mut self.trigger_on_load) && !self.is_playing {
+            info!("Starting playback on load");
+            self.toggle_playback();
         }
         
         Ok(())
     }
     
-    /// Initialize the audio system with the current sample directory and track configurations
-    pub fn initialize_audio(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Initialize the audio system with the current sample directory and track configurations.
+    /// `config` overrides the output stream settings; None uses the device defaults.
+    pub fn initialize_audio(&mut self, mode: AudioMode, config: Option<AudioConfig>) -> Result<(), Box<dyn std::error::Error>> {
+        self.audio_mode = mode;
+        match mode {
+            AudioMode::Hardware => {}
+            AudioMode::Headless => {
+                info!("Initializing headless audio");
+                let connector = HeadlessAudioConnector::new(&self.sample_dir);
+                connector.initialize(&self.tracks)?;
+                self.headless_audio = Some(connector);
+                return Ok(());
+            }
+            AudioMode::Disabled => {
+                info!("Audio disabled");
+                return Ok(());
+            }
+        }
+        
         info!("Initializing audio system with sample directory: {}", self.sample_dir.display());
         
         // Create an AudioConnector with the sample directory
-        let connector = AudioConnector::new(&self.sample_dir)?;
+        let connector = AudioConnector::with_options(&self.sample_dir, self.audio_device.as_deref(), config)?;
         
         // Initialize the connector with track data
         connector.initialize(&self.tracks)?;
+        if !self.effects.is_empty() {
+            connector.load_effect_state(self.effects.clone())?;
+        }
+        for (track_idx, &semitones) in self.track_tuning.iter().enumerate().filter(|(_, &semitones)| semitones != 0.0) {
+            connector.set_track_tuning(track_idx, semitones)?;
+        }
+        if self.master_volume != 1.0 {
+            connector.set_master_volume(self.master_volume)?;
+        }
+        for &track_idx in &self.soloed_tracks {
+            connector.solo_track(track_idx)?;
+        }
         
         self.audio = Some(connector);
         info!("Audio system initialized successfully");
@@ -264,6 +1403,10 @@ impl AppState {
     
     /// Connect the audio system to the sequencer to handle trigger events
     pub fn connect_audio_to_sequencer(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let (Some(headless), Some(sequencer)) = (&self.headless_audio, &self.sequencer) {
+            headless.connect_to_sequencer(sequencer);
+            return Ok(());
+        }
         if self.audio.is_none() || self.sequencer.is_none() {
             return Err("Audio or sequencer not initialized".into());
         }
@@ -271,18 +1414,9 @@ impl AppState {
         let audio = self.audio.as_ref().unwrap();
         
         info!("Connecting audio system to event bus");
-        
-        // Connect audio directly to the event bus
-        // audio.connect_to_event_bus(Arc::clone(&self.event_bus));
-        
+        audio.connect_to_event_bus(Arc::clone(&self.event_bus));
         info!("Audio-event bus connection established");
         
-        // Also maintain backwards compatibility by connecting to sequencer
-        let sequencer = self.sequencer.as_ref().unwrap();
-        if !audio.connect_to_sequencer(sequencer) {
-            debug!("Legacy sequencer connection failed, but event bus connection should work");
-        }
-        
         Ok(())
     }
     
@@ -290,13 +1424,20 @@ impl AppState {
     pub fn cleanup_audio(&mut self) {
         info!("Cleaning up audio resources");
         
-        // Deactivate audio connector if it exists
+        // Deactivate audio connector if it exists, letting queued triggers play first
         if let Some(audio) = &self.audio {
+            if !audio.flush(Duration::from_secs(1)) {
+                debug!("Audio queue was not drained before shutdown");
+            }
             audio.deactivate();
         }
+        if let Some(headless) = &self.headless_audio {
+            headless.deactivate();
+        }
         
         // Clear the audio connector
         self.audio = None;
+        self.headless_audio = None;
         
         info!("Audio resources cleaned up");
     }
@@ -324,6 +1465,420 @@ impl AppState {
         }
     }
     
+    /// Get the combined state of a track
+    pub fn get_track_info(&self, track_idx: usize) -> Option<TrackInfo> {
+        let track = self.tracks.get(track_idx)?;
+        Some(TrackInfo {
+            name: self.track_names.get(track_idx).unwrap_or(&track.name).clone(),
+            sample: track.sample.clone(),
+            volume: track.volume,
+            muted: self.is_track_muted(track_idx),
+            soloed: self.is_track_soloed(track_idx),
+            tuning_semitones: self.get_track_tuning(track_idx).unwrap_or(0.0),
+            pan: track.pan,
+            effect_count: self.effects.get(&track_idx).map_or(0, Vec::len),
+            color: track.color.clone(),
+        })
+    }
+    
+    /// Get the combined state of every track, in track order
+    pub fn get_all_track_info(&self) -> Vec<TrackInfo> {
+        (0..self.tracks.len()).filter_map(|idx| self.get_track_info(idx)).collect()
+    }
+    
+    /// Choose how audio is produced when the sequencer starts with audio
+    pub fn with_audio_mode(mut self, mode: AudioMode) -> Self {
+        self.audio_mode = mode;
+        self
+    }
+    
+    /// How audio is produced when the sequencer starts with audio
+    pub fn audio_mode(&self) -> AudioMode {
+        self.audio_mode
+    }
+    
+    /// Get the directory samples are loaded from
+    pub fn get_sample_dir(&self) -> &Path {
+        &self.sample_dir
+    }
+    
+    /// Change the sample file used by a track (path relative to the sample directory)
+    pub fn set_track_sample(&mut self, track_idx: usize, sample: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if track_idx >= self.tracks.len() {
+            return Err(format!("Track index {} out of bounds", track_idx).into());
+        }
+        
+        info!("Setting track {} sample to {}", track_idx, sample);
+        self.tracks[track_idx].sample = sample.to_string();
+        
+        if let Some(audio) = &self.audio {
+            audio.reload_sample(track_idx, sample)?;
+        }
+        if let Some(headless) = &self.headless_audio {
+            headless.reload_sample(track_idx, sample)?;
+        }
+        
+        Ok(())
+    }
+
+    /// Append a track with an empty row of steps and return its index
+    pub fn add_track(&mut self, track: Track) -> Result<usize, Box<dyn std::error::Error>> {
+        let track_idx = self.steps.len();
+        let num_steps = self.num_steps();
+        
+        info!("Adding track {}: '{}' with sample '{}'", track_idx, track.name, track.sample);
+        // Let the audio side fail before any per-track list grows
+        if let Some(audio) = &self.audio {
+            audio.add_track(&track)?;
+        }
+        if let Some(headless) = &self.headless_audio {
+            headless.add_track(&track)?;
+        }
+        self.steps.push(vec![false; num_steps]);
+        for pattern in &mut self.patterns {
+            pattern.steps.push(vec![false; pattern.length]);
+        }
+        self.velocities.push(vec![1.0; num_steps]);
+        self.probabilities.push(vec![1.0; num_steps]);
+        self.step_lengths.push(vec![1.0; num_steps]);
+        self.step_retrigger_count.push(vec![1; num_steps]);
+        self.step_retrigger_interval_ms.push(vec![0.0; num_steps]);
+        self.truncated_steps.push(Vec::new());
+        self.track_names.push(track.name.clone());
+        if track.muted {
+            self.muted_tracks.insert(track_idx);
+        }
+        self.tracks.push(track);
+        self.track_tuning.push(0.0);
+        
+        // Saved patterns no longer match the track layout
+        self.undo_stack.clear();
+        
+        self.event_bus.emit(TrackerEvent::TrackAdded(track_idx));
+        self.refresh_sequencer();
+        Ok(track_idx)
+    }
+    
+    /// Remove a track and its steps; later tracks move down one index.
+    /// The last track can't be removed.
+    pub fn remove_track(&mut self, track_idx: usize) -> Result<(), Box<dyn std::error::Error>> {
+        if track_idx >= self.steps.len() {
+            return Err(format!("Track index {} out of bounds", track_idx).into());
+        }
+        if self.steps.len() == 1 {
+            return Err("Cannot remove the only track".into());
+        }
+        
+        info!("Removing track {}", track_idx);
+        // Let the audio side fail before any per-track list shrinks
+        if let Some(audio) = &self.audio {
+            audio.remove_track(track_idx)?;
+        }
+        if let Some(headless) = &self.headless_audio {
+            headless.remove_track(track_idx)?;
+        }
+        self.steps.remove(track_idx);
+        for pattern in &mut self.patterns {
+            if track_idx < pattern.steps.len() {
+                pattern.steps.remove(track_idx);
+            }
+        }
+        self.velocities.remove(track_idx);
+        self.probabilities.remove(track_idx);
+        self.step_lengths.remove(track_idx);
+        self.step_retrigger_count.remove(track_idx);
+        self.step_retrigger_interval_ms.remove(track_idx);
+        self.muted_tracks = self.muted_tracks.iter()
+            .filter(|&&track| track != track_idx)
+            .map(|&track| if track > track_idx { track - 1 } else { track })
+            .collect();
+        self.soloed_tracks = self.soloed_tracks.iter()
+            .filter(|&&track| track != track_idx)
+            .map(|&track| if track > track_idx { track - 1 } else { track })
+            .collect();
+        self.effects = std::mem::take(&mut self.effects)
+            .into_iter()
+            .filter(|(track, _)| *track != track_idx)
+            .map(|(track, effects)| (if track > track_idx { track - 1 } else { track }, effects))
+            .collect();
+        let shift = |track: usize| if track > track_idx { track - 1 } else { track };
+        self.step_gates = std::mem::take(&mut self.step_gates)
+            .into_iter()
+            .filter(|&((track, _), (required, _))| track != track_idx && required != track_idx)
+            .map(|((track, step), (required, offset))| ((shift(track), step), (shift(required), offset)))
+            .collect();
+        if track_idx < self.truncated_steps.len() {
+            self.truncated_steps.remove(track_idx);
+        }
+        {
+            let mut counts = self.step_hit_counts.lock().unwrap();
+            if track_idx < counts.len() {
+                counts.remove(track_idx);
+            }
+        }
+        if track_idx < self.tracks.len() {
+            self.tracks.remove(track_idx);
+        }
+        if track_idx < self.track_tuning.len() {
+            self.track_tuning.remove(track_idx);
+        }
+        if track_idx < self.track_names.len() {
+            self.track_names.remove(track_idx);
+        }
+        
+        self.undo_stack.clear();
+        self.selection_mode = false;
+        self.selected_track = self.selected_track.min(self.steps.len().saturating_sub(1));
+        
+        self.event_bus.emit(TrackerEvent::TrackRemoved(track_idx));
+        self.refresh_sequencer();
+        Ok(())
+    }
+    
+    /// Swap two tracks along with their steps, settings and effects, as an undoable edit
+    pub fn swap_tracks(&mut self, track_a: usize, track_b: usize) -> Result<(), Box<dyn std::error::Error>> {
+        self.reorder_tracks(track_a, track_b, |idx| {
+            if idx == track_a {
+                track_b
+            } else if idx == track_b {
+                track_a
+            } else {
+                idx
+            }
+        })
+    }
+    
+    /// Move a track to another position; the tracks in between shift over by one.
+    /// Like `swap_tracks`, this can be undone.
+    pub fn move_track_to(&mut self, from: usize, to: usize) -> Result<(), Box<dyn std::error::Error>> {
+        self.reorder_tracks(from, to, |idx| {
+            if idx == from {
+                to
+            } else if from < to && idx > from && idx <= to {
+                idx - 1
+            } else if to < from && idx >= to && idx < from {
+                idx + 1
+            } else {
+                idx
+            }
+        })
+    }
+    
+    /// Move track `idx` to `new_index(idx)` after checking both tracks exist, recording the move for undo
+    fn reorder_tracks(&mut self, track_a: usize, track_b: usize, new_index: impl Fn(usize) -> usize) -> Result<(), Box<dyn std::error::Error>> {
+        let count = self.steps.len();
+        if track_a >= count || track_b >= count {
+            return Err(format!("Track index {} out of bounds", track_a.max(track_b)).into());
+        }
+        if track_a == track_b {
+            return Ok(());
+        }
+        
+        info!("Reordering tracks {} and {}", track_a, track_b);
+        self.push_undo_entry(UndoEntry::Reorder((0..count).map(&new_index).collect()));
+        self.apply_track_order(new_index);
+        Ok(())
+    }
+    
+    /// Rearrange every per-track list and map so track `idx` ends up at `new_index(idx)`.
+    /// Undo entries are left alone: entries below a reorder are undone after it.
+    fn apply_track_order(&mut self, new_index: impl Fn(usize) -> usize) {
+        let count = self.steps.len();
+        permute_tracks(&mut self.steps, count, &new_index);
+        permute_tracks(&mut self.velocities, count, &new_index);
+        permute_tracks(&mut self.probabilities, count, &new_index);
+        permute_tracks(&mut self.step_lengths, count, &new_index);
+        permute_tracks(&mut self.step_retrigger_count, count, &new_index);
+        permute_tracks(&mut self.step_retrigger_interval_ms, count, &new_index);
+        permute_tracks(&mut self.truncated_steps, count, &new_index);
+        permute_tracks(&mut self.tracks, count, &new_index);
+        permute_tracks(&mut self.track_names, count, &new_index);
+        permute_tracks(&mut self.track_tuning, count, &new_index);
+        for pattern in &mut self.patterns {
+            permute_tracks(&mut pattern.steps, count, &new_index);
+        }
+        {
+            let mut counts = self.step_hit_counts.lock().unwrap();
+            let rows = count.max(counts.len());
+            counts.resize(rows, Vec::new());
+            permute_tracks(&mut counts, count, &new_index);
+        }
+        self.muted_tracks = self.muted_tracks.iter().map(|&track| new_index(track)).collect();
+        self.soloed_tracks = self.soloed_tracks.iter().map(|&track| new_index(track)).collect();
+        self.effects = std::mem::take(&mut self.effects)
+            .into_iter()
+            .map(|(track, effects)| (new_index(track), effects))
+            .collect();
+        self.step_gates = std::mem::take(&mut self.step_gates)
+            .into_iter()
+            .map(|((track, step), (required, offset))| ((new_index(track), step), (new_index(required), offset)))
+            .collect();
+        
+        // Keep the cursor on the track it was on
+        self.selected_track = new_index(self.selected_track);
+        self.selection_mode = false;
+        
+        self.event_bus.emit(TrackerEvent::PatternChanged);
+        self.refresh_sequencer();
+    }
+
+    /// Replace every track with one per sample file found in `dir`, keeping the step count.
+    /// Returns the number of tracks created.
+    pub fn auto_populate_tracks(&mut self, dir: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut tracks = project::scan_samples_directory(dir, 1.0)?;
+        
+        // Sample paths are resolved against the sample directory
+        let base = match dir.strip_prefix(&self.sample_dir) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => std::fs::canonicalize(dir)?,
+        };
+        for track in &mut tracks {
+            track.sample = base.join(&track.sample).to_string_lossy().into_owned();
+        }
+        
+        // Add before removing so the new tracks get the current step count
+        let old_count = self.steps.len();
+        info!("Replacing {} tracks with {} from {}", old_count, tracks.len(), dir.display());
+        let count = tracks.len();
+        for track in tracks {
+            self.add_track(track)?;
+        }
+        for _ in 0..old_count {
+            self.remove_track(0)?;
+        }
+        Ok(count)
+    }
+
+    /// Save every pattern, the tracks and effect chains as a project folder
+    pub fn save_project(&self, folder: &Path, project: &Project) -> Result<(), Box<dyn std::error::Error>> {
+        let patterns: Vec<Pattern> = (0..self.patterns.len() as u32)
+            .filter_map(|idx| self.pattern(idx))
+            .collect();
+        let tracks: Vec<Track> = self.tracks.iter().enumerate()
+            .map(|(idx, track)| Track { muted: self.is_track_muted(idx), ..track.clone() })
+            .collect();
+        let project = Project { groove_template: self.groove_template.clone(), ..project.clone() };
+        project::save_project(folder, &project, &tracks, &patterns)?;
+        project::loader::save_effects(folder, &self.effects)?;
+        Ok(())
+    }
+
+    /// Append an effect to a track's chain, applied from its next trigger
+    pub fn add_effect_to_track(&mut self, track_idx: usize, effect: SampleEffect) -> Result<(), Box<dyn std::error::Error>> {
+        if track_idx >= self.steps.len() {
+            return Err(format!("Track index {} out of bounds", track_idx).into());
+        }
+        info!("Adding {} effect to track {}", effect, track_idx);
+        self.effects.entry(track_idx).or_default().push(effect.clone());
+        if let Some(audio) = &self.audio {
+            audio.add_track_effect(track_idx, effect)?;
+        }
+        Ok(())
+    }
+    
+    /// Load effect chains saved with the project and apply them to the audio engine.
+    /// A project without saved effects leaves the current ones in place.
+    pub fn load_effects(&mut self, folder: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(effects) = project::loader::load_effects(folder)? else {
+            return Ok(());
+        };
+        self.effects = effects;
+        if let Some(audio) = &self.audio {
+            audio.load_effect_state(self.effects.clone())?;
+        }
+        info!("Loaded effects for {} tracks", self.effects.len());
+        Ok(())
+    }
+
+    /// Replace the current project with the one in `folder`, keeping the event bus and its listeners.
+    /// Playback stops, and the sequencer and audio are rebuilt if they were running.
+    pub fn load_project_from_path(&mut self, folder: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let (project, tracks, patterns, _) = project::load_project(folder)?;
+        info!("Loading project '{}' into the running app", project.name);
+
+        let reinitialize = self.sequencer.is_some();
+        let with_audio = self.audio.is_some() || self.headless_audio.is_some();
+        if self.is_playing {
+            self.toggle_playback();
+        }
+        self.cleanup_audio();
+        self.sequencer = None;
+
+        let num_tracks = tracks.len();
+        let num_steps = patterns.first()
+            .and_then(|pattern| pattern.steps.first())
+            .map_or(16, |row| row.len());
+        let mut track_names: Vec<String> = tracks.iter().map(|t| t.name.clone()).collect();
+        if track_names.is_empty() {
+            track_names = (0..num_tracks).map(|i| format!("tr-{:<2}", i)).collect();
+        }
+
+        let mut loaded = AppState::new(num_tracks, num_steps)
+            .with_patterns(patterns)
+            .with_sample_dir(folder.join("samples"))
+            .with_tracks(tracks)
+            .with_audio_mode(self.audio_mode)
+            .with_audio_device(self.audio_device.take());
+        loaded.track_names = track_names;
+        loaded.bpm = project.bpm;
+        loaded.swing = project.swing;
+        if let Some(offsets) = project.groove_template {
+            loaded.apply_groove_template(offsets);
+        }
+        if let Err(e) = loaded.load_effects(folder) {
+            error!("Failed to load saved effects: {}", e);
+        }
+        if let Err(e) = loaded.load_grooves(folder) {
+            error!("Failed to load grooves: {}", e);
+        }
+
+        // Keep the shared state listeners were registered against
+        loaded.event_bus = Arc::clone(&self.event_bus);
+        loaded.step_hit_counts = Arc::clone(&self.step_hit_counts);
+        loaded.step_hit_counts.lock().unwrap().clear();
+        loaded.osc_server = self.osc_server.take();
+        *self = loaded;
+
+        if reinitialize {
+            if let Err(e) = self.initialize_sequencer(with_audio) {
+                error!("Failed to initialize audio for loaded project: {}", e);
+                self.initialize_sequencer(false)?;
+            }
+        }
+
+        self.event_bus.emit(TrackerEvent::ProjectLoaded(project.name));
+        Ok(())
+    }
+
+    /// Render `bars` bars of the current pattern to a WAV file without an audio device.
+    /// The pattern is played into a headless connector and its trigger log is mixed, with
+    /// the mutes, solos, velocities and master volume live playback would use.
+    pub fn render_to_wav(&self, output: &Path, bars: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let connector = HeadlessAudioConnector::new(&self.sample_dir);
+        connector.initialize(&self.tracks)?;
+        connector.set_master_volume(self.master_volume);
+
+        // Same step length as the sequencer; a bar holds 4 beats at any subdivision
+        let factor = self.subdivision.factor();
+        let step_duration = Duration::from_secs_f64(60.0 / self.bpm.max(1) as f64 / factor as f64);
+        let total_steps = bars * STEPS_PER_BAR * factor as usize / Subdivision::default().factor() as usize;
+        for step in 0..total_steps {
+            for (track_idx, row) in self.steps.iter().enumerate() {
+                let silenced = self.muted_tracks.contains(&track_idx)
+                    || (!self.soloed_tracks.is_empty() && !self.soloed_tracks.contains(&track_idx));
+                if silenced || row.is_empty() || !row[step % row.len()] {
+                    continue;
+                }
+                let step_idx = step % row.len();
+                let velocity = self.velocities.get(track_idx).and_then(|row| row.get(step_idx)).copied().unwrap_or(1.0);
+                connector.trigger_at(&TriggerEvent { track_idx, step_idx }, velocity, step_duration * step as u32);
+            }
+        }
+
+        connector.render_to_wav(output, step_duration * total_steps as u32)?;
+        Ok(())
+    }
+
     /// Get the volume for a specific track
     pub fn get_track_volume(&self, track_idx: usize) -> Option<f32> {
         if track_idx < self.tracks.len() {
@@ -333,6 +1888,24 @@ impl AppState {
         }
     }
     
+    /// Gain applied on top of every track's volume
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume
+    }
+
+    /// Scale every track's volume, from silent (0.0) up to `MAX_MASTER_VOLUME`
+    pub fn set_master_volume(&mut self, volume: f32) {
+        let volume = volume.clamp(0.0, MAX_MASTER_VOLUME);
+        self.master_volume = volume;
+        info!("Setting master volume to {:.2}", volume);
+        if let Some(audio) = &self.audio {
+            if let Err(e) = audio.set_master_volume(volume) {
+                debug!("Master volume update failed: {}", e);
+            }
+        }
+        self.event_bus.emit(TrackerEvent::MasterVolumeChanged(volume));
+    }
+    
     /// Set the volume for a specific track
     pub fn set_track_volume(&mut self, track_idx: usize, volume: f32) -> Result<(), Box<dyn std::error::Error>> {
         // Update the track configuration
@@ -354,6 +1927,9 @@ impl AppState {
                     debug!("Legacy volume update failed: {}", e);
                 }
             }
+            if let Some(headless) = &self.headless_audio {
+                let _ = headless.set_track_volume(track_idx, volume);
+            }
             
             Ok(())
         } else {
@@ -361,6 +1937,60 @@ impl AppState {
         }
     }
     
+    /// Set a track's stereo position, clamped to -1.0 (left) ..= 1.0 (right)
+    pub fn set_track_pan(&mut self, track_idx: usize, pan: f32) -> Result<(), Box<dyn std::error::Error>> {
+        let track = self.tracks.get_mut(track_idx)
+            .ok_or_else(|| format!("Track index {} out of bounds", track_idx))?;
+        track.pan = pan.clamp(-1.0, 1.0);
+        info!("Setting track {} pan to {:.2}", track_idx, track.pan);
+        
+        if let Some(audio) = &self.audio {
+            audio.set_track_pan(track_idx, track.pan)?;
+        }
+        Ok(())
+    }
+    
+    /// Shift a track's pitch, clamped to two octaves either way
+    pub fn set_track_pitch(&mut self, track_idx: usize, semitones: f32) -> Result<(), Box<dyn std::error::Error>> {
+        let track = self.tracks.get_mut(track_idx)
+            .ok_or_else(|| format!("Track index {} out of bounds", track_idx))?;
+        track.pitch_semitones = semitones.clamp(-MAX_PITCH_SEMITONES, MAX_PITCH_SEMITONES);
+        info!("Setting track {} pitch to {:+.1} semitones", track_idx, track.pitch_semitones);
+        
+        if let Some(audio) = &self.audio {
+            audio.set_track_pitch(track_idx, track.pitch_semitones)?;
+        }
+        Ok(())
+    }
+    
+    /// Get a track's stereo position
+    pub fn get_track_pan(&self, track_idx: usize) -> Option<f32> {
+        self.tracks.get(track_idx).map(|track| track.pan)
+    }
+    
+    /// Get a track's pitch shift in semitones
+    pub fn get_track_pitch(&self, track_idx: usize) -> Option<f32> {
+        self.tracks.get(track_idx).map(|track| track.pitch_semitones)
+    }
+    
+    /// Tune a track by a number of semitones, added to its pitch
+    pub fn set_track_tuning(&mut self, track_idx: usize, semitones: f32) -> Result<(), Box<dyn std::error::Error>> {
+        let tuning = self.track_tuning.get_mut(track_idx)
+            .ok_or_else(|| format!("Track index {} out of bounds", track_idx))?;
+        *tuning = semitones.clamp(-MAX_PITCH_SEMITONES, MAX_PITCH_SEMITONES);
+        info!("Tuning track {} by {:+.1} semitones", track_idx, *tuning);
+        
+        if let Some(audio) = &self.audio {
+            audio.set_track_tuning(track_idx, *tuning)?;
+        }
+        Ok(())
+    }
+    
+    /// Semitones a track is tuned by
+    pub fn get_track_tuning(&self, track_idx: usize) -> Option<f32> {
+        self.track_tuning.get(track_idx).copied()
+    }
+    
     /// Get a reference to the event bus
     pub fn get_event_bus(&self) -> &SharedEventBus {
         &self.event_bus
@@ -374,12 +2004,53 @@ impl AppState {
         self.event_bus.subscribe(listener)
     }
     
+    /// Subscribe a listener that is removed once it returns true, e.g. to react to the
+    /// next trigger of a particular step
+    pub fn subscribe_once<F>(&self, listener: F) -> usize
+    where
+        F: Fn(&TrackerEvent) -> bool + Send + Sync + 'static
+    {
+        self.event_bus.subscribe_once(listener)
+    }
+    
+    /// Remove a listener added with `subscribe_to_events` or `subscribe_once`
+    pub fn unsubscribe_from_events(&self, id: usize) -> bool {
+        self.event_bus.unsubscribe(id)
+    }
+    
     /// Emit an event through the app state's event bus
     pub fn emit_event(&self, event: TrackerEvent) {
         self.event_bus.emit(event);
     }
 }
 
+/// Stretch or squeeze a row of steps from `from` to `to` steps per beat. Going finer
+/// puts `fill` between the old steps; going coarser drops the steps between new ones.
+fn resample_steps<T: Clone>(row: &[T], from: usize, to: usize, fill: T) -> Vec<T> {
+    (0..row.len() * to / from)
+        .map(|step| {
+            if (step * from).is_multiple_of(to) {
+                row[step * from / to].clone()
+            } else {
+                fill.clone()
+            }
+        })
+        .collect()
+}
+
+/// Move each row of a per-track list to `new_index(idx)`.
+/// Lists that don't hold exactly `count` tracks are left alone.
+fn permute_tracks<T>(rows: &mut Vec<T>, count: usize, new_index: &impl Fn(usize) -> usize) {
+    if rows.len() != count {
+        return;
+    }
+    let mut slots: Vec<Option<T>> = (0..count).map(|_| None).collect();
+    for (idx, row) in rows.drain(..).enumerate() {
+        slots[new_index(idx)] = Some(row);
+    }
+    rows.extend(slots.into_iter().flatten());
+}
+
 impl Drop for AppState {
     fn drop(&mut self) {
         // Ensure we clean up resources properly
@@ -0,0 +1,241 @@
+// Open Sound Control input for remote pattern control
+// Messages arrive on a UDP listener thread and are handed to the UI thread over a channel
+
+use std::error::Error;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use log::{debug, info, warn};
+
+/// How often the listener thread checks whether it should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Largest datagram the listener accepts
+const MAX_PACKET_SIZE: usize = 1536;
+
+/// An argument of an OSC message
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+impl OscArg {
+    fn as_f32(&self) -> Option<f32> {
+        match self {
+            OscArg::Int(value) => Some(*value as f32),
+            OscArg::Float(value) => Some(*value),
+            OscArg::String(_) => None,
+        }
+    }
+
+    fn as_index(&self) -> Option<usize> {
+        match self {
+            OscArg::Int(value) => usize::try_from(*value).ok(),
+            OscArg::Float(value) if *value >= 0.0 && value.fract() == 0.0 => Some(*value as usize),
+            _ => None,
+        }
+    }
+}
+
+/// A decoded OSC message
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>,
+}
+
+impl OscMessage {
+    pub fn new(address: &str, args: Vec<OscArg>) -> Self {
+        Self { address: address.to_string(), args }
+    }
+
+    /// Encode the message as an OSC 1.0 packet
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        put_string(&mut out, &self.address);
+        let tags: String = std::iter::once(',')
+            .chain(self.args.iter().map(|arg| match arg {
+                OscArg::Int(_) => 'i',
+                OscArg::Float(_) => 'f',
+                OscArg::String(_) => 's',
+            }))
+            .collect();
+        put_string(&mut out, &tags);
+        for arg in &self.args {
+            match arg {
+                OscArg::Int(value) => out.extend_from_slice(&value.to_be_bytes()),
+                OscArg::Float(value) => out.extend_from_slice(&value.to_be_bytes()),
+                OscArg::String(value) => put_string(&mut out, value),
+            }
+        }
+        out
+    }
+}
+
+/// Write a null-terminated string padded to a multiple of four bytes
+fn put_string(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(value.as_bytes());
+    let padding = 4 - value.len() % 4;
+    out.resize(out.len() + padding, 0);
+}
+
+/// Read a padded string starting at `*pos` and move past it
+fn get_string(data: &[u8], pos: &mut usize) -> Result<String, String> {
+    let rest = data.get(*pos..).ok_or("Unexpected end of OSC packet")?;
+    let len = rest.iter().position(|&b| b == 0).ok_or("Unterminated OSC string")?;
+    let value = String::from_utf8(rest[..len].to_vec()).map_err(|e| format!("Invalid OSC string: {}", e))?;
+    *pos += (len / 4 + 1) * 4;
+    Ok(value)
+}
+
+fn get_bytes4(data: &[u8], pos: &mut usize) -> Result<[u8; 4], String> {
+    let bytes = data.get(*pos..*pos + 4).ok_or("Unexpected end of OSC packet")?;
+    *pos += 4;
+    Ok([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Decode an OSC packet; bundles are flattened into their messages
+pub fn decode_packet(data: &[u8]) -> Result<Vec<OscMessage>, String> {
+    if data.starts_with(b"#bundle\0") {
+        // Skip the tag and the time tag: elements are applied as soon as they arrive
+        let mut pos = 16;
+        let mut messages = Vec::new();
+        while pos < data.len() {
+            let size = u32::from_be_bytes(get_bytes4(data, &mut pos)?) as usize;
+            let element = data.get(pos..pos + size).ok_or("Unexpected end of OSC bundle")?;
+            messages.extend(decode_packet(element)?);
+            pos += size;
+        }
+        return Ok(messages);
+    }
+
+    let mut pos = 0;
+    let address = get_string(data, &mut pos)?;
+    if !address.starts_with('/') {
+        return Err(format!("Invalid OSC address: {}", address));
+    }
+    // Very old senders omit the type tags; treat that as no arguments
+    if pos >= data.len() {
+        return Ok(vec![OscMessage { address, args: Vec::new() }]);
+    }
+    let tags = get_string(data, &mut pos)?;
+    let tags = tags.strip_prefix(',').ok_or("OSC type tags must start with ','")?;
+
+    let mut args = Vec::new();
+    for tag in tags.chars() {
+        let arg = match tag {
+            'i' => OscArg::Int(i32::from_be_bytes(get_bytes4(data, &mut pos)?)),
+            'f' => OscArg::Float(f32::from_be_bytes(get_bytes4(data, &mut pos)?)),
+            's' => OscArg::String(get_string(data, &mut pos)?),
+            other => return Err(format!("Unsupported OSC argument type '{}'", other)),
+        };
+        args.push(arg);
+    }
+    Ok(vec![OscMessage { address, args }])
+}
+
+/// An `AppState` change requested over OSC
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscCommand {
+    /// `/gaucho/step/toggle TRACK STEP`
+    ToggleStep(usize, usize),
+    /// `/gaucho/bpm BPM`
+    SetBpm(u32),
+    /// `/gaucho/play`
+    TogglePlayback,
+    /// `/gaucho/track/volume TRACK VOLUME`
+    SetTrackVolume(usize, f32),
+}
+
+impl OscCommand {
+    /// Map a message to a command; None for unknown addresses or wrong arguments
+    pub fn from_message(message: &OscMessage) -> Option<Self> {
+        let args = &message.args;
+        match (message.address.as_str(), args.len()) {
+            ("/gaucho/step/toggle", 2) => Some(OscCommand::ToggleStep(args[0].as_index()?, args[1].as_index()?)),
+            ("/gaucho/bpm", 1) => {
+                let bpm = args[0].as_f32()?;
+                (bpm >= 1.0).then(|| OscCommand::SetBpm(bpm.round() as u32))
+            },
+            ("/gaucho/play", _) => Some(OscCommand::TogglePlayback),
+            ("/gaucho/track/volume", 2) => Some(OscCommand::SetTrackVolume(args[0].as_index()?, args[1].as_f32()?)),
+            _ => None,
+        }
+    }
+}
+
+/// UDP listener that turns OSC messages into commands for the UI thread
+pub struct OscServer {
+    commands: Receiver<OscCommand>,
+    local_addr: SocketAddr,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl OscServer {
+    /// Listen for OSC on `port` on every interface; port 0 picks a free port
+    pub fn start(port: u16) -> Result<Self, Box<dyn Error>> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        let local_addr = socket.local_addr()?;
+        let (sender, commands) = mpsc::channel();
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread_running = Arc::clone(&running);
+        let thread = thread::Builder::new()
+            .name("osc-listener".to_string())
+            .spawn(move || {
+                let mut buffer = [0u8; MAX_PACKET_SIZE];
+                while thread_running.load(Ordering::SeqCst) {
+                    // Timeouts just give the loop a chance to check the running flag
+                    let Ok((len, from)) = socket.recv_from(&mut buffer) else {
+                        continue;
+                    };
+                    let messages = match decode_packet(&buffer[..len]) {
+                        Ok(messages) => messages,
+                        Err(e) => {
+                            warn!("Ignoring OSC packet from {}: {}", from, e);
+                            continue;
+                        }
+                    };
+                    for message in messages {
+                        match OscCommand::from_message(&message) {
+                            Some(command) => {
+                                if sender.send(command).is_err() {
+                                    return;
+                                }
+                            },
+                            None => debug!("Ignoring OSC message {} {:?}", message.address, message.args),
+                        }
+                    }
+                }
+            })?;
+
+        info!("Listening for OSC on {}", local_addr);
+        Ok(Self { commands, local_addr, running, thread: Some(thread) })
+    }
+
+    /// Address the listener is bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Commands received since the last call, oldest first
+    pub fn drain(&self) -> Vec<OscCommand> {
+        self.commands.try_iter().collect()
+    }
+}
+
+impl Drop for OscServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,97 @@
+// Text commands for scripting the tracker, one per line
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::info;
+use crate::AppState;
+
+/// Parse the argument at `idx` of a command, naming it in the error
+fn arg<T: std::str::FromStr>(args: &[&str], idx: usize, name: &str) -> Result<T, String> {
+    let value = args.get(idx).ok_or_else(|| format!("Missing {}", name))?;
+    value.parse().map_err(|_| format!("Invalid {}: {}", name, value))
+}
+
+/// `count` steps, each on with probability `density`, from a xorshift generator
+pub(crate) fn random_steps(count: usize, density: f32, seed: u64) -> Vec<bool> {
+    let mut state = seed.max(1);
+    (0..count)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            ((state >> 40) as f32 / (1u64 << 24) as f32) < density
+        })
+        .collect()
+}
+
+impl AppState {
+    /// Run one command, returning a status line or an error message. Commands:
+    /// `set_step TRACK STEP true|false`, `set_bpm BPM`, `mute TRACK`, `unmute TRACK`,
+    /// `play`, `stop` and `randomize TRACK DENSITY`.
+    pub fn execute_command(&mut self, cmd: &str) -> Result<String, String> {
+        let words: Vec<&str> = cmd.split_whitespace().collect();
+        let Some((&name, args)) = words.split_first() else {
+            return Err("Empty command".to_string());
+        };
+        let expected = match name {
+            "set_step" => 3,
+            "set_bpm" | "mute" | "unmute" => 1,
+            "randomize" => 2,
+            "play" | "stop" => 0,
+            _ => return Err(format!("Unknown command: {}", name)),
+        };
+        if args.len() != expected {
+            return Err(format!("{} takes {} arguments, got {}", name, expected, args.len()));
+        }
+
+        match name {
+            "set_step" => {
+                let (track_idx, step_idx) = (arg(args, 0, "track")?, arg(args, 1, "step")?);
+                let value: bool = arg(args, 2, "value")?;
+                self.set_step(track_idx, step_idx, value).map_err(|e| e.to_string())?;
+                Ok(format!("Step {}:{} {}", track_idx, step_idx, if value { "on" } else { "off" }))
+            },
+            "set_bpm" => {
+                let bpm: u32 = arg(args, 0, "BPM")?;
+                if bpm == 0 {
+                    return Err("BPM must be at least 1".to_string());
+                }
+                self.set_bpm(bpm);
+                Ok(format!("BPM {}", bpm))
+            },
+            "mute" | "unmute" => {
+                let track_idx = arg(args, 0, "track")?;
+                self.set_track_muted(track_idx, name == "mute").map_err(|e| e.to_string())?;
+                Ok(format!("Track {} {}d", track_idx, name))
+            },
+            "play" | "stop" => {
+                if self.is_playing != (name == "play") {
+                    self.toggle_playback();
+                }
+                Ok(if self.is_playing { "Playing" } else { "Stopped" }.to_string())
+            },
+            _ => {
+                let track_idx = arg(args, 0, "track")?;
+                let density: f32 = arg(args, 1, "density")?;
+                if !(0.0..=1.0).contains(&density) {
+                    return Err(format!("Density must be between 0 and 1, got {}", density));
+                }
+                let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+                let steps = random_steps(self.num_steps(), density, seed);
+                let active = steps.iter().filter(|&&on| on).count();
+                self.bulk_set_pattern(track_idx, steps)?;
+                Ok(format!("Track {} randomized, {} steps on", track_idx, active))
+            },
+        }
+    }
+
+    /// Run every line of a script as a command, in order. Blank lines and
+    /// lines starting with `#` are skipped and get no result.
+    pub fn execute_script(&mut self, script: &str) -> Vec<Result<String, String>> {
+        let results: Vec<Result<String, String>> = script.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| self.execute_command(line))
+            .collect();
+        info!("Ran script: {} commands, {} failed", results.len(), results.iter().filter(|r| r.is_err()).count());
+        results
+    }
+}
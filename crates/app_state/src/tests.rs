@@ -2,9 +2,10 @@ use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::collections::VecDeque;
 
-use crate::AppState;
+use crate::{AppState, AudioMode, PatternRegion};
 use project::model::Track;
 use core::TrackerEvent;
+use sequencer::Subdivision;
 
 // Basic tests
 
@@ -67,16 +68,28 @@ fn create_test_tracks() -> Vec<Track> {
             name: "Kick".to_string(),
             sample: "kick.wav".to_string(),
             volume: 1.0,
+            pan: 0.0,
+            pitch_semitones: 0.0,
+            muted: false,
+            color: None,
         },
         Track {
             name: "Snare".to_string(),
             sample: "snare.wav".to_string(),
             volume: 0.8,
+            pan: 0.0,
+            pitch_semitones: 0.0,
+            muted: false,
+            color: None,
         },
         Track {
             name: "HiHat".to_string(),
             sample: "hihat.wav".to_string(),
             volume: 0.5,
+            pan: 0.0,
+            pitch_semitones: 0.0,
+            muted: false,
+            color: None,
         },
     ]
 }
@@ -188,6 +201,30 @@ fn test_event_subscription_and_emission() {
             TrackerEvent::TrackVolumeChanged(track, volume) => {
                 events_clone.lock().unwrap().push_back(format!("TrackVolumeChanged:{},{:.2}", track, volume));
             },
+            TrackerEvent::BarStarted(bar) => {
+                events_clone.lock().unwrap().push_back(format!("BarStarted:{}", bar));
+            },
+            TrackerEvent::TrackAdded(track) => {
+                events_clone.lock().unwrap().push_back(format!("TrackAdded:{}", track));
+            },
+            TrackerEvent::TrackRemoved(track) => {
+                events_clone.lock().unwrap().push_back(format!("TrackRemoved:{}", track));
+            },
+            TrackerEvent::PatternLoaded(pattern) => {
+                events_clone.lock().unwrap().push_back(format!("PatternLoaded:{}", pattern));
+            },
+            TrackerEvent::ProjectLoaded(name) => {
+                events_clone.lock().unwrap().push_back(format!("ProjectLoaded:{}", name));
+            },
+            TrackerEvent::MasterVolumeChanged(volume) => {
+                events_clone.lock().unwrap().push_back(format!("MasterVolumeChanged:{:.2}", volume));
+            },
+            TrackerEvent::TrackSoloChanged(track, soloed) => {
+                events_clone.lock().unwrap().push_back(format!("TrackSoloChanged:{},{}", track, soloed));
+            },
+            TrackerEvent::SubdivisionChanged(note_value) => {
+                events_clone.lock().unwrap().push_back(format!("SubdivisionChanged:{}", note_value));
+            },
         }
     });
     
@@ -208,6 +245,30 @@ fn test_event_subscription_and_emission() {
     assert_eq!(collected_events[4], "TrackVolumeChanged:1,0.75");
 }
 
+#[test]
+fn test_subscribe_once() {
+    let app = AppState::new(2, 4);
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let fired_clone = fired.clone();
+    
+    app.subscribe_once(move |event| match event {
+        TrackerEvent::StepTriggered(1, step) => {
+            fired_clone.lock().unwrap().push(*step);
+            true
+        },
+        _ => false,
+    });
+    
+    app.emit_event(TrackerEvent::StepTriggered(0, 0));
+    app.emit_event(TrackerEvent::StepTriggered(1, 2));
+    app.emit_event(TrackerEvent::StepTriggered(1, 3));
+    assert_eq!(*fired.lock().unwrap(), vec![2]);
+    
+    let id = app.subscribe_to_events(|_| {});
+    assert!(app.unsubscribe_from_events(id));
+    assert!(!app.unsubscribe_from_events(id));
+}
+
 #[test]
 fn test_volume_changes() {
     let tracks = create_test_tracks();
@@ -310,3 +371,1228 @@ fn test_get_track_info() {
     assert_eq!(app.get_track_volume(2), Some(0.5));
     assert_eq!(app.get_track_volume(3), None);
 }
+
+#[test]
+fn test_selection_copy_paste() {
+    let mut app = AppState::new(3, 8);
+    app.steps[0] = vec![true, false, true, false, false, false, false, false];
+    app.steps[1] = vec![false, true, true, true, false, false, false, false];
+
+    // Select a 2x4 region starting at the top-left corner
+    app.enter_selection_mode();
+    app.move_cursor_down();
+    for _ in 0..3 {
+        app.move_cursor_right();
+    }
+    assert!(app.is_selected(1, 3));
+    assert!(!app.is_selected(2, 0));
+
+    app.copy_selection();
+    assert!(!app.selection_mode);
+    assert_eq!(app.clipboard.as_ref().map(|c| (c.len(), c[0].len())), Some((2, 4)));
+
+    // Paste two steps to the right of the original region
+    app.selected_track = 0;
+    app.selected_step = 2;
+    app.paste_clipboard();
+
+    assert_eq!(app.steps[0], vec![true, false, true, false, true, false, false, false]);
+    assert_eq!(app.steps[1], vec![false, true, false, true, true, true, false, false]);
+    assert_eq!(app.steps[2], vec![false; 8]);
+}
+
+#[test]
+fn test_copy_region_to_clipboard_and_paste() {
+    let mut app = AppState::new(3, 8);
+    app.steps[0] = vec![true, false, true, false, false, false, false, false];
+    app.steps[1] = vec![false, true, true, true, false, false, false, false];
+
+    let region = PatternRegion { start_track: 0, end_track: 1, start_step: 0, end_step: 3 };
+    app.copy_steps_to_clipboard(region).unwrap();
+    let (copied, steps) = app.steps_clipboard.clone().unwrap();
+    assert_eq!(copied, region);
+    assert_eq!(steps, vec![vec![true, false, true, false], vec![false, true, true, true]]);
+
+    // Paste one track down and four steps over; the bottom row falls off the pattern
+    app.selected_track = 1;
+    app.selected_step = 4;
+    app.paste_steps_from_clipboard(app.selected_track, app.selected_step);
+
+    assert_eq!(app.steps[1], vec![false, true, true, true, true, false, true, false]);
+    assert_eq!(app.steps[2], vec![false, false, false, false, false, true, true, true]);
+    assert!(app.undo());
+    assert_eq!(app.steps[2], vec![false; 8]);
+
+    assert!(app.copy_steps_to_clipboard(PatternRegion { start_track: 0, end_track: 3, start_step: 0, end_step: 1 }).is_err());
+    assert!(app.copy_steps_to_clipboard(PatternRegion { start_track: 0, end_track: 0, start_step: 4, end_step: 2 }).is_err());
+}
+
+#[test]
+fn test_paste_clips_to_pattern_bounds() {
+    let mut app = AppState::new(2, 4);
+    app.clipboard = Some(vec![vec![true, true, true], vec![true, true, true]]);
+
+    app.selected_track = 1;
+    app.selected_step = 2;
+    app.paste_clipboard();
+
+    assert_eq!(app.steps[0], vec![false; 4]);
+    assert_eq!(app.steps[1], vec![false, false, true, true]);
+}
+
+#[test]
+fn test_set_track_sample() {
+    let tracks = create_test_tracks();
+    let mut app = AppState::new(3, 16).with_tracks(tracks);
+
+    app.set_track_sample(1, "snares/rimshot.wav").unwrap();
+    assert_eq!(app.get_track_sample(1), Some("snares/rimshot.wav"));
+    assert_eq!(app.get_track_sample(0), Some("kick.wav"));
+
+    assert!(app.set_track_sample(5, "kick.wav").is_err());
+}
+
+#[test]
+fn test_invert_and_undo() {
+    let mut app = AppState::new(2, 4);
+    app.steps[0] = vec![true, false, false, true];
+
+    let changes = Arc::new(Mutex::new(0));
+    let changes_clone = changes.clone();
+    app.subscribe_to_events(move |event| {
+        if let TrackerEvent::PatternChanged = event {
+            *changes_clone.lock().unwrap() += 1;
+        }
+    });
+
+    app.invert_track(0);
+    assert_eq!(app.steps[0], vec![false, true, true, false]);
+    assert_eq!(app.steps[1], vec![false; 4]);
+
+    app.invert_pattern();
+    assert_eq!(app.steps[0], vec![true, false, false, true]);
+    assert_eq!(app.steps[1], vec![true; 4]);
+    assert_eq!(*changes.lock().unwrap(), 2);
+    assert_eq!(app.undo_stack.len(), 2);
+
+    assert!(app.undo());
+    assert_eq!(app.steps[0], vec![false, true, true, false]);
+    assert_eq!(app.steps[1], vec![false; 4]);
+    assert!(app.undo());
+    assert_eq!(app.steps[0], vec![true, false, false, true]);
+    assert!(!app.undo());
+}
+
+#[test]
+fn test_undo_cursor_toggle_and_paste() {
+    let mut app = AppState::new(2, 4);
+    app.steps[0] = vec![true, false, false, true];
+
+    app.invert_track(0);
+    app.selected_track = 0;
+    app.selected_step = 0;
+    app.toggle_step();
+    assert_eq!(app.steps[0], vec![true, true, true, false]);
+
+    // Undoing the toggle keeps the inversion before it
+    assert!(app.undo());
+    assert_eq!(app.steps[0], vec![false, true, true, false]);
+
+    app.clipboard = Some(vec![vec![true, true]]);
+    app.selected_track = 1;
+    app.selected_step = 2;
+    app.paste_clipboard();
+    assert_eq!(app.steps[1], vec![false, false, true, true]);
+    assert!(app.undo());
+    assert_eq!(app.steps[1], vec![false; 4]);
+
+    assert!(app.undo());
+    assert_eq!(app.steps[0], vec![true, false, false, true]);
+    assert!(!app.undo());
+}
+
+#[test]
+fn test_add_and_remove_track() {
+    let mut app = AppState::new(3, 8).with_tracks(create_test_tracks());
+    app.steps[2][1] = true;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events_clone = events.clone();
+    app.subscribe_to_events(move |event| {
+        match event {
+            TrackerEvent::TrackAdded(idx) => events_clone.lock().unwrap().push(format!("added:{}", idx)),
+            TrackerEvent::TrackRemoved(idx) => events_clone.lock().unwrap().push(format!("removed:{}", idx)),
+            _ => {}
+        }
+    });
+
+    let idx = app.add_track(Track {
+        name: "Clap".to_string(),
+        sample: "clap.wav".to_string(),
+        volume: 0.7,
+        pan: 0.0,
+        pitch_semitones: 0.0,
+        muted: false,
+        color: None,
+    }).unwrap();
+    assert_eq!(idx, 3);
+    assert_eq!(app.num_tracks(), 4);
+    assert_eq!(app.steps[3], vec![false; 8]);
+    assert_eq!(app.velocities[3], vec![1.0; 8]);
+    assert_eq!(app.probabilities[3], vec![1.0; 8]);
+    assert_eq!(app.track_names[3], "Clap");
+    assert_eq!(app.get_track_sample(3), Some("clap.wav"));
+
+    app.selected_track = 3;
+    app.remove_track(0).unwrap();
+    assert_eq!(app.num_tracks(), 3);
+    assert_eq!(app.velocities.len(), 3);
+    assert_eq!(app.probabilities.len(), 3);
+    assert_eq!(app.track_names, vec!["Snare", "HiHat", "Clap"]);
+    assert!(app.steps[1][1]);
+    assert_eq!(app.get_track_volume(2), Some(0.7));
+    assert_eq!(app.selected_track, 2);
+
+    assert!(app.remove_track(3).is_err());
+    assert_eq!(*events.lock().unwrap(), vec!["added:3", "removed:0"]);
+}
+
+#[test]
+fn test_last_track_cannot_be_removed() {
+    let mut app = AppState::new(2, 4);
+    app.initialize_sequencer(false).unwrap();
+    app.remove_track(1).unwrap();
+    assert!(app.remove_track(0).is_err());
+    assert_eq!(app.num_tracks(), 1);
+    assert_eq!(app.velocities.len(), 1);
+    assert!(app.sequencer.as_ref().unwrap().check_thread().is_ok());
+}
+
+#[test]
+fn test_auto_populate_tracks() {
+    let dir = tempfile::tempdir().unwrap();
+    let drums = dir.path().join("drums");
+    std::fs::create_dir_all(&drums).unwrap();
+    for file in ["tom.wav", "cowbell.wav", "rim.wav"] {
+        std::fs::write(drums.join(file), b"").unwrap();
+    }
+
+    let mut app = AppState::new(3, 8).with_tracks(create_test_tracks()).with_sample_dir(dir.path());
+    assert_eq!(app.auto_populate_tracks(&drums).unwrap(), 3);
+    assert_eq!(app.track_names, vec!["cowbell", "rim", "tom"]);
+    assert_eq!(app.get_track_sample(0), Some("drums/cowbell.wav"));
+    assert_eq!(app.num_steps(), 8);
+    assert!(app.steps.iter().flatten().all(|&on| !on));
+}
+
+#[test]
+fn test_pattern_json_round_trip() {
+    let mut app = AppState::new(2, 4);
+    app.steps[0][1] = true;
+    app.steps[1][3] = true;
+    let json = app.get_pattern_as_json().unwrap();
+    assert!(!json.contains('\n'));
+
+    let mut other = AppState::new(2, 4);
+    let changed = Arc::new(Mutex::new(0));
+    let changed_clone = changed.clone();
+    other.subscribe_to_events(move |event| {
+        if let TrackerEvent::PatternChanged = event {
+            *changed_clone.lock().unwrap() += 1;
+        }
+    });
+    other.set_pattern_from_json(&json).unwrap();
+    assert_eq!(other.steps, app.steps);
+    assert_eq!(*changed.lock().unwrap(), 1);
+    assert!(other.undo());
+    assert!(other.steps.iter().flatten().all(|&on| !on));
+
+    assert!(AppState::new(3, 4).set_pattern_from_json(&json).is_err());
+    assert!(AppState::new(2, 8).set_pattern_from_json(&json).is_err());
+    assert!(other.set_pattern_from_json("not json").is_err());
+    assert!(other.undo_stack.is_empty());
+}
+
+#[test]
+fn test_set_step_count() {
+    let mut app = AppState::new(2, 16);
+    app.steps[0][3] = true;
+    app.steps[0][12] = true;
+    app.steps[1][9] = true;
+    app.selected_step = 15;
+
+    assert!(app.set_step_count(12).is_err());
+    assert!(app.set_step_count(256).is_err());
+    assert!(app.set_step_count(0).is_err());
+
+    app.set_step_count(8).unwrap();
+    assert_eq!(app.num_steps(), 8);
+    assert_eq!(app.velocities[0].len(), 8);
+    assert_eq!(app.probabilities[1].len(), 8);
+    assert!(app.steps[0][3]);
+    assert_eq!(app.selected_step, 7);
+
+    // Growing again brings back the steps that were cut off
+    app.set_step_count(32).unwrap();
+    assert_eq!(app.num_steps(), 32);
+    assert!(app.steps[0][12]);
+    assert!(app.steps[1][9]);
+    assert_eq!(app.steps[0][16..], vec![false; 16]);
+    assert_eq!(app.velocities[1], vec![1.0; 32]);
+
+    assert!(app.undo());
+    assert_eq!(app.num_steps(), 8);
+    assert_eq!(app.velocities[0].len(), 8);
+}
+
+#[test]
+fn test_playback_progress() {
+    let mut app = AppState::new(1, 16);
+    assert_eq!(app.get_playback_progress(), 0.0);
+
+    app.current_step = 5;
+    assert_eq!(app.get_playback_progress(), 5.0);
+
+    app.initialize_sequencer(false).unwrap();
+    app.toggle_playback();
+    std::thread::sleep(std::time::Duration::from_millis(30));
+    app.process_sequencer_events();
+    let progress = app.get_playback_progress();
+    assert!(progress >= app.current_step as f32 && progress < app.current_step as f32 + 1.0);
+    app.toggle_playback();
+}
+
+#[test]
+fn test_headless_audio_mode_logs_triggers() {
+    let mut app = AppState::new(3, 16)
+        .with_tracks(create_test_tracks())
+        .with_audio_mode(AudioMode::Headless);
+    app.initialize_sequencer(true).unwrap();
+
+    assert!(app.audio.is_none());
+    let headless = app.headless_audio.as_ref().expect("headless connector");
+    assert!(headless.is_active());
+
+    app.test_track_sound(1).unwrap();
+    let triggers = app.headless_audio.as_ref().unwrap().triggers();
+    assert_eq!(triggers.len(), 1);
+    assert_eq!(triggers[0].track_idx, 1);
+
+    app.cleanup_audio();
+    assert!(app.headless_audio.is_none());
+}
+
+#[test]
+fn test_headless_audio_does_not_leak_listeners_on_edit() {
+    let mut app = AppState::new(3, 16)
+        .with_tracks(create_test_tracks())
+        .with_audio_mode(AudioMode::Headless);
+    app.initialize_sequencer(true).unwrap();
+    let listeners = app.event_bus.listener_count();
+
+    for step in 0..10 {
+        app.toggle_step_at(0, step).unwrap();
+    }
+    assert_eq!(app.event_bus.listener_count(), listeners);
+}
+
+#[test]
+fn test_remove_track_leaves_state_alone_when_audio_fails() {
+    let mut app = AppState::new(3, 16)
+        .with_tracks(create_test_tracks())
+        .with_audio_mode(AudioMode::Headless);
+    app.initialize_sequencer(true).unwrap();
+    // Out of step with the app, so removing the last track fails on the audio side
+    app.headless_audio.as_ref().unwrap().remove_track(2).unwrap();
+
+    assert!(app.remove_track(2).is_err());
+    assert_eq!(app.steps.len(), 3);
+    assert_eq!(app.tracks.len(), 3);
+    assert_eq!(app.track_names.len(), 3);
+}
+
+#[test]
+fn test_disabled_audio_mode() {
+    let mut app = AppState::new(3, 16)
+        .with_tracks(create_test_tracks())
+        .with_audio_mode(AudioMode::Disabled);
+    app.initialize_sequencer(true).unwrap();
+
+    assert!(app.sequencer.is_some());
+    assert!(app.audio.is_none());
+    assert!(app.headless_audio.is_none());
+    assert!(app.test_track_sound(0).is_err());
+}
+
+#[test]
+fn test_step_lengths_follow_pattern_shape() {
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    assert!(app.step_lengths.iter().all(|row| row.len() == 16 && row.iter().all(|&l| l == 1.0)));
+
+    app.set_step_length(1, 4, 2.0).unwrap();
+    assert_eq!(app.step_lengths[1][4], 2.0);
+    assert!(app.set_step_length(1, 4, 0.0).is_err());
+    assert!(app.set_step_length(5, 0, 0.5).is_err());
+    assert!(app.set_step_length(0, 16, 0.5).is_err());
+
+    app.set_step_count(32).unwrap();
+    assert_eq!(app.step_lengths[1].len(), 32);
+    assert_eq!(app.step_lengths[1][4], 2.0);
+}
+
+#[test]
+fn test_step_retriggers_follow_tracks() {
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    assert!(app.step_retrigger_count.iter().all(|row| row.len() == 16 && row.iter().all(|&c| c == 1)));
+
+    app.set_step_retrigger(2, 4, 3, 20.0).unwrap();
+    assert_eq!((app.step_retrigger_count[2][4], app.step_retrigger_interval_ms[2][4]), (3, 20.0));
+    assert!(app.set_step_retrigger(2, 4, 0, 20.0).is_err());
+    assert!(app.set_step_retrigger(2, 4, 2, -1.0).is_err());
+    assert!(app.set_step_retrigger(5, 0, 2, 0.0).is_err());
+    assert!(app.set_step_retrigger(0, 16, 2, 0.0).is_err());
+
+    app.remove_track(0).unwrap();
+    assert_eq!(app.step_retrigger_count[1][4], 3);
+    app.set_step_count(32).unwrap();
+    assert_eq!(app.step_retrigger_count[1].len(), 32);
+    assert_eq!(app.step_retrigger_interval_ms[1][4], 20.0);
+}
+
+#[test]
+fn test_subdivision_resamples_pattern() {
+    let mut app = AppState::new(2, 8).with_tracks(create_test_tracks());
+    app.steps[0] = vec![true, false, true, true, false, false, false, true];
+    app.set_step_length(0, 2, 2.0).unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events_clone = Arc::clone(&events);
+    app.subscribe_to_events(move |event| {
+        if let TrackerEvent::SubdivisionChanged(note_value) = event {
+            events_clone.lock().unwrap().push(*note_value);
+        }
+    });
+
+    app.set_subdivision(Subdivision::ThirtySecond).unwrap();
+    assert_eq!(app.num_steps(), 16);
+    assert_eq!(app.steps[0], vec![
+        true, false, false, false, true, false, true, false,
+        false, false, false, false, false, false, true, false,
+    ]);
+    assert_eq!(app.step_lengths[0][4], 2.0);
+    assert_eq!(app.step_lengths[0][5], 1.0);
+
+    // Back to 8ths: only steps on the coarser grid survive
+    app.set_subdivision(Subdivision::Eighth).unwrap();
+    assert_eq!(app.num_steps(), 4);
+    assert_eq!(app.steps[0], vec![true, true, false, false]);
+    assert_eq!(app.subdivision, Subdivision::Eighth);
+    assert_eq!(*events.lock().unwrap(), vec![32, 8]);
+}
+
+#[test]
+fn test_effects_survive_project_save_and_load() {
+    use audio::SampleEffect;
+    use project::model::Project;
+
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    app.effects.insert(0, vec![SampleEffect::Reverse, SampleEffect::FadeIn(0.5)]);
+    let project = Project {
+        name: "Effects".to_string(),
+        version: "1.0".to_string(),
+        bpm: 120,
+        swing: 0.0,
+        author: "test".to_string(),
+        created: "2025-01-01T00:00:00Z".to_string(),
+        auto_start: None,
+        groove_template: None,
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    app.save_project(dir.path(), &project).unwrap();
+
+    let mut reloaded = AppState::new(3, 16).with_tracks(create_test_tracks());
+    reloaded.load_effects(dir.path()).unwrap();
+    assert_eq!(reloaded.effects.get(&0), Some(&vec![SampleEffect::Reverse, SampleEffect::FadeIn(0.5)]));
+    assert!(project::load_project(dir.path()).is_ok());
+
+    reloaded.remove_track(0).unwrap();
+    assert!(reloaded.effects.is_empty());
+}
+
+#[test]
+fn test_track_pan_and_pitch() {
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    assert_eq!(app.get_track_pan(0), Some(0.0));
+    assert_eq!(app.get_track_pitch(0), Some(0.0));
+
+    app.set_track_pan(0, -0.5).unwrap();
+    app.set_track_pan(1, 3.0).unwrap();
+    app.set_track_pitch(2, -30.0).unwrap();
+    assert_eq!(app.get_track_pan(0), Some(-0.5));
+    assert_eq!(app.get_track_pan(1), Some(1.0));
+    assert_eq!(app.get_track_pitch(2), Some(-24.0));
+
+    assert!(app.set_track_pan(3, 0.0).is_err());
+    assert!(app.set_track_pitch(3, 0.0).is_err());
+}
+
+#[test]
+fn test_track_tuning() {
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    assert_eq!(app.get_track_tuning(0), Some(0.0));
+
+    // Tuning is kept apart from the track pitch
+    app.set_track_pitch(1, 5.0).unwrap();
+    app.set_track_tuning(1, 2.0).unwrap();
+    app.set_track_tuning(2, 40.0).unwrap();
+    assert_eq!(app.get_track_tuning(1), Some(2.0));
+    assert_eq!(app.get_track_pitch(1), Some(5.0));
+    assert_eq!(app.get_track_tuning(2), Some(24.0));
+    assert!(app.set_track_tuning(3, 1.0).is_err());
+
+    app.remove_track(0).unwrap();
+    assert_eq!(app.get_track_tuning(0), Some(2.0));
+    let track = create_test_tracks().remove(0);
+    let idx = app.add_track(track).unwrap();
+    assert_eq!(app.get_track_tuning(idx), Some(0.0));
+}
+
+#[test]
+fn test_mute_persists_through_save_and_load() {
+    use project::model::Project;
+
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    app.steps[1][0] = true;
+    app.initialize_sequencer(false).unwrap();
+    app.toggle_track_mute(1).unwrap();
+    assert!(app.is_track_muted(1));
+    assert!(!app.is_track_muted(0));
+    assert!(app.set_track_muted(7, true).is_err());
+    // Muted steps stay in the pattern but the sequencer doesn't play them
+    assert!(app.steps[1][0]);
+    assert!(app.sequencer.as_ref().unwrap().get_pattern()[1][0]);
+    assert!(app.sequencer.as_ref().unwrap().is_track_muted(1));
+
+    let project = Project {
+        name: "Mutes".to_string(),
+        version: "1.0".to_string(),
+        bpm: 120,
+        swing: 0.0,
+        author: "test".to_string(),
+        created: "2025-01-01T00:00:00Z".to_string(),
+        auto_start: None,
+        groove_template: None,
+    };
+    let dir = tempfile::tempdir().unwrap();
+    app.save_project(dir.path(), &project).unwrap();
+
+    let (_, tracks, _, _) = project::load_project(dir.path()).unwrap();
+    let reloaded = AppState::new(3, 16).with_tracks(tracks);
+    assert!(reloaded.is_track_muted(1));
+    assert!(!reloaded.is_track_muted(0));
+    assert!(!reloaded.is_track_muted(2));
+}
+
+#[test]
+fn test_tracks_without_muted_field_load_unmuted() {
+    let tracks = Track::from_json_array(
+        r#"[{ "name": "Kick", "sample": "kick.wav", "volume": 1.0 }, { "name": "Snare", "sample": "snare.wav", "volume": 0.8 }]"#,
+    ).unwrap();
+    let app = AppState::new(2, 16).with_tracks(tracks);
+    assert!(!app.is_track_muted(0));
+    assert!(!app.is_track_muted(1));
+}
+
+#[test]
+fn test_osc_remote_control() {
+    use crate::osc::{OscArg, OscMessage};
+    use std::net::UdpSocket;
+    use std::time::{Duration, Instant};
+
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    app.enable_osc(0).unwrap();
+    let port = app.osc_port().unwrap();
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let messages = [
+        OscMessage::new("/gaucho/step/toggle", vec![OscArg::Int(1), OscArg::Int(4)]),
+        OscMessage::new("/gaucho/bpm", vec![OscArg::Float(140.0)]),
+        OscMessage::new("/gaucho/track/volume", vec![OscArg::Int(2), OscArg::Float(0.25)]),
+        OscMessage::new("/gaucho/unknown", vec![]),
+        OscMessage::new("/gaucho/play", vec![]),
+    ];
+    for message in &messages {
+        socket.send_to(&message.encode(), ("127.0.0.1", port)).unwrap();
+    }
+
+    // The unknown address is dropped by the listener, so four commands arrive
+    let deadline = Instant::now() + Duration::from_secs(2);
+    let mut applied = 0;
+    while applied < 4 && Instant::now() < deadline {
+        applied += app.process_osc_messages();
+        std::thread::sleep(Duration::from_millis(5));
+    }
+
+    assert_eq!(applied, 4);
+    assert!(app.steps[1][4]);
+    assert_eq!(app.bpm, 140);
+    assert_eq!(app.get_track_volume(2), Some(0.25));
+    assert!(app.is_playing);
+}
+
+#[test]
+fn test_osc_packet_decoding() {
+    use crate::osc::{decode_packet, OscArg, OscCommand, OscMessage};
+
+    let message = OscMessage::new("/gaucho/step/toggle", vec![OscArg::Int(0), OscArg::String("x".to_string())]);
+    assert_eq!(decode_packet(&message.encode()).unwrap(), vec![message]);
+
+    // A bundle carrying two messages
+    let first = OscMessage::new("/gaucho/play", vec![]).encode();
+    let second = OscMessage::new("/gaucho/bpm", vec![OscArg::Int(90)]).encode();
+    let mut bundle = b"#bundle\0".to_vec();
+    bundle.extend_from_slice(&1u64.to_be_bytes());
+    for element in [&first, &second] {
+        bundle.extend_from_slice(&(element.len() as u32).to_be_bytes());
+        bundle.extend_from_slice(element);
+    }
+    let decoded = decode_packet(&bundle).unwrap();
+    let commands: Vec<_> = decoded.iter().filter_map(OscCommand::from_message).collect();
+    assert_eq!(commands, vec![OscCommand::TogglePlayback, OscCommand::SetBpm(90)]);
+
+    assert!(decode_packet(b"nope").is_err());
+    let bad_step = OscMessage::new("/gaucho/step/toggle", vec![OscArg::Int(-1), OscArg::Int(0)]);
+    assert_eq!(OscCommand::from_message(&bad_step), None);
+}
+
+#[test]
+fn test_live_pattern_follows_edits() {
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    assert_eq!(app.get_live_pattern(), None);
+
+    app.initialize_sequencer(false).unwrap();
+    app.toggle_step_at(2, 5).unwrap();
+    let live = app.get_live_pattern().unwrap();
+    assert!(live[2][5]);
+    assert_eq!(live, app.steps);
+
+    // Mutes don't change the pattern the sequencer holds
+    app.toggle_track_mute(2).unwrap();
+    assert!(app.get_live_pattern().unwrap()[2][5]);
+    assert!(app.sequencer.as_ref().unwrap().is_track_muted(2));
+}
+
+#[test]
+fn test_clone_pattern_copies_independently() {
+    let mut app = AppState::new(3, 8).with_tracks(create_test_tracks());
+    app.steps[0][0] = true;
+    app.steps[1][4] = true;
+
+    let loaded = Arc::new(Mutex::new(Vec::new()));
+    let loaded_clone = Arc::clone(&loaded);
+    app.subscribe_to_events(move |event| {
+        if let TrackerEvent::PatternLoaded(idx) = event {
+            loaded_clone.lock().unwrap().push(*idx);
+        }
+    });
+
+    // Cloning past the end allocates the slots in between
+    app.clone_pattern(0, 2).unwrap();
+    assert_eq!(app.pattern_count(), 3);
+    assert_eq!(app.pattern(1).unwrap().steps, vec![vec![false; 8]; 3]);
+    assert_eq!(app.pattern(2).unwrap().steps, app.steps);
+    assert_eq!(*loaded.lock().unwrap(), vec![2]);
+
+    // Editing the clone leaves the original alone
+    app.select_pattern(2).unwrap();
+    app.toggle_step_at(2, 7).unwrap();
+    app.toggle_step_at(0, 0).unwrap();
+    let original = app.pattern(0).unwrap();
+    assert!(original.steps[0][0]);
+    assert!(!original.steps[2][7]);
+    assert!(app.pattern(2).unwrap().steps[2][7]);
+
+    app.select_pattern(0).unwrap();
+    assert!(app.steps[0][0]);
+    assert_eq!(app.current_pattern(), 0);
+
+    assert_eq!(app.clone_pattern(9, 1), Err("Source pattern does not exist"));
+    assert!(app.clone_pattern(0, u32::MAX).is_err());
+    assert_eq!(app.select_pattern(7), Err("Pattern does not exist"));
+}
+
+#[test]
+fn test_new_empty_pattern_and_track_changes() {
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    let id = app.new_empty_pattern(8);
+    assert_eq!(id, 1);
+    let pattern = app.pattern(id).unwrap();
+    assert_eq!(pattern.length, 8);
+    assert_eq!(pattern.steps, vec![vec![false; 8]; 3]);
+
+    // Stored patterns gain and lose rows with the tracks
+    app.remove_track(0).unwrap();
+    assert_eq!(app.pattern(id).unwrap().steps.len(), 2);
+}
+
+#[test]
+fn test_export_pattern_as_ascii_art() {
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    for step in [0, 4, 8, 12] {
+        app.steps[0][step] = true;
+    }
+    app.steps[1][4] = true;
+    app.steps[1][12] = true;
+    app.selected_track = 1;
+    app.selected_step = 12;
+
+    let expected = "\
+Kick       |X...|X...|X...|X...|
+Snare      |....|X...|....|x...|
+HiHat      |....|....|....|....|
+";
+    assert_eq!(app.export_pattern_as_ascii_art(), expected);
+
+    // An inactive selected step stays a dot
+    app.selected_track = 2;
+    assert!(app.export_pattern_as_ascii_art().contains("Snare      |....|X...|....|X...|"));
+}
+
+#[test]
+fn test_swap_tracks() {
+    use audio::SampleEffect;
+
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    app.initialize_sequencer(false).unwrap();
+    app.toggle_step_at(0, 0).unwrap();
+    app.toggle_step_at(1, 4).unwrap();
+    app.set_track_muted(1, true).unwrap();
+    app.effects.insert(0, vec![SampleEffect::Reverse]);
+
+    let before = app.steps.clone();
+    app.swap_tracks(0, 1).unwrap();
+    assert_eq!(app.steps[0], before[1]);
+    assert_eq!(app.steps[1], before[0]);
+    assert_eq!(app.track_names[..2], ["Snare".to_string(), "Kick".to_string()]);
+    assert_eq!(app.get_track_volume(0), Some(0.8));
+    assert!(app.is_track_muted(0));
+    assert!(!app.is_track_muted(1));
+    assert_eq!(app.effects.get(&1), Some(&vec![SampleEffect::Reverse]));
+    assert!(!app.effects.contains_key(&0));
+
+    // The sequencer plays the new order
+    let sequencer = app.sequencer.as_ref().unwrap();
+    assert_eq!(app.get_live_pattern().unwrap(), app.steps);
+    assert!(sequencer.is_track_muted(0));
+
+    // Undo puts the tracks back, then reverts the edit made before the swap
+    let before_invert = app.steps.clone();
+    app.invert_track(2);
+    let inverted = app.steps.clone();
+    app.swap_tracks(0, 2).unwrap();
+    assert_eq!(app.track_names[0], "HiHat");
+    assert!(app.undo());
+    assert_eq!(app.steps, inverted);
+    assert_eq!(app.track_names, vec!["Snare", "Kick", "HiHat"]);
+    assert!(app.is_track_muted(0));
+    assert_eq!(app.get_live_pattern().unwrap(), app.steps);
+    assert!(app.undo());
+    assert_eq!(app.steps, before_invert);
+
+    assert!(app.swap_tracks(0, 3).is_err());
+}
+
+#[test]
+fn test_move_track_to() {
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    app.toggle_step_at(0, 0).unwrap();
+    app.selected_track = 0;
+
+    app.move_track_to(0, 2).unwrap();
+    assert_eq!(app.track_names, vec!["Snare", "HiHat", "Kick"]);
+    assert!(app.steps[2][0]);
+    assert_eq!(app.selected_track, 2);
+
+    app.move_track_to(2, 0).unwrap();
+    assert_eq!(app.track_names, vec!["Kick", "Snare", "HiHat"]);
+    assert!(app.steps[0][0]);
+
+    app.move_track_to(0, 1).unwrap();
+    assert_eq!(app.track_names, vec!["Snare", "Kick", "HiHat"]);
+    assert!(app.undo());
+    assert_eq!(app.track_names, vec!["Kick", "Snare", "HiHat"]);
+    assert!(app.steps[0][0]);
+    assert!(app.move_track_to(0, 3).is_err());
+}
+
+#[test]
+fn test_auto_start_plays_after_sequencer_init() {
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks()).with_auto_start(true);
+    assert!(!app.is_playing);
+    app.initialize_sequencer(false).unwrap();
+    assert!(app.is_playing);
+
+    // Later sequencer rebuilds don't restart stopped playback
+    app.toggle_playback();
+    app.toggle_step_at(0, 0).unwrap();
+    assert!(!app.is_playing);
+
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    app.initialize_sequencer(false).unwrap();
+    assert!(!app.is_playing);
+}
+
+#[test]
+fn test_track_info_matches_individual_getters() {
+    use audio::SampleEffect;
+
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    app.set_track_pan(1, -0.5).unwrap();
+    app.set_track_tuning(1, 3.0).unwrap();
+    app.set_track_muted(1, true).unwrap();
+    app.effects.insert(1, vec![SampleEffect::Reverse, SampleEffect::FadeIn(0.1)]);
+
+    let info = app.get_track_info(1).unwrap();
+    assert_eq!(info.name, app.track_names[1]);
+    assert_eq!(info.sample, app.get_track_sample(1).unwrap());
+    assert_eq!(info.volume, app.get_track_volume(1).unwrap());
+    assert_eq!(info.muted, app.is_track_muted(1));
+    assert!(!info.soloed);
+    assert_eq!(info.tuning_semitones, app.get_track_tuning(1).unwrap());
+    assert_eq!(info.pan, app.get_track_pan(1).unwrap());
+    assert_eq!(info.effect_count, 2);
+
+    let all = app.get_all_track_info();
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[1], info);
+    assert_eq!(all[0].effect_count, 0);
+    assert!(app.get_track_info(3).is_none());
+}
+
+#[test]
+fn test_groove_template_replaces_swing() {
+    use project::model::Project;
+
+    let mut app = AppState::new(3, 4).with_tracks(create_test_tracks());
+    app.swing = 0.5;
+    assert_eq!(app.compute_groove_template(), vec![0.0, 0.25, 0.0, 0.25]);
+
+    app.initialize_sequencer(false).unwrap();
+    app.apply_groove_template(vec![0.1, 0.0, -0.1, 0.3]);
+    assert_eq!(app.compute_groove_template(), vec![0.1, 0.0, -0.1, 0.3]);
+    assert_eq!(app.sequencer.as_ref().unwrap().get_groove_template(), vec![0.1, 0.0, -0.1, 0.3]);
+
+    // The template is saved with the project
+    let project = Project {
+        name: "Groove".to_string(),
+        version: "1.0".to_string(),
+        bpm: 120,
+        swing: 0.5,
+        author: "test".to_string(),
+        created: "2025-01-01T00:00:00Z".to_string(),
+        auto_start: None,
+        groove_template: None,
+    };
+    let dir = tempfile::tempdir().unwrap();
+    app.save_project(dir.path(), &project).unwrap();
+    let (saved, _, _, _) = project::load_project(dir.path()).unwrap();
+    assert_eq!(saved.groove_template, Some(vec![0.1, 0.0, -0.1, 0.3]));
+
+    app.apply_groove_template(Vec::new());
+    assert_eq!(app.compute_groove_template(), vec![0.0, 0.25, 0.0, 0.25]);
+    assert!(app.sequencer.as_ref().unwrap().get_groove_template().is_empty());
+}
+
+#[test]
+fn test_step_gate_follows_required_track() {
+    let mut app = AppState::new(3, 4).with_tracks(create_test_tracks());
+    app.toggle_step_at(0, 0).unwrap();
+    app.toggle_step_at(0, 2).unwrap();
+    for step in 0..4 {
+        app.toggle_step_at(1, step).unwrap();
+        app.set_step_gate(1, step, 0, 0).unwrap();
+    }
+    assert_eq!(app.get_step_gate(1, 1), Some((0, 0)));
+    assert!(app.set_step_gate(3, 0, 0, 0).is_err());
+    assert!(app.set_step_gate(1, 4, 0, 0).is_err());
+
+    app.initialize_sequencer(false).unwrap();
+    let sequencer = app.sequencer.as_ref().unwrap();
+    sequencer.enable_external_clock_mode();
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let fired_clone = Arc::clone(&fired);
+    sequencer.add_listener(Box::new(move |event| {
+        if event.track_idx == 1 {
+            fired_clone.lock().unwrap().push(event.step_idx);
+        }
+    }));
+    sequencer.start();
+    for _ in 0..4 * sequencer::MIDI_CLOCKS_PER_STEP {
+        sequencer.external_clock_tick();
+    }
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    assert_eq!(*fired.lock().unwrap(), vec![0, 2]);
+
+    // Gates move with their tracks
+    app.swap_tracks(0, 2).unwrap();
+    assert_eq!(app.get_step_gate(1, 1), Some((2, 0)));
+    app.clear_step_gate(1, 1);
+    assert_eq!(app.get_step_gate(1, 1), None);
+    app.remove_track(2).unwrap();
+    assert_eq!(app.get_step_gate(1, 0), None);
+}
+
+#[test]
+fn test_step_hit_counts_follow_triggers() {
+    let mut app = AppState::new(3, 4).with_tracks(create_test_tracks());
+    app.emit_event(TrackerEvent::StepTriggered(0, 1));
+    app.emit_event(TrackerEvent::StepTriggered(0, 1));
+    app.emit_event(TrackerEvent::StepTriggered(2, 3));
+    assert_eq!(app.step_hit_counts(), vec![vec![0, 2, 0, 0], vec![0; 4], vec![0, 0, 0, 1]]);
+
+    app.swap_tracks(0, 1).unwrap();
+    assert_eq!(app.step_hit_counts()[1], vec![0, 2, 0, 0]);
+    app.remove_track(0).unwrap();
+    assert_eq!(app.step_hit_counts(), vec![vec![0, 2, 0, 0], vec![0, 0, 0, 1]]);
+
+    app.reset_step_hit_counts();
+    assert_eq!(app.step_hit_counts(), vec![vec![0; 4]; 2]);
+}
+
+#[test]
+fn test_add_effect_to_track() {
+    use audio::SampleEffect;
+
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    app.add_effect_to_track(2, SampleEffect::Reverse).unwrap();
+    app.add_effect_to_track(2, SampleEffect::FadeOut(0.2)).unwrap();
+    assert_eq!(app.effects.get(&2), Some(&vec![SampleEffect::Reverse, SampleEffect::FadeOut(0.2)]));
+    assert_eq!(app.get_track_info(2).unwrap().effect_count, 2);
+    assert!(app.add_effect_to_track(3, SampleEffect::Reverse).is_err());
+}
+
+#[test]
+fn test_load_project_from_path_replaces_state() {
+    use project::model::Project;
+
+    // Save a two-track, 8-step project to load later
+    let mut source = AppState::new(2, 8).with_tracks(create_test_tracks()[..2].to_vec());
+    source.track_names = vec!["kick".to_string(), "snare".to_string()];
+    source.toggle_step_at(1, 3).unwrap();
+    let project = Project {
+        name: "Other".to_string(),
+        version: "1.0".to_string(),
+        bpm: 95,
+        swing: 0.0,
+        author: "test".to_string(),
+        created: "2025-01-01T00:00:00Z".to_string(),
+        auto_start: None,
+        groove_template: None,
+    };
+    let dir = tempfile::tempdir().unwrap();
+    source.save_project(dir.path(), &project).unwrap();
+
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks());
+    app.initialize_sequencer(false).unwrap();
+    app.toggle_playback();
+    let loaded = Arc::new(Mutex::new(Vec::new()));
+    let loaded_clone = Arc::clone(&loaded);
+    app.subscribe_to_events(move |event| {
+        if let TrackerEvent::ProjectLoaded(name) = event {
+            loaded_clone.lock().unwrap().push(name.clone());
+        }
+    });
+
+    app.load_project_from_path(dir.path()).unwrap();
+    assert!(!app.is_playing);
+    assert_eq!(app.bpm, 95);
+    assert_eq!(app.steps.len(), 2);
+    assert_eq!(app.steps[0].len(), 8);
+    assert!(app.steps[1][3]);
+    assert_eq!(app.get_all_track_info().len(), 2);
+    assert!(app.sequencer.is_some());
+    assert_eq!(*loaded.lock().unwrap(), vec!["Other".to_string()]);
+
+    assert!(app.load_project_from_path(&dir.path().join("missing")).is_err());
+}
+
+#[test]
+fn test_set_step_keeps_cursor() {
+    let mut app = AppState::new(3, 8).with_tracks(create_test_tracks());
+    app.selected_track = 1;
+    app.selected_step = 2;
+
+    app.set_step(2, 5, true).unwrap();
+    assert!(app.step_at(2, 5));
+    assert_eq!((app.selected_track, app.selected_step), (1, 2));
+    assert_eq!(app.undo_stack.len(), 1);
+
+    // Writing the same value again is not an edit
+    app.set_step(2, 5, true).unwrap();
+    assert_eq!(app.undo_stack.len(), 1);
+
+    app.toggle_step_at(2, 5).unwrap();
+    assert!(!app.step_at(2, 5));
+    assert!(app.undo());
+    assert!(app.step_at(2, 5));
+
+    assert!(app.set_step(3, 0, true).is_err());
+    assert!(app.set_step(0, 8, true).is_err());
+    assert!(!app.step_at(3, 0));
+}
+
+#[test]
+fn test_bulk_set_pattern() {
+    let mut app = AppState::new(3, 4).with_tracks(create_test_tracks());
+    app.initialize_sequencer(false).unwrap();
+
+    app.bulk_set_pattern(1, vec![true, false, true, false]).unwrap();
+    assert_eq!(app.steps[1], vec![true, false, true, false]);
+    assert_eq!(app.get_live_pattern().unwrap(), app.steps);
+    assert_eq!(app.undo_stack.len(), 1);
+    assert!(app.bulk_set_pattern(1, vec![true; 3]).is_err());
+    assert!(app.bulk_set_pattern(3, vec![true; 4]).is_err());
+
+    let full = vec![vec![true; 4], vec![false; 4], vec![true, true, false, false]];
+    app.bulk_set_all_tracks(full.clone()).unwrap();
+    assert_eq!(app.steps, full);
+    assert_eq!(app.undo_stack.len(), 2);
+    assert!(app.bulk_set_all_tracks(vec![vec![true; 4]; 2]).is_err());
+    assert!(app.bulk_set_all_tracks(vec![vec![true; 4], vec![true; 4], vec![true; 5]]).is_err());
+
+    assert!(app.undo());
+    assert_eq!(app.steps[1], vec![true, false, true, false]);
+}
+
+#[test]
+fn test_cycle_project_grooves() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("grooves")).unwrap();
+    std::fs::write(dir.path().join("grooves/shuffle.groove.json"), "[0.0, 0.3]").unwrap();
+
+    let mut app = AppState::new(3, 4).with_tracks(create_test_tracks());
+    app.initialize_sequencer(false).unwrap();
+    app.load_grooves(dir.path()).unwrap();
+    assert_eq!(app.groove_names(), vec!["shuffle".to_string(), "straight".to_string()]);
+    assert_eq!(app.active_groove(), None);
+
+    app.apply_groove("shuffle").unwrap();
+    assert_eq!(app.active_groove(), Some("shuffle"));
+    assert_eq!(app.sequencer.as_ref().unwrap().get_groove_template(), vec![0.0, 0.3]);
+    assert!(app.apply_groove("missing").is_err());
+
+    assert_eq!(app.cycle_groove().as_deref(), Some("straight"));
+    assert_eq!(app.compute_groove_template(), vec![0.0]);
+    assert_eq!(app.cycle_groove().as_deref(), Some("shuffle"));
+
+    // A template set directly isn't one of the named grooves
+    app.apply_groove_template(vec![0.1]);
+    assert_eq!(app.active_groove(), None);
+}
+
+#[test]
+fn test_master_volume_is_clamped() {
+    let mut app = AppState::new(3, 4).with_tracks(create_test_tracks());
+    let volumes = Arc::new(Mutex::new(Vec::new()));
+    let volumes_clone = Arc::clone(&volumes);
+    app.subscribe_to_events(move |event| {
+        if let TrackerEvent::MasterVolumeChanged(volume) = event {
+            volumes_clone.lock().unwrap().push(*volume);
+        }
+    });
+
+    assert_eq!(app.master_volume(), 1.0);
+    app.set_master_volume(0.0);
+    assert_eq!(app.master_volume(), 0.0);
+    app.set_master_volume(3.0);
+    assert_eq!(app.master_volume(), crate::MAX_MASTER_VOLUME);
+    app.set_master_volume(-1.0);
+    assert_eq!(*volumes.lock().unwrap(), vec![0.0, 2.0, 0.0]);
+    // Track volumes are left alone
+    assert_eq!(app.get_track_volume(1), Some(0.8));
+}
+
+#[test]
+fn test_solo_track() {
+    let mut app = AppState::new(3, 4).with_tracks(create_test_tracks());
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let changes_clone = Arc::clone(&changes);
+    app.subscribe_to_events(move |event| {
+        if let TrackerEvent::TrackSoloChanged(track_idx, soloed) = event {
+            changes_clone.lock().unwrap().push((*track_idx, *soloed));
+        }
+    });
+
+    app.solo_track(2).unwrap();
+    assert!(app.is_track_soloed(2));
+    assert!(app.get_track_info(2).unwrap().soloed);
+    assert!(!app.get_track_info(1).unwrap().soloed);
+    assert!(app.solo_track(3).is_err());
+
+    // Solo follows the track when tracks move
+    app.swap_tracks(1, 2).unwrap();
+    assert!(app.is_track_soloed(1));
+    app.remove_track(0).unwrap();
+    assert!(app.is_track_soloed(0));
+
+    app.unsolo_track(0).unwrap();
+    assert!(!app.is_track_soloed(0));
+    assert_eq!(*changes.lock().unwrap(), vec![(2, true), (0, false)]);
+}
+
+#[test]
+fn test_execute_script() {
+    let mut app = AppState::new(3, 8).with_tracks(create_test_tracks());
+    app.initialize_sequencer(false).unwrap();
+    let results = app.execute_script("
+        # Four on the floor, faster
+        set_bpm 140
+        set_step 0 0 true
+        set_step 0 4 true
+        set_step 1 2 true
+        set_step 1 2 false
+        mute 2
+        randomize 1 1.0
+        play
+        stop
+        set_step 5 0 true
+        explode
+    ");
+    assert_eq!(results.len(), 11);
+    assert_eq!(results[0], Ok("BPM 140".to_string()));
+    assert_eq!(results[5], Ok("Track 2 muted".to_string()));
+    assert_eq!(results[7], Ok("Playing".to_string()));
+    assert_eq!(results[8], Ok("Stopped".to_string()));
+    assert!(results[9].is_err());
+    assert_eq!(results[10], Err("Unknown command: explode".to_string()));
+
+    assert_eq!(app.bpm, 140);
+    assert_eq!(app.steps[0], vec![true, false, false, false, true, false, false, false]);
+    assert_eq!(app.steps[1], vec![true; 8]);
+    assert!(app.is_track_muted(2));
+    assert!(!app.is_playing);
+
+    assert!(app.execute_command("set_step 0 1").is_err());
+    assert!(app.execute_command("set_step 0 1 maybe").is_err());
+    assert!(app.execute_command("randomize 0 1.5").is_err());
+    assert!(app.execute_command("   ").is_err());
+}
+
+#[test]
+fn test_random_steps_density() {
+    use crate::script::random_steps;
+
+    assert!(random_steps(64, 0.0, 42).iter().all(|&on| !on));
+    assert!(random_steps(64, 1.0, 42).iter().all(|&on| on));
+    let half = random_steps(1000, 0.5, 42).iter().filter(|&&on| on).count();
+    assert!((400..600).contains(&half), "{} of 1000 steps on", half);
+    assert_eq!(random_steps(16, 0.5, 7), random_steps(16, 0.5, 7));
+}
+
+#[test]
+fn test_record_step_at_playback_position() {
+    let mut app = AppState::new(3, 8).with_tracks(create_test_tracks());
+    app.selected_track = 2;
+    app.selected_step = 1;
+    assert!(app.record_step(0).is_err());
+
+    app.toggle_record_mode();
+    assert!(app.record_mode);
+    app.current_step = 5;
+    assert_eq!(app.record_step(1).unwrap(), 5);
+    assert!(app.step_at(1, 5));
+    assert_eq!((app.selected_track, app.selected_step), (2, 1));
+    assert!(app.record_step(3).is_err());
+
+    app.toggle_record_mode();
+    assert!(app.record_step(1).is_err());
+}
+
+#[test]
+fn test_reverse_pattern_time() {
+    let mut app = AppState::new(3, 4).with_tracks(create_test_tracks());
+    app.initialize_sequencer(false).unwrap();
+    app.bulk_set_all_tracks(vec![
+        vec![true, true, false, false],
+        vec![true, false, false, true],
+        vec![false, true, false, false],
+    ]).unwrap();
+
+    app.reverse_pattern_time(0);
+    assert_eq!(app.steps[0], vec![false, false, true, true]);
+    assert_eq!(app.steps[2], vec![false, true, false, false]);
+    assert_eq!(app.get_live_pattern().unwrap(), app.steps);
+
+    app.reverse_all_tracks();
+    assert_eq!(app.steps, vec![
+        vec![true, true, false, false],
+        vec![true, false, false, true],
+        vec![false, false, true, false],
+    ]);
+
+    assert!(app.undo());
+    assert_eq!(app.steps[0], vec![false, false, true, true]);
+    app.reverse_pattern_time(3);
+    assert_eq!(app.undo_stack.len(), 2);
+}
+
+#[test]
+fn test_stop_track_checks_bounds() {
+    let mut app = AppState::new(3, 4).with_tracks(create_test_tracks());
+    assert!(app.stop_track(2).is_ok());
+    assert!(app.stop_track(3).is_err());
+}
+
+#[test]
+fn test_builder_methods() {
+    let app = AppState::new(2, 4).with_bpm(140);
+    assert_eq!(app.bpm, 140);
+
+    let steps = vec![vec![true, false, true], vec![false, true, false]];
+    let app = app.with_steps(steps.clone());
+    assert_eq!(app.steps, steps);
+    assert_eq!(app.patterns[app.current_pattern as usize].steps, steps);
+    assert_eq!(app.velocities[1].len(), 3);
+
+    let app = app.with_num_steps(6);
+    assert_eq!(app.num_steps(), 6);
+    assert_eq!(app.steps[0], vec![true, false, true, false, false, false]);
+    assert_eq!(app.step_lengths[0].len(), 6);
+    assert_eq!(app.with_num_steps(2).steps[1], vec![false, true]);
+}
+
+#[test]
+fn test_render_to_wav_skips_muted_tracks_and_follows_subdivision() {
+    let dir = tempfile::tempdir().unwrap();
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 44100,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    for file in ["kick.wav", "snare.wav", "hihat.wav"] {
+        let mut writer = hound::WavWriter::create(dir.path().join(file), spec).unwrap();
+        for _ in 0..100 {
+            writer.write_sample(8000i16).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    let mut app = AppState::new(3, 16).with_tracks(create_test_tracks()).with_sample_dir(dir.path());
+    app.set_subdivision(Subdivision::ThirtySecond).unwrap();
+    app.steps[0][1] = true;
+    app.velocities[0][1] = 0.5;
+    app.steps[1][2] = true;
+    app.set_track_muted(1, true).unwrap();
+
+    let output = dir.path().join("out.wav");
+    app.render_to_wav(&output, 1).unwrap();
+
+    // One bar at 120 BPM is 2 seconds whatever the subdivision; a 32nd step is 62.5ms
+    let mut reader = hound::WavReader::open(&output).unwrap();
+    assert_eq!(reader.duration(), 2 * 44100);
+    let samples: Vec<i16> = reader.samples::<i16>().map(Result::unwrap).collect();
+    let frame_at = |step: usize| (step as f64 * 0.0625 * 44100.0).round() as usize * 2;
+    assert_eq!(samples[frame_at(1)], 4000);
+    assert!(samples[frame_at(2)..frame_at(3)].iter().all(|&s| s == 0));
+}
@@ -0,0 +1,56 @@
+// Audio output configuration
+
+/// Output stream settings for the audio system.
+///
+/// rodio opens its stream with the device's default buffer size, so there is no
+/// buffer size setting here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioConfig {
+    /// Output sample rate in Hz
+    pub sample_rate: u32,
+    /// Decoded sample data the player may hold before refusing to load more
+    pub max_sample_memory_bytes: usize,
+    /// Samples the player keeps loaded before evicting the least recently played
+    pub max_samples: usize,
+}
+
+/// Audio system the connector plays through, chosen when it's created
+#[cfg(feature = "jack")]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AudioBackend {
+    /// The system's default output through rodio
+    #[default]
+    Default,
+    /// A JACK client with an output port, for low-latency setups running `jackd`
+    Jack { client_name: String },
+}
+
+/// Default limit on decoded sample data, sized for a Raspberry Pi
+pub const DEFAULT_MAX_SAMPLE_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Default number of samples kept loaded at once
+pub const DEFAULT_MAX_SAMPLES: usize = 256;
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 44100,
+            max_sample_memory_bytes: DEFAULT_MAX_SAMPLE_MEMORY_BYTES,
+            max_samples: DEFAULT_MAX_SAMPLES,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        assert_eq!(AudioConfig::default(), AudioConfig {
+            sample_rate: 44100,
+            max_sample_memory_bytes: DEFAULT_MAX_SAMPLE_MEMORY_BYTES,
+            max_samples: DEFAULT_MAX_SAMPLES,
+        });
+    }
+}
@@ -2,36 +2,205 @@
 // Handles integration between sequencer and audio playback
 
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, TryLockError};
 use std::thread::{self, JoinHandle};
-use log::{debug, info};
+use std::time::{Duration, Instant};
+use log::{debug, error, info, warn};
 use crossbeam_channel::{bounded, Sender};
-use std::collections::VecDeque;
+use rtrb::{Consumer, PopError, Producer, PushError, RingBuffer};
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use crate::{AudioError, SamplePlayer, SampleEffect};
+use crate::{AudioConfig, AudioError, SamplePlayer, SampleEffect};
+#[cfg(feature = "jack")]
+use crate::{jack_output::JackPlayer, AudioBackend};
 use sequencer::TriggerEvent;
 use project::model::Track;
-use core::TrackerEvent;
+use core::{SharedEventBus, TrackerEvent};
 
 /// Audio connector that receives trigger events from the sequencer
 /// and manages the sample player
 /// Commands for the audio system
 #[derive(Debug, Clone)]
 enum AudioCommand {
-    TriggerSample(usize, usize),
+    /// Play a track's sample (track_idx, step_idx, velocity, nanoseconds from `dispatch_clock` when sent)
+    TriggerSample(usize, usize, Option<f32>, u64),
     SetTrackVolume(usize, f32),
+    /// Scale every track's volume
+    SetMasterVolume(f32),
+    /// Normalize samples loaded from now on to a peak level (enabled, target_peak)
+    SetAutoNormalize(bool, f32),
+    /// Mute or unmute a track (track_idx, muted)
+    SetTrackMuted(usize, bool),
+    /// Silence every track that isn't soloed
+    SoloTrack(usize),
+    /// Stop soloing a track; with no soloed tracks left, all tracks play
+    UnsoloTrack(usize),
+    /// Tune a track by a number of semitones (track_idx, semitones)
+    SetTrackTuning(usize, f32),
+    /// Stop whatever one track is playing
+    StopTrack(usize),
     StopAll,
     Deactivate,
     Initialize(Vec<Track>),
+    /// Prepare sinks and run each track's sample through its effects once
+    Preload,
+    /// Preload, then play a short silence on every track to prime the output
+    WarmUp,
     ConfigureEffects(Vec<EffectConfig>),
+    /// Add an effect to a track, replacing one of the same kind
+    AddEffect(usize, SampleEffect),
+    /// Remove every effect from a track, including step overrides
+    ClearEffects(usize),
+    /// Replace every track's effect chain with a saved state
+    ConfigureEffectsFromState(HashMap<usize, Vec<SampleEffect>>),
+    ReloadSample(usize, String),
+    AddTrack(Track),
+    RemoveTrack(usize),
+    /// Acknowledge once every earlier command has been processed
+    Sync(Sender<()>),
+    /// Reply with the bytes of decoded sample data held by the player
+    QueryMemoryUsage(Sender<usize>),
 }
 
 #[derive(Debug, Clone)]
 pub struct EffectConfig {
     pub track_idx: usize,
+    /// Step the effect is limited to, or None for every step of the track
+    pub step_idx: Option<usize>,
     pub effect: SampleEffect,
 }
 
+/// Commands the audio thread's queue can hold
+const COMMAND_QUEUE_CAPACITY: usize = 100;
+
+/// Writing end of the command queue; the mutex lets the main thread and event bus callbacks share it
+type CommandSender = Arc<Mutex<Producer<AudioCommand>>>;
+
+/// Reading end of the command queue, held by the running audio thread
+type CommandReceiver = Arc<Mutex<Consumer<AudioCommand>>>;
+
+/// How long a command may wait for space in the audio thread's queue by default
+pub const DEFAULT_SEND_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How long the audio thread sleeps when its queue is empty
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// How often the audio thread reports that it is alive
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Heartbeats older than this mean the audio thread has stopped
+const HEARTBEAT_STALE_AFTER: Duration = Duration::from_secs(2);
+
+/// How long `reconnect` waits for the old audio thread to exit
+const THREAD_EXIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Weight of the newest latency in the moving average
+const LATENCY_EMA_ALPHA: f64 = 0.1;
+
+/// Nanoseconds since a fixed point in this process, used to timestamp queued triggers
+fn dispatch_clock() -> u64 {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// Time of the audio thread's latest heartbeat, overwritten on every beat so it never goes stale while the thread runs
+struct Heartbeat(AtomicU64);
+
+impl Heartbeat {
+    /// A heartbeat that counts as recent until `HEARTBEAT_STALE_AFTER` from now
+    fn new() -> Self {
+        let heartbeat = Self(AtomicU64::new(0));
+        heartbeat.beat();
+        heartbeat
+    }
+
+    fn beat(&self) {
+        self.0.store(dispatch_clock(), Ordering::Relaxed);
+    }
+
+    fn is_recent(&self) -> bool {
+        let age = dispatch_clock().saturating_sub(self.0.load(Ordering::Relaxed));
+        Duration::from_nanos(age) < HEARTBEAT_STALE_AFTER
+    }
+}
+
+/// Trigger statistics of an `AudioConnector`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AudioMetrics {
+    /// Triggers the audio thread has handed to the sample player
+    pub triggers_fired: u64,
+    /// Triggers that could not be queued or played
+    pub errors: u64,
+    /// Moving average of the time from queueing a trigger to the audio thread picking it up
+    pub avg_dispatch_latency_us: f64,
+    pub max_dispatch_latency_us: f64,
+}
+
+/// Counters shared between the connector and its audio thread; latencies are stored as f64 bits
+#[derive(Default)]
+struct MetricsCounters {
+    triggers_fired: AtomicU64,
+    errors: AtomicU64,
+    avg_latency_us: AtomicU64,
+    max_latency_us: AtomicU64,
+}
+
+impl MetricsCounters {
+    fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    /// Count a trigger picked up by the audio thread and fold in its queueing latency.
+    /// Only the audio thread calls this, so the read-modify-write of the averages doesn't race.
+    fn record_trigger(&self, sent_at: u64) {
+        let latency_us = dispatch_clock().saturating_sub(sent_at) as f64 / 1000.0;
+        let fired = self.triggers_fired.fetch_add(1, Ordering::Relaxed);
+        let avg = if fired == 0 {
+            latency_us
+        } else {
+            let avg = f64::from_bits(self.avg_latency_us.load(Ordering::Relaxed));
+            avg + LATENCY_EMA_ALPHA * (latency_us - avg)
+        };
+        self.avg_latency_us.store(avg.to_bits(), Ordering::Relaxed);
+        let max = f64::from_bits(self.max_latency_us.load(Ordering::Relaxed));
+        if latency_us > max {
+            self.max_latency_us.store(latency_us.to_bits(), Ordering::Relaxed);
+        }
+    }
+    
+    fn snapshot(&self) -> AudioMetrics {
+        AudioMetrics {
+            triggers_fired: self.triggers_fired.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            avg_dispatch_latency_us: f64::from_bits(self.avg_latency_us.load(Ordering::Relaxed)),
+            max_dispatch_latency_us: f64::from_bits(self.max_latency_us.load(Ordering::Relaxed)),
+        }
+    }
+    
+    fn reset(&self) {
+        for counter in [&self.triggers_fired, &self.errors, &self.avg_latency_us, &self.max_latency_us] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Queue a command for the audio thread, giving up after `timeout`
+fn send_command(sender: &Mutex<Producer<AudioCommand>>, mut command: AudioCommand, timeout: Duration) -> Result<(), AudioError> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match sender.lock().unwrap().push(command) {
+            Ok(()) => return Ok(()),
+            Err(PushError::Full(rejected)) => command = rejected,
+        }
+        if Instant::now() >= deadline {
+            error!("Audio thread did not accept {:?} within {:?}", command, timeout);
+            return Err(AudioError::Timeout(timeout));
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
 pub struct AudioConnector {
     /// Sample player instance (shared, thread-safe)
     // player: Arc<Mutex<SamplePlayer>>,
@@ -47,45 +216,209 @@ pub struct AudioConnector {
     #[allow(dead_code)]
     command_queue: Arc<Mutex<VecDeque<AudioCommand>>>,
     
-    /// Event bus subscription ID
-    #[allow(dead_code)]
-    subscription_id: Option<usize>,
+    /// Event bus this connector listens to, with its subscription ID
+    subscription: Mutex<Option<(SharedEventBus, usize)>>,
+    
+    /// Queue for sending audio events to the audio thread
+    message_sender: CommandSender,
+    
+    /// Receiving end of the command queue, kept so a restarted thread can take over the queue
+    message_receiver: CommandReceiver,
+    
+    /// How long a command may wait for space in the queue
+    send_timeout: Duration,
+    
+    /// Heartbeat written by the audio thread
+    heartbeat: Arc<Heartbeat>,
+    
+    /// Output device and stream settings, reused on restart
+    device_name: Option<String>,
+    config: Option<AudioConfig>,
     
-    /// Channel sender for sending audio events to the audio thread
-    message_sender: Sender<AudioCommand>,
+    /// Audio system the thread plays through, reused on restart
+    #[cfg(feature = "jack")]
+    backend: AudioBackend,
     
-    /// Background audio thread handle
-    _audio_thread: Option<JoinHandle<()>>,
+    /// Tracks from the last `initialize`, reloaded on reconnect
+    last_tracks: Arc<Mutex<Vec<Track>>>,
+    
+    /// Effect chains from `load_effect_state` and later programmatic changes, reloaded on reconnect
+    last_effect_state: Mutex<HashMap<usize, Vec<SampleEffect>>>,
+    
+    /// Gain from `set_master_volume`, reapplied on reconnect
+    master_volume: Mutex<f32>,
+    
+    /// Tracks from `solo_track`, soloed again on reconnect
+    soloed_tracks: Mutex<HashSet<usize>>,
+    
+    /// Target peak from `set_auto_normalize`, if enabled; reapplied on reconnect
+    auto_normalize: Mutex<Option<f32>>,
+    
+    /// Background audio thread handle, replaced on reconnect
+    audio_thread: Mutex<Option<JoinHandle<()>>>,
+    
+    /// Trigger counts and latencies, shared with the audio thread
+    metrics: Arc<MetricsCounters>,
 }
 
 impl AudioConnector {
-    /// Create a new audio connector
+    /// Create a new audio connector on the default output device
     pub fn new(sample_dir: impl AsRef<Path>) -> Result<Self, AudioError> {
-        // let player = SamplePlayer::new(sample_dir.as_ref())?;  // <-- Just keep player here
-        let sample_dir_clone = sample_dir.as_ref().to_path_buf();
-        let (sender, receiver) = bounded::<AudioCommand>(100);
+        Self::with_options(sample_dir, None, None)
+    }
+    
+    /// Create a new audio connector on the named output device
+    pub fn new_with_device(sample_dir: impl AsRef<Path>, device_name: &str) -> Result<Self, AudioError> {
+        // Fail here rather than in the audio thread when the device doesn't exist
+        Self::with_options(sample_dir, Some(device_name), None)
+    }
+    
+    /// Create a new audio connector on the default output device with explicit stream settings
+    pub fn new_with_config(sample_dir: impl AsRef<Path>, config: AudioConfig) -> Result<Self, AudioError> {
+        Self::with_options(sample_dir, None, Some(config))
+    }
+    
+    /// Create a new audio connector, optionally choosing the device and stream settings
+    pub fn with_options(sample_dir: impl AsRef<Path>, device_name: Option<&str>, config: Option<AudioConfig>) -> Result<Self, AudioError> {
+        // Fail here rather than in the audio thread when the device doesn't exist
+        if let Some(name) = device_name {
+            crate::find_output_device(name)?;
+        }
+        Self::spawn(sample_dir, device_name.map(str::to_string), config)
+    }
+    
+    /// Names of the available audio output devices
+    pub fn list_audio_devices() -> Vec<String> {
+        crate::output_device_names()
+    }
+    
+    /// Create a new audio connector on the given backend
+    #[cfg(feature = "jack")]
+    pub fn with_backend(sample_dir: impl AsRef<Path>, backend: AudioBackend) -> Result<Self, AudioError> {
+        let mut connector = Self::unstarted(sample_dir, None, None);
+        connector.backend = backend;
+        let audio_thread = connector.start_thread()?;
+        *connector.audio_thread.get_mut().unwrap() = Some(audio_thread);
+        Ok(connector)
+    }
+    
+    /// Create a new audio connector that plays through a JACK client, mixing
+    /// triggered samples in the process callback with `mixer::Mixer`.
+    /// Fails if the JACK library can't be loaded or no server is running.
+    #[cfg(feature = "jack")]
+    pub fn new_jack(sample_dir: impl AsRef<Path>, client_name: &str) -> Result<Self, AudioError> {
+        Self::with_backend(sample_dir, AudioBackend::Jack { client_name: client_name.to_string() })
+    }
+    
+    fn spawn(sample_dir: impl AsRef<Path>, device_name: Option<String>, config: Option<AudioConfig>) -> Result<Self, AudioError> {
+        let mut connector = Self::unstarted(sample_dir, device_name, config);
+        let audio_thread = connector.start_thread()?;
+        *connector.audio_thread.get_mut().unwrap() = Some(audio_thread);
+        Ok(connector)
+    }
+    
+    /// A connector on the default backend whose audio thread hasn't been started
+    fn unstarted(sample_dir: impl AsRef<Path>, device_name: Option<String>, config: Option<AudioConfig>) -> Self {
+        let (producer, consumer) = RingBuffer::<AudioCommand>::new(COMMAND_QUEUE_CAPACITY);
+        Self {
+            sample_dir: sample_dir.as_ref().to_path_buf(),
+            active: Arc::new(Mutex::new(false)),
+            command_queue: Arc::new(Mutex::new(VecDeque::new())),
+            subscription: Mutex::new(None),
+            message_sender: Arc::new(Mutex::new(producer)),
+            message_receiver: Arc::new(Mutex::new(consumer)),
+            send_timeout: DEFAULT_SEND_TIMEOUT,
+            heartbeat: Arc::new(Heartbeat::new()),
+            device_name,
+            config,
+            #[cfg(feature = "jack")]
+            backend: AudioBackend::Default,
+            last_tracks: Arc::new(Mutex::new(Vec::new())),
+            last_effect_state: Mutex::new(HashMap::new()),
+            master_volume: Mutex::new(1.0),
+            soloed_tracks: Mutex::new(HashSet::new()),
+            auto_normalize: Mutex::new(None),
+            audio_thread: Mutex::new(None),
+            metrics: Arc::new(MetricsCounters::default()),
+        }
+    }
     
-        let active = Arc::new(Mutex::new(false));
-        let thread_active = active.clone();
+    /// Start an audio thread for this connector's backend
+    fn start_thread(&self) -> Result<JoinHandle<()>, AudioError> {
+        #[cfg(feature = "jack")]
+        if let AudioBackend::Jack { client_name } = &self.backend {
+            let player = JackPlayer::new(&self.sample_dir, client_name)?;
+            return Ok(Self::spawn_jack_thread(
+                player,
+                Arc::clone(&self.message_receiver),
+                Arc::clone(&self.heartbeat),
+                Arc::clone(&self.active),
+                Arc::clone(&self.metrics),
+            ));
+        }
+        Ok(Self::spawn_thread(
+            self.sample_dir.clone(),
+            self.device_name.clone(),
+            self.config,
+            Arc::clone(&self.message_receiver),
+            Arc::clone(&self.heartbeat),
+            Arc::clone(&self.active),
+            Arc::clone(&self.metrics),
+        ))
+    }
     
-        let audio_thread = thread::spawn(move || {
+    /// Start the thread that owns the sample player and processes queued commands
+    fn spawn_thread(
+        sample_dir: PathBuf,
+        device_name: Option<String>,
+        config: Option<AudioConfig>,
+        receiver: CommandReceiver,
+        heartbeat: Arc<Heartbeat>,
+        thread_active: Arc<Mutex<bool>>,
+        metrics: Arc<MetricsCounters>,
+    ) -> JoinHandle<()> {
+        thread::spawn(move || {
             debug!("Audio processing thread started");
-            let mut player = match SamplePlayer::new(&sample_dir_clone) {
+            let player = match (&device_name, &config) {
+                (_, Some(config)) => SamplePlayer::new_with_config(&sample_dir, device_name.as_deref(), config),
+                (Some(name), None) => SamplePlayer::new_with_device(&sample_dir, name),
+                (None, None) => SamplePlayer::new(&sample_dir),
+            };
+            let mut player = match player {
                 Ok(player) => player,
                 Err(err) => {
                     debug!("Failed to initialize audio player in thread: {:?}", err);
                     return; // Exit the thread early
                 }
             };
-            while let Ok(message) = receiver.recv() {
+            
+            // Hold the queue for as long as this thread runs
+            let mut receiver = receiver.lock().unwrap_or_else(PoisonError::into_inner);
+            let mut last_beat = Instant::now();
+            heartbeat.beat();
+            loop {
+                if last_beat.elapsed() >= HEARTBEAT_INTERVAL {
+                    last_beat = Instant::now();
+                    heartbeat.beat();
+                }
+                
+                let message = match receiver.pop() {
+                    Ok(message) => message,
+                    Err(PopError::Empty) => {
+                        thread::sleep(COMMAND_POLL_INTERVAL);
+                        continue;
+                    }
+                };
                 match message {
-                    AudioCommand::TriggerSample(track_idx, step_idx) => {
+                    AudioCommand::TriggerSample(track_idx, step_idx, velocity, sent_at) => {
                         if !*thread_active.lock().unwrap() {
                             continue;
                         }
+                        metrics.record_trigger(sent_at);
                         let trigger = TriggerEvent { track_idx, step_idx };
-                        if let Err(err) = player.process_trigger(&trigger) {
+                        if let Err(err) = player.process_trigger_with_velocity(&trigger, velocity) {
                             debug!("Error processing trigger: {:?}", err);
+                            metrics.record_error();
                         }
                     },
                     AudioCommand::SetTrackVolume(track_idx, volume) => {
@@ -93,6 +426,31 @@ impl AudioConnector {
                             debug!("Error setting track volume: {:?}", err);
                         }
                     },
+                    AudioCommand::SetMasterVolume(volume) => {
+                        player.set_master_volume(volume);
+                    },
+                    AudioCommand::SetAutoNormalize(enabled, target_peak) => {
+                        player.set_auto_normalize(enabled, target_peak);
+                    },
+                    AudioCommand::SetTrackMuted(track_idx, muted) => {
+                        if let Err(err) = player.set_track_muted(track_idx, muted) {
+                            debug!("Error setting track mute: {:?}", err);
+                        }
+                    },
+                    AudioCommand::SoloTrack(track_idx) => {
+                        player.set_track_soloed(track_idx, true);
+                    },
+                    AudioCommand::UnsoloTrack(track_idx) => {
+                        player.set_track_soloed(track_idx, false);
+                    },
+                    AudioCommand::SetTrackTuning(track_idx, semitones) => {
+                        player.processor.set_tuning(track_idx, semitones);
+                    },
+                    AudioCommand::StopTrack(track_idx) => {
+                        if let Err(err) = player.stop_track(track_idx) {
+                            debug!("Error stopping track {}: {:?}", track_idx, err);
+                        }
+                    },
                     AudioCommand::StopAll => {
                         player.stop_all();
                     },
@@ -107,28 +465,246 @@ impl AudioConnector {
                             debug!("Error initializing tracks: {:?}", err);
                         }
                     },
+                    AudioCommand::QueryMemoryUsage(reply) => {
+                        let _ = reply.send(player.memory_usage());
+                    },
+                    AudioCommand::Preload => {
+                        if let Err(err) = player.preload_all_samples() {
+                            debug!("Error preloading samples: {:?}", err);
+                        }
+                    },
+                    AudioCommand::WarmUp => {
+                        if let Err(err) = player.warm_up() {
+                            debug!("Error warming up audio: {:?}", err);
+                        }
+                    },
                     AudioCommand::ConfigureEffects(effects) => {
                         let effects_count = effects.len();
                         for effect_config in effects {
-                            player.processor.add_effect(effect_config.track_idx, effect_config.effect);
+                            match effect_config.step_idx {
+                                Some(step_idx) => player.processor.add_step_effect(effect_config.track_idx, step_idx, effect_config.effect),
+                                None => player.processor.add_effect(effect_config.track_idx, effect_config.effect),
+                            }
                         }
                         debug!("Applied {} effects", effects_count);
                     },
+                    AudioCommand::AddEffect(track_idx, effect) => {
+                        player.processor.set_effect(track_idx, effect);
+                    },
+                    AudioCommand::ClearEffects(track_idx) => {
+                        player.processor.clear_effects(track_idx);
+                    },
+                    AudioCommand::ConfigureEffectsFromState(state) => {
+                        debug!("Loaded effect chains for {} tracks", state.len());
+                        player.processor.load_state(state);
+                    },
+                    AudioCommand::ReloadSample(track_idx, sample_path) => {
+                        if let Err(err) = player.set_track_sample(track_idx, &sample_path) {
+                            debug!("Error reloading sample for track {}: {:?}", track_idx, err);
+                        }
+                    },
+                    AudioCommand::AddTrack(track) => {
+                        if let Err(err) = player.add_track(track) {
+                            debug!("Error adding track: {:?}", err);
+                        }
+                    },
+                    AudioCommand::RemoveTrack(track_idx) => {
+                        player.remove_track(track_idx);
+                    },
+                    AudioCommand::Sync(ack) => {
+                        let _ = ack.send(());
+                    },
                 }
             }
     
             debug!("Audio processing thread stopped");
-        });
+        })
+    }
     
-        Ok(Self {
-            sample_dir: sample_dir.as_ref().to_path_buf(),
-            active,
-            command_queue: Arc::new(Mutex::new(VecDeque::new())),
-            subscription_id: None,
-            message_sender: sender,
-            _audio_thread: Some(audio_thread),
+    /// Start the thread that feeds queued commands to a JACK player.
+    /// Effects and tuning aren't applied on JACK, so those commands are skipped.
+    #[cfg(feature = "jack")]
+    fn spawn_jack_thread(
+        mut player: JackPlayer,
+        receiver: CommandReceiver,
+        heartbeat: Arc<Heartbeat>,
+        thread_active: Arc<Mutex<bool>>,
+        metrics: Arc<MetricsCounters>,
+    ) -> JoinHandle<()> {
+        thread::spawn(move || {
+            debug!("JACK audio thread started");
+            let mut receiver = receiver.lock().unwrap_or_else(PoisonError::into_inner);
+            let mut last_beat = Instant::now();
+            heartbeat.beat();
+            loop {
+                if last_beat.elapsed() >= HEARTBEAT_INTERVAL {
+                    last_beat = Instant::now();
+                    heartbeat.beat();
+                }
+                
+                let message = match receiver.pop() {
+                    Ok(message) => message,
+                    Err(PopError::Empty) => {
+                        thread::sleep(COMMAND_POLL_INTERVAL);
+                        continue;
+                    }
+                };
+                let result = match message {
+                    AudioCommand::TriggerSample(track_idx, _, velocity, sent_at) => {
+                        if !*thread_active.lock().unwrap() {
+                            continue;
+                        }
+                        metrics.record_trigger(sent_at);
+                        let result = player.trigger(track_idx, velocity);
+                        if result.is_err() {
+                            metrics.record_error();
+                        }
+                        result
+                    },
+                    AudioCommand::SetTrackVolume(track_idx, volume) => player.set_track_volume(track_idx, volume),
+                    AudioCommand::SetMasterVolume(volume) => {
+                        player.set_master_volume(volume);
+                        Ok(())
+                    },
+                    AudioCommand::SetAutoNormalize(enabled, target_peak) => {
+                        player.set_auto_normalize(enabled, target_peak);
+                        Ok(())
+                    },
+                    AudioCommand::SetTrackMuted(track_idx, muted) => player.set_track_muted(track_idx, muted),
+                    AudioCommand::SoloTrack(track_idx) => player.set_track_soloed(track_idx, true),
+                    AudioCommand::UnsoloTrack(track_idx) => player.set_track_soloed(track_idx, false),
+                    AudioCommand::StopTrack(track_idx) => player.stop_track(track_idx),
+                    AudioCommand::StopAll => player.stop_all(),
+                    AudioCommand::Deactivate => {
+                        *thread_active.lock().unwrap() = false;
+                        let _ = player.stop_all();
+                        debug!("JACK audio thread deactivated");
+                        break;
+                    },
+                    AudioCommand::Initialize(tracks) => player.initialize_with_tracks(&tracks),
+                    AudioCommand::ReloadSample(track_idx, sample_path) => player.set_track_sample(track_idx, &sample_path),
+                    AudioCommand::AddTrack(track) => player.add_track(track).map(|_| ()),
+                    AudioCommand::RemoveTrack(track_idx) => player.remove_track(track_idx),
+                    AudioCommand::QueryMemoryUsage(reply) => {
+                        let _ = reply.send(player.memory_usage());
+                        Ok(())
+                    },
+                    AudioCommand::Sync(ack) => {
+                        let _ = ack.send(());
+                        Ok(())
+                    },
+                    // Samples are decoded when tracks are set up, and nothing needs priming
+                    AudioCommand::Preload | AudioCommand::WarmUp => Ok(()),
+                    AudioCommand::SetTrackTuning(..)
+                    | AudioCommand::ConfigureEffects(_)
+                    | AudioCommand::AddEffect(..)
+                    | AudioCommand::ClearEffects(_)
+                    | AudioCommand::ConfigureEffectsFromState(_) => {
+                        debug!("Ignoring effect command on the JACK backend");
+                        Ok(())
+                    },
+                };
+                if let Err(err) = result {
+                    debug!("Error in JACK audio thread: {:?}", err);
+                }
+            }
+            
+            debug!("JACK audio thread stopped");
         })
     }
+    
+    /// Wait at most `timeout` for space in the audio thread's queue before giving up
+    pub fn with_send_timeout(mut self, timeout: Duration) -> Self {
+        self.send_timeout = timeout;
+        self
+    }
+    
+    /// Queue a command for the audio thread within the send timeout
+    fn send(&self, command: AudioCommand) -> Result<(), AudioError> {
+        send_command(&self.message_sender, command, self.send_timeout)
+    }
+    
+    /// Whether the audio thread is still running and sent a heartbeat recently
+    pub fn check_thread_alive(&self) -> bool {
+        if self.audio_thread.lock().unwrap().as_ref().is_none_or(|handle| handle.is_finished()) {
+            return false;
+        }
+        self.heartbeat.is_recent()
+    }
+    
+    /// Reconnect if the connector is in use but its audio thread has died.
+    /// Returns true if a reconnect was needed.
+    pub fn ensure_thread_alive(&self) -> Result<bool, AudioError> {
+        if !self.is_active() || self.check_thread_alive() {
+            return Ok(false);
+        }
+        error!("Audio thread is not responding, reconnecting");
+        self.reconnect()?;
+        Ok(true)
+    }
+    
+    /// Replace the audio thread with a fresh one on the same device and reload the last
+    /// known tracks, e.g. after the output device was unplugged and plugged back in.
+    /// Commands still queued for the old thread are discarded.
+    pub fn reconnect(&self) -> Result<(), AudioError> {
+        info!("Reconnecting audio output");
+        
+        // A dead thread never reads this, so don't fail if the queue is full
+        let _ = self.send(AudioCommand::Deactivate);
+        *self.active.lock().unwrap() = false;
+        
+        let mut audio_thread = self.audio_thread.lock().unwrap();
+        if let Some(handle) = audio_thread.take() {
+            let deadline = Instant::now() + THREAD_EXIT_TIMEOUT;
+            while !handle.is_finished() && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(5));
+            }
+            if handle.is_finished() {
+                let _ = handle.join();
+            } else {
+                error!("Audio thread did not exit within {:?}, abandoning it", THREAD_EXIT_TIMEOUT);
+            }
+        }
+        
+        // An abandoned thread that is still running keeps hold of the queue
+        let mut receiver = match self.message_receiver.try_lock() {
+            Ok(receiver) => receiver,
+            Err(TryLockError::Poisoned(err)) => err.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                return Err(AudioError::InitializationError("Audio thread is stuck and still owns the command queue".into()));
+            }
+        };
+        let mut stale = 0;
+        while receiver.pop().is_ok() {
+            stale += 1;
+        }
+        drop(receiver);
+        debug!("Discarded {} queued audio commands", stale);
+        
+        *audio_thread = Some(self.start_thread()?);
+        drop(audio_thread);
+        self.heartbeat.beat();
+        
+        if let Some(target_peak) = *self.auto_normalize.lock().unwrap() {
+            self.send(AudioCommand::SetAutoNormalize(true, target_peak))?;
+        }
+        let tracks = self.last_tracks.lock().unwrap().clone();
+        self.initialize(&tracks)?;
+        let effect_state = self.last_effect_state.lock().unwrap().clone();
+        if !effect_state.is_empty() {
+            self.send(AudioCommand::ConfigureEffectsFromState(effect_state))?;
+        }
+        let master_volume = *self.master_volume.lock().unwrap();
+        if master_volume != 1.0 {
+            self.send(AudioCommand::SetMasterVolume(master_volume))?;
+        }
+        let soloed: Vec<usize> = self.soloed_tracks.lock().unwrap().iter().copied().collect();
+        for track_idx in soloed {
+            self.send(AudioCommand::SoloTrack(track_idx))?;
+        }
+        Ok(())
+    }
+    
     // pub fn new(sample_dir: impl AsRef<Path>) -> Result<Self, AudioError> {
     //     // Initialize the sample player
     //     // let player = SamplePlayer::new(sample_dir.as_ref())?;
@@ -237,16 +813,32 @@ impl AudioConnector {
     
         // Clone tracks because we're sending them into the thread
         let tracks_clone = tracks.to_vec();
+        *self.last_tracks.lock().unwrap() = tracks_clone.clone();
     
-        if let Err(_) = self.message_sender.send(AudioCommand::Initialize(tracks_clone)) {
-            return Err(AudioError::PlaybackError("Failed to send initialize command to audio thread".into()));
+        self.send(AudioCommand::Initialize(tracks_clone))?;
+        
+        // Pan and pitch are played as track effects
+        for (track_idx, track) in tracks.iter().enumerate() {
+            if track.pan != 0.0 {
+                self.set_track_pan(track_idx, track.pan)?;
+            }
+            if track.pitch_semitones != 0.0 {
+                self.set_track_pitch(track_idx, track.pitch_semitones)?;
+            }
         }
+        self.send(AudioCommand::Preload)?;
     
         *self.active.lock().unwrap() = true;
     
         Ok(())
     }
 
+    /// Prime the audio output so the first trigger doesn't pay for the driver
+    /// setting up its buffers. Call after `initialize`.
+    pub fn warm_up(&self) -> Result<(), AudioError> {
+        self.send(AudioCommand::WarmUp)
+    }
+
     pub fn configure_effects(&self, pattern_metas: &[project::model::PatternMeta]) -> Result<(), AudioError> {
         if pattern_metas.is_empty() {
             info!("No pattern metadata available for effects configuration");
@@ -262,12 +854,18 @@ impl AudioConnector {
                 // Parse the key which is in the format "track:step"
                 let parts: Vec<&str> = fx_key.split(':').collect();
                 if parts.len() == 2 {
-                    if let (Ok(track_idx), Ok(_step_idx)) = (parts[0].parse::<usize>(), parts[1].parse::<usize>()) {
-                        if let Some(true) = fx_entry.reverse {
-                            info!("Adding reverse effect to track {}", track_idx);
+                    if let (Ok(track_idx), Ok(step_idx)) = (parts[0].parse::<usize>(), parts[1].parse::<usize>()) {
+                        if let Err(errors) = fx_entry.validate() {
+                            warn!("Skipping invalid FX entry {}: {}", fx_key, errors.join(", "));
+                            continue;
+                        }
+                        
+                        for effect in effects_from_fx(fx_entry) {
+                            info!("Adding {} effect to track {} step {}", effect, track_idx, step_idx);
                             effect_configs.push(EffectConfig {
                                 track_idx,
-                                effect: SampleEffect::Reverse,
+                                step_idx: Some(step_idx),
+                                effect,
                             });
                         }
     
@@ -286,13 +884,59 @@ impl AudioConnector {
             return Ok(());
         }
     
-        if let Err(_) = self.message_sender.send(AudioCommand::ConfigureEffects(effect_configs)) {
-            return Err(AudioError::PlaybackError("Failed to send configure effects command to audio thread".into()));
-        }
+        self.send(AudioCommand::ConfigureEffects(effect_configs))?;
     
         Ok(())
     }
 
+    /// Append effects to the chains of the tracks in the map, applied from the next trigger
+    pub fn configure_effects_from_map(&self, effects: HashMap<usize, Vec<SampleEffect>>) -> Result<(), AudioError> {
+        let mut effect_configs = Vec::new();
+        {
+            let mut state = self.last_effect_state.lock().unwrap();
+            for (track_idx, track_effects) in effects {
+                state.entry(track_idx).or_default().extend(track_effects.iter().cloned());
+                effect_configs.extend(track_effects.into_iter().map(|effect| EffectConfig {
+                    track_idx,
+                    step_idx: None,
+                    effect,
+                }));
+            }
+        }
+        if effect_configs.is_empty() {
+            return Ok(());
+        }
+        self.send(AudioCommand::ConfigureEffects(effect_configs))
+    }
+    
+    /// Append an effect to a track's chain
+    pub fn add_track_effect(&self, track_idx: usize, effect: SampleEffect) -> Result<(), AudioError> {
+        self.configure_effects_from_map(HashMap::from([(track_idx, vec![effect])]))
+    }
+    
+    /// Remove every effect from a track
+    pub fn clear_track_effects(&self, track_idx: usize) -> Result<(), AudioError> {
+        self.last_effect_state.lock().unwrap().remove(&track_idx);
+        self.send(AudioCommand::ClearEffects(track_idx))
+    }
+    
+    /// Effects configured on a track through this connector
+    pub fn get_effects(&self, track_idx: usize) -> Vec<SampleEffect> {
+        self.last_effect_state.lock().unwrap().get(&track_idx).cloned().unwrap_or_default()
+    }
+    
+    /// Replace every track's effect chain, e.g. with state loaded from `patterns/effects.json`
+    pub fn load_effect_state(&self, state: HashMap<usize, Vec<SampleEffect>>) -> Result<(), AudioError> {
+        info!("Loading effect chains for {} tracks", state.len());
+        *self.last_effect_state.lock().unwrap() = state.clone();
+        self.send(AudioCommand::ConfigureEffectsFromState(state))
+    }
+    
+    /// Effect chains last loaded with `load_effect_state`
+    pub fn effect_state(&self) -> HashMap<usize, Vec<SampleEffect>> {
+        self.last_effect_state.lock().unwrap().clone()
+    }
+
     /// Configure effects based on pattern metadata
     // pub fn configure_effects(&self, pattern_metas: &[project::model::PatternMeta]) -> Result<(), AudioError> {
     //     if pattern_metas.is_empty() {
@@ -333,6 +977,11 @@ impl AudioConnector {
     
     /// Process a trigger event from the sequencer
     pub fn process_trigger(&self, event: &TriggerEvent) -> Result<(), AudioError> {
+        self.trigger_with_velocity(event, None)
+    }
+    
+    /// Process a trigger event, scaling this playback by `velocity` (0.0 to 1.0) when given
+    pub fn trigger_with_velocity(&self, event: &TriggerEvent, velocity: Option<f32>) -> Result<(), AudioError> {
         // Check if we're active
         if !*self.active.lock().unwrap() {
             debug!("Audio connector is not active, ignoring trigger");
@@ -340,60 +989,106 @@ impl AudioConnector {
         }
         
         // Send a message to the audio thread
-        if let Err(_) = self.message_sender.send(AudioCommand::TriggerSample(
-            event.track_idx, 
-            event.step_idx
-        )) {
-            return Err(AudioError::PlaybackError("Failed to send trigger to audio thread".into()));
+        let command = AudioCommand::TriggerSample(event.track_idx, event.step_idx, velocity, dispatch_clock());
+        let result = match self.send(command.clone()) {
+            Err(err) if !self.check_thread_alive() => {
+                error!("Failed to send trigger ({}), audio thread is gone", err);
+                self.reconnect()?;
+                self.send(command)
+            },
+            result => result,
+        };
+        if result.is_err() {
+            self.metrics.record_error();
         }
-        
-        Ok(())
+        result
     }
     
-    /// Set up a callback to process trigger events from a sequencer
-    /// Returns a boolean indicating success
-    pub fn connect_to_sequencer(&self, sequencer: &sequencer::Sequencer) -> bool {
-        info!("Connecting audio to sequencer");
+    /// Trigger counts and dispatch latencies since creation or the last `reset_metrics`
+    pub fn metrics(&self) -> AudioMetrics {
+        self.metrics.snapshot()
+    }
+    
+    /// Start counting triggers, errors and latencies from zero
+    pub fn reset_metrics(&self) {
+        self.metrics.reset();
+    }
+    
+    /// Forward step triggers and volume changes published on `event_bus` to the audio thread.
+    /// Replaces any earlier subscription; the listener is removed when the connector is dropped.
+    pub fn connect_to_event_bus(&self, event_bus: SharedEventBus) -> bool {
+        info!("Connecting audio to event bus");
         
         // Mark the connector as active
         *self.active.lock().unwrap() = true;
         
-        // Get the event bus from the sequencer
-        let event_bus = sequencer.get_event_bus().clone();
-        
-        // Create a channel to the audio thread
-        let sender = self.message_sender.clone();
-        
-        // Connect to the event bus
-        event_bus.subscribe(move |event| {
-            match event {
-                TrackerEvent::StepTriggered(track_idx, step_idx) => {
-                    // Send trigger message to audio thread
-                    let _ = sender.send(AudioCommand::TriggerSample(*track_idx, *step_idx));
-                },
-                TrackerEvent::TrackVolumeChanged(track_idx, volume) => {
-                    // Send volume change message to audio thread
-                    let _ = sender.send(AudioCommand::SetTrackVolume(*track_idx, *volume));
-                },
-                _ => {
-                    // Ignore other events
-                }
+        let sender = Arc::clone(&self.message_sender);
+        let metrics = Arc::clone(&self.metrics);
+        // Immediate, so triggers emitted asynchronously by the sequencer aren't delayed by UI listeners
+        let id = event_bus.subscribe_immediate(move |event| {
+            let command = match event {
+                TrackerEvent::StepTriggered(track_idx, step_idx) => AudioCommand::TriggerSample(*track_idx, *step_idx, None, dispatch_clock()),
+                TrackerEvent::TrackVolumeChanged(track_idx, volume) => AudioCommand::SetTrackVolume(*track_idx, *volume),
+                _ => return,
+            };
+            // Runs on the emitting thread, so drop the command rather than block when the queue is full
+            if sender.lock().unwrap().push(command).is_err() {
+                metrics.record_error();
             }
         });
         
-        debug!("Audio connector ready to process events from sequencer");
+        if let Some((old_bus, old_id)) = self.subscription.lock().unwrap().replace((event_bus, id)) {
+            old_bus.unsubscribe(old_id);
+        }
+        
+        debug!("Audio connector ready to process events from the event bus");
         true
     }
     
+    /// Set up a callback to process trigger events from a sequencer's event bus
+    /// Returns a boolean indicating success
+    pub fn connect_to_sequencer(&self, sequencer: &sequencer::Sequencer) -> bool {
+        self.connect_to_event_bus(Arc::clone(sequencer.get_event_bus()))
+    }
+    
+    /// Stop one track's playback, leaving the other tracks sounding
+    pub fn stop_track(&self, track_idx: usize) -> Result<(), AudioError> {
+        self.send(AudioCommand::StopTrack(track_idx))
+    }
+    
     /// Stop all audio playback
     pub fn stop_all(&self) {
-        let _ = self.message_sender.send(AudioCommand::StopAll);
+        let _ = self.send(AudioCommand::StopAll);
+    }
+    
+    /// Wait until the audio thread has processed every command queued so far.
+    /// Returns false if that didn't happen within `timeout`.
+    pub fn flush(&self, timeout: Duration) -> bool {
+        if !self.check_thread_alive() {
+            return false;
+        }
+        let (ack_sender, ack_receiver) = bounded(1);
+        if self.send(AudioCommand::Sync(ack_sender)).is_err() {
+            return false;
+        }
+        ack_receiver.recv_timeout(timeout).is_ok()
+    }
+    
+    /// Bytes of decoded sample data held by the audio thread, or None if it
+    /// doesn't answer within `timeout`
+    pub fn memory_usage(&self, timeout: Duration) -> Option<usize> {
+        if !self.check_thread_alive() {
+            return None;
+        }
+        let (reply_sender, reply_receiver) = bounded(1);
+        self.send(AudioCommand::QueryMemoryUsage(reply_sender)).ok()?;
+        reply_receiver.recv_timeout(timeout).ok()
     }
     
     /// Deactivate the connector
     pub fn deactivate(&self) {
         *self.active.lock().unwrap() = false;
-        let _ = self.message_sender.send(AudioCommand::Deactivate);
+        let _ = self.send(AudioCommand::Deactivate);
     }
     
     /// Check if the connector is active
@@ -401,19 +1096,146 @@ impl AudioConnector {
         *self.active.lock().unwrap()
     }
     
+    /// Number of commands waiting to be processed by the audio thread
+    pub fn queue_depth(&self) -> usize {
+        let sender = self.message_sender.lock().unwrap();
+        sender.buffer().capacity() - sender.slots()
+    }
+    
     /// Set volume for a specific track
     pub fn set_track_volume(&self, track_idx: usize, volume: f32) -> Result<(), AudioError> {
-        if let Err(_) = self.message_sender.send(AudioCommand::SetTrackVolume(track_idx, volume)) {
-            return Err(AudioError::PlaybackError("Failed to send volume change to audio thread".into()));
+        self.send(AudioCommand::SetTrackVolume(track_idx, volume))
+    }
+    
+    /// Scale every track's volume, on top of its own
+    pub fn set_master_volume(&self, volume: f32) -> Result<(), AudioError> {
+        *self.master_volume.lock().unwrap() = volume;
+        self.send(AudioCommand::SetMasterVolume(volume))
+    }
+    
+    /// Normalize samples loaded from now on to `target_peak` of full scale.
+    /// Samples already loaded keep their levels until they are reloaded.
+    pub fn set_auto_normalize(&self, enabled: bool, target_peak: f32) -> Result<(), AudioError> {
+        *self.auto_normalize.lock().unwrap() = enabled.then_some(target_peak);
+        self.send(AudioCommand::SetAutoNormalize(enabled, target_peak))
+    }
+    
+    /// Gain last passed to `set_master_volume`
+    pub fn master_volume(&self) -> f32 {
+        *self.master_volume.lock().unwrap()
+    }
+    
+    /// Mute or unmute a track in the audio thread
+    pub fn set_track_muted(&self, track_idx: usize, muted: bool) -> Result<(), AudioError> {
+        self.send(AudioCommand::SetTrackMuted(track_idx, muted))
+    }
+    
+    /// Solo a track in the audio thread, so other tracks stay silent even if triggered
+    pub fn solo_track(&self, track_idx: usize) -> Result<(), AudioError> {
+        self.soloed_tracks.lock().unwrap().insert(track_idx);
+        self.send(AudioCommand::SoloTrack(track_idx))
+    }
+    
+    /// Stop soloing a track in the audio thread
+    pub fn unsolo_track(&self, track_idx: usize) -> Result<(), AudioError> {
+        self.soloed_tracks.lock().unwrap().remove(&track_idx);
+        self.send(AudioCommand::UnsoloTrack(track_idx))
+    }
+    
+    /// Set a track's stereo position (-1.0 left ..= 1.0 right)
+    pub fn set_track_pan(&self, track_idx: usize, pan: f32) -> Result<(), AudioError> {
+        self.send(AudioCommand::AddEffect(track_idx, SampleEffect::Pan(pan)))
+    }
+    
+    /// Shift a track's pitch by a number of semitones
+    pub fn set_track_pitch(&self, track_idx: usize, semitones: f32) -> Result<(), AudioError> {
+        self.send(AudioCommand::AddEffect(track_idx, SampleEffect::Pitch(semitones)))
+    }
+    
+    /// Tune a track by a number of semitones, added to any pitch effects
+    pub fn set_track_tuning(&self, track_idx: usize, semitones: f32) -> Result<(), AudioError> {
+        self.send(AudioCommand::SetTrackTuning(track_idx, semitones))
+    }
+    
+    /// Swap the sample played by a track (path relative to the sample directory)
+    pub fn reload_sample(&self, track_idx: usize, sample_path: &str) -> Result<(), AudioError> {
+        self.send(AudioCommand::ReloadSample(track_idx, sample_path.to_string()))
+    }
+
+    /// Append a track; its sample is loaded on the audio thread
+    pub fn add_track(&self, track: &Track) -> Result<(), AudioError> {
+        self.send(AudioCommand::AddTrack(track.clone()))
+    }
+
+    /// Stop and remove a track; later tracks move down one index
+    pub fn remove_track(&self, track_idx: usize) -> Result<(), AudioError> {
+        {
+            let mut soloed = self.soloed_tracks.lock().unwrap();
+            *soloed = soloed.iter()
+                .filter(|&&track| track != track_idx)
+                .map(|&track| if track > track_idx { track - 1 } else { track })
+                .collect();
         }
-        Ok(())
+        self.send(AudioCommand::RemoveTrack(track_idx))
+    }
+}
+
+impl Drop for AudioConnector {
+    fn drop(&mut self) {
+        if let Some((event_bus, id)) = self.subscription.lock().unwrap().take() {
+            event_bus.unsubscribe(id);
+        }
+    }
+}
+
+/// Tremolo depth used when a pattern gives only a rate
+const DEFAULT_TREMOLO_DEPTH: f32 = 0.5;
+
+/// Compressor settings used when a pattern gives only a threshold
+const DEFAULT_COMPRESS_RATIO: f32 = 4.0;
+const DEFAULT_COMPRESS_ATTACK_SAMPLES: usize = 44;
+const DEFAULT_COMPRESS_RELEASE_SAMPLES: usize = 4410;
+
+/// Effects described by a pattern metadata FX entry, in the order they're applied
+fn effects_from_fx(fx_entry: &project::model::FxEntry) -> Vec<SampleEffect> {
+    let mut effects = Vec::new();
+    if let Some(true) = fx_entry.reverse {
+        effects.push(SampleEffect::Reverse);
+    }
+    if let Some(semitones) = fx_entry.pitch_semitones {
+        effects.push(SampleEffect::Pitch(semitones));
+    }
+    if let Some(cutoff) = fx_entry.filter_cutoff_hz {
+        match fx_entry.filter_type.as_deref() {
+            Some("high") => effects.push(SampleEffect::HighPass(cutoff)),
+            _ => effects.push(SampleEffect::LowPass(cutoff)),
+        }
+    }
+    if let Some(delay_ms) = fx_entry.delay_ms {
+        effects.push(SampleEffect::Delay(delay_ms));
+    }
+    if let Some(rate_hz) = fx_entry.tremolo_rate_hz {
+        effects.push(SampleEffect::Tremolo { rate_hz, depth: fx_entry.tremolo_depth.unwrap_or(DEFAULT_TREMOLO_DEPTH) });
     }
+    if let Some(threshold) = fx_entry.compress_threshold {
+        effects.push(SampleEffect::Compress {
+            threshold,
+            ratio: fx_entry.compress_ratio.unwrap_or(DEFAULT_COMPRESS_RATIO),
+            attack_samples: fx_entry.compress_attack_samples.unwrap_or(DEFAULT_COMPRESS_ATTACK_SAMPLES),
+            release_samples: fx_entry.compress_release_samples.unwrap_or(DEFAULT_COMPRESS_RELEASE_SAMPLES),
+        });
+    }
+    if let Some(pan) = fx_entry.pan {
+        effects.push(SampleEffect::Pan(pan));
+    }
+    effects
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::path::Path;
+    use std::sync::atomic::AtomicBool;
     use std::fs::{self, File};
     use std::io::Write;
     use std::path::PathBuf;
@@ -421,6 +1243,46 @@ mod tests {
     use project::model::Track;
     use sequencer::TriggerEvent;
     
+    #[test]
+    fn test_effects_from_fx_entry() {
+        let fx = project::model::FxEntry {
+            reverse: Some(true),
+            filter_cutoff_hz: Some(800.0),
+            filter_type: Some("high".to_string()),
+            pitch_semitones: Some(-3.0),
+            pan: Some(0.5),
+            delay_ms: Some(120.0),
+            tremolo_rate_hz: Some(4.0),
+            tremolo_depth: Some(0.8),
+            ..Default::default()
+        };
+        assert_eq!(effects_from_fx(&fx), vec![
+            SampleEffect::Reverse,
+            SampleEffect::Pitch(-3.0),
+            SampleEffect::HighPass(800.0),
+            SampleEffect::Delay(120.0),
+            SampleEffect::Tremolo { rate_hz: 4.0, depth: 0.8 },
+            SampleEffect::Pan(0.5),
+        ]);
+
+        let fx = project::model::FxEntry { filter_cutoff_hz: Some(800.0), ..Default::default() };
+        assert_eq!(effects_from_fx(&fx), vec![SampleEffect::LowPass(800.0)]);
+        
+        // Depth alone does nothing; a rate alone uses the default depth
+        let fx = project::model::FxEntry { tremolo_depth: Some(1.0), ..Default::default() };
+        assert!(effects_from_fx(&fx).is_empty());
+        let fx = project::model::FxEntry { tremolo_rate_hz: Some(2.0), ..Default::default() };
+        assert_eq!(effects_from_fx(&fx), vec![SampleEffect::Tremolo { rate_hz: 2.0, depth: DEFAULT_TREMOLO_DEPTH }]);
+        
+        let fx = project::model::FxEntry { compress_threshold: Some(0.6), compress_ratio: Some(8.0), ..Default::default() };
+        assert_eq!(effects_from_fx(&fx), vec![SampleEffect::Compress {
+            threshold: 0.6,
+            ratio: 8.0,
+            attack_samples: DEFAULT_COMPRESS_ATTACK_SAMPLES,
+            release_samples: DEFAULT_COMPRESS_RELEASE_SAMPLES,
+        }]);
+    }
+    
     #[test]
     fn test_connector_creation() {
         // This test won't actually interact with audio devices
@@ -430,6 +1292,266 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_list_audio_devices() {
+        let devices = AudioConnector::list_audio_devices();
+        // Only hosts that can actually open an output stream are expected to list devices
+        if rodio::OutputStream::try_default().is_ok() {
+            assert!(!devices.is_empty());
+        }
+    }
+    
+    #[test]
+    fn test_connector_with_config() {
+        if let Ok(connector) = AudioConnector::new_with_config(Path::new("./samples"), AudioConfig { sample_rate: 48000, ..AudioConfig::default() }) {
+            assert!(!connector.is_active());
+        }
+    }
+    
+    #[test]
+    fn test_new_with_invalid_device() {
+        let result = AudioConnector::new_with_device(Path::new("./samples"), "no-such-device-gaucho");
+        assert!(matches!(result, Err(AudioError::InitializationError(_))));
+    }
+    
+    #[test]
+    fn test_send_command_times_out_when_queue_is_full() {
+        let (producer, _consumer) = RingBuffer::<AudioCommand>::new(1);
+        let sender = Mutex::new(producer);
+        send_command(&sender, AudioCommand::StopAll, DEFAULT_SEND_TIMEOUT).unwrap();
+        
+        let timeout = Duration::from_millis(20);
+        let started = Instant::now();
+        let result = send_command(&sender, AudioCommand::StopAll, timeout);
+        assert!(matches!(result, Err(AudioError::Timeout(t)) if t == timeout));
+        assert!(started.elapsed() >= timeout);
+    }
+    
+    #[test]
+    fn test_check_thread_alive() {
+        let (_temp_dir, samples_dir, tracks) = setup_test_environment();
+        if let Ok(connector) = AudioConnector::new(&samples_dir) {
+            connector.initialize(&tracks).unwrap();
+            // The audio thread exits straight away when no output stream can be opened
+            thread::sleep(Duration::from_millis(200));
+            let has_output = rodio::OutputStream::try_default().is_ok();
+            assert_eq!(connector.check_thread_alive(), has_output);
+            assert_eq!(connector.ensure_thread_alive().unwrap(), !has_output);
+            connector.deactivate();
+        }
+    }
+    
+    #[test]
+    fn test_heartbeat_stays_recent_while_beating() {
+        let heartbeat = Arc::new(Heartbeat::new());
+        let running = Arc::new(AtomicBool::new(true));
+        let beater = {
+            let (heartbeat, running) = (Arc::clone(&heartbeat), Arc::clone(&running));
+            thread::spawn(move || {
+                while running.load(Ordering::Relaxed) {
+                    heartbeat.beat();
+                    thread::sleep(HEARTBEAT_INTERVAL);
+                }
+            })
+        };
+        
+        // Well past the first beat, only the latest one counts
+        thread::sleep(HEARTBEAT_STALE_AFTER + HEARTBEAT_INTERVAL / 2);
+        assert!(heartbeat.is_recent());
+        
+        running.store(false, Ordering::Relaxed);
+        beater.join().unwrap();
+        thread::sleep(HEARTBEAT_STALE_AFTER);
+        assert!(!heartbeat.is_recent());
+    }
+    
+    #[test]
+    fn test_check_thread_alive_after_heartbeat_interval() {
+        let (_temp_dir, samples_dir, tracks) = setup_test_environment();
+        if let Ok(connector) = AudioConnector::new(&samples_dir) {
+            connector.initialize(&tracks).unwrap();
+            let has_output = rodio::OutputStream::try_default().is_ok();
+            thread::sleep(HEARTBEAT_STALE_AFTER + HEARTBEAT_INTERVAL);
+            assert_eq!(connector.check_thread_alive(), has_output);
+            connector.deactivate();
+        }
+    }
+    
+    #[test]
+    fn test_flush() {
+        let (_temp_dir, samples_dir, tracks) = setup_test_environment();
+        if let Ok(connector) = AudioConnector::new(&samples_dir) {
+            connector.initialize(&tracks).unwrap();
+            let _ = connector.process_trigger(&TriggerEvent { track_idx: 0, step_idx: 0 });
+            thread::sleep(Duration::from_millis(100));
+            
+            // Only a running audio thread can acknowledge
+            let alive = connector.check_thread_alive();
+            assert_eq!(connector.flush(Duration::from_secs(1)), alive);
+            if alive {
+                assert_eq!(connector.queue_depth(), 0);
+            }
+            connector.deactivate();
+        }
+    }
+    
+    #[test]
+    fn test_metrics() {
+        let (_temp_dir, samples_dir, tracks) = setup_test_environment();
+        if let Ok(connector) = AudioConnector::new(&samples_dir) {
+            connector.initialize(&tracks).unwrap();
+            thread::sleep(Duration::from_millis(100));
+            let alive = connector.check_thread_alive();
+            
+            for step_idx in 0..10 {
+                connector.process_trigger(&TriggerEvent { track_idx: step_idx % tracks.len(), step_idx }).unwrap();
+            }
+            connector.flush(Duration::from_secs(1));
+            
+            // Only a running audio thread picks triggers up
+            let metrics = connector.metrics();
+            if alive {
+                assert_eq!(metrics.triggers_fired, 10);
+                assert!(metrics.avg_dispatch_latency_us > 0.0);
+                assert!(metrics.max_dispatch_latency_us >= metrics.avg_dispatch_latency_us);
+            } else {
+                assert_eq!(metrics.triggers_fired, 0);
+            }
+            
+            connector.reset_metrics();
+            assert_eq!(connector.metrics(), AudioMetrics::default());
+            connector.deactivate();
+        }
+    }
+    
+    #[test]
+    fn test_first_trigger_after_warm_up_is_fast() {
+        let (_temp_dir, samples_dir, tracks) = setup_test_environment();
+        if let Ok(connector) = AudioConnector::new(&samples_dir) {
+            connector.initialize(&tracks).unwrap();
+            connector.warm_up().unwrap();
+            thread::sleep(Duration::from_millis(100));
+            let alive = connector.flush(Duration::from_secs(2));
+            connector.reset_metrics();
+            
+            connector.process_trigger(&TriggerEvent { track_idx: 0, step_idx: 0 }).unwrap();
+            connector.flush(Duration::from_secs(1));
+            if alive {
+                let metrics = connector.metrics();
+                assert_eq!(metrics.triggers_fired, 1);
+                assert!(metrics.max_dispatch_latency_us < 20_000.0, "First trigger took {} us", metrics.max_dispatch_latency_us);
+            }
+            connector.deactivate();
+        }
+    }
+    
+    #[cfg(feature = "jack")]
+    #[test]
+    fn test_jack_backend_fails_without_a_server() {
+        // Only meaningful where no JACK server is running, like CI
+        let (_temp_dir, samples_dir, _) = setup_test_environment();
+        if let Err(err) = AudioConnector::new_jack(&samples_dir, "gaucho-test") {
+            assert!(matches!(err, AudioError::InitializationError(msg) if msg.contains("gaucho-test")));
+        }
+    }
+    
+    #[test]
+    fn test_programmatic_effects() {
+        let (_temp_dir, samples_dir, tracks) = setup_test_environment();
+        if let Ok(connector) = AudioConnector::new(&samples_dir) {
+            connector.initialize(&tracks).unwrap();
+            connector.add_track_effect(0, SampleEffect::Reverse).unwrap();
+            connector.configure_effects_from_map(HashMap::from([(1, vec![SampleEffect::FadeIn(0.1), SampleEffect::Reverse])])).unwrap();
+            assert_eq!(connector.get_effects(0), vec![SampleEffect::Reverse]);
+            assert_eq!(connector.get_effects(1), vec![SampleEffect::FadeIn(0.1), SampleEffect::Reverse]);
+            
+            // The next trigger plays through the new chain
+            thread::sleep(Duration::from_millis(100));
+            let alive = connector.check_thread_alive();
+            connector.process_trigger(&TriggerEvent { track_idx: 0, step_idx: 0 }).unwrap();
+            connector.flush(Duration::from_secs(1));
+            if alive {
+                assert_eq!(connector.metrics().triggers_fired, 1);
+                assert_eq!(connector.metrics().errors, 0);
+            }
+            
+            connector.clear_track_effects(1).unwrap();
+            assert!(connector.get_effects(1).is_empty());
+            assert_eq!(connector.effect_state().len(), 1);
+            connector.deactivate();
+        }
+    }
+    
+    #[test]
+    fn test_metrics_moving_average() {
+        let metrics = MetricsCounters::default();
+        let now = dispatch_clock();
+        metrics.record_trigger(now);
+        let first = metrics.snapshot();
+        assert_eq!(first.triggers_fired, 1);
+        assert_eq!(first.avg_dispatch_latency_us, first.max_dispatch_latency_us);
+        
+        // A trigger that waited 10ms moves the average a tenth of the way towards it
+        let sent_at = dispatch_clock();
+        thread::sleep(Duration::from_millis(10));
+        metrics.record_trigger(sent_at);
+        let second = metrics.snapshot();
+        assert_eq!(second.triggers_fired, 2);
+        assert!(second.max_dispatch_latency_us >= 10_000.0);
+        let expected = first.avg_dispatch_latency_us + LATENCY_EMA_ALPHA * (second.max_dispatch_latency_us - first.avg_dispatch_latency_us);
+        assert!((second.avg_dispatch_latency_us - expected).abs() < 1e-6);
+        
+        metrics.record_error();
+        assert_eq!(metrics.snapshot().errors, 1);
+    }
+    
+    #[test]
+    fn test_reconnect_after_thread_death() {
+        let (_temp_dir, samples_dir, tracks) = setup_test_environment();
+        // An unknown device makes the audio thread exit without reading the queue
+        let connector = AudioConnector::spawn(&samples_dir, Some("no-such-device-gaucho".to_string()), None)
+            .unwrap()
+            .with_send_timeout(Duration::from_millis(10));
+        connector.initialize(&tracks).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        assert!(!connector.check_thread_alive());
+        
+        // Nothing drains the queue, so it eventually fills and the trigger that fails reconnects
+        let event = TriggerEvent { track_idx: 0, step_idx: 0 };
+        for _ in 0..200 {
+            connector.process_trigger(&event).unwrap();
+        }
+        assert!(connector.is_active());
+        assert_eq!(connector.last_tracks.lock().unwrap().len(), tracks.len());
+        assert!(connector.queue_depth() < 100, "Stale commands should be discarded on reconnect");
+        
+        connector.reconnect().unwrap();
+        assert_eq!(connector.queue_depth(), 2, "Only the re-initialize and preload should be queued");
+    }
+    
+    #[test]
+    fn test_queue_depth_counts_waiting_commands() {
+        let (_temp_dir, samples_dir, _) = setup_test_environment();
+        // An unknown device makes the audio thread exit, so nothing consumes the queue
+        let connector = AudioConnector::spawn(&samples_dir, Some("no-such-device-gaucho".to_string()), None).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        assert!(!connector.check_thread_alive());
+        assert_eq!(connector.queue_depth(), 0);
+        
+        for track_idx in 0..5 {
+            connector.set_track_volume(track_idx, 0.5).unwrap();
+        }
+        assert_eq!(connector.queue_depth(), 5);
+        
+        // Drain it the way the audio thread would
+        let mut receiver = connector.message_receiver.lock().unwrap();
+        assert!(receiver.pop().is_ok());
+        assert!(receiver.pop().is_ok());
+        assert_eq!(connector.queue_depth(), 3);
+        while receiver.pop().is_ok() {}
+        assert_eq!(connector.queue_depth(), 0);
+    }
+    
     /// Creates a temporary WAV file that can be used for testing.
     fn create_test_wav_file(dir: &Path, name: &str) -> Result<PathBuf, std::io::Error> {
         let path = dir.join(name);
@@ -491,16 +1613,28 @@ mod tests {
                 name: "Kick".to_string(),
                 sample: "kick.wav".to_string(),
                 volume: 1.0,
+                pan: 0.0,
+                pitch_semitones: 0.0,
+                muted: false,
+                color: None,
             },
             Track {
                 name: "Snare".to_string(),
                 sample: "snare.wav".to_string(),
                 volume: 0.8,
+                pan: 0.0,
+                pitch_semitones: 0.0,
+                muted: false,
+                color: None,
             },
             Track {
                 name: "HiHat".to_string(),
                 sample: "hihat.wav".to_string(),
                 volume: 0.7,
+                pan: 0.0,
+                pitch_semitones: 0.0,
+                muted: false,
+                color: None,
             }
         ];
         
@@ -515,6 +1649,7 @@ mod tests {
         if let Ok(connector) = AudioConnector::new(&samples_dir) {
             // Test initial state
             assert!(!connector.is_active());
+            assert_eq!(connector.queue_depth(), 0);
             
             // Test initialization
             let result = connector.initialize(&tracks);
@@ -555,6 +1690,24 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_event_bus_subscription_lifecycle() {
+        let (_temp_dir, samples_dir, _) = setup_test_environment();
+        let event_bus = Arc::new(core::EventBus::new());
+        
+        if let Ok(connector) = AudioConnector::new(&samples_dir) {
+            assert!(connector.connect_to_event_bus(Arc::clone(&event_bus)));
+            assert_eq!(event_bus.listener_count(), 1);
+            
+            // Connecting again replaces the subscription instead of adding a second one
+            assert!(connector.connect_to_event_bus(Arc::clone(&event_bus)));
+            assert_eq!(event_bus.listener_count(), 1);
+            
+            drop(connector);
+            assert_eq!(event_bus.listener_count(), 0);
+        }
+    }
+    
     #[test]
     fn test_connector_sequencer_integration() {
         let (_temp_dir, samples_dir, tracks) = setup_test_environment();
@@ -0,0 +1,304 @@
+// Audio connector for machines without an audio device
+// Triggers are logged with their time instead of being played, and can be mixed to a WAV file
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use log::{debug, info};
+
+use crate::render::{decode_track_samples, mix_triggers, write_wav};
+use crate::AudioError;
+use sequencer::TriggerEvent;
+use project::model::Track;
+use core::{SharedEventBus, TrackerEvent};
+
+/// A trigger received by a headless connector
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoggedTrigger {
+    pub track_idx: usize,
+    pub step_idx: usize,
+    /// Gain of the hit, 1.0 for triggers from the event bus
+    pub velocity: f32,
+    /// Time since the connector was created, or the time given to `trigger_at`
+    pub at: Duration,
+}
+
+/// Stand-in for `AudioConnector` that records triggers in memory
+pub struct HeadlessAudioConnector {
+    /// Project sample directory, used when rendering
+    sample_dir: PathBuf,
+
+    /// Whether triggers are currently being recorded
+    active: Arc<Mutex<bool>>,
+
+    /// Reference point for trigger times
+    started: Instant,
+
+    /// Current track configuration, including volume changes
+    tracks: Arc<Mutex<Vec<Track>>>,
+
+    /// Every trigger recorded so far, in arrival order
+    triggers: Arc<Mutex<Vec<LoggedTrigger>>>,
+
+    /// Scale applied to every track's volume when rendering
+    master_volume: Mutex<f32>,
+
+    /// Event bus this connector listens to, with its subscription ID
+    subscription: Mutex<Option<(SharedEventBus, usize)>>,
+}
+
+impl HeadlessAudioConnector {
+    /// Create a headless connector; no device or thread is opened
+    pub fn new(sample_dir: impl AsRef<Path>) -> Self {
+        Self {
+            sample_dir: sample_dir.as_ref().to_path_buf(),
+            active: Arc::new(Mutex::new(false)),
+            started: Instant::now(),
+            tracks: Arc::new(Mutex::new(Vec::new())),
+            triggers: Arc::new(Mutex::new(Vec::new())),
+            master_volume: Mutex::new(1.0),
+            subscription: Mutex::new(None),
+        }
+    }
+
+    pub fn initialize(&self, tracks: &[Track]) -> Result<(), AudioError> {
+        info!("Initializing headless audio connector with {} tracks", tracks.len());
+        *self.tracks.lock().unwrap() = tracks.to_vec();
+        *self.active.lock().unwrap() = true;
+        Ok(())
+    }
+
+    /// Effects aren't applied when rendering headless; accepted so callers can treat both connectors alike
+    pub fn configure_effects(&self, pattern_metas: &[project::model::PatternMeta]) -> Result<(), AudioError> {
+        debug!("Ignoring effects from {} pattern metadata entries in headless mode", pattern_metas.len());
+        Ok(())
+    }
+
+    /// Record a trigger event from the sequencer at the current time
+    pub fn process_trigger(&self, event: &TriggerEvent) -> Result<(), AudioError> {
+        if !*self.active.lock().unwrap() {
+            debug!("Headless audio connector is not active, ignoring trigger");
+            return Ok(());
+        }
+        self.trigger_at(event, 1.0, self.started.elapsed());
+        Ok(())
+    }
+
+    /// Record a trigger with its velocity at an explicit time, for offline rendering
+    pub fn trigger_at(&self, event: &TriggerEvent, velocity: f32, at: Duration) {
+        record(&self.triggers, event.track_idx, event.step_idx, velocity, at);
+    }
+
+    /// Record triggers and volume changes published on the sequencer's event bus
+    pub fn connect_to_sequencer(&self, sequencer: &sequencer::Sequencer) -> bool {
+        info!("Connecting headless audio to sequencer");
+        *self.active.lock().unwrap() = true;
+
+        let active = Arc::clone(&self.active);
+        let tracks = Arc::clone(&self.tracks);
+        let triggers = Arc::clone(&self.triggers);
+        let started = self.started;
+        let event_bus = Arc::clone(sequencer.get_event_bus());
+        let id = event_bus.subscribe_immediate(move |event| {
+            match event {
+                TrackerEvent::StepTriggered(track_idx, step_idx) if *active.lock().unwrap() => {
+                    record(&triggers, *track_idx, *step_idx, 1.0, started.elapsed());
+                },
+                TrackerEvent::TrackVolumeChanged(track_idx, volume) => {
+                    if let Some(track) = tracks.lock().unwrap().get_mut(*track_idx) {
+                        track.volume = *volume;
+                    }
+                },
+                _ => {}
+            }
+        });
+        if let Some((old_bus, old_id)) = self.subscription.lock().unwrap().replace((event_bus, id)) {
+            old_bus.unsubscribe(old_id);
+        }
+        true
+    }
+
+    /// Nothing is playing, so there is nothing to stop
+    pub fn stop_all(&self) {}
+
+    /// Triggers are recorded synchronously, so the queue is always empty
+    pub fn flush(&self, _timeout: Duration) -> bool {
+        true
+    }
+
+    pub fn deactivate(&self) {
+        *self.active.lock().unwrap() = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        *self.active.lock().unwrap()
+    }
+
+    pub fn queue_depth(&self) -> usize {
+        0
+    }
+
+    pub fn set_track_volume(&self, track_idx: usize, volume: f32) -> Result<(), AudioError> {
+        let mut tracks = self.tracks.lock().unwrap();
+        let track = tracks.get_mut(track_idx)
+            .ok_or_else(|| AudioError::PlaybackError(format!("Invalid track index: {}", track_idx)))?;
+        track.volume = volume.clamp(0.0, 1.0);
+        Ok(())
+    }
+
+    /// Scale every track's volume in renders, on top of its own
+    pub fn set_master_volume(&self, volume: f32) {
+        *self.master_volume.lock().unwrap() = volume;
+    }
+
+    pub fn reload_sample(&self, track_idx: usize, sample_path: &str) -> Result<(), AudioError> {
+        let mut tracks = self.tracks.lock().unwrap();
+        let track = tracks.get_mut(track_idx)
+            .ok_or_else(|| AudioError::PlaybackError(format!("Invalid track index: {}", track_idx)))?;
+        track.sample = sample_path.to_string();
+        Ok(())
+    }
+
+    pub fn add_track(&self, track: &Track) -> Result<(), AudioError> {
+        self.tracks.lock().unwrap().push(track.clone());
+        Ok(())
+    }
+
+    pub fn remove_track(&self, track_idx: usize) -> Result<(), AudioError> {
+        let mut tracks = self.tracks.lock().unwrap();
+        if track_idx >= tracks.len() {
+            return Err(AudioError::PlaybackError(format!("Invalid track index: {}", track_idx)));
+        }
+        tracks.remove(track_idx);
+        Ok(())
+    }
+
+    /// Copy of every trigger recorded so far
+    pub fn triggers(&self) -> Vec<LoggedTrigger> {
+        self.triggers.lock().unwrap().clone()
+    }
+
+    /// Forget all recorded triggers
+    pub fn clear_triggers(&self) {
+        self.triggers.lock().unwrap().clear();
+    }
+
+    /// Mix the recorded triggers with the tracks' samples into a WAV file `length` long
+    pub fn render_to_wav(&self, output: &Path, length: Duration) -> Result<(), AudioError> {
+        let tracks = self.tracks.lock().unwrap().clone();
+        let track_samples = decode_track_samples(&tracks, &self.sample_dir)?;
+        let master_volume = *self.master_volume.lock().unwrap();
+        let volumes: Vec<f32> = tracks.iter().map(|t| t.volume * master_volume).collect();
+        let timed: Vec<(Duration, usize, f32)> = self.triggers().iter()
+            .map(|trigger| (trigger.at, trigger.track_idx, trigger.velocity))
+            .collect();
+
+        info!("Rendering {} headless triggers to {}", timed.len(), output.display());
+        write_wav(output, &mix_triggers(&timed, &track_samples, &volumes, length))
+    }
+}
+
+impl Drop for HeadlessAudioConnector {
+    fn drop(&mut self) {
+        *self.active.lock().unwrap() = false;
+        if let Some((event_bus, id)) = self.subscription.lock().unwrap().take() {
+            event_bus.unsubscribe(id);
+        }
+    }
+}
+
+fn record(triggers: &Mutex<Vec<LoggedTrigger>>, track_idx: usize, step_idx: usize, velocity: f32, at: Duration) {
+    triggers.lock().unwrap().push(LoggedTrigger { track_idx, step_idx, velocity, at });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{RENDER_CHANNELS, RENDER_SAMPLE_RATE};
+    use core::EventBus;
+
+    fn click_track(dir: &Path) -> Track {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(dir.join("click.wav"), spec).unwrap();
+        for _ in 0..100 {
+            writer.write_sample(8000i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        Track { name: "Click".to_string(), sample: "click.wav".to_string(), volume: 1.0, pan: 0.0, pitch_semitones: 0.0, muted: false, color: None }
+    }
+
+    #[test]
+    fn test_triggers_logged_only_while_active() {
+        let connector = HeadlessAudioConnector::new("samples");
+        let event = TriggerEvent { track_idx: 1, step_idx: 3 };
+        connector.process_trigger(&event).unwrap();
+        assert!(connector.triggers().is_empty());
+
+        connector.initialize(&[]).unwrap();
+        connector.process_trigger(&event).unwrap();
+        let triggers = connector.triggers();
+        assert_eq!(triggers.len(), 1);
+        assert_eq!((triggers[0].track_idx, triggers[0].step_idx), (1, 3));
+
+        connector.deactivate();
+        connector.process_trigger(&event).unwrap();
+        assert_eq!(connector.triggers().len(), 1);
+        assert!(connector.flush(Duration::ZERO));
+        assert_eq!(connector.queue_depth(), 0);
+    }
+
+    #[test]
+    fn test_event_bus_triggers_are_logged() {
+        let bus = Arc::new(EventBus::new());
+        let sequencer = sequencer::Sequencer::new_with_event_bus(120, vec![vec![true; 16]], Arc::clone(&bus));
+        let connector = HeadlessAudioConnector::new("samples");
+        assert!(connector.connect_to_sequencer(&sequencer));
+
+        bus.emit(TrackerEvent::StepTriggered(0, 5));
+        bus.emit(TrackerEvent::BpmChanged(90));
+        let triggers = connector.triggers();
+        assert_eq!(triggers.len(), 1);
+        assert_eq!(triggers[0].step_idx, 5);
+    }
+
+    #[test]
+    fn test_dropping_unsubscribes_from_event_bus() {
+        let bus = Arc::new(EventBus::new());
+        let sequencer = sequencer::Sequencer::new_with_event_bus(120, vec![vec![true; 16]], Arc::clone(&bus));
+        let listeners = bus.listener_count();
+
+        let connector = HeadlessAudioConnector::new("samples");
+        connector.connect_to_sequencer(&sequencer);
+        // Reconnecting replaces the subscription rather than adding one
+        connector.connect_to_sequencer(&sequencer);
+        assert_eq!(bus.listener_count(), listeners + 1);
+
+        drop(connector);
+        assert_eq!(bus.listener_count(), listeners);
+    }
+
+    #[test]
+    fn test_render_logged_triggers() {
+        let dir = tempfile::tempdir().unwrap();
+        let connector = HeadlessAudioConnector::new(dir.path());
+        connector.initialize(&[click_track(dir.path())]).unwrap();
+        connector.trigger_at(&TriggerEvent { track_idx: 0, step_idx: 0 }, 1.0, Duration::ZERO);
+        connector.trigger_at(&TriggerEvent { track_idx: 0, step_idx: 8 }, 0.5, Duration::from_millis(500));
+
+        let output = dir.path().join("out.wav");
+        connector.render_to_wav(&output, Duration::from_secs(1)).unwrap();
+
+        let mut reader = hound::WavReader::open(&output).unwrap();
+        assert_eq!(reader.spec().channels, RENDER_CHANNELS);
+        assert_eq!(reader.duration(), RENDER_SAMPLE_RATE);
+        let samples: Vec<i16> = reader.samples::<i16>().map(Result::unwrap).collect();
+        assert_eq!(samples[0], 8000);
+        assert_eq!(samples[RENDER_SAMPLE_RATE as usize], 4000);
+        assert_eq!(samples[RENDER_SAMPLE_RATE as usize / 2], 0);
+    }
+}
@@ -0,0 +1,255 @@
+// JACK output for the audio connector
+// Samples are decoded to mono at the server's rate up front and mixed by `Mixer` in the process callback
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use jack::{AsyncClient, AudioOut, Client, ClientOptions, Control, Port, PortFlags, ProcessHandler, ProcessScope};
+use log::{debug, info, warn};
+use project::model::Track;
+use rtrb::{Consumer, Producer, RingBuffer};
+
+use crate::mixer::{ActiveSample, Mixer};
+use crate::{decode_to_pcm, AudioError, SampleProcessor};
+
+/// Voice changes the process callback can pick up in one cycle before `JackPlayer` has to fail
+const VOICE_QUEUE_CAPACITY: usize = 256;
+
+/// Mixer voices, and so tracks, a JACK player can hold; fixed so the callback never allocates
+const MAX_VOICES: usize = 64;
+
+/// Change to the mixer's voices, sent to the process callback
+enum VoiceCommand {
+    Trigger { voice: usize, data: Arc<Vec<i16>>, gain: f32 },
+    Stop(usize),
+    StopAll,
+}
+
+/// Process callback state: the mixer and the queue feeding it
+struct MixerProcess {
+    port: Port<AudioOut>,
+    mixer: Mixer,
+    commands: Consumer<VoiceCommand>,
+}
+
+impl ProcessHandler for MixerProcess {
+    fn process(&mut self, _: &Client, ps: &ProcessScope) -> Control {
+        while let Ok(command) = self.commands.pop() {
+            match command {
+                VoiceCommand::Trigger { voice, data, gain } => self.mixer.trigger(voice, data, gain),
+                VoiceCommand::Stop(voice) => self.mixer.stop(voice),
+                VoiceCommand::StopAll => self.mixer.stop_all(),
+            }
+        }
+        self.mixer.process(self.port.as_mut_slice(ps));
+        Control::Continue
+    }
+}
+
+/// Plays tracks through a JACK client with one mono output port, one mixer voice per track.
+/// Volume changes apply from the next trigger on; effects and tuning aren't supported.
+pub(crate) struct JackPlayer {
+    /// Keeps the client and its process callback running
+    _client: AsyncClient<(), MixerProcess>,
+    voices: Producer<VoiceCommand>,
+    /// Samples the mixer stopped playing, dropped here rather than on the JACK thread
+    retired: Consumer<ActiveSample>,
+    sample_dir: PathBuf,
+    /// JACK server sample rate; samples are resampled to it on load
+    sample_rate: u32,
+    tracks: Vec<Track>,
+    /// Decoded sample of each track, shared by tracks playing the same file
+    track_data: Vec<Arc<Vec<i16>>>,
+    loaded: HashMap<PathBuf, Arc<Vec<i16>>>,
+    master_volume: f32,
+    soloed_tracks: HashSet<usize>,
+    auto_normalize: Option<f32>,
+}
+
+impl JackPlayer {
+    /// Open a JACK client named `client_name` and connect its output to the system playback ports
+    pub(crate) fn new(sample_dir: impl AsRef<Path>, client_name: &str) -> Result<Self, AudioError> {
+        let jack_error = |e: jack::Error| AudioError::InitializationError(format!("JACK client '{}': {}", client_name, e));
+        let (client, _status) = Client::new(client_name, ClientOptions::NO_START_SERVER).map_err(jack_error)?;
+        let port = client.register_port("out", AudioOut).map_err(jack_error)?;
+        let port_name = port.name().map_err(jack_error)?;
+        let sample_rate = client.sample_rate() as u32;
+
+        let (voices, commands) = RingBuffer::new(VOICE_QUEUE_CAPACITY);
+        // Every queued trigger and every playing voice can retire at most one sample
+        let (mixer, retired) = Mixer::with_return_queue(MAX_VOICES, VOICE_QUEUE_CAPACITY + MAX_VOICES);
+        let process = MixerProcess { port, mixer, commands };
+        let client = client.activate_async((), process).map_err(jack_error)?;
+
+        // The port is mono, so feed it to both sides of the default output
+        let playback = client.as_client().ports(Some("system:playback_.*"), None, PortFlags::IS_INPUT);
+        for destination in playback.iter().take(2) {
+            if let Err(e) = client.as_client().connect_ports_by_name(&port_name, destination) {
+                warn!("Failed to connect {} to {}: {}", port_name, destination, e);
+            }
+        }
+        info!("JACK client '{}' running at {} Hz", client_name, sample_rate);
+
+        Ok(Self {
+            _client: client,
+            voices,
+            retired,
+            sample_dir: sample_dir.as_ref().to_path_buf(),
+            sample_rate,
+            tracks: Vec::new(),
+            track_data: Vec::new(),
+            loaded: HashMap::new(),
+            master_volume: 1.0,
+            soloed_tracks: HashSet::new(),
+            auto_normalize: None,
+        })
+    }
+
+    /// Decode a sample file to mono, reusing an earlier load of the same file
+    fn load(&mut self, file_path: &str) -> Result<Arc<Vec<i16>>, AudioError> {
+        let path = self.sample_dir.join(file_path);
+        if let Some(data) = self.loaded.get(&path) {
+            return Ok(Arc::clone(data));
+        }
+        debug!("Loading sample {} for JACK", path.display());
+        let bytes = std::fs::read(&path)
+            .map_err(|e| AudioError::SampleLoadError(file_path.to_string(), format!("Failed to open file: {}", e)))?;
+        let (pcm, channels) = decode_to_pcm(bytes, self.sample_rate)
+            .map_err(|e| AudioError::SampleLoadError(file_path.to_string(), e))?;
+        let mut mono: Vec<i16> = pcm.chunks(channels.max(1) as usize)
+            .map(|frame| (frame.iter().map(|&s| i32::from(s)).sum::<i32>() / frame.len() as i32) as i16)
+            .collect();
+        if let Some(target_peak) = self.auto_normalize {
+            SampleProcessor::normalize(&mut mono, target_peak);
+        }
+        let data = Arc::new(mono);
+        self.loaded.insert(path, Arc::clone(&data));
+        Ok(data)
+    }
+
+    fn send(&mut self, command: VoiceCommand) -> Result<(), AudioError> {
+        while self.retired.pop().is_ok() {}
+        self.voices.push(command)
+            .map_err(|_| AudioError::PlaybackError("JACK voice queue is full".to_string()))
+    }
+
+    /// Replace the tracks, loading their samples
+    pub(crate) fn initialize_with_tracks(&mut self, tracks: &[Track]) -> Result<(), AudioError> {
+        self.send(VoiceCommand::StopAll)?;
+        self.tracks.clear();
+        self.track_data.clear();
+        self.loaded.clear();
+        for track in tracks {
+            self.add_track(track.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Append a track after the existing ones, loading its sample if needed
+    pub(crate) fn add_track(&mut self, track: Track) -> Result<usize, AudioError> {
+        if self.tracks.len() >= MAX_VOICES {
+            return Err(AudioError::PlaybackError(format!("JACK output supports at most {} tracks", MAX_VOICES)));
+        }
+        let data = self.load(&track.sample)?;
+        self.tracks.push(track);
+        self.track_data.push(data);
+        Ok(self.tracks.len() - 1)
+    }
+
+    /// Remove a track; tracks after it move down one index and are cut off
+    pub(crate) fn remove_track(&mut self, track_idx: usize) -> Result<(), AudioError> {
+        if track_idx >= self.tracks.len() {
+            return Ok(());
+        }
+        for voice in track_idx..self.tracks.len() {
+            self.send(VoiceCommand::Stop(voice))?;
+        }
+        self.tracks.remove(track_idx);
+        self.track_data.remove(track_idx);
+        self.soloed_tracks = std::mem::take(&mut self.soloed_tracks)
+            .into_iter()
+            .filter(|&track| track != track_idx)
+            .map(|track| if track > track_idx { track - 1 } else { track })
+            .collect();
+        Ok(())
+    }
+
+    /// Point a track at a different sample file, loading it if needed
+    pub(crate) fn set_track_sample(&mut self, track_idx: usize, file_path: &str) -> Result<(), AudioError> {
+        let data = self.load(file_path)?;
+        let track = self.tracks.get_mut(track_idx)
+            .ok_or_else(|| AudioError::PlaybackError(format!("Track {} not found", track_idx)))?;
+        track.sample = file_path.to_string();
+        self.track_data[track_idx] = data;
+        Ok(())
+    }
+
+    /// Start a track's sample from the beginning, scaled by `velocity` (0.0 to 1.0) when given
+    pub(crate) fn trigger(&mut self, track_idx: usize, velocity: Option<f32>) -> Result<(), AudioError> {
+        let Some(track) = self.tracks.get(track_idx) else {
+            debug!("No JACK track {}", track_idx);
+            return Ok(());
+        };
+        if track.muted || (!self.soloed_tracks.is_empty() && !self.soloed_tracks.contains(&track_idx)) {
+            return Ok(());
+        }
+        let gain = track.volume * self.master_volume * velocity.unwrap_or(1.0);
+        let data = Arc::clone(&self.track_data[track_idx]);
+        self.send(VoiceCommand::Trigger { voice: track_idx, data, gain })
+    }
+
+    pub(crate) fn set_track_volume(&mut self, track_idx: usize, volume: f32) -> Result<(), AudioError> {
+        let track = self.tracks.get_mut(track_idx)
+            .ok_or_else(|| AudioError::PlaybackError(format!("Track {} not found", track_idx)))?;
+        track.volume = volume;
+        Ok(())
+    }
+
+    pub(crate) fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume;
+    }
+
+    /// Mute or unmute a track; muting also cuts off anything it is playing
+    pub(crate) fn set_track_muted(&mut self, track_idx: usize, muted: bool) -> Result<(), AudioError> {
+        let track = self.tracks.get_mut(track_idx)
+            .ok_or_else(|| AudioError::PlaybackError(format!("Track {} not found", track_idx)))?;
+        track.muted = muted;
+        if muted {
+            self.send(VoiceCommand::Stop(track_idx))?;
+        }
+        Ok(())
+    }
+
+    /// Solo or unsolo a track. While any track is soloed, the others are cut off and ignore triggers.
+    pub(crate) fn set_track_soloed(&mut self, track_idx: usize, soloed: bool) -> Result<(), AudioError> {
+        if !soloed {
+            self.soloed_tracks.remove(&track_idx);
+            return Ok(());
+        }
+        self.soloed_tracks.insert(track_idx);
+        for voice in 0..self.tracks.len() {
+            if !self.soloed_tracks.contains(&voice) {
+                self.send(VoiceCommand::Stop(voice))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Normalize every sample loaded from now on to `target_peak` (0.0 to 1.0 of full scale)
+    pub(crate) fn set_auto_normalize(&mut self, enabled: bool, target_peak: f32) {
+        self.auto_normalize = enabled.then_some(target_peak.clamp(0.0, 1.0));
+    }
+
+    pub(crate) fn stop_track(&mut self, track_idx: usize) -> Result<(), AudioError> {
+        self.send(VoiceCommand::Stop(track_idx))
+    }
+
+    pub(crate) fn stop_all(&mut self) -> Result<(), AudioError> {
+        self.send(VoiceCommand::StopAll)
+    }
+
+    /// Bytes of decoded sample data held for the tracks
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.loaded.values().map(|data| data.len() * std::mem::size_of::<i16>()).sum()
+    }
+}
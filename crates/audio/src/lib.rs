@@ -1,8 +1,15 @@
 // audio module
+mod config;
 mod connector;
+mod headless;
+#[cfg(feature = "jack")]
+mod jack_output;
+#[cfg(feature = "jack")]
+pub mod mixer;
+pub mod render;
 
 use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
 use std::io::{BufReader, Cursor};
 use std::path::{Path, PathBuf};
@@ -10,9 +17,15 @@ use log::{debug, error, info};
 use sequencer::TriggerEvent;
 use project::model::Track;
 use rodio::source::Source;
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use serde::{Deserialize, Serialize};
 
 // Re-export important types
-pub use crate::connector::AudioConnector;
+pub use crate::config::{AudioConfig, DEFAULT_MAX_SAMPLE_MEMORY_BYTES};
+#[cfg(feature = "jack")]
+pub use crate::config::AudioBackend;
+pub use crate::connector::{AudioConnector, AudioMetrics};
+pub use crate::headless::{HeadlessAudioConnector, LoggedTrigger};
 
 /// Error types for the audio system
 #[derive(Debug)]
@@ -22,6 +35,10 @@ pub enum AudioError {
     PlaybackError(String),
     SampleNotFound(String),
     IoError(std::io::Error),
+    /// The audio thread did not accept a command in time
+    Timeout(std::time::Duration),
+    /// A sample has no clear fundamental frequency (sample index)
+    PitchNotDetected(usize),
 }
 
 impl std::fmt::Display for AudioError {
@@ -32,6 +49,8 @@ impl std::fmt::Display for AudioError {
             Self::PlaybackError(msg) => write!(f, "Playback error: {}", msg),
             Self::SampleNotFound(msg) => write!(f, "Sample not found: {}", msg),
             Self::IoError(err) => write!(f, "IO error: {}", err),
+            Self::Timeout(timeout) => write!(f, "Audio thread did not respond within {:?}", timeout),
+            Self::PitchNotDetected(sample_idx) => write!(f, "No pitch detected in sample {}", sample_idx),
         }
     }
 }
@@ -51,19 +70,210 @@ impl From<std::io::Error> for AudioError {
     }
 }
 
+/// Names of the audio output devices available on the default host
+pub fn output_device_names() -> Vec<String> {
+    match rodio::cpal::default_host().output_devices() {
+        Ok(devices) => devices.filter_map(|device| device.name().ok()).collect(),
+        Err(e) => {
+            debug!("Failed to enumerate output devices: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Find an output device by its exact name
+pub(crate) fn find_output_device(name: &str) -> Result<rodio::Device, AudioError> {
+    rodio::cpal::default_host()
+        .output_devices()
+        .map_err(|e| AudioError::InitializationError(e.to_string()))?
+        .find(|device| device.name().is_ok_and(|n| n == name))
+        .ok_or_else(|| AudioError::InitializationError(format!("Audio device '{}' not found", name)))
+}
+
+/// Pick a stream configuration on `device` that runs at the configured sample rate
+fn stream_config_for(device: &rodio::Device, config: &AudioConfig) -> Result<rodio::SupportedStreamConfig, AudioError> {
+    let ranges: Vec<_> = device
+        .supported_output_configs()
+        .map_err(|e| AudioError::InitializationError(e.to_string()))?
+        .filter(|range| {
+            range.min_sample_rate().0 <= config.sample_rate && config.sample_rate <= range.max_sample_rate().0
+        })
+        .collect();
+    
+    // Prefer stereo, but take any channel layout supporting the rate
+    let range = ranges.iter()
+        .find(|range| range.channels() == 2)
+        .or_else(|| ranges.first())
+        .ok_or_else(|| AudioError::InitializationError(format!(
+            "Sample rate {} Hz is not supported by the audio device", config.sample_rate
+        )))?;
+    
+    Ok((*range).with_sample_rate(rodio::cpal::SampleRate(config.sample_rate)))
+}
+
+/// Decode an audio file and resample it to `output_rate`.
+/// Returns interleaved PCM and the channel count.
+fn decode_to_pcm(bytes: Vec<u8>, output_rate: u32) -> Result<(Vec<i16>, u16), String> {
+    let decoder = Decoder::new(Cursor::new(bytes)).map_err(|e| format!("Failed to decode file: {}", e))?;
+    let channels = decoder.channels();
+    let source_rate = decoder.sample_rate();
+    let pcm: Vec<i16> = decoder.collect();
+    
+    if source_rate != output_rate {
+        debug!("Resampling from {} Hz to {} Hz", source_rate, output_rate);
+    }
+    Ok((resample_linear(&pcm, channels, source_rate, output_rate), channels))
+}
+
+/// Resample interleaved PCM with linear interpolation between neighbouring frames
+fn resample_linear(data: &[i16], channels: u16, from_rate: u32, to_rate: u32) -> Vec<i16> {
+    let channels = channels.max(1) as usize;
+    let in_frames = data.len() / channels;
+    if from_rate == to_rate || from_rate == 0 || in_frames == 0 {
+        return data.to_vec();
+    }
+    
+    let out_frames = (in_frames as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let step = from_rate as f64 / to_rate as f64;
+    let mut resampled = Vec::with_capacity(out_frames * channels);
+    
+    for frame in 0..out_frames {
+        let pos = frame as f64 * step;
+        let idx = (pos as usize).min(in_frames - 1);
+        let next = (idx + 1).min(in_frames - 1);
+        let frac = pos - idx as f64;
+        for ch in 0..channels {
+            let a = data[idx * channels + ch] as f64;
+            let b = data[next * channels + ch] as f64;
+            resampled.push((a + (b - a) * frac).round() as i16);
+        }
+    }
+    
+    resampled
+}
+
+/// Frames from the start of a sample used for pitch detection
+const PITCH_DETECTION_FRAMES: usize = 4096;
+
+/// Range of fundamentals pitch detection looks for, in Hz
+const MIN_PITCH_HZ: f32 = 50.0;
+const MAX_PITCH_HZ: f32 = 2000.0;
+
+/// Normalized autocorrelation a sample must reach to count as pitched
+const PITCH_CLARITY_THRESHOLD: f32 = 0.6;
+
+/// Fundamental frequency of interleaved PCM in Hz, from the autocorrelation of its
+/// first `PITCH_DETECTION_FRAMES` frames mixed to mono. None if nothing between
+/// `MIN_PITCH_HZ` and `MAX_PITCH_HZ` repeats clearly enough, as with noisy hits.
+fn detect_fundamental(data: &[i16], channels: u16, sample_rate: u32) -> Option<f32> {
+    let channels = channels.max(1) as usize;
+    let mono: Vec<f32> = data.chunks(channels)
+        .take(PITCH_DETECTION_FRAMES)
+        .map(|frame| frame.iter().map(|&s| s as f32).sum::<f32>() / channels as f32)
+        .collect();
+    let min_lag = ((sample_rate as f32 / MAX_PITCH_HZ) as usize).max(2);
+    let max_lag = (sample_rate as f32 / MIN_PITCH_HZ).ceil() as usize;
+    if mono.len() <= max_lag + 1 {
+        return None;
+    }
+    
+    // Correlation at `lag`, scaled by the energy of both windows so a perfect repeat scores 1
+    let clarity = |lag: usize| {
+        let (mut correlation, mut energy) = (0.0f64, 0.0f64);
+        for (a, b) in mono.iter().zip(&mono[lag..]) {
+            correlation += (*a as f64) * (*b as f64);
+            energy += (*a as f64).powi(2) + (*b as f64).powi(2);
+        }
+        if energy == 0.0 { 0.0 } else { (2.0 * correlation / energy) as f32 }
+    };
+    let scores: Vec<f32> = (min_lag - 1..=max_lag + 1).map(clarity).collect();
+    let best = scores.iter().copied().fold(0.0f32, f32::max);
+    if best < PITCH_CLARITY_THRESHOLD {
+        return None;
+    }
+    
+    // The first peak close to the best avoids picking a multiple of the period
+    let peak = (1..scores.len() - 1).find(|&i| {
+        scores[i] >= 0.9 * best && scores[i] >= scores[i - 1] && scores[i] >= scores[i + 1]
+    })?;
+    let (a, b, c) = (scores[peak - 1], scores[peak], scores[peak + 1]);
+    let denominator = a - 2.0 * b + c;
+    let offset = if denominator == 0.0 { 0.0 } else { 0.5 * (a - c) / denominator };
+    let period = (min_lag - 1 + peak) as f32 + offset;
+    Some(sample_rate as f32 / period)
+}
+
+/// Output sample rate the default configuration of `device` runs at
+fn device_sample_rate(device: &rodio::Device) -> u32 {
+    device.default_output_config()
+        .map(|config| config.sample_rate().0)
+        .unwrap_or(AudioConfig::default().sample_rate)
+}
+
+/// Rekey a per-track map after `removed` is deleted so later tracks move down one index
+fn shift_down<V>(map: HashMap<usize, V>, removed: usize) -> HashMap<usize, V> {
+    map.into_iter()
+        .filter(|(idx, _)| *idx != removed)
+        .map(|(idx, value)| if idx > removed { (idx - 1, value) } else { (idx, value) })
+        .collect()
+}
+
+/// Drop entries pointing at a removed sample and renumber the ones after it
+fn forget_sample_index<K>(map: &mut HashMap<K, usize>, removed: usize) {
+    map.retain(|_, idx| *idx != removed);
+    for idx in map.values_mut() {
+        if *idx > removed {
+            *idx -= 1;
+        }
+    }
+}
+
+/// Format information about a loaded sample
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleMetadata {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub duration: std::time::Duration,
+}
+
 /// Represents an audio sample in memory
 pub struct Sample {
     /// The name of the sample
     pub name: String,
     
-    /// The raw audio data
-    data: Vec<u8>,
+    /// Decoded interleaved PCM at the player's output rate
+    data: Vec<i16>,
+    
+    /// Number of interleaved channels in `data`
+    channels: u16,
     
-    /// File path for debugging/reference
-    #[allow(dead_code)]
+    /// Sample rate of `data`
+    sample_rate: u32,
+    
+    /// File path the sample was loaded from
     path: PathBuf,
 }
 
+impl Sample {
+    /// Channel count, sample rate and playing time of the decoded data
+    pub fn metadata(&self) -> SampleMetadata {
+        let frames = self.data.len() / self.channels.max(1) as usize;
+        SampleMetadata {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            duration: std::time::Duration::from_secs_f64(frames as f64 / self.sample_rate.max(1) as f64),
+        }
+    }
+    
+    /// Bytes of decoded PCM held in memory
+    pub fn size_bytes(&self) -> usize {
+        self.data.len() * std::mem::size_of::<i16>()
+    }
+}
+
+/// Length of the silence `warm_up` plays on every track: 0.1 s at 44.1 kHz
+const WARM_UP_SILENCE_SAMPLES: usize = 4410;
+
 /// Audio player for sample playback
 pub struct SamplePlayer {
     /// Output stream and handle for audio playback
@@ -90,6 +300,30 @@ pub struct SamplePlayer {
     
     /// Sample processor for effects
     processor: SampleProcessor,
+    
+    /// Sample rate of the output stream; samples are resampled to it on load
+    output_sample_rate: u32,
+    
+    /// Loading a sample fails if it would take `memory_usage` past this
+    max_sample_memory_bytes: usize,
+    
+    /// Sample indexes from least to most recently played
+    access_order: VecDeque<usize>,
+    
+    /// Loading past this many samples evicts the least recently played one
+    max_samples: usize,
+    
+    /// CRC-32 of each loaded sample's file contents -> sample index
+    content_hashes: HashMap<u32, usize>,
+    
+    /// Gain applied on top of every track's volume
+    master_volume: f32,
+    
+    /// Tracks being soloed; when any are, every other track is silent
+    soloed_tracks: HashSet<usize>,
+    
+    /// Peak, as a fraction of full scale, that samples are normalized to when loaded
+    auto_normalize: Option<f32>,
 }
 
 impl SamplePlayer {
@@ -98,8 +332,42 @@ impl SamplePlayer {
         // Initialize audio output stream
         let (stream, stream_handle) = OutputStream::try_default()
             .map_err(|e| AudioError::InitializationError(e.to_string()))?;
-            
-        Ok(Self {
+        let output_sample_rate = rodio::cpal::default_host()
+            .default_output_device()
+            .map(|device| device_sample_rate(&device))
+            .unwrap_or(AudioConfig::default().sample_rate);
+        Ok(Self::with_stream(stream, stream_handle, sample_dir, output_sample_rate))
+    }
+    
+    /// Create a SamplePlayer that plays through the named output device
+    pub fn new_with_device(sample_dir: impl AsRef<Path>, device_name: &str) -> Result<Self, AudioError> {
+        let device = find_output_device(device_name)?;
+        let (stream, stream_handle) = OutputStream::try_from_device(&device)
+            .map_err(|e| AudioError::InitializationError(e.to_string()))?;
+        info!("Using audio device: {}", device_name);
+        Ok(Self::with_stream(stream, stream_handle, sample_dir, device_sample_rate(&device)))
+    }
+    
+    /// Create a SamplePlayer with explicit stream settings, optionally on a named device
+    pub fn new_with_config(sample_dir: impl AsRef<Path>, device_name: Option<&str>, config: &AudioConfig) -> Result<Self, AudioError> {
+        let device = match device_name {
+            Some(name) => find_output_device(name)?,
+            None => rodio::cpal::default_host()
+                .default_output_device()
+                .ok_or_else(|| AudioError::InitializationError("No default audio device".to_string()))?,
+        };
+        let stream_config = stream_config_for(&device, config)?;
+        let (stream, stream_handle) = OutputStream::try_from_device_config(&device, stream_config)
+            .map_err(|e| AudioError::InitializationError(e.to_string()))?;
+        info!("Audio stream opened at {} Hz", config.sample_rate);
+        let mut player = Self::with_stream(stream, stream_handle, sample_dir, config.sample_rate);
+        player.max_sample_memory_bytes = config.max_sample_memory_bytes;
+        player.max_samples = config.max_samples;
+        Ok(player)
+    }
+    
+    fn with_stream(stream: OutputStream, stream_handle: OutputStreamHandle, sample_dir: impl AsRef<Path>, output_sample_rate: u32) -> Self {
+        Self {
             _stream: stream,
             stream_handle,
             track_sinks: HashMap::new(),
@@ -109,10 +377,74 @@ impl SamplePlayer {
             sample_dir: sample_dir.as_ref().to_path_buf(),
             active: false,
             processor: SampleProcessor::new(),
-        })
+            output_sample_rate,
+            max_sample_memory_bytes: config::DEFAULT_MAX_SAMPLE_MEMORY_BYTES,
+            access_order: VecDeque::new(),
+            max_samples: config::DEFAULT_MAX_SAMPLES,
+            content_hashes: HashMap::new(),
+            master_volume: 1.0,
+            soloed_tracks: HashSet::new(),
+            auto_normalize: None,
+        }
+    }
+    
+    /// Sample rate of the output stream
+    pub fn output_sample_rate(&self) -> u32 {
+        self.output_sample_rate
+    }
+    
+    /// Get a loaded sample by index
+    pub fn sample(&self, sample_idx: usize) -> Option<&Sample> {
+        self.samples.get(sample_idx)
+    }
+    
+    /// Total bytes of decoded sample data held by the player
+    pub fn memory_usage(&self) -> usize {
+        self.samples.iter().map(Sample::size_bytes).sum()
+    }
+    
+    /// The loaded sample using the most memory
+    pub fn largest_sample(&self) -> Option<&Sample> {
+        self.samples.iter().max_by_key(|sample| sample.size_bytes())
+    }
+    
+    /// Change the limit on decoded sample data; samples already loaded are kept
+    pub fn set_max_sample_memory(&mut self, bytes: usize) {
+        self.max_sample_memory_bytes = bytes;
+    }
+    
+    /// Change how many samples stay loaded; extra samples are evicted on the next load
+    pub fn set_max_samples(&mut self, max_samples: usize) {
+        self.max_samples = max_samples.max(1);
+    }
+    
+    /// Move a sample to the most recently used end of the access order
+    fn touch_sample(&mut self, sample_idx: usize) {
+        self.access_order.retain(|&idx| idx != sample_idx);
+        self.access_order.push_back(sample_idx);
+    }
+    
+    /// Drop the least recently played sample. Tracks that used it lose their mapping
+    /// and reload the sample on their next trigger.
+    fn evict_least_recent_sample(&mut self) {
+        let Some(evicted) = self.access_order.pop_front() else {
+            return;
+        };
+        let sample = self.samples.remove(evicted);
+        info!("Evicted sample '{}' to stay within {} loaded samples", sample.name, self.max_samples);
+        
+        for idx in self.access_order.iter_mut() {
+            if *idx > evicted {
+                *idx -= 1;
+            }
+        }
+        forget_sample_index(&mut self.track_to_sample, evicted);
+        forget_sample_index(&mut self.content_hashes, evicted);
     }
     
-    /// Load a sample into memory
+    /// Load a sample into memory, decoded and resampled to the output rate.
+    /// If `max_samples` are already loaded, the least recently played one is evicted first.
+    /// A file with the same contents as a loaded sample reuses that sample's index.
     pub fn load_sample(&mut self, name: &str, file_path: &str) -> Result<usize, AudioError> {
         let path = self.sample_dir.join(file_path);
         debug!("Loading sample '{}' from {}", name, path.display());
@@ -132,14 +464,51 @@ impl SamplePlayer {
                 file_path.to_string(), 
                 format!("Failed to read file: {}", e)
             ))?;
+        
+        // The same audio saved under another name shares one copy in memory
+        let content_hash = crc32fast::hash(&buffer);
+        if let Some(&existing) = self.content_hashes.get(&content_hash) {
+            // A matching checksum alone could be a collision, so compare the files themselves
+            if std::fs::read(&self.samples[existing].path).is_ok_and(|existing_bytes| existing_bytes == buffer) {
+                debug!("Sample '{}' has the same contents as '{}', reusing it", name, self.samples[existing].name);
+                return Ok(existing);
+            }
+        }
+        
+        // Decode now so playback doesn't depend on implicit rate conversion
+        let (mut data, channels) = decode_to_pcm(buffer, self.output_sample_rate)
+            .map_err(|e| AudioError::SampleLoadError(file_path.to_string(), e))?;
+        if let Some(target_peak) = self.auto_normalize {
+            SampleProcessor::normalize(&mut data, target_peak);
+        }
+        
+        let size = data.len() * std::mem::size_of::<i16>();
+        let usage = self.memory_usage();
+        if usage + size > self.max_sample_memory_bytes {
+            return Err(AudioError::SampleLoadError(
+                file_path.to_string(),
+                format!(
+                    "Sample memory limit exceeded: {} bytes needed, {} of {} bytes in use",
+                    size, usage, self.max_sample_memory_bytes
+                ),
+            ));
+        }
             
+        while self.samples.len() >= self.max_samples && !self.access_order.is_empty() {
+            self.evict_least_recent_sample();
+        }
+        
         // Store the sample
         let sample_idx = self.samples.len();
         self.samples.push(Sample {
             name: name.to_string(),
-            data: buffer,
+            data,
+            channels,
+            sample_rate: self.output_sample_rate,
             path,
         });
+        self.access_order.push_back(sample_idx);
+        self.content_hashes.insert(content_hash, sample_idx);
         
         Ok(sample_idx)
     }
@@ -168,7 +537,7 @@ impl SamplePlayer {
                 ))?;
                 
             // Set the track's volume
-            sink.set_volume(track.volume);
+            sink.set_volume(track.volume * self.master_volume);
                 
             // Store the mappings
             self.track_sinks.insert(track_idx, sink);
@@ -191,22 +560,95 @@ impl SamplePlayer {
             return Ok(idx);
         }
         
-        // If not, load it
+        // If not, load it; files with the same contents as a loaded sample still share it
         self.load_sample(name, file_path)
     }
     
+    /// Point a track at a different sample file, loading it if needed
+    pub fn set_track_sample(&mut self, track_idx: usize, file_path: &str) -> Result<usize, AudioError> {
+        let path = self.sample_dir.join(file_path);
+        let sample_idx = match self.samples.iter().position(|s| s.path == path) {
+            Some(idx) => idx,
+            None => self.load_sample(file_path, file_path)?,
+        };
+
+        self.track_to_sample.insert(track_idx, sample_idx);
+        if let Some(track) = self.tracks.get_mut(&track_idx) {
+            track.sample = file_path.to_string();
+        }
+
+        info!("Track {} now plays sample '{}'", track_idx, file_path);
+        Ok(sample_idx)
+    }
+
+    /// Append a track after the existing ones, loading its sample if needed
+    pub fn add_track(&mut self, track: Track) -> Result<usize, AudioError> {
+        let track_idx = self.tracks.len();
+        let sample_idx = self.find_or_load_sample(&track.name, &track.sample)?;
+
+        let sink = Sink::try_new(&self.stream_handle)
+            .map_err(|e| AudioError::InitializationError(
+                format!("Failed to create sink for track {}: {}", track_idx, e)
+            ))?;
+        sink.set_volume(track.volume * self.master_volume);
+
+        info!("Added track {}: '{}' with sample '{}'", track_idx, track.name, track.sample);
+        self.track_sinks.insert(track_idx, sink);
+        self.track_to_sample.insert(track_idx, sample_idx);
+        self.tracks.insert(track_idx, track);
+        Ok(track_idx)
+    }
+
+    /// Stop and remove a track; tracks after it move down one index
+    pub fn remove_track(&mut self, track_idx: usize) {
+        if let Some(sink) = self.track_sinks.remove(&track_idx) {
+            sink.stop();
+        }
+        self.track_to_sample.remove(&track_idx);
+        self.tracks.remove(&track_idx);
+
+        self.track_sinks = shift_down(std::mem::take(&mut self.track_sinks), track_idx);
+        self.track_to_sample = shift_down(std::mem::take(&mut self.track_to_sample), track_idx);
+        self.tracks = shift_down(std::mem::take(&mut self.tracks), track_idx);
+        self.soloed_tracks = std::mem::take(&mut self.soloed_tracks)
+            .into_iter()
+            .filter(|&track| track != track_idx)
+            .map(|track| if track > track_idx { track - 1 } else { track })
+            .collect();
+        self.processor.remove_track(track_idx);
+        info!("Removed track {}", track_idx);
+    }
+
     /// Process trigger events from the sequencer
     pub fn process_trigger(&mut self, event: &TriggerEvent) -> Result<(), AudioError> {
+        self.process_trigger_with_velocity(event, None)
+    }
+    
+    /// Process a trigger, scaling the sample by `velocity` (0.0 to 1.0) when given
+    pub fn process_trigger_with_velocity(&mut self, event: &TriggerEvent, velocity: Option<f32>) -> Result<(), AudioError> {
         if !self.active {
             return Ok(());
         }
         
-        // Check if we have a mapping for this track
+        // Muted tracks stay silent even if a trigger reaches them
         let track_idx = event.track_idx;
-        if !self.track_to_sample.contains_key(&track_idx) {
-            debug!("No sample mapping for track {}", track_idx);
+        if self.tracks.get(&track_idx).is_some_and(|track| track.muted) {
+            debug!("Ignoring trigger for muted track {}", track_idx);
             return Ok(());
         }
+        if !self.soloed_tracks.is_empty() && !self.soloed_tracks.contains(&track_idx) {
+            debug!("Ignoring trigger for track {} while others are soloed", track_idx);
+            return Ok(());
+        }
+        
+        // Check if we have a mapping for this track, reloading an evicted sample
+        if !self.track_to_sample.contains_key(&track_idx) {
+            if !self.tracks.contains_key(&track_idx) {
+                debug!("No sample mapping for track {}", track_idx);
+                return Ok(());
+            }
+            self.reload_track_sample(track_idx)?;
+        }
         
         // Get the sample
         let sample_idx = self.track_to_sample[&track_idx];
@@ -217,12 +659,29 @@ impl SamplePlayer {
         }
         
         // Play the sample
-        self.play_sample(track_idx, sample_idx)
+        self.play_sample(track_idx, event.step_idx, sample_idx, velocity)
+    }
+    
+    /// Load the sample of a track whose sample was evicted
+    fn reload_track_sample(&mut self, track_idx: usize) -> Result<usize, AudioError> {
+        let track = self.tracks[&track_idx].clone();
+        let path = self.sample_dir.join(&track.sample);
+        let sample_idx = match self.samples.iter().position(|s| s.path == path) {
+            Some(idx) => idx,
+            None => {
+                debug!("Reloading evicted sample '{}' for track {}", track.sample, track_idx);
+                self.load_sample(&track.name, &track.sample)?
+            }
+        };
+        self.track_to_sample.insert(track_idx, sample_idx);
+        Ok(sample_idx)
     }
     
-    /// Play a specific sample on a specific track
-    pub fn play_sample(&mut self, track_idx: usize, sample_idx: usize) -> Result<(), AudioError> {
+    /// Play a specific sample on a specific track, applying any effects set for this step.
+    /// `velocity` scales this one playback on top of the track volume.
+    pub fn play_sample(&mut self, track_idx: usize, step_idx: usize, sample_idx: usize, velocity: Option<f32>) -> Result<(), AudioError> {
         debug!("Playing sample {} on track {}", sample_idx, track_idx);
+        self.touch_sample(sample_idx);
         
         // Get the sink for this track
         let sink = match self.track_sinks.get_mut(&track_idx) {
@@ -250,22 +709,11 @@ impl SamplePlayer {
         // Force resetting the sink to ensure clean playback
         sink.clear();
         
-        // Create a cursor for the sample data
-        let cursor = Cursor::new(sample.data.clone());
-        
-        // Decode the sample
-        let source = match Decoder::new(cursor) {
-            Ok(source) => source,
-            Err(e) => {
-                error!("Failed to decode sample {}: {}", sample.name, e);
-                return Err(AudioError::PlaybackError(format!(
-                    "Failed to decode sample {}: {}", sample.name, e
-                )));
-            }
-        };
+        // Wrap the decoded sample data in a playable source
+        let source = rodio::buffer::SamplesBuffer::new(sample.channels, sample.sample_rate, sample.data.clone());
         
         // Apply effects using the processor
-        let processed_source = match self.processor.process_sample(track_idx, source) {
+        let processed_source = match self.processor.process_sample(track_idx, step_idx, source) {
             Ok(src) => src,
             Err(e) => {
                 error!("Failed to process effects for sample {}: {}", sample.name, e);
@@ -274,12 +722,15 @@ impl SamplePlayer {
         };
         
         // Play the processed sample
-        sink.append(processed_source);
+        match velocity {
+            Some(velocity) => sink.append(GainSource::new(processed_source, velocity.clamp(0.0, 1.0))),
+            None => sink.append(processed_source),
+        }
         sink.play(); // Explicitly set to play mode
         
         // Set the volume for this track
         if let Some(track) = self.tracks.get(&track_idx) {
-            sink.set_volume(track.volume);
+            sink.set_volume(track.volume * self.master_volume);
             debug!("Set volume for track {} to {}", track_idx, track.volume);
         }
         
@@ -295,6 +746,49 @@ impl SamplePlayer {
         Ok(())
     }
     
+    /// Prepare every track for its first trigger: create any missing sink and run the
+    /// track's sample through its effect chain once, so setup costs aren't paid mid-pattern.
+    /// Samples are already decoded when loaded, so there is no decoder to warm up.
+    pub fn preload_all_samples(&mut self) -> Result<(), AudioError> {
+        let mut assignments: Vec<(usize, usize)> = self.track_to_sample.iter()
+            .map(|(&track_idx, &sample_idx)| (track_idx, sample_idx))
+            .collect();
+        assignments.sort_unstable();
+        
+        for (track_idx, sample_idx) in assignments {
+            let sample = self.samples.get(sample_idx)
+                .ok_or_else(|| AudioError::SampleNotFound(format!("Sample index {} out of bounds", sample_idx)))?;
+            
+            if !self.track_sinks.contains_key(&track_idx) {
+                let sink = Sink::try_new(&self.stream_handle)
+                    .map_err(|e| AudioError::InitializationError(
+                        format!("Failed to create sink for track {}: {}", track_idx, e)
+                    ))?;
+                self.track_sinks.insert(track_idx, sink);
+            }
+            self.track_sinks[&track_idx].clear();
+            
+            let source = rodio::buffer::SamplesBuffer::new(sample.channels, sample.sample_rate, sample.data.clone());
+            let processed = self.processor.process_sample(track_idx, 0, source)?.count();
+            debug!("Preloaded sample '{}' for track {} ({} samples after effects)", sample.name, track_idx, processed);
+        }
+        
+        info!("Preloaded samples for {} tracks", self.track_to_sample.len());
+        Ok(())
+    }
+    
+    /// Preload every sample, then play a short silence on each track sink so the
+    /// output's buffers are running before the first real trigger
+    pub fn warm_up(&mut self) -> Result<(), AudioError> {
+        self.preload_all_samples()?;
+        for sink in self.track_sinks.values() {
+            sink.append(rodio::buffer::SamplesBuffer::new(1, 44100, vec![0i16; WARM_UP_SILENCE_SAMPLES]));
+            sink.play();
+        }
+        debug!("Warmed up {} track sinks", self.track_sinks.len());
+        Ok(())
+    }
+    
     /// Stop all playback
     pub fn stop_all(&mut self) {
         for (_, sink) in &self.track_sinks {
@@ -302,16 +796,108 @@ impl SamplePlayer {
         }
     }
     
+    /// Fundamental frequency of a loaded sample in Hz, or None if it has no clear pitch
+    pub fn detect_pitch(&self, sample_idx: usize) -> Option<f32> {
+        let sample = self.samples.get(sample_idx)?;
+        detect_fundamental(&sample.data, sample.channels, sample.sample_rate)
+    }
+    
+    /// Tune every track playing a sample so it sounds at `target_hz`. The track's
+    /// tuning is set so that, with its pitch effects, the sample is shifted by the
+    /// returned number of semitones.
+    pub fn auto_tune_to(&mut self, sample_idx: usize, target_hz: f32) -> Result<f32, AudioError> {
+        if sample_idx >= self.samples.len() {
+            return Err(AudioError::SampleNotFound(format!("Sample index {} out of bounds", sample_idx)));
+        }
+        let detected = self.detect_pitch(sample_idx).ok_or(AudioError::PitchNotDetected(sample_idx))?;
+        let semitones = 12.0 * (target_hz / detected).log2();
+        for (&track_idx, _) in self.track_to_sample.iter().filter(|(_, &idx)| idx == sample_idx) {
+            self.processor.set_total_pitch(track_idx, semitones);
+        }
+        debug!("Tuned sample {} from {:.1} Hz to {:.1} Hz ({:+.2} semitones)", sample_idx, detected, target_hz, semitones);
+        Ok(semitones)
+    }
+    
+    /// Stop whatever one track is playing, leaving the others alone
+    pub fn stop_track(&mut self, track_idx: usize) -> Result<(), AudioError> {
+        self.track_sinks.get(&track_idx).ok_or_else(|| {
+            AudioError::PlaybackError(format!("Track {} not found", track_idx))
+        })?.stop();
+        Ok(())
+    }
+    
+    /// Whether a track is producing audio
+    pub fn is_track_active(&self, track_idx: usize) -> bool {
+        self.track_sinks.get(&track_idx).is_some_and(|sink| !sink.empty())
+    }
+    
     /// Set volume for a specific track (0.0 to 1.0)
     pub fn set_track_volume(&mut self, track_idx: usize, volume: f32) -> Result<(), AudioError> {
         let sink = self.track_sinks.get(&track_idx).ok_or_else(|| {
             AudioError::PlaybackError(format!("Track {} not found", track_idx))
         })?;
         
-        sink.set_volume(volume);
+        sink.set_volume(volume * self.master_volume);
+        // Later triggers reapply the track's volume, so keep it current
+        if let Some(track) = self.tracks.get_mut(&track_idx) {
+            track.volume = volume;
+        }
+        Ok(())
+    }
+    
+    /// Scale every track's volume by `volume`, including samples already playing
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume;
+        for (track_idx, sink) in &self.track_sinks {
+            let track_volume = self.tracks.get(track_idx).map_or(1.0, |track| track.volume);
+            sink.set_volume(track_volume * volume);
+        }
+    }
+    
+    /// Gain applied on top of every track's volume
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume
+    }
+    
+    /// Volume a track's sink is playing at, or None if the track has no sink
+    pub fn track_sink_volume(&self, track_idx: usize) -> Option<f32> {
+        self.track_sinks.get(&track_idx).map(Sink::volume)
+    }
+    
+    /// Mute or unmute a track; muting also cuts off anything it is playing
+    pub fn set_track_muted(&mut self, track_idx: usize, muted: bool) -> Result<(), AudioError> {
+        let track = self.tracks.get_mut(&track_idx).ok_or_else(|| {
+            AudioError::PlaybackError(format!("Track {} not found", track_idx))
+        })?;
+        track.muted = muted;
+        if muted {
+            if let Some(sink) = self.track_sinks.get(&track_idx) {
+                sink.stop();
+            }
+        }
         Ok(())
     }
     
+    /// Normalize every sample loaded from now on to `target_peak` (0.0 to 1.0 of full scale).
+    /// Samples already loaded keep their levels.
+    pub fn set_auto_normalize(&mut self, enabled: bool, target_peak: f32) {
+        self.auto_normalize = enabled.then_some(target_peak.clamp(0.0, 1.0));
+    }
+    
+    /// Solo or unsolo a track. While any track is soloed, the others are cut off and ignore triggers.
+    pub fn set_track_soloed(&mut self, track_idx: usize, soloed: bool) {
+        if soloed {
+            self.soloed_tracks.insert(track_idx);
+            for (idx, sink) in &self.track_sinks {
+                if !self.soloed_tracks.contains(idx) {
+                    sink.stop();
+                }
+            }
+        } else {
+            self.soloed_tracks.remove(&track_idx);
+        }
+    }
+    
     /// Check if the player is active
     pub fn is_active(&self) -> bool {
         self.active
@@ -319,7 +905,7 @@ impl SamplePlayer {
 }
 
 /// Effects that can be applied to samples
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SampleEffect {
     /// Play the sample in reverse
     Reverse,
@@ -332,17 +918,110 @@ pub enum SampleEffect {
     
     /// Play only a portion of the sample (start_fraction, end_fraction)
     Partial(f32, f32),
+    
+    /// Shape the volume with an attack-decay-sustain-release envelope
+    Adsr {
+        attack_secs: f32,
+        decay_secs: f32,
+        sustain_level: f32,
+        release_secs: f32,
+    },
+    
+    /// Reduce bit depth and sample rate for a lo-fi sound
+    Bitcrush {
+        bit_depth: u8,
+        sample_rate_divisor: u32,
+    },
+    
+    /// Place the sample in the stereo field (-1.0 left ..= 1.0 right); mono samples become stereo
+    Pan(f32),
+    
+    /// Shift pitch (and length) by a number of semitones
+    Pitch(f32),
+    
+    /// Cut frequencies above the cutoff (Hz)
+    LowPass(f32),
+    
+    /// Cut frequencies below the cutoff (Hz)
+    HighPass(f32),
+    
+    /// Repeat the sample after a delay (milliseconds), each echo quieter than the last
+    Delay(f32),
+    
+    /// Pulse the volume with a sine LFO; `depth` 0.0-1.0 is how far the volume dips
+    Tremolo {
+        rate_hz: f32,
+        depth: f32,
+    },
+    
+    /// Turn down peaks above `threshold` (fraction of full scale) by `ratio`.
+    /// Attack and release are how many samples the gain takes to reach its target.
+    Compress {
+        threshold: f32,
+        ratio: f32,
+        attack_samples: usize,
+        release_samples: usize,
+    },
+}
+
+impl std::fmt::Display for SampleEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SampleEffect::Reverse => write!(f, "Reverse"),
+            SampleEffect::FadeIn(secs) => write!(f, "FadeIn({}s)", secs),
+            SampleEffect::FadeOut(secs) => write!(f, "FadeOut({}s)", secs),
+            SampleEffect::Partial(start, end) => write!(f, "Partial({}-{})", start, end),
+            SampleEffect::Adsr { attack_secs, decay_secs, sustain_level, release_secs } => write!(
+                f,
+                "ADSR(A {}s, D {}s, S {}, R {}s)",
+                attack_secs, decay_secs, sustain_level, release_secs
+            ),
+            SampleEffect::Bitcrush { bit_depth, sample_rate_divisor } => {
+                write!(f, "Bitcrush({}-bit, 1/{})", bit_depth, sample_rate_divisor)
+            }
+            SampleEffect::Pan(pan) => write!(f, "Pan({})", pan),
+            SampleEffect::Pitch(semitones) => write!(f, "Pitch({:+}st)", semitones),
+            SampleEffect::LowPass(cutoff) => write!(f, "LowPass({}Hz)", cutoff),
+            SampleEffect::HighPass(cutoff) => write!(f, "HighPass({}Hz)", cutoff),
+            SampleEffect::Delay(ms) => write!(f, "Delay({}ms)", ms),
+            SampleEffect::Tremolo { rate_hz, depth } => write!(f, "Tremolo({}Hz, {})", rate_hz, depth),
+            SampleEffect::Compress { threshold, ratio, attack_samples, release_samples } => write!(
+                f,
+                "Compress({} {}:1, A {}, R {})",
+                threshold, ratio, attack_samples, release_samples
+            ),
+        }
+    }
 }
 
 /// Sample processor for audio effects
 pub struct SampleProcessor {
     effects: HashMap<usize, Vec<SampleEffect>>,
+    /// Effects for a single (track, step), used instead of the track effects
+    step_effects: HashMap<(usize, usize), Vec<SampleEffect>>,
+    /// Per-track tuning in semitones, applied before any effect
+    tuning: HashMap<usize, f32>,
 }
 
 impl SampleProcessor {
     pub fn new() -> Self {
         Self {
             effects: HashMap::new(),
+            step_effects: HashMap::new(),
+            tuning: HashMap::new(),
+        }
+    }
+    
+    /// Scale `samples` so the loudest one reaches `target_peak` of full scale.
+    /// Silent buffers are left alone.
+    pub fn normalize(samples: &mut [i16], target_peak: f32) {
+        let max_abs = samples.iter().map(|sample| sample.unsigned_abs()).max().unwrap_or(0);
+        if max_abs == 0 {
+            return;
+        }
+        let scale = target_peak * i16::MAX as f32 / max_abs as f32;
+        for sample in samples.iter_mut() {
+            *sample = (*sample as f32 * scale).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16;
         }
     }
     
@@ -352,9 +1031,80 @@ impl SampleProcessor {
         track_effects.push(effect);
     }
     
-    /// Remove all effects from a track
+    /// Add an effect to a track, replacing an effect of the same kind if the track has one
+    pub fn set_effect(&mut self, track_idx: usize, effect: SampleEffect) {
+        let track_effects = self.effects.entry(track_idx).or_default();
+        let kind = std::mem::discriminant(&effect);
+        match track_effects.iter_mut().find(|existing| std::mem::discriminant(*existing) == kind) {
+            Some(existing) => *existing = effect,
+            None => track_effects.push(effect),
+        }
+    }
+    
+    /// Add an effect applied only when a track triggers on a specific step
+    pub fn add_step_effect(&mut self, track_idx: usize, step_idx: usize, effect: SampleEffect) {
+        self.step_effects.entry((track_idx, step_idx)).or_default().push(effect);
+    }
+    
+    /// Remove all effects from a track, including step overrides
     pub fn clear_effects(&mut self, track_idx: usize) {
         self.effects.remove(&track_idx);
+        self.step_effects.retain(|(track, _), _| *track != track_idx);
+    }
+
+    /// Tune a track by a number of semitones, on top of its pitch effects
+    pub fn set_tuning(&mut self, track_idx: usize, semitones: f32) {
+        if semitones == 0.0 {
+            self.tuning.remove(&track_idx);
+        } else {
+            self.tuning.insert(track_idx, semitones);
+        }
+    }
+    
+    /// Tuning of a track in semitones
+    pub fn tuning(&self, track_idx: usize) -> f32 {
+        self.tuning.get(&track_idx).copied().unwrap_or(0.0)
+    }
+    
+    /// Tune a track so its tuning and its pitch effects add up to `semitones`
+    pub fn set_total_pitch(&mut self, track_idx: usize, semitones: f32) {
+        let pitch_effects: f32 = self.effects.get(&track_idx)
+            .into_iter()
+            .flatten()
+            .map(|effect| match effect {
+                SampleEffect::Pitch(semitones) => *semitones,
+                _ => 0.0,
+            })
+            .sum();
+        self.set_tuning(track_idx, semitones - pitch_effects);
+    }
+    
+    /// Drop a track's effects and move the effects of later tracks down one index
+    pub fn remove_track(&mut self, track_idx: usize) {
+        self.clear_effects(track_idx);
+        self.effects = shift_down(std::mem::take(&mut self.effects), track_idx);
+        self.tuning = shift_down(std::mem::take(&mut self.tuning), track_idx);
+        self.step_effects = std::mem::take(&mut self.step_effects)
+            .into_iter()
+            .map(|((track, step), effects)| {
+                let track = if track > track_idx { track - 1 } else { track };
+                ((track, step), effects)
+            })
+            .collect();
+    }
+
+    /// Copy of every track's effect chain, keyed by track index, for saving.
+    /// Step overrides come from pattern metadata and are not included.
+    pub fn save_state(&self) -> HashMap<usize, Vec<SampleEffect>> {
+        self.effects.iter()
+            .filter(|(_, effects)| !effects.is_empty())
+            .map(|(&track_idx, effects)| (track_idx, effects.clone()))
+            .collect()
+    }
+    
+    /// Replace every track's effect chain with a saved state
+    pub fn load_state(&mut self, state: HashMap<usize, Vec<SampleEffect>>) {
+        self.effects = state;
     }
     
     /// Get all effects for a track
@@ -362,33 +1112,77 @@ impl SampleProcessor {
         self.effects.get(track_idx)
     }
     
-    /// Process a sample using the registered effects for a track
+    /// Number of effects in a track's chain (step overrides not included)
+    pub fn get_effect_count(&self, track_idx: usize) -> usize {
+        self.effects.get(&track_idx).map_or(0, Vec::len)
+    }
+    
+    /// Display names of a track's effects, in the order they are applied
+    pub fn list_effects(&self, track_idx: usize) -> Vec<String> {
+        self.effects.get(&track_idx)
+            .map(|effects| effects.iter().map(ToString::to_string).collect())
+            .unwrap_or_default()
+    }
+    
+    /// Remove one effect from a track's chain and return it
+    pub fn remove_effect(&mut self, track_idx: usize, effect_idx: usize) -> Result<SampleEffect, AudioError> {
+        match self.effects.get_mut(&track_idx) {
+            Some(effects) if effect_idx < effects.len() => Ok(effects.remove(effect_idx)),
+            _ => Err(AudioError::PlaybackError(format!("No effect {} on track {}", effect_idx, track_idx))),
+        }
+    }
+    
+    /// Swap one effect in a track's chain for another, returning the old one
+    pub fn replace_effect(&mut self, track_idx: usize, effect_idx: usize, effect: SampleEffect) -> Result<SampleEffect, AudioError> {
+        match self.effects.get_mut(&track_idx).and_then(|effects| effects.get_mut(effect_idx)) {
+            Some(slot) => Ok(std::mem::replace(slot, effect)),
+            None => Err(AudioError::PlaybackError(format!("No effect {} on track {}", effect_idx, track_idx))),
+        }
+    }
+    
+    /// Get the effects that apply to a track on a specific step
+    pub fn effects_for_step(&self, track_idx: usize, step_idx: usize) -> Option<&Vec<SampleEffect>> {
+        self.step_effects
+            .get(&(track_idx, step_idx))
+            .or_else(|| self.effects.get(&track_idx))
+    }
+    
+    /// Process a sample using the registered effects for a track and step.
+    /// Step effects replace the track effects when present.
     /// This returns a processed source ready for playback
-    pub fn process_sample(&self, track_idx: usize, source: Decoder<Cursor<Vec<u8>>>) 
-        -> Result<Box<dyn rodio::Source<Item = i16> + Send>, AudioError> {
+    pub fn process_sample<S>(&self, track_idx: usize, step_idx: usize, source: S) 
+        -> Result<Box<dyn rodio::Source<Item = i16> + Send>, AudioError>
+    where
+        S: rodio::Source<Item = i16> + Send + 'static,
+    {
+        
+        // Tuning goes first so pitch effects add to it
+        let tuning = self.tuning.get(&track_idx).map(|&semitones| SampleEffect::Pitch(semitones));
+        let effects = self.effects_for_step(track_idx, step_idx);
         
-        // If no effects registered for this track, return as-is
-        if !self.effects.contains_key(&track_idx) {
+        // If no effects registered for this track and step, return as-is
+        if tuning.is_none() && effects.is_none() {
             return Ok(Box::new(source));
         }
         
-        // Get the effects for this track
-        let effects = self.effects.get(&track_idx).unwrap();
-        
         // Save initial source properties before any processing
-        let channels = source.channels();
-        let sample_rate = source.sample_rate();
+        let mut channels = source.channels();
+        let mut sample_rate = source.sample_rate();
         
         // Apply each effect in order
         let mut processed: Box<dyn rodio::Source<Item = i16> + Send> = Box::new(source);
         
-        for effect in effects {
+        for effect in tuning.iter().chain(effects.into_iter().flatten()) {
             match effect {
                 SampleEffect::Reverse => {
-                    // Collect source into a buffer and reverse it
+                    // Reverse the order of frames, keeping each frame's channels in order
                     let collected: Vec<i16> = processed.collect();
-                    let mut reversed = collected;
-                    reversed.reverse();
+                    let reversed: Vec<i16> = collected
+                        .chunks(channels.max(1) as usize)
+                        .rev()
+                        .flatten()
+                        .copied()
+                        .collect();
                     
                     // Create a new source from the reversed buffer
                     processed = Box::new(rodio::buffer::SamplesBuffer::new(
@@ -411,13 +1205,7 @@ impl SampleProcessor {
                 SampleEffect::FadeOut(duration_secs) => {
                     // Convert seconds to samples
                     let fade_samples = (sample_rate as f32 * duration_secs * channels as f32) as usize;
-                    // Collect and recreate to avoid borrowing issues
-                    let collected: Vec<i16> = processed.collect();
-                    let fade_source = FadeOut::new(
-                        rodio::buffer::SamplesBuffer::new(channels, sample_rate, collected),
-                        fade_samples
-                    );
-                    processed = Box::new(fade_source);
+                    processed = Box::new(FadeOut::new(processed, fade_samples));
                 },
                 SampleEffect::Partial(start_frac, end_frac) => {
                     // Validate fractions
@@ -437,11 +1225,154 @@ impl SampleProcessor {
                         sample_rate,
                         partial
                     ));
-                }
-            }
-        }
-        
-        Ok(processed)
+                },
+                SampleEffect::Adsr { attack_secs, decay_secs, sustain_level, release_secs } => {
+                    let samples_per_sec = sample_rate as f32 * channels as f32;
+                    let mut collected: Vec<i16> = processed.collect();
+                    apply_adsr(
+                        &mut collected,
+                        (samples_per_sec * attack_secs) as usize,
+                        (samples_per_sec * decay_secs) as usize,
+                        sustain_level.clamp(0.0, 1.0),
+                        (samples_per_sec * release_secs) as usize,
+                    );
+                    processed = Box::new(rodio::buffer::SamplesBuffer::new(
+                        channels,
+                        sample_rate,
+                        collected
+                    ));
+                },
+                SampleEffect::Bitcrush { bit_depth, sample_rate_divisor } => {
+                    processed = Box::new(BitcrushSource::new(processed, *bit_depth, *sample_rate_divisor));
+                },
+                SampleEffect::Pan(pan) => {
+                    let collected: Vec<i16> = processed.collect();
+                    processed = Box::new(rodio::buffer::SamplesBuffer::new(
+                        2,
+                        sample_rate,
+                        apply_pan(&collected, channels, pan.clamp(-1.0, 1.0))
+                    ));
+                    channels = 2;
+                },
+                SampleEffect::Pitch(semitones) => {
+                    processed = Box::new(processed.speed(2f32.powf(semitones / 12.0)));
+                    sample_rate = processed.sample_rate();
+                },
+                SampleEffect::LowPass(cutoff) | SampleEffect::HighPass(cutoff) => {
+                    let mut collected: Vec<i16> = processed.collect();
+                    let high_pass = matches!(effect, SampleEffect::HighPass(_));
+                    apply_filter(&mut collected, channels, sample_rate, *cutoff, high_pass);
+                    processed = Box::new(rodio::buffer::SamplesBuffer::new(
+                        channels,
+                        sample_rate,
+                        collected
+                    ));
+                },
+                SampleEffect::Delay(ms) => {
+                    let collected: Vec<i16> = processed.collect();
+                    let delay_frames = (sample_rate as f32 * ms.max(0.0) / 1000.0) as usize;
+                    processed = Box::new(rodio::buffer::SamplesBuffer::new(
+                        channels,
+                        sample_rate,
+                        apply_delay(&collected, delay_frames * channels as usize)
+                    ));
+                },
+                SampleEffect::Tremolo { rate_hz, depth } => {
+                    processed = Box::new(TremoloSource::new(processed, *rate_hz, *depth));
+                },
+                SampleEffect::Compress { threshold, ratio, attack_samples, release_samples } => {
+                    processed = Box::new(CompressorSource::new(processed, *threshold, *ratio, *attack_samples, *release_samples));
+                }
+            }
+        }
+        
+        Ok(processed)
+    }
+}
+
+/// Pan interleaved samples into a stereo buffer. Mono input is duplicated to both
+/// channels; input with more than two channels keeps only the first two.
+fn apply_pan(samples: &[i16], channels: u16, pan: f32) -> Vec<i16> {
+    let left_gain = (1.0 - pan).min(1.0);
+    let right_gain = (1.0 + pan).min(1.0);
+    samples
+        .chunks(channels.max(1) as usize)
+        .flat_map(|frame| {
+            let left = frame[0];
+            let right = frame.get(1).copied().unwrap_or(left);
+            [(left as f32 * left_gain) as i16, (right as f32 * right_gain) as i16]
+        })
+        .collect()
+}
+
+/// One-pole low or high pass filter, applied in place to each channel
+fn apply_filter(samples: &mut [i16], channels: u16, sample_rate: u32, cutoff_hz: f32, high_pass: bool) {
+    let channels = channels.max(1) as usize;
+    let dt = 1.0 / sample_rate.max(1) as f32;
+    let rc = 1.0 / (2.0 * std::f32::consts::PI * cutoff_hz.max(1.0));
+    let alpha = dt / (rc + dt);
+    
+    for channel in 0..channels {
+        let mut low = 0.0f32;
+        for sample in samples.iter_mut().skip(channel).step_by(channels) {
+            let input = *sample as f32;
+            low += alpha * (input - low);
+            let output = if high_pass { input - low } else { low };
+            *sample = output.clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        }
+    }
+}
+
+/// Number of echoes added by the delay effect
+const DELAY_REPEATS: usize = 3;
+
+/// Gain of each echo relative to the previous one
+const DELAY_FEEDBACK: f32 = 0.5;
+
+/// Mix echoes `delay` interleaved samples apart into a longer buffer
+fn apply_delay(samples: &[i16], delay: usize) -> Vec<i16> {
+    if delay == 0 {
+        return samples.to_vec();
+    }
+    let mut mixed: Vec<f32> = samples.iter().map(|&s| s as f32).collect();
+    mixed.resize(samples.len() + delay * DELAY_REPEATS, 0.0);
+    let mut gain = 1.0;
+    for repeat in 1..=DELAY_REPEATS {
+        gain *= DELAY_FEEDBACK;
+        for (i, &sample) in samples.iter().enumerate() {
+            mixed[i + delay * repeat] += sample as f32 * gain;
+        }
+    }
+    mixed.iter().map(|&s| s.clamp(i16::MIN as f32, i16::MAX as f32) as i16).collect()
+}
+
+/// Apply an ADSR envelope in place. Phase lengths are in interleaved samples.
+/// The release phase always ends with the buffer; if it starts before the
+/// attack or decay has finished, it ramps down from the level reached so far.
+fn apply_adsr(samples: &mut [i16], attack: usize, decay: usize, sustain_level: f32, release: usize) {
+    let release_start = samples.len().saturating_sub(release);
+    
+    // Envelope level ignoring the release phase
+    let level_at = |i: usize| -> f32 {
+        if i < attack {
+            i as f32 / attack as f32
+        } else if i < attack + decay {
+            let progress = (i - attack) as f32 / decay as f32;
+            1.0 + (sustain_level - 1.0) * progress
+        } else {
+            sustain_level
+        }
+    };
+    let release_level = level_at(release_start);
+    let len = samples.len();
+    
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let gain = if i >= release_start {
+            release_level * (len - i - 1) as f32 / release as f32
+        } else {
+            level_at(i)
+        };
+        *sample = (*sample as f32 * gain) as i16;
     }
 }
 
@@ -502,61 +1433,215 @@ where
     }
 }
 
-/// Effect that applies a fade-out to a source
-pub struct FadeOut<S> {
+/// Effect that reduces bit depth and holds samples to lower the effective sample rate
+pub struct BitcrushSource<S> {
     source: S,
-    fade_samples: usize,
-    total_samples: Option<usize>,
-    current_sample: usize,
+    /// Quantization step; samples are rounded down to multiples of it
+    step: i32,
+    /// Number of output frames each input frame is held for
+    divisor: u32,
+    channels: usize,
+    /// Frame currently being repeated
+    held_frame: Vec<i16>,
+    /// Position within the current frame
+    channel_pos: usize,
+    /// Remaining repetitions of the held frame
+    frames_left: u32,
 }
 
-impl<S> FadeOut<S>
+impl<S> BitcrushSource<S>
 where
-    S: rodio::Source<Item = i16> + Iterator<Item = i16>,
+    S: rodio::Source<Item = i16>,
 {
-    fn new(source: S, fade_samples: usize) -> Self {
+    fn new(source: S, bit_depth: u8, sample_rate_divisor: u32) -> Self {
+        let bit_depth = bit_depth.clamp(1, 16);
+        let channels = source.channels().max(1) as usize;
         Self {
             source,
-            fade_samples,
-            total_samples: None,
-            current_sample: 0,
+            step: 1 << (16 - bit_depth),
+            divisor: sample_rate_divisor.max(1),
+            channels,
+            held_frame: Vec::with_capacity(channels),
+            channel_pos: 0,
+            frames_left: 0,
         }
     }
+    
+    fn quantize(&self, sample: i16) -> i16 {
+        ((sample as i32 / self.step) * self.step) as i16
+    }
 }
 
-impl<S> Iterator for FadeOut<S>
+impl<S> Iterator for BitcrushSource<S>
 where
-    S: rodio::Source<Item = i16> + Iterator<Item = i16>,
+    S: rodio::Source<Item = i16>,
 {
     type Item = i16;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.total_samples.is_none() {
-            // Determine the total number of samples if not already known
-            if let Some(duration) = self.source.total_duration() {
-                let sample_rate = self.source.sample_rate();
-                let channels = self.source.channels() as u32;
-                let total_samples = (duration.as_secs_f32() * sample_rate as f32 * channels as f32) as usize;
-                self.total_samples = Some(total_samples);
+        // Every output sample replaces one input sample, so the output ends with the input
+        let sample = self.source.next()?;
+        if self.channel_pos == 0 {
+            if self.frames_left == 0 {
+                // Start holding the frame that begins here
+                self.held_frame.clear();
+                self.frames_left = self.divisor;
             }
+            self.frames_left -= 1;
         }
         
-        self.source.next().map(|sample| {
-            if let Some(total) = self.total_samples {
-                if self.current_sample >= total.saturating_sub(self.fade_samples) {
-                    let remaining = total.saturating_sub(self.current_sample);
-                    let factor = remaining as f32 / self.fade_samples as f32;
-                    self.current_sample += 1;
-                    return (sample as f32 * factor) as i16;
-                }
+        let output = match self.held_frame.get(self.channel_pos) {
+            Some(&held) => held,
+            None => {
+                let quantized = self.quantize(sample);
+                self.held_frame.push(quantized);
+                quantized
             }
-            self.current_sample += 1;
-            sample
-        })
+        };
+        self.channel_pos = (self.channel_pos + 1) % self.channels;
+        Some(output)
+    }
+}
+
+impl<S> rodio::Source for BitcrushSource<S>
+where
+    S: rodio::Source<Item = i16>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.source.total_duration()
+    }
+}
+
+/// Effect that modulates the volume with a sine LFO
+pub struct TremoloSource<S> {
+    source: S,
+    rate_hz: f32,
+    depth: f32,
+    channels: usize,
+    /// Samples read so far; all channels of a frame share one gain
+    sample_idx: usize,
+}
+
+impl<S> TremoloSource<S>
+where
+    S: rodio::Source<Item = i16>,
+{
+    pub fn new(source: S, rate_hz: f32, depth: f32) -> Self {
+        let channels = source.channels().max(1) as usize;
+        Self {
+            source,
+            rate_hz: rate_hz.max(0.0),
+            depth: depth.clamp(0.0, 1.0),
+            channels,
+            sample_idx: 0,
+        }
+    }
+    
+    /// Gain at the current frame: 1.0 at the LFO's peaks, 1.0 - depth at its troughs
+    fn gain(&self) -> f32 {
+        let frame = (self.sample_idx / self.channels) as f32;
+        let phase = 2.0 * std::f32::consts::PI * self.rate_hz * frame / self.source.sample_rate().max(1) as f32;
+        1.0 - self.depth * (1.0 - phase.sin()) / 2.0
+    }
+}
+
+impl<S> Iterator for TremoloSource<S>
+where
+    S: rodio::Source<Item = i16>,
+{
+    type Item = i16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let gain = self.gain();
+        let sample = self.source.next()?;
+        self.sample_idx += 1;
+        Some((sample as f32 * gain) as i16)
+    }
+}
+
+impl<S> rodio::Source for TremoloSource<S>
+where
+    S: rodio::Source<Item = i16>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.source.total_duration()
+    }
+}
+
+/// Simple feed-forward compressor: samples louder than the threshold pull the gain down
+/// towards `ratio`, quieter ones let it recover towards 1.0
+pub struct CompressorSource<S> {
+    source: S,
+    /// Threshold as an absolute sample level
+    threshold_level: f32,
+    ratio: f32,
+    /// Gain change per sample while compressing
+    attack_step: f32,
+    /// Gain change per sample while recovering
+    release_step: f32,
+    gain: f32,
+}
+
+impl<S> CompressorSource<S> {
+    pub fn new(source: S, threshold: f32, ratio: f32, attack_samples: usize, release_samples: usize) -> Self {
+        Self {
+            source,
+            threshold_level: threshold.clamp(0.0, 1.0) * i16::MAX as f32,
+            ratio: ratio.max(1.0),
+            attack_step: 1.0 / attack_samples.max(1) as f32,
+            release_step: 1.0 / release_samples.max(1) as f32,
+            gain: 1.0,
+        }
+    }
+}
+
+impl<S> Iterator for CompressorSource<S>
+where
+    S: Iterator<Item = i16>,
+{
+    type Item = i16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.source.next()?;
+        let level = (sample as f32).abs();
+        if level > self.threshold_level {
+            // Only the part above the threshold is scaled down by the ratio
+            let target = (self.threshold_level + (level - self.threshold_level) / self.ratio) / level;
+            if self.gain > target {
+                self.gain = (self.gain - self.attack_step).max(target);
+            }
+        } else {
+            self.gain = (self.gain + self.release_step).min(1.0);
+        }
+        Some((sample as f32 * self.gain) as i16)
     }
 }
 
-impl<S> rodio::Source for FadeOut<S>
+impl<S> rodio::Source for CompressorSource<S>
 where
     S: rodio::Source<Item = i16>,
 {
@@ -577,6 +1662,217 @@ where
     }
 }
 
+/// Scales every sample of a source by a constant gain, e.g. a trigger's velocity
+pub struct GainSource<S> {
+    source: S,
+    gain: f32,
+}
+
+impl<S> GainSource<S> {
+    pub fn new(source: S, gain: f32) -> Self {
+        Self { source, gain }
+    }
+}
+
+impl<S> Iterator for GainSource<S>
+where
+    S: Iterator<Item = i16>,
+{
+    type Item = i16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.source.next().map(|sample| (sample as f32 * self.gain).clamp(i16::MIN as f32, i16::MAX as f32) as i16)
+    }
+}
+
+impl<S> rodio::Source for GainSource<S>
+where
+    S: rodio::Source<Item = i16>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.source.total_duration()
+    }
+}
+
+/// Plays a source once up to the loop end, then repeats the loop region forever.
+/// Samples are kept as they are first played, so every later pass reads the same data.
+pub struct LoopSource<S> {
+    /// Source still being read on the first pass; None once the loop region is buffered
+    source: Option<S>,
+    buffer: Vec<i16>,
+    channels: u16,
+    sample_rate: u32,
+    loop_start: usize,
+    loop_end: usize,
+    position: usize,
+}
+
+impl<S> LoopSource<S>
+where
+    S: rodio::Source<Item = i16>,
+{
+    /// Loop the whole source
+    pub fn new(source: S) -> Self {
+        Self::with_loop_points(source, 0, usize::MAX)
+    }
+
+    /// Loop the samples from `loop_start_sample` up to, but not including, `loop_end_sample`.
+    /// Points are interleaved sample offsets, moved down to a frame boundary and clamped to the data.
+    pub fn with_loop_points(source: S, loop_start_sample: usize, loop_end_sample: usize) -> Self {
+        let channels = source.channels().max(1);
+        let sample_rate = source.sample_rate();
+        let mut looped = Self {
+            source: Some(source),
+            buffer: Vec::new(),
+            channels,
+            sample_rate,
+            loop_start: loop_start_sample,
+            loop_end: loop_end_sample,
+            position: 0,
+        };
+        looped.align_loop_points();
+        looped
+    }
+
+    fn align_loop_points(&mut self) {
+        let frame = self.channels as usize;
+        self.loop_end = (self.loop_end / frame) * frame;
+        self.loop_start = (self.loop_start.min(self.loop_end.saturating_sub(frame)) / frame) * frame;
+    }
+}
+
+impl<S> Iterator for LoopSource<S>
+where
+    S: rodio::Source<Item = i16>,
+{
+    type Item = i16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(source) = self.source.as_mut() {
+            if self.buffer.len() < self.loop_end {
+                if let Some(sample) = source.next() {
+                    self.buffer.push(sample);
+                    self.position += 1;
+                    return Some(sample);
+                }
+                // The source ended before the loop end; loop up to its last whole frame
+                self.loop_end = self.buffer.len();
+                self.align_loop_points();
+            }
+            self.source = None;
+        }
+
+        if self.loop_end == 0 {
+            return None;
+        }
+        if self.position >= self.loop_end {
+            self.position = self.loop_start;
+        }
+        let sample = self.buffer[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl<S> rodio::Source for LoopSource<S>
+where
+    S: rodio::Source<Item = i16>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        None
+    }
+}
+
+/// Effect that applies a fade-out to a source.
+/// The source is buffered up front so the fade start is known even when
+/// `total_duration()` isn't, as with some decoders.
+pub struct FadeOut {
+    samples: std::vec::IntoIter<i16>,
+    channels: u16,
+    sample_rate: u32,
+    fade_samples: usize,
+    total_samples: usize,
+    current_sample: usize,
+}
+
+impl FadeOut {
+    fn new<S>(source: S, fade_samples: usize) -> Self
+    where
+        S: rodio::Source<Item = i16>,
+    {
+        let channels = source.channels();
+        let sample_rate = source.sample_rate();
+        let samples: Vec<i16> = source.collect();
+        Self {
+            total_samples: samples.len(),
+            samples: samples.into_iter(),
+            channels,
+            sample_rate,
+            fade_samples,
+            current_sample: 0,
+        }
+    }
+}
+
+impl Iterator for FadeOut {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.samples.next().map(|sample| {
+            let remaining = self.total_samples - self.current_sample;
+            self.current_sample += 1;
+            if remaining <= self.fade_samples {
+                let factor = remaining as f32 / self.fade_samples as f32;
+                return (sample as f32 * factor) as i16;
+            }
+            sample
+        })
+    }
+}
+
+impl rodio::Source for FadeOut {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        let frames = self.total_samples / self.channels.max(1) as usize;
+        Some(std::time::Duration::from_secs_f64(frames as f64 / self.sample_rate.max(1) as f64))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -602,12 +1898,12 @@ mod tests {
         let path = dir.join(name);
         
         // Create a simple WAV file with minimal headers to be a valid file
-        // This is a minimal valid WAV with 0.1s of silence
+        // It holds a single frame derived from the name, so differently named files differ in content
         let wav_header: [u8; 44] = [
             // RIFF header
             b'R', b'I', b'F', b'F',
-            // File size - 8 (36 bytes for rest of the header + no data)
-            36, 0, 0, 0,
+            // File size - 8 (36 bytes for rest of the header + 2 bytes of data)
+            38, 0, 0, 0,
             // WAVE header
             b'W', b'A', b'V', b'E',
             // fmt chunk marker
@@ -628,12 +1924,14 @@ mod tests {
             16, 0,
             // data chunk marker
             b'd', b'a', b't', b'a',
-            // Data size (0 bytes)
-            0, 0, 0, 0
+            // Data size (2 bytes)
+            2, 0, 0, 0
         ];
+        let frame = name.bytes().fold(0i16, |acc, b| acc.wrapping_mul(31).wrapping_add(b as i16));
         
         let mut file = File::create(&path)?;
         file.write_all(&wav_header)?;
+        file.write_all(&frame.to_le_bytes())?;
         
         Ok(path)
     }
@@ -659,16 +1957,28 @@ mod tests {
                 name: "Kick".to_string(),
                 sample: "kick.wav".to_string(),
                 volume: 1.0,
+                pan: 0.0,
+                pitch_semitones: 0.0,
+                muted: false,
+                color: None,
             },
             Track {
                 name: "Snare".to_string(),
                 sample: "snare.wav".to_string(),
                 volume: 0.8,
+                pan: 0.0,
+                pitch_semitones: 0.0,
+                muted: false,
+                color: None,
             },
             Track {
                 name: "HiHat".to_string(),
                 sample: "hihat.wav".to_string(),
                 volume: 0.7,
+                pan: 0.0,
+                pitch_semitones: 0.0,
+                muted: false,
+                color: None,
             }
         ];
         
@@ -690,6 +2000,746 @@ mod tests {
         // No assertions needed as we're just testing the API doesn't panic
     }
     
+    /// Encode constant-amplitude mono samples as an in-memory WAV and decode it
+    fn constant_source(value: i16, len: usize, sample_rate: u32) -> Decoder<Cursor<Vec<u8>>> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut writer = hound::WavWriter::new(&mut buffer, spec).unwrap();
+            for _ in 0..len {
+                writer.write_sample(value).unwrap();
+            }
+            writer.finalize().unwrap();
+        }
+        Decoder::new(Cursor::new(buffer.into_inner())).unwrap()
+    }
+    
+    #[test]
+    fn test_adsr_envelope() {
+        let mut processor = SampleProcessor::new();
+        processor.add_effect(0, SampleEffect::Adsr {
+            attack_secs: 0.1,
+            decay_secs: 0.1,
+            sustain_level: 0.5,
+            release_secs: 0.2,
+        });
+        
+        // One second at 1 kHz: attack 100, decay 100, release the last 200 samples
+        let output: Vec<i16> = processor
+            .process_sample(0, 0, constant_source(10000, 1000, 1000))
+            .unwrap()
+            .collect();
+        assert_eq!(output.len(), 1000);
+        
+        let peak_idx = output.iter().enumerate().max_by_key(|(_, s)| **s).map(|(i, _)| i).unwrap();
+        assert_eq!(peak_idx, 100, "Peak should be at the end of the attack phase");
+        assert_eq!(output[500], 5000, "Sustain should hold at the sustain level");
+        assert!(output[999].abs() < 50, "Release should end near silence, got {}", output[999]);
+    }
+    
+    #[test]
+    fn test_step_effects_override_track_effects() {
+        let mut processor = SampleProcessor::new();
+        processor.add_effect(0, SampleEffect::FadeIn(0.5));
+        processor.add_step_effect(0, 4, SampleEffect::Reverse);
+        
+        assert!(matches!(processor.effects_for_step(0, 4).unwrap()[..], [SampleEffect::Reverse]));
+        assert!(matches!(processor.effects_for_step(0, 5).unwrap()[..], [SampleEffect::FadeIn(_)]));
+        assert!(processor.effects_for_step(1, 4).is_none());
+        
+        processor.clear_effects(0);
+        assert!(processor.effects_for_step(0, 4).is_none());
+    }
+
+    #[test]
+    fn test_inspect_and_edit_effect_chain() {
+        let mut processor = SampleProcessor::new();
+        assert_eq!(processor.get_effect_count(0), 0);
+        assert!(processor.list_effects(0).is_empty());
+
+        processor.add_effect(0, SampleEffect::Reverse);
+        processor.add_effect(0, SampleEffect::FadeIn(0.5));
+        processor.add_effect(0, SampleEffect::Bitcrush { bit_depth: 8, sample_rate_divisor: 4 });
+        assert_eq!(processor.get_effect_count(0), 3);
+        assert_eq!(processor.list_effects(0), vec!["Reverse", "FadeIn(0.5s)", "Bitcrush(8-bit, 1/4)"]);
+
+        let old = processor.replace_effect(0, 1, SampleEffect::FadeOut(0.25)).unwrap();
+        assert!(matches!(old, SampleEffect::FadeIn(_)));
+        let removed = processor.remove_effect(0, 0).unwrap();
+        assert!(matches!(removed, SampleEffect::Reverse));
+        assert_eq!(processor.list_effects(0), vec!["FadeOut(0.25s)", "Bitcrush(8-bit, 1/4)"]);
+
+        assert!(processor.remove_effect(0, 2).is_err());
+        assert!(processor.remove_effect(3, 0).is_err());
+        assert!(processor.replace_effect(3, 0, SampleEffect::Reverse).is_err());
+    }
+
+    #[test]
+    fn test_effect_state_round_trips_through_project_folder() {
+        let mut processor = SampleProcessor::new();
+        processor.add_effect(0, SampleEffect::Reverse);
+        processor.add_effect(0, SampleEffect::FadeIn(0.5));
+        processor.add_effect(2, SampleEffect::Bitcrush { bit_depth: 8, sample_rate_divisor: 2 });
+
+        let dir = tempfile::tempdir().unwrap();
+        project::loader::save_effects(dir.path(), &processor.save_state()).unwrap();
+        let state: HashMap<usize, Vec<SampleEffect>> = project::loader::load_effects(dir.path()).unwrap().unwrap();
+
+        let mut reloaded = SampleProcessor::new();
+        reloaded.load_state(state);
+        assert_eq!(reloaded.list_effects(0), vec!["Reverse", "FadeIn(0.5s)"]);
+        assert_eq!(reloaded.get_effects(&2), processor.get_effects(&2));
+        assert_eq!(reloaded.get_effect_count(1), 0);
+    }
+
+    #[test]
+    fn test_pan_and_pitch_effects() {
+        let mut processor = SampleProcessor::new();
+        processor.set_effect(0, SampleEffect::Pan(-1.0));
+        processor.set_effect(0, SampleEffect::Pan(0.5));
+        assert_eq!(processor.list_effects(0), vec!["Pan(0.5)"]);
+
+        let output = processor.process_sample(0, 0, constant_source(1000, 100, 1000)).unwrap();
+        assert_eq!(output.channels(), 2);
+        let samples: Vec<i16> = output.collect();
+        assert_eq!(samples.len(), 200);
+        assert_eq!(&samples[..2], &[500, 1000]);
+
+        processor.set_effect(1, SampleEffect::Pitch(12.0));
+        let output = processor.process_sample(1, 0, constant_source(1000, 100, 1000)).unwrap();
+        assert_eq!(output.sample_rate(), 2000);
+        assert_eq!(processor.list_effects(1), vec!["Pitch(+12st)"]);
+    }
+
+    #[test]
+    fn test_remove_track_shifts_effects() {
+        let mut processor = SampleProcessor::new();
+        processor.add_effect(0, SampleEffect::Reverse);
+        processor.add_effect(2, SampleEffect::FadeIn(0.5));
+        processor.add_step_effect(2, 3, SampleEffect::FadeOut(0.5));
+
+        processor.remove_track(0);
+        assert!(matches!(processor.effects_for_step(1, 0).unwrap()[..], [SampleEffect::FadeIn(_)]));
+        assert!(matches!(processor.effects_for_step(1, 3).unwrap()[..], [SampleEffect::FadeOut(_)]));
+        assert!(processor.effects_for_step(0, 0).is_none());
+        assert!(processor.effects_for_step(2, 0).is_none());
+    }
+
+    #[test]
+    fn test_resample_linear() {
+        // 48 kHz -> 44.1 kHz keeps duration and interpolates between frames
+        let ramp: Vec<i16> = (0..4800).map(|i| i as i16).collect();
+        let resampled = resample_linear(&ramp, 1, 48000, 44100);
+        assert_eq!(resampled.len(), 4410);
+        assert_eq!(resampled[0], 0);
+        assert_eq!(resampled[1], 1); // 1.088 rounds to 1
+        assert_eq!(resampled[100], 109); // 108.84 rounds to 109
+        
+        // Stereo channels are interpolated independently
+        let stereo = vec![0, 100, 10, 200];
+        assert_eq!(resample_linear(&stereo, 2, 1, 2), vec![0, 100, 5, 150, 10, 200, 10, 200]);
+        
+        assert_eq!(resample_linear(&ramp, 1, 44100, 44100), ramp);
+    }
+    
+    #[test]
+    fn test_decode_resamples_to_output_rate() {
+        // One second at 48 kHz
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 48000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut writer = hound::WavWriter::new(&mut buffer, spec).unwrap();
+            for _ in 0..48000 {
+                writer.write_sample(1000i16).unwrap();
+            }
+            writer.finalize().unwrap();
+        }
+        
+        let (data, channels) = decode_to_pcm(buffer.into_inner(), 44100).unwrap();
+        assert_eq!(channels, 1);
+        assert_eq!(data.len(), 44100);
+        
+        let sample = Sample {
+            name: "tone".to_string(),
+            data,
+            channels,
+            sample_rate: 44100,
+            path: PathBuf::from("tone.wav"),
+        };
+        let metadata = sample.metadata();
+        assert_eq!(metadata.sample_rate, 44100);
+        assert_eq!(metadata.duration, std::time::Duration::from_secs(1));
+    }
+    
+    #[test]
+    fn test_player_resamples_on_load() {
+        let dir = tempdir().unwrap();
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 48000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(dir.path().join("tone.wav"), spec).unwrap();
+        for _ in 0..4800 {
+            writer.write_sample(1000i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        
+        if let Ok(mut player) = SamplePlayer::new(dir.path()) {
+            let idx = player.load_sample("tone", "tone.wav").unwrap();
+            let metadata = player.sample(idx).unwrap().metadata();
+            assert_eq!(metadata.sample_rate, player.output_sample_rate());
+            let expected = 0.1 * player.output_sample_rate() as f64;
+            let frames = metadata.duration.as_secs_f64() * metadata.sample_rate as f64;
+            assert!((frames - expected).abs() < 1.0);
+        }
+    }
+    
+    #[test]
+    fn test_preload_all_samples() {
+        let (_temp_dir, samples_dir, tracks) = setup_test_environment();
+        if let Ok(mut player) = SamplePlayer::new(&samples_dir) {
+            player.initialize_with_tracks(&tracks).unwrap();
+            player.processor.add_effect(1, SampleEffect::Reverse);
+            player.preload_all_samples().unwrap();
+            assert_eq!(player.track_sinks.len(), tracks.len());
+        }
+    }
+    
+    #[test]
+    fn test_lru_sample_eviction() {
+        let (_temp_dir, samples_dir, tracks) = setup_test_environment();
+        create_test_wav_file(&samples_dir, "clap.wav").unwrap();
+        let trigger = |track_idx| TriggerEvent { track_idx, step_idx: 0 };
+        
+        if let Ok(mut player) = SamplePlayer::new(&samples_dir) {
+            player.set_max_samples(3);
+            player.initialize_with_tracks(&tracks).unwrap();
+            for track_idx in 0..3 {
+                player.process_trigger(&trigger(track_idx)).unwrap();
+            }
+            
+            // A fourth sample pushes out the least recently played one, the kick
+            player.add_track(Track {
+                name: "Clap".to_string(),
+                sample: "clap.wav".to_string(),
+                volume: 1.0,
+                pan: 0.0,
+                pitch_semitones: 0.0,
+                muted: false,
+                color: None,
+            }).unwrap();
+            assert_eq!(player.samples.len(), 3);
+            assert!(!player.track_to_sample.contains_key(&0));
+            assert!(player.samples.iter().all(|s| s.name != "Kick"));
+            
+            // Triggering the kick track reloads its sample, evicting the snare next
+            player.process_trigger(&trigger(0)).unwrap();
+            assert_eq!(player.samples.len(), 3);
+            assert_eq!(player.samples[player.track_to_sample[&0]].name, "Kick");
+            assert!(!player.track_to_sample.contains_key(&1));
+            assert_eq!(player.samples[player.track_to_sample[&3]].name, "Clap");
+        }
+    }
+    
+    #[test]
+    fn test_identical_samples_are_loaded_once() {
+        let (_temp_dir, samples_dir, _tracks) = setup_test_environment();
+        fs::copy(samples_dir.join("kick.wav"), samples_dir.join("kick copy.wav")).unwrap();
+        
+        if let Ok(mut player) = SamplePlayer::new(&samples_dir) {
+            let first = player.load_sample("Kick", "kick.wav").unwrap();
+            let second = player.load_sample("Kick Copy", "kick copy.wav").unwrap();
+            assert_eq!(first, second);
+            assert_eq!(player.samples.len(), 1);
+            
+            // Different contents still get their own sample
+            let snare = player.load_sample("Snare", "snare.wav").unwrap();
+            assert_ne!(snare, first);
+            assert_eq!(player.samples.len(), 2);
+            
+            // Tracks pointing at either copy share the sample
+            player.set_track_sample(0, "kick copy.wav").unwrap();
+            player.set_track_sample(1, "kick.wav").unwrap();
+            assert_eq!(player.track_to_sample[&0], player.track_to_sample[&1]);
+            assert_eq!(player.samples.len(), 2);
+        }
+    }
+    
+    #[test]
+    fn test_memory_usage_and_limit() {
+        let dir = tempdir().unwrap();
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        for (name, len) in [("short.wav", 100), ("long.wav", 1000)] {
+            let mut writer = hound::WavWriter::create(dir.path().join(name), spec).unwrap();
+            for _ in 0..len {
+                writer.write_sample(1000i16).unwrap();
+            }
+            writer.finalize().unwrap();
+        }
+        
+        if let Ok(mut player) = SamplePlayer::new(dir.path()) {
+            assert_eq!(player.memory_usage(), 0);
+            assert!(player.largest_sample().is_none());
+            
+            player.load_sample("short", "short.wav").unwrap();
+            player.load_sample("long", "long.wav").unwrap();
+            let usage = player.memory_usage();
+            assert_eq!(usage, player.samples.iter().map(|s| s.data.len() * 2).sum::<usize>());
+            assert_eq!(player.largest_sample().unwrap().name, "long");
+            
+            player.set_max_sample_memory(usage + 10);
+            match player.load_sample("again", "short.wav") {
+                Err(AudioError::SampleLoadError(_, msg)) => assert!(msg.contains("memory limit exceeded")),
+                other => panic!("Expected memory limit error, got {:?}", other.map(|_| ())),
+            }
+            assert_eq!(player.memory_usage(), usage);
+        }
+    }
+    
+    #[test]
+    fn test_gain_source() {
+        let input: Vec<i16> = vec![1000, -1000, 32767, -32768];
+        let source = GainSource::new(rodio::buffer::SamplesBuffer::new(2, 44100, input), 0.5);
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.collect::<Vec<i16>>(), vec![500, -500, 16383, -16384]);
+        
+        let silent: Vec<i16> = GainSource::new(rodio::buffer::SamplesBuffer::new(1, 44100, vec![1000i16; 4]), 0.0).collect();
+        assert_eq!(silent, vec![0; 4]);
+    }
+    
+    #[test]
+    fn test_loop_source_boundary() {
+        let input: Vec<i16> = (0..10).map(|i| i * 100).collect();
+        let looped: Vec<i16> = LoopSource::with_loop_points(
+            rodio::buffer::SamplesBuffer::new(1, 44100, input.clone()),
+            2,
+            6,
+        ).take(14).collect();
+        
+        // Intro up to the loop end, then the loop region repeats
+        assert_eq!(&looped[..6], &input[..6]);
+        assert_eq!(&looped[6..10], &input[2..6]);
+        assert_eq!(&looped[10..14], &input[2..6]);
+        // The sample after the last one of the region is its first, on every pass
+        assert_eq!(looped[5], input[5]);
+        assert_eq!(looped[6], input[2]);
+        assert_eq!(looped[9], input[5]);
+        assert_eq!(looped[10], input[2]);
+        
+        let source = LoopSource::new(rodio::buffer::SamplesBuffer::new(1, 44100, input.clone()));
+        assert!(source.total_duration().is_none());
+        let whole: Vec<i16> = source.take(20).collect();
+        assert_eq!(&whole[..10], &whole[10..]);
+    }
+    
+    #[test]
+    fn test_loop_source_stereo_and_empty() {
+        // Loop points inside a frame move down so channels stay in order
+        let stereo: Vec<i16> = vec![1, -1, 2, -2, 3, -3];
+        let looped: Vec<i16> = LoopSource::with_loop_points(
+            rodio::buffer::SamplesBuffer::new(2, 44100, stereo),
+            3,
+            5,
+        ).take(8).collect();
+        assert_eq!(looped, vec![1, -1, 2, -2, 2, -2, 2, -2]);
+        
+        let mut empty = LoopSource::new(rodio::buffer::SamplesBuffer::new(1, 44100, Vec::<i16>::new()));
+        assert_eq!(empty.next(), None);
+    }
+    
+    /// Source that doesn't report its length, like some decoders
+    struct UnknownLength(std::vec::IntoIter<i16>);
+
+    impl Iterator for UnknownLength {
+        type Item = i16;
+
+        fn next(&mut self) -> Option<i16> {
+            self.0.next()
+        }
+    }
+
+    impl rodio::Source for UnknownLength {
+        fn current_frame_len(&self) -> Option<usize> {
+            None
+        }
+
+        fn channels(&self) -> u16 {
+            1
+        }
+
+        fn sample_rate(&self) -> u32 {
+            44100
+        }
+
+        fn total_duration(&self) -> Option<std::time::Duration> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_fade_out_without_total_duration() {
+        let source = UnknownLength(vec![1000i16; 10].into_iter());
+        let faded: Vec<i16> = FadeOut::new(source, 4).collect();
+        assert_eq!(faded, vec![1000, 1000, 1000, 1000, 1000, 1000, 1000, 750, 500, 250]);
+    }
+
+    #[test]
+    fn test_normalize_to_target_peak() {
+        let mut samples: Vec<i16> = vec![0, 1000, -4000, 2000, -500];
+        SampleProcessor::normalize(&mut samples, 0.9);
+        let target = 0.9 * i16::MAX as f32;
+        let peak = samples.iter().map(|s| s.unsigned_abs()).max().unwrap() as f32;
+        assert!((peak - target).abs() <= target * 0.001, "peak {} vs target {}", peak, target);
+        assert_eq!(samples[2], -(target.round() as i16));
+        assert_eq!(samples[1], (target / 4.0).round() as i16);
+        
+        let mut silence = vec![0i16; 8];
+        SampleProcessor::normalize(&mut silence, 0.9);
+        assert!(silence.iter().all(|&s| s == 0));
+    }
+    
+    #[test]
+    fn test_bitcrush_bit_depth() {
+        let input: Vec<i16> = vec![0, 255, 256, 1000, -1000, 32767];
+        let crushed: Vec<i16> = BitcrushSource::new(
+            rodio::buffer::SamplesBuffer::new(1, 44100, input),
+            8,
+            1,
+        ).collect();
+        assert_eq!(crushed.len(), 6);
+        assert!(crushed.iter().all(|s| s % 256 == 0), "8-bit crush should round to multiples of 256: {:?}", crushed);
+        assert_eq!(crushed[2], 256);
+    }
+    
+    #[test]
+    fn test_bitcrush_sample_rate_hold() {
+        let input: Vec<i16> = (0..16).map(|i| i * 1000).collect();
+        let crushed: Vec<i16> = BitcrushSource::new(
+            rodio::buffer::SamplesBuffer::new(1, 44100, input),
+            16,
+            4,
+        ).collect();
+        assert_eq!(crushed.len(), 16);
+        for run in crushed.chunks(4) {
+            assert!(run.iter().all(|s| *s == run[0]), "Expected a run of 4 identical samples: {:?}", run);
+        }
+        assert_eq!(crushed[4], 4000);
+    }
+    
+    #[test]
+    fn test_bitcrush_ends_with_source() {
+        // 10 stereo frames don't divide evenly into holds of 4
+        let input: Vec<i16> = (0..20).map(|i| i * 100).collect();
+        let crushed: Vec<i16> = BitcrushSource::new(
+            rodio::buffer::SamplesBuffer::new(2, 44100, input.clone()),
+            16,
+            4,
+        ).collect();
+        assert_eq!(crushed.len(), input.len());
+        assert_eq!(&crushed[16..], &[1600, 1700, 1600, 1700]);
+    }
+    
+    #[test]
+    fn test_total_pitch_keeps_pitch_effects() {
+        let mut processor = SampleProcessor::new();
+        processor.set_effect(0, SampleEffect::Pitch(-12.0));
+        processor.set_tuning(0, 5.0);
+        processor.set_total_pitch(0, 12.0);
+        assert_eq!(processor.tuning(0), 24.0);
+        assert_eq!(processor.list_effects(0), vec!["Pitch(-12st)"]);
+        
+        // Together they play an octave up
+        let output = processor.process_sample(0, 0, constant_source(1000, 100, 1000)).unwrap();
+        assert_eq!(output.sample_rate(), 2000);
+        
+        processor.set_total_pitch(1, 7.0);
+        assert_eq!(processor.tuning(1), 7.0);
+    }
+    
+    #[test]
+    fn test_tuning_adds_to_pitch_effect() {
+        let input = || rodio::buffer::SamplesBuffer::new(1, 44100, vec![0i16; 1000]);
+        let mut processor = SampleProcessor::new();
+        
+        // An octave up halves the length
+        processor.set_tuning(0, 12.0);
+        assert_eq!(processor.tuning(0), 12.0);
+        let tuned = processor.process_sample(0, 0, input()).unwrap();
+        assert_eq!(tuned.sample_rate(), 88200);
+        
+        // Another octave from the track's pitch effect stacks on top
+        processor.set_effect(0, SampleEffect::Pitch(12.0));
+        let tuned = processor.process_sample(0, 0, input()).unwrap();
+        assert_eq!(tuned.sample_rate(), 176400);
+        
+        processor.set_tuning(0, 0.0);
+        assert_eq!(processor.process_sample(0, 0, input()).unwrap().sample_rate(), 88200);
+        
+        // Tuning follows tracks down when an earlier one is removed
+        processor.set_tuning(2, -12.0);
+        processor.remove_track(1);
+        assert_eq!(processor.tuning(1), -12.0);
+        assert_eq!(processor.tuning(2), 0.0);
+    }
+    
+    #[test]
+    fn test_reverse_keeps_stereo_channels() {
+        let mut processor = SampleProcessor::new();
+        processor.add_effect(0, SampleEffect::Reverse);
+        
+        // Left counts up from 1, right counts down from -1
+        let input: Vec<i16> = (1..=4).flat_map(|i| [i, -i]).collect();
+        let output: Vec<i16> = processor
+            .process_sample(0, 0, rodio::buffer::SamplesBuffer::new(2, 44100, input))
+            .unwrap()
+            .collect();
+        assert_eq!(output, vec![4, -4, 3, -3, 2, -2, 1, -1]);
+        
+        let left: Vec<i16> = output.iter().step_by(2).copied().collect();
+        let right: Vec<i16> = output.iter().skip(1).step_by(2).copied().collect();
+        assert_eq!(left, vec![4, 3, 2, 1]);
+        assert_eq!(right, vec![-4, -3, -2, -1]);
+        
+        // Mono is still reversed sample by sample
+        let output: Vec<i16> = processor
+            .process_sample(0, 0, rodio::buffer::SamplesBuffer::new(1, 44100, vec![1, 2, 3]))
+            .unwrap()
+            .collect();
+        assert_eq!(output, vec![3, 2, 1]);
+    }
+    
+    #[test]
+    fn test_tremolo_zero_rate_is_constant() {
+        let input = vec![10000i16; 1000];
+        let output: Vec<i16> = TremoloSource::new(
+            rodio::buffer::SamplesBuffer::new(1, 1000, input),
+            0.0,
+            0.8,
+        ).collect();
+        assert_eq!(output.len(), 1000);
+        // sin(0) = 0 puts the gain halfway into the dip
+        assert!(output.iter().all(|s| *s == output[0]), "Amplitude should not change at 0 Hz");
+        assert_eq!(output[0], 6000);
+    }
+    
+    #[test]
+    fn test_tremolo_full_depth_reaches_silence() {
+        // 2 Hz at 1000 frames per second: troughs at frames 375 and 875, peaks at 125 and 625
+        let input = vec![10000i16; 2000];
+        let output: Vec<i16> = TremoloSource::new(
+            rodio::buffer::SamplesBuffer::new(2, 1000, input),
+            2.0,
+            1.0,
+        ).collect();
+        for trough in [375, 875] {
+            assert_eq!(output[trough * 2], 0);
+            assert_eq!(output[trough * 2 + 1], 0, "Both channels of a frame share the gain");
+        }
+        for peak in [125, 625] {
+            assert_eq!(output[peak * 2], 10000);
+        }
+    }
+    
+    #[test]
+    fn test_compressor_reduces_peaks() {
+        // Square wave at 30000, well above a threshold of half scale
+        let input: Vec<i16> = (0..2000).map(|i| if (i / 50) % 2 == 0 { 30000 } else { -30000 }).collect();
+        let output: Vec<i16> = CompressorSource::new(
+            rodio::buffer::SamplesBuffer::new(1, 44100, input),
+            0.5,
+            4.0,
+            10,
+            1000,
+        ).collect();
+        assert_eq!(output.len(), 2000);
+        assert_eq!(output[0], 27000, "The attack takes a tenth off per sample");
+        
+        // Once attacked, peaks sit near threshold + (30000 - threshold) / 4
+        let threshold = 0.5 * i16::MAX as f32;
+        let expected = (threshold + (30000.0 - threshold) / 4.0) as i16;
+        let peak = output[100..].iter().map(|s| s.unsigned_abs()).max().unwrap();
+        assert!(peak < 30000);
+        assert!((peak as i16 - expected).abs() <= 1, "Expected peaks around {}, got {}", expected, peak);
+    }
+    
+    #[test]
+    fn test_compressor_leaves_quiet_signal_alone() {
+        let input: Vec<i16> = (0..500).map(|i| ((i % 20) as i16 - 10) * 1000).collect();
+        let output: Vec<i16> = CompressorSource::new(
+            rodio::buffer::SamplesBuffer::new(1, 44100, input.clone()),
+            0.5,
+            4.0,
+            10,
+            1000,
+        ).collect();
+        assert_eq!(output, input);
+    }
+    
+    #[test]
+    fn test_adsr_release_before_sustain() {
+        // Release starts mid-attack and ramps down from the level reached there
+        let mut samples = vec![10000i16; 100];
+        apply_adsr(&mut samples, 80, 10, 0.5, 60);
+        assert!(samples[40] <= 5000, "Release should start from the attack level at sample 40");
+        assert!(samples[40] > samples[41]);
+        assert_eq!(samples[99], 0);
+    }
+    
+    #[test]
+    fn test_filters() {
+        // A constant signal is all low frequency: a low pass settles on it, a high pass removes it
+        let mut low = vec![10000i16; 2000];
+        apply_filter(&mut low, 1, 44100, 1000.0, false);
+        assert!(low[0] < 10000);
+        assert!(low[1999] > 9990);
+
+        let mut high = vec![10000i16; 2000];
+        apply_filter(&mut high, 1, 44100, 1000.0, true);
+        assert!(high[0] > 0);
+        assert!(high[1999].abs() < 10);
+    }
+    
+    #[test]
+    fn test_delay_adds_echoes() {
+        let delayed = apply_delay(&[1000, 1000], 4);
+        assert_eq!(delayed.len(), 2 + 4 * DELAY_REPEATS);
+        assert_eq!(&delayed[..6], &[1000, 1000, 0, 0, 500, 500]);
+        assert_eq!(delayed[12], 125);
+        assert_eq!(apply_delay(&[1000], 0), vec![1000]);
+    }
+    
+    #[test]
+    fn test_master_volume_scales_track_volume() {
+        let (_temp_dir, samples_dir, tracks) = setup_test_environment();
+        
+        if let Ok(mut player) = SamplePlayer::new(&samples_dir) {
+            player.initialize_with_tracks(&tracks).unwrap();
+            player.set_master_volume(0.0);
+            player.process_trigger(&TriggerEvent { track_idx: 1, step_idx: 0 }).unwrap();
+            assert_eq!(player.track_sink_volume(1), Some(0.0));
+            
+            player.set_master_volume(0.5);
+            assert_eq!(player.track_sink_volume(1), Some(0.4));
+            player.set_track_volume(1, 1.0).unwrap();
+            player.process_trigger(&TriggerEvent { track_idx: 1, step_idx: 0 }).unwrap();
+            assert_eq!(player.track_sink_volume(1), Some(0.5));
+        }
+    }
+    
+    #[test]
+    fn test_solo_silences_other_tracks() {
+        let (_temp_dir, samples_dir, tracks) = setup_test_environment();
+        
+        if let Ok(mut player) = SamplePlayer::new(&samples_dir) {
+            player.initialize_with_tracks(&tracks).unwrap();
+            player.set_track_soloed(1, true);
+            player.process_trigger(&TriggerEvent { track_idx: 0, step_idx: 0 }).unwrap();
+            player.process_trigger(&TriggerEvent { track_idx: 1, step_idx: 0 }).unwrap();
+            assert!(player.track_sinks[&0].empty());
+            assert!(!player.track_sinks[&1].empty());
+            
+            // The soloed track moves down when the one before it is removed
+            player.remove_track(0);
+            assert!(player.soloed_tracks.contains(&0));
+            player.set_track_soloed(0, false);
+            player.process_trigger(&TriggerEvent { track_idx: 1, step_idx: 0 }).unwrap();
+            assert!(!player.track_sinks[&1].empty());
+        }
+    }
+    
+    fn sine(freq: f32, sample_rate: u32, frames: usize) -> Vec<i16> {
+        (0..frames)
+            .map(|i| ((2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate as f32).sin() * 16000.0) as i16)
+            .collect()
+    }
+    
+    fn cents(detected: f32, expected: f32) -> f32 {
+        (1200.0 * (detected / expected).log2()).abs()
+    }
+    
+    #[test]
+    fn test_detect_fundamental_of_sine() {
+        for (freq, sample_rate) in [(440.0, 44100), (82.41, 44100), (1500.0, 48000)] {
+            let detected = detect_fundamental(&sine(freq, sample_rate, 8192), 1, sample_rate).unwrap();
+            assert!(cents(detected, freq) < 5.0, "{} Hz detected as {} Hz", freq, detected);
+        }
+        
+        // Channels are mixed down before detection
+        let stereo: Vec<i16> = sine(220.0, 44100, 4096).into_iter().flat_map(|s| [s, s]).collect();
+        assert!(cents(detect_fundamental(&stereo, 2, 44100).unwrap(), 220.0) < 5.0);
+    }
+    
+    #[test]
+    fn test_detect_fundamental_rejects_unpitched_audio() {
+        assert_eq!(detect_fundamental(&vec![0; 4096], 1, 44100), None);
+        assert_eq!(detect_fundamental(&sine(440.0, 44100, 100), 1, 44100), None);
+        
+        // A noise burst, like a snare or hi-hat
+        let mut state = 12345u32;
+        let noise: Vec<i16> = (0..4096).map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as i16
+        }).collect();
+        assert_eq!(detect_fundamental(&noise, 1, 44100), None);
+    }
+    
+    #[test]
+    fn test_auto_tune_to_target_pitch() {
+        let temp_dir = tempdir().unwrap();
+        let spec = hound::WavSpec { channels: 1, sample_rate: 44100, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(temp_dir.path().join("tone.wav"), spec).unwrap();
+        for sample in sine(440.0, 44100, 8192) {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        
+        if let Ok(mut player) = SamplePlayer::new(temp_dir.path()) {
+            let sample_idx = player.set_track_sample(0, "tone.wav").unwrap();
+            assert!(cents(player.detect_pitch(sample_idx).unwrap(), 440.0) < 5.0);
+            player.processor.set_effect(0, SampleEffect::Pitch(2.0));
+            let semitones = player.auto_tune_to(sample_idx, 880.0).unwrap();
+            assert!((semitones - 12.0).abs() < 0.05);
+            assert!((player.processor.tuning(0) - 10.0).abs() < 0.05);
+            assert_eq!(player.processor.list_effects(0), vec!["Pitch(+2st)"]);
+            assert!(matches!(player.auto_tune_to(99, 440.0), Err(AudioError::SampleNotFound(_))));
+        }
+    }
+    
+    #[test]
+    fn test_stop_track_leaves_other_tracks_playing() {
+        let (_temp_dir, samples_dir, tracks) = setup_test_environment();
+        
+        if let Ok(mut player) = SamplePlayer::new(&samples_dir) {
+            player.initialize_with_tracks(&tracks).unwrap();
+            player.process_trigger(&TriggerEvent { track_idx: 0, step_idx: 0 }).unwrap();
+            player.process_trigger(&TriggerEvent { track_idx: 1, step_idx: 0 }).unwrap();
+            assert!(player.is_track_active(0) && player.is_track_active(1));
+            
+            player.stop_track(0).unwrap();
+            assert!(!player.is_track_active(0));
+            assert!(player.is_track_active(1));
+            assert!(player.stop_track(10).is_err());
+            assert!(!player.is_track_active(10));
+        }
+    }
+    
     #[test]
     fn test_error_handling() {
         let (_temp_dir, samples_dir, _) = setup_test_environment();
@@ -80,9 +80,9 @@ fn find_project_samples() -> Option<PathBuf> {
         PathBuf::from("../../gaucho-projects/my-song/samples"),
         // From project root
         PathBuf::from("./gaucho-projects/my-song/samples"),
-        // From project root with full path
-        PathBuf::from("/Users/esteban.chq/informalthinkers/gaucho-tracker/gaucho-projects/my-song/samples"),
     ]);
+    // Wherever the configured project folders hold it
+    possible_paths.push(project::get_project_path("my-song").join("samples"));
     
     // Print all paths we're checking
     println!("Looking for sample files in:");
@@ -111,26 +111,46 @@ fn run_interactive_demo(sample_dir: &Path) -> Result<(), Box<dyn Error>> {
             name: "Kick".to_string(), 
             sample: "kick.wav".to_string(),
             volume: 1.0,
+            pan: 0.0,
+            pitch_semitones: 0.0,
+            muted: false,
+            color: None,
         },
         Track {
             name: "Snare".to_string(),
             sample: "snare.wav".to_string(),
             volume: 0.8,
+            pan: 0.0,
+            pitch_semitones: 0.0,
+            muted: false,
+            color: None,
         },
         Track {
             name: "HiHat".to_string(),
             sample: "hihat.wav".to_string(),
             volume: 0.7,
+            pan: 0.0,
+            pitch_semitones: 0.0,
+            muted: false,
+            color: None,
         },
         Track {
             name: "Open HiHat".to_string(),
             sample: "open-hihat.wav".to_string(),
             volume: 0.7,
+            pan: 0.0,
+            pitch_semitones: 0.0,
+            muted: false,
+            color: None,
         },
         Track {
             name: "Clap".to_string(),
             sample: "reverbered-clap-punchy-shot.wav".to_string(),
             volume: 0.6,
+            pan: 0.0,
+            pitch_semitones: 0.0,
+            muted: false,
+            color: None,
         },
     ];
     
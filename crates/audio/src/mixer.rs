@@ -0,0 +1,183 @@
+// Callback-driven sample mixer for backends that pull audio, like JACK
+use std::sync::Arc;
+use rtrb::{Consumer, Producer, RingBuffer};
+
+/// A sample playing on one voice
+#[derive(Debug, Clone)]
+pub struct ActiveSample {
+    /// Mono sample data
+    pub data: Arc<Vec<i16>>,
+    /// Next sample to play
+    pub position: usize,
+    pub gain: f32,
+}
+
+/// Mixes the samples playing on a fixed set of voices, one per track, into the
+/// output buffer of each process cycle. A new trigger on a voice cuts off the
+/// sample it was playing, like a rodio sink being cleared.
+///
+/// Nothing here allocates or frees once the mixer is built, so it can run on a
+/// real-time thread: samples that stop playing go to the return queue, if there
+/// is one, and are dropped by whoever drains it.
+#[derive(Debug)]
+pub struct Mixer {
+    voices: Vec<Option<ActiveSample>>,
+    retired: Option<Producer<ActiveSample>>,
+}
+
+impl Mixer {
+    /// A mixer with `voices` silent voices that drops finished samples itself
+    pub fn new(voices: usize) -> Self {
+        Self { voices: vec![None; voices], retired: None }
+    }
+
+    /// A mixer with `voices` silent voices that hands finished samples back through
+    /// the returned queue. If the queue is full they are dropped in place.
+    pub fn with_return_queue(voices: usize, capacity: usize) -> (Self, Consumer<ActiveSample>) {
+        let (retired, returned) = RingBuffer::new(capacity);
+        (Self { voices: vec![None; voices], retired: Some(retired) }, returned)
+    }
+
+    /// Start `data` from the beginning on a voice. Voices past the ones the mixer was built
+    /// with are ignored.
+    pub fn trigger(&mut self, voice: usize, data: Arc<Vec<i16>>, gain: f32) {
+        let sample = ActiveSample { data, position: 0, gain };
+        match self.voices.get_mut(voice) {
+            Some(slot) => {
+                if let Some(previous) = slot.replace(sample) {
+                    retire(&mut self.retired, previous);
+                }
+            },
+            None => retire(&mut self.retired, sample),
+        }
+    }
+
+    /// Silence a voice
+    pub fn stop(&mut self, voice: usize) {
+        if let Some(previous) = self.voices.get_mut(voice).and_then(Option::take) {
+            retire(&mut self.retired, previous);
+        }
+    }
+
+    /// Silence every voice
+    pub fn stop_all(&mut self) {
+        for slot in &mut self.voices {
+            if let Some(previous) = slot.take() {
+                retire(&mut self.retired, previous);
+            }
+        }
+    }
+
+    /// Number of voices
+    pub fn voice_count(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Whether a voice is still playing
+    pub fn is_active(&self, voice: usize) -> bool {
+        self.voices.get(voice).is_some_and(Option::is_some)
+    }
+
+    /// Fill `out` with the sum of every playing voice, advancing them.
+    /// Voices that reach the end of their sample go silent.
+    pub fn process(&mut self, out: &mut [f32]) {
+        out.fill(0.0);
+        for slot in &mut self.voices {
+            let Some(active) = slot else { continue };
+            let remaining = &active.data[active.position.min(active.data.len())..];
+            for (frame, &sample) in out.iter_mut().zip(remaining) {
+                *frame += sample as f32 / i16::MAX as f32 * active.gain;
+            }
+            active.position += out.len().min(remaining.len());
+            if active.position >= active.data.len() {
+                if let Some(finished) = slot.take() {
+                    retire(&mut self.retired, finished);
+                }
+            }
+        }
+        for frame in out.iter_mut() {
+            *frame = frame.clamp(-1.0, 1.0);
+        }
+    }
+}
+
+/// Hand a sample that stopped playing to the return queue, or drop it when there is no room
+fn retire(retired: &mut Option<Producer<ActiveSample>>, sample: ActiveSample) {
+    if let Some(queue) = retired {
+        let _ = queue.push(sample);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mixes_and_advances_voices() {
+        let mut mixer = Mixer::new(2);
+        mixer.trigger(0, Arc::new(vec![i16::MAX; 6]), 0.5);
+        mixer.trigger(1, Arc::new(vec![i16::MAX; 3]), 0.25);
+
+        let mut out = [0.0f32; 4];
+        mixer.process(&mut out);
+        assert_eq!(out, [0.75, 0.75, 0.75, 0.5]);
+        assert!(mixer.is_active(0));
+        assert!(!mixer.is_active(1));
+
+        mixer.process(&mut out);
+        assert_eq!(out, [0.5, 0.5, 0.0, 0.0]);
+        assert!(!mixer.is_active(0));
+    }
+
+    #[test]
+    fn test_retrigger_restarts_and_stop_silences() {
+        let mut mixer = Mixer::new(4);
+        let data = Arc::new(vec![i16::MAX; 4]);
+        mixer.trigger(3, Arc::clone(&data), 1.0);
+        let mut out = [0.0f32; 2];
+        mixer.process(&mut out);
+        mixer.trigger(3, data, 1.0);
+        mixer.process(&mut out);
+        assert!(mixer.is_active(3), "Retrigger should start over");
+
+        mixer.stop(3);
+        mixer.process(&mut out);
+        assert_eq!(out, [0.0, 0.0]);
+
+        mixer.trigger(0, Arc::new(vec![i16::MAX; 4]), 1.0);
+        mixer.stop_all();
+        assert!(!mixer.is_active(0));
+    }
+
+    #[test]
+    fn test_out_of_range_voices_are_ignored() {
+        let mut mixer = Mixer::new(2);
+        mixer.trigger(5, Arc::new(vec![i16::MAX; 4]), 1.0);
+        assert_eq!(mixer.voice_count(), 2);
+        assert!(!mixer.is_active(5));
+
+        let mut out = [0.0f32; 2];
+        mixer.process(&mut out);
+        assert_eq!(out, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_displaced_samples_go_to_return_queue() {
+        let (mut mixer, mut returned) = Mixer::with_return_queue(2, 8);
+        let data = Arc::new(vec![i16::MAX; 2]);
+        mixer.trigger(0, Arc::clone(&data), 1.0);
+        mixer.trigger(0, Arc::clone(&data), 1.0);
+        mixer.trigger(1, Arc::clone(&data), 1.0);
+        mixer.stop(1);
+        mixer.trigger(0, Arc::clone(&data), 1.0);
+        mixer.trigger(9, Arc::clone(&data), 1.0);
+        let mut out = [0.0f32; 2];
+        mixer.process(&mut out);
+        assert!(!mixer.is_active(0));
+
+        // Retriggered, stopped, retriggered, out of range and finished
+        assert_eq!(returned.slots(), 5);
+        while returned.pop().is_ok() {}
+        assert_eq!(Arc::strong_count(&data), 1);
+    }
+}
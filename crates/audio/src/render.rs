@@ -0,0 +1,212 @@
+// Offline rendering of patterns to WAV files
+// Decodes samples directly and mixes them into a buffer, so no audio device is required
+
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::time::Duration;
+use log::{debug, info};
+use rodio::source::UniformSourceIterator;
+use rodio::Decoder;
+
+use crate::AudioError;
+use project::model::Track;
+
+/// Sample rate of rendered files
+pub const RENDER_SAMPLE_RATE: u32 = 44100;
+
+/// Channel count of rendered files
+pub const RENDER_CHANNELS: u16 = 2;
+
+/// Steps in one bar (16th notes in 4/4)
+pub const STEPS_PER_BAR: usize = 16;
+
+/// Decode a sample file into interleaved stereo frames at the render sample rate
+fn decode_for_render(path: &Path) -> Result<Vec<i16>, AudioError> {
+    let data = fs::read(path).map_err(|e| AudioError::SampleLoadError(
+        path.display().to_string(),
+        format!("Failed to read file: {}", e),
+    ))?;
+    let decoder = Decoder::new(Cursor::new(data)).map_err(|e| AudioError::SampleLoadError(
+        path.display().to_string(),
+        format!("Failed to decode file: {}", e),
+    ))?;
+    let converted: UniformSourceIterator<_, i16> =
+        UniformSourceIterator::new(decoder, RENDER_CHANNELS, RENDER_SAMPLE_RATE);
+    Ok(converted.collect())
+}
+
+/// Mix a pattern into an interleaved stereo buffer.
+/// `pattern` is indexed [track][step] and loops until `bars` bars have been rendered.
+pub fn mix_pattern(pattern: &[Vec<bool>], track_samples: &[Vec<i16>], volumes: &[f32], bpm: u32, bars: usize) -> Vec<i16> {
+    let total_steps = bars * STEPS_PER_BAR;
+    let step_frames = (RENDER_SAMPLE_RATE as f64 * 60.0 / bpm.max(1) as f64 / 4.0) as usize;
+    let channels = RENDER_CHANNELS as usize;
+    let mut mix = vec![0i32; total_steps * step_frames * channels];
+
+    for step in 0..total_steps {
+        for (track_idx, track_steps) in pattern.iter().enumerate() {
+            if track_steps.is_empty() || !track_steps[step % track_steps.len()] {
+                continue;
+            }
+            let Some(sample) = track_samples.get(track_idx) else { continue };
+            let volume = volumes.get(track_idx).copied().unwrap_or(1.0);
+            let offset = step * step_frames * channels;
+            for (dst, &src) in mix[offset..].iter_mut().zip(sample.iter()) {
+                *dst += (src as f32 * volume) as i32;
+            }
+        }
+    }
+
+    mix.into_iter()
+        .map(|s| s.clamp(i16::MIN as i32, i16::MAX as i32) as i16)
+        .collect()
+}
+
+/// Mix timed triggers into an interleaved stereo buffer `length` long.
+/// Each trigger is (time from the start, track index, velocity); sounds past the end are cut off.
+pub fn mix_triggers(triggers: &[(Duration, usize, f32)], track_samples: &[Vec<i16>], volumes: &[f32], length: Duration) -> Vec<i16> {
+    let channels = RENDER_CHANNELS as usize;
+    let total_frames = (length.as_secs_f64() * RENDER_SAMPLE_RATE as f64).round() as usize;
+    let mut mix = vec![0i32; total_frames * channels];
+
+    for &(at, track_idx, velocity) in triggers {
+        let Some(sample) = track_samples.get(track_idx) else { continue };
+        let volume = volumes.get(track_idx).copied().unwrap_or(1.0) * velocity;
+        let offset = (at.as_secs_f64() * RENDER_SAMPLE_RATE as f64).round() as usize * channels;
+        if offset >= mix.len() {
+            continue;
+        }
+        for (dst, &src) in mix[offset..].iter_mut().zip(sample.iter()) {
+            *dst += (src as f32 * volume) as i32;
+        }
+    }
+
+    mix.into_iter()
+        .map(|s| s.clamp(i16::MIN as i32, i16::MAX as i32) as i16)
+        .collect()
+}
+
+/// Decode the sample of every track for offline mixing
+pub fn decode_track_samples(tracks: &[Track], sample_dir: &Path) -> Result<Vec<Vec<i16>>, AudioError> {
+    tracks.iter()
+        .map(|track| {
+            debug!("Decoding sample '{}' for track '{}'", track.sample, track.name);
+            decode_for_render(&sample_dir.join(&track.sample))
+        })
+        .collect()
+}
+
+/// Write an interleaved stereo buffer to a 16-bit WAV file
+pub fn write_wav(output: &Path, mix: &[i16]) -> Result<(), AudioError> {
+    let spec = hound::WavSpec {
+        channels: RENDER_CHANNELS,
+        sample_rate: RENDER_SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(output, spec)
+        .map_err(|e| AudioError::PlaybackError(format!("Failed to create {}: {}", output.display(), e)))?;
+    for &sample in mix {
+        writer.write_sample(sample)
+            .map_err(|e| AudioError::PlaybackError(format!("Failed to write sample: {}", e)))?;
+    }
+    writer.finalize()
+        .map_err(|e| AudioError::PlaybackError(format!("Failed to finalize {}: {}", output.display(), e)))?;
+    Ok(())
+}
+
+/// Render `bars` bars of a pattern to a 16-bit stereo WAV file
+pub fn render_pattern_to_wav(
+    pattern: &[Vec<bool>],
+    tracks: &[Track],
+    sample_dir: &Path,
+    bpm: u32,
+    bars: usize,
+    output: &Path,
+) -> Result<(), AudioError> {
+    info!("Rendering {} bars at {} BPM to {}", bars, bpm, output.display());
+
+    let track_samples = decode_track_samples(tracks, sample_dir)?;
+    let volumes: Vec<f32> = tracks.iter().map(|t| t.volume).collect();
+
+    let mix = mix_pattern(pattern, &track_samples, &volumes, bpm, bars);
+    write_wav(output, &mix)?;
+
+    info!("Render complete: {}", output.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mix_pattern_places_hits_on_steps() {
+        // At 150 BPM a 16th note is exactly 4410 frames
+        let pattern = vec![vec![true, false]];
+        let click = vec![1000i16, 1000, 500, 500];
+        let mix = mix_pattern(&pattern, &[click], &[1.0], 150, 1);
+
+        assert_eq!(mix.len(), 16 * 4410 * 2);
+        assert_eq!(&mix[0..4], &[1000, 1000, 500, 500]);
+        assert_eq!(mix[4410 * 2], 0);
+        assert_eq!(mix[2 * 4410 * 2], 1000);
+    }
+
+    #[test]
+    fn test_mix_pattern_applies_volume_and_clamps() {
+        let pattern = vec![vec![true], vec![true]];
+        let loud = vec![i16::MAX, i16::MAX];
+        let mix = mix_pattern(&pattern, &[loud.clone(), loud], &[1.0, 0.5], 120, 1);
+        assert_eq!(mix[0], i16::MAX);
+
+        let quiet = mix_pattern(&pattern[..1], &[vec![1000, 1000]], &[0.5], 120, 1);
+        assert_eq!(quiet[0], 500);
+    }
+
+    #[test]
+    fn test_mix_triggers_places_hits_at_times() {
+        let click = vec![1000i16, 1000];
+        let triggers = [(Duration::ZERO, 0, 1.0), (Duration::from_millis(10), 0, 0.5), (Duration::from_secs(5), 0, 1.0)];
+        let mix = mix_triggers(&triggers, &[click], &[0.5], Duration::from_millis(20));
+
+        assert_eq!(mix.len(), 882 * 2);
+        assert_eq!(&mix[0..2], &[500, 500]);
+        assert_eq!(mix[2], 0);
+        assert_eq!(&mix[441 * 2..441 * 2 + 2], &[250, 250]);
+    }
+
+    #[test]
+    fn test_render_pattern_to_wav() {
+        let dir = tempfile::tempdir().unwrap();
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(dir.path().join("click.wav"), spec).unwrap();
+        for _ in 0..100 {
+            writer.write_sample(8000i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let tracks = vec![Track {
+            name: "Click".to_string(),
+            sample: "click.wav".to_string(),
+            volume: 1.0,
+            pan: 0.0,
+            pitch_semitones: 0.0,
+            muted: false,
+            color: None,
+        }];
+        let output = dir.path().join("out.wav");
+        render_pattern_to_wav(&[vec![true, false, false, false]], &tracks, dir.path(), 120, 2, &output).unwrap();
+
+        let reader = hound::WavReader::open(&output).unwrap();
+        assert_eq!(reader.spec().channels, RENDER_CHANNELS);
+        assert_eq!(reader.spec().sample_rate, RENDER_SAMPLE_RATE);
+        assert_eq!(reader.duration() as usize, 2 * STEPS_PER_BAR * 5512);
+    }
+}
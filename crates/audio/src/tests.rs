@@ -73,16 +73,28 @@ mod tests {
                 name: "Kick".to_string(),
                 sample: "kick.wav".to_string(),
                 volume: 1.0,
+                pan: 0.0,
+                pitch_semitones: 0.0,
+                muted: false,
+                color: None,
             },
             Track {
                 name: "Snare".to_string(),
                 sample: "snare.wav".to_string(),
                 volume: 0.8,
+                pan: 0.0,
+                pitch_semitones: 0.0,
+                muted: false,
+                color: None,
             },
             Track {
                 name: "HiHat".to_string(),
                 sample: "hihat.wav".to_string(),
                 volume: 0.7,
+                pan: 0.0,
+                pitch_semitones: 0.0,
+                muted: false,
+                color: None,
             }
         ];
         
@@ -1,7 +1,12 @@
 // Core event system for Gaucho Tracker
 // This provides a common event system that different components can use
 
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::collections::VecDeque;
+use std::thread;
+use std::time::Instant;
+use crossbeam_channel::{unbounded, Sender};
 
 /// Event type for the tracker system
 #[derive(Debug, Clone)]
@@ -20,18 +25,78 @@ pub enum TrackerEvent {
     
     /// Track volume changed (track_idx, volume)
     TrackVolumeChanged(usize, f32),
+    
+    /// A new bar started playing (bar number, counted from 0 since playback started)
+    BarStarted(u32),
+
+    /// A track was appended to the pattern (track_idx)
+    TrackAdded(usize),
+
+    /// A track was removed from the pattern (track_idx)
+    TrackRemoved(usize),
+
+    /// A pattern slot received new contents or became the pattern being edited (pattern index)
+    PatternLoaded(u32),
+
+    /// Another project replaced the one being edited (project name)
+    ProjectLoaded(String),
+
+    /// Gain applied on top of every track's volume changed (volume)
+    MasterVolumeChanged(f32),
+
+    /// A track was soloed or unsoloed (track_idx, soloed)
+    TrackSoloChanged(usize, bool),
+
+    /// The note value of a step changed (8, 16 or 32)
+    SubdivisionChanged(u32),
 }
 
+type Listener = Arc<dyn Fn(&TrackerEvent) + Send + Sync>;
+
+/// Listeners with the id returned when they subscribed
+type ListenerList = Arc<Mutex<Vec<(usize, Listener)>>>;
+
 /// A simple event bus implementation
 pub struct EventBus {
-    listeners: Arc<Mutex<Vec<Box<dyn Fn(&TrackerEvent) + Send + Sync>>>>,
+    listeners: ListenerList,
+    /// Listeners called on the emitting thread, even for `emit_async` events
+    immediate_listeners: ListenerList,
+    next_id: Arc<AtomicUsize>,
+    /// Queue of events for the dispatch thread, fed by `emit_async`
+    async_sender: Sender<TrackerEvent>,
+    /// Recent events with the time they were emitted; None while history is disabled
+    history: Arc<Mutex<Option<EventHistory>>>,
+}
+
+/// Bounded record of emitted events, oldest first
+struct EventHistory {
+    max_events: usize,
+    events: VecDeque<(Instant, TrackerEvent)>,
 }
 
 impl EventBus {
     /// Create a new event bus
     pub fn new() -> Self {
+        let listeners: ListenerList = Arc::new(Mutex::new(Vec::new()));
+        let (async_sender, receiver) = unbounded::<TrackerEvent>();
+        
+        // Delivers emit_async events in order; exits once every clone of the bus is dropped
+        let dispatch_listeners = Arc::clone(&listeners);
+        thread::Builder::new()
+            .name("event-dispatch".to_string())
+            .spawn(move || {
+                for event in receiver {
+                    dispatch(&dispatch_listeners, &event);
+                }
+            })
+            .expect("Failed to spawn event dispatch thread");
+        
         Self {
-            listeners: Arc::new(Mutex::new(Vec::new())),
+            listeners,
+            immediate_listeners: Arc::new(Mutex::new(Vec::new())),
+            next_id: Arc::new(AtomicUsize::new(0)),
+            async_sender,
+            history: Arc::new(Mutex::new(None)),
         }
     }
     
@@ -40,17 +105,165 @@ impl EventBus {
     where
         F: Fn(&TrackerEvent) + Send + Sync + 'static,
     {
-        let mut listeners = self.listeners.lock().unwrap();
-        let id = listeners.len();
-        listeners.push(Box::new(listener));
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.listeners.lock().unwrap().push((id, Arc::new(listener)));
+        id
+    }
+    
+    /// Subscribe a listener that is called on the emitting thread for every event,
+    /// including `emit_async` ones, so it sees events in emission order without waiting
+    /// for the dispatch thread. Meant for forwarders such as the audio connector's;
+    /// it must return quickly, since it holds up whoever emits.
+    pub fn subscribe_immediate<F>(&self, listener: F) -> usize
+    where
+        F: Fn(&TrackerEvent) + Send + Sync + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.immediate_listeners.lock().unwrap().push((id, Arc::new(listener)));
+        id
+    }
+    
+    /// Subscribe a listener that only receives events accepted by `filter`
+    pub fn subscribe_filtered<P, F>(&self, filter: P, listener: F) -> usize
+    where
+        P: Fn(&TrackerEvent) -> bool + Send + Sync + 'static,
+        F: Fn(&TrackerEvent) + Send + Sync + 'static,
+    {
+        self.subscribe(move |event| {
+            if filter(event) {
+                listener(event);
+            }
+        })
+    }
+    
+    /// Subscribe a listener that stops receiving events once it returns true
+    pub fn subscribe_once<F>(&self, listener: F) -> usize
+    where
+        F: Fn(&TrackerEvent) -> bool + Send + Sync + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let once = OnceListener {
+            id,
+            fired: AtomicBool::new(false),
+            listeners: Arc::downgrade(&self.listeners),
+            listener,
+        };
+        self.listeners.lock().unwrap().push((id, Arc::new(move |event: &TrackerEvent| once.call(event))));
         id
     }
     
-    /// Emit an event to all listeners
+    /// Remove a listener; returns false if the id isn't subscribed
+    pub fn unsubscribe(&self, id: usize) -> bool {
+        remove_listener(&self.listeners, id) || remove_listener(&self.immediate_listeners, id)
+    }
+    
+    /// Number of subscribed listeners
+    pub fn listener_count(&self) -> usize {
+        self.listeners.lock().unwrap().len() + self.immediate_listeners.lock().unwrap().len()
+    }
+    
+    /// Emit an event to all listeners.
+    /// Listeners are called without holding the lock, so they may subscribe or unsubscribe.
     pub fn emit(&self, event: TrackerEvent) {
-        let listeners = self.listeners.lock().unwrap();
-        for listener in listeners.iter() {
-            listener(&event);
+        self.record(&event);
+        dispatch(&self.immediate_listeners, &event);
+        dispatch(&self.listeners, &event);
+    }
+    
+    /// Start recording emitted events, keeping the most recent `max_events`.
+    /// History is off by default; enabling it again keeps the events already recorded.
+    pub fn enable_history(&self, max_events: usize) {
+        let mut history = self.history.lock().unwrap();
+        let mut events = history.take().map(|h| h.events).unwrap_or_default();
+        while events.len() > max_events {
+            events.pop_front();
+        }
+        *history = Some(EventHistory { max_events, events });
+    }
+    
+    /// Stop recording events and drop the recorded history
+    pub fn disable_history(&self) {
+        *self.history.lock().unwrap() = None;
+    }
+    
+    /// Snapshot of the recorded events, oldest first; empty while history is disabled
+    pub fn get_history(&self) -> Vec<(Instant, TrackerEvent)> {
+        self.history.lock().unwrap()
+            .as_ref()
+            .map(|h| h.events.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+    
+    /// Forget recorded events, leaving history enabled
+    pub fn clear_history(&self) {
+        if let Some(history) = self.history.lock().unwrap().as_mut() {
+            history.events.clear();
+        }
+    }
+    
+    fn record(&self, event: &TrackerEvent) {
+        let mut history = self.history.lock().unwrap();
+        let Some(history) = history.as_mut() else { return };
+        if history.max_events == 0 {
+            return;
+        }
+        if history.events.len() == history.max_events {
+            history.events.pop_front();
+        }
+        history.events.push_back((Instant::now(), event.clone()));
+    }
+    
+    /// Queue an event for delivery on the dispatch thread and return immediately.
+    /// Events sent this way reach listeners in order, but not in order with `emit`.
+    /// Listeners added with `subscribe_immediate` still get the event before this returns.
+    pub fn emit_async(&self, event: TrackerEvent) {
+        self.record(&event);
+        dispatch(&self.immediate_listeners, &event);
+        // The dispatch thread only stops when the bus is dropped, so this can't fail while we hold it
+        let _ = self.async_sender.send(event);
+    }
+}
+
+fn dispatch(listeners: &Mutex<Vec<(usize, Listener)>>, event: &TrackerEvent) {
+    let listeners: Vec<Listener> = listeners.lock().unwrap()
+        .iter()
+        .map(|(_, listener)| Arc::clone(listener))
+        .collect();
+    for listener in listeners {
+        listener(event);
+    }
+}
+
+fn remove_listener(listeners: &Mutex<Vec<(usize, Listener)>>, id: usize) -> bool {
+    let mut listeners = listeners.lock().unwrap();
+    let len = listeners.len();
+    listeners.retain(|(listener_id, _)| *listener_id != id);
+    listeners.len() != len
+}
+
+/// Wraps a `subscribe_once` listener so it handles at most one event, then unsubscribes itself
+struct OnceListener<F> {
+    id: usize,
+    fired: AtomicBool,
+    /// Weak so the bus isn't kept alive by its own listener
+    listeners: Weak<Mutex<Vec<(usize, Listener)>>>,
+    listener: F,
+}
+
+impl<F> OnceListener<F>
+where
+    F: Fn(&TrackerEvent) -> bool,
+{
+    fn call(&self, event: &TrackerEvent) {
+        if self.fired.load(Ordering::SeqCst) || !(self.listener)(event) {
+            return;
+        }
+        // Another thread may have handled an event at the same time; only the first counts
+        if self.fired.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        if let Some(listeners) = self.listeners.upgrade() {
+            remove_listener(&listeners, self.id);
         }
     }
 }
@@ -68,6 +281,10 @@ impl Clone for EventBus {
     fn clone(&self) -> Self {
         Self {
             listeners: Arc::clone(&self.listeners),
+            immediate_listeners: Arc::clone(&self.immediate_listeners),
+            next_id: Arc::clone(&self.next_id),
+            async_sender: self.async_sender.clone(),
+            history: Arc::clone(&self.history),
         }
     }
 }
@@ -75,7 +292,6 @@ impl Clone for EventBus {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::atomic::{AtomicUsize, Ordering};
     
     #[test]
     fn test_event_bus() {
@@ -98,4 +314,141 @@ mod tests {
         // Check counter was incremented only for StepTriggered events
         assert_eq!(counter.load(Ordering::SeqCst), 2);
     }
+    
+    #[test]
+    fn test_subscribe_filtered() {
+        let bus = EventBus::new();
+        let volumes = Arc::new(Mutex::new(Vec::new()));
+        let volumes_clone = Arc::clone(&volumes);
+        bus.subscribe_filtered(
+            |event| matches!(event, TrackerEvent::TrackVolumeChanged(_, _)),
+            move |event| volumes_clone.lock().unwrap().push(format!("{:?}", event)),
+        );
+        
+        bus.emit(TrackerEvent::StepTriggered(0, 0));
+        bus.emit(TrackerEvent::TrackVolumeChanged(1, 0.5));
+        bus.emit(TrackerEvent::PatternChanged);
+        
+        assert_eq!(*volumes.lock().unwrap(), vec!["TrackVolumeChanged(1, 0.5)".to_string()]);
+    }
+    
+    #[test]
+    fn test_unsubscribe() {
+        let bus = EventBus::new();
+        let counter = Arc::new(AtomicUsize::new(0));
+        let counter_clone = Arc::clone(&counter);
+        let id = bus.subscribe(move |_| {
+            counter_clone.fetch_add(1, Ordering::SeqCst);
+        });
+        let other = bus.subscribe(|_| {});
+        assert_ne!(id, other);
+        
+        bus.emit(TrackerEvent::PatternChanged);
+        assert!(bus.unsubscribe(id));
+        assert!(!bus.unsubscribe(id));
+        bus.emit(TrackerEvent::PatternChanged);
+        
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        assert_eq!(bus.listener_count(), 1);
+    }
+    
+    #[test]
+    fn test_subscribe_once_fires_exactly_once() {
+        let bus = EventBus::new();
+        let seen = Arc::new(AtomicUsize::new(0));
+        let fired = Arc::new(AtomicUsize::new(0));
+        let seen_clone = Arc::clone(&seen);
+        let fired_clone = Arc::clone(&fired);
+        bus.subscribe_once(move |event| {
+            seen_clone.fetch_add(1, Ordering::SeqCst);
+            let matched = matches!(event, TrackerEvent::StepTriggered(0, 4));
+            if matched {
+                fired_clone.fetch_add(1, Ordering::SeqCst);
+            }
+            matched
+        });
+        
+        bus.emit(TrackerEvent::StepTriggered(0, 3));
+        bus.emit(TrackerEvent::BpmChanged(100));
+        bus.emit(TrackerEvent::StepTriggered(0, 4));
+        bus.emit(TrackerEvent::StepTriggered(0, 4));
+        bus.emit(TrackerEvent::StepTriggered(0, 5));
+        
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+        assert_eq!(seen.load(Ordering::SeqCst), 3);
+        assert_eq!(bus.listener_count(), 0);
+    }
+    
+    #[test]
+    fn test_emit_async_does_not_block() {
+        use std::time::{Duration, Instant};
+        
+        let bus = EventBus::new();
+        let (done_sender, done) = crossbeam_channel::unbounded();
+        bus.subscribe(move |event| {
+            if let TrackerEvent::StepTriggered(_, step) = event {
+                thread::sleep(Duration::from_millis(100));
+                done_sender.send(*step).unwrap();
+            }
+        });
+        
+        let started = Instant::now();
+        bus.emit_async(TrackerEvent::StepTriggered(0, 1));
+        bus.emit_async(TrackerEvent::StepTriggered(0, 2));
+        assert!(started.elapsed() < Duration::from_millis(50));
+        
+        // Delivered in order on the dispatch thread
+        assert_eq!(done.recv_timeout(Duration::from_secs(2)).unwrap(), 1);
+        assert_eq!(done.recv_timeout(Duration::from_secs(2)).unwrap(), 2);
+    }
+    
+    #[test]
+    fn test_immediate_listeners_run_before_emit_async_returns() {
+        let bus = EventBus::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_clone = Arc::clone(&seen);
+        let id = bus.subscribe_immediate(move |event| {
+            seen_clone.lock().unwrap().push(format!("{:?}", event));
+        });
+        bus.subscribe(|_| thread::sleep(std::time::Duration::from_millis(100)));
+        
+        bus.emit(TrackerEvent::PlaybackStateChanged(true));
+        bus.emit_async(TrackerEvent::StepTriggered(0, 1));
+        bus.emit(TrackerEvent::PlaybackStateChanged(false));
+        assert_eq!(*seen.lock().unwrap(), vec![
+            "PlaybackStateChanged(true)".to_string(),
+            "StepTriggered(0, 1)".to_string(),
+            "PlaybackStateChanged(false)".to_string(),
+        ]);
+        
+        assert_eq!(bus.listener_count(), 2);
+        assert!(bus.unsubscribe(id));
+        assert_eq!(bus.listener_count(), 1);
+    }
+    
+    #[test]
+    fn test_event_history() {
+        let bus = EventBus::new();
+        bus.emit(TrackerEvent::PatternChanged);
+        assert!(bus.get_history().is_empty(), "History should be off by default");
+        
+        bus.enable_history(3);
+        for bpm in 0..5 {
+            bus.emit(TrackerEvent::BpmChanged(100 + bpm));
+        }
+        let history = bus.get_history();
+        assert_eq!(history.len(), 3);
+        assert!(matches!(history[0].1, TrackerEvent::BpmChanged(102)));
+        assert!(matches!(history[2].1, TrackerEvent::BpmChanged(104)));
+        assert!(history.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+        
+        bus.clear_history();
+        assert!(bus.get_history().is_empty());
+        bus.emit(TrackerEvent::PatternChanged);
+        assert_eq!(bus.get_history().len(), 1);
+        
+        bus.disable_history();
+        bus.emit(TrackerEvent::PatternChanged);
+        assert!(bus.get_history().is_empty());
+    }
 }
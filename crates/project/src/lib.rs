@@ -1,5 +1,9 @@
 pub mod loader;
+pub mod migration;
 pub mod model;
+pub mod paths;
+mod midi;
 
-pub use loader::{load_project, get_project_path};
+pub use loader::{load_project, save_project, get_project_path, get_projects_base_dir, list_projects, list_projects_in, load_grooves, LoadedProject, ProjectSummary, import_midi_pattern, default_drum_map, export_midi_pattern, export_midi_pattern_with_notes, export_project_zip, import_project_zip, load_project_from_url, download_project, url_cache_dir, scan_samples_directory};
 pub use model::{Project, Pattern};
+pub use paths::{get_project_path_with_config, Config, ProjectPathConfig, PROJECTS_DIR_ENV_VAR};
@@ -1,47 +1,122 @@
+use crate::midi::{self, Timing};
+use crate::migration;
+use crate::paths::{get_project_path_with_config, ProjectPathConfig};
 use crate::model::{Project, Pattern, Track, PatternMeta};
+use std::collections::HashMap;
 use std::env;
-use std::fs;
-use std::path::Path;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Seek, Write};
+use std::path::{Component, Path};
+use std::error::Error;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::path::{PathBuf};
-use dirs::home_dir;
-use log::{debug, info};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use log::{debug, info, warn};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
 
+/// Folder of the named project, searched for in the default `ProjectPathConfig`
 pub fn get_project_path(project_name: &str) -> PathBuf {
-    // Check for projects in the current directory first
-    let base_path = env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
-    let dev_paths = vec![
-        base_path.join("projects").join(project_name),
-        base_path.join("gaucho-projects").join(project_name)
-    ];
-    
-    for path in dev_paths {
-        debug!("Checking path: {:?}", path);
-        if path.exists() {
-            return path;
+    get_project_path_with_config(project_name, &ProjectPathConfig::default())
+}
+
+/// Everything `load_project` reads from a project folder
+pub type LoadedProject = (Project, Vec<Track>, Vec<Pattern>, Vec<PatternMeta>);
+
+/// Folder new projects go in and the project browser lists
+pub fn get_projects_base_dir() -> PathBuf {
+    ProjectPathConfig::default().base_dir()
+}
+
+/// What a project browser shows for a project, read without loading its patterns
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectSummary {
+    pub name: String,
+    pub path: PathBuf,
+    pub bpm: u32,
+    pub num_tracks: usize,
+    pub author: String,
+}
+
+impl ProjectSummary {
+    /// Read the summary of the project folder at `path`
+    fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let project = Project::from_toml(&fs::read_to_string(path.join("gaucho.toml"))?)?;
+        // Count the tracks without parsing them; a project may not have any yet
+        let num_tracks = match fs::read_to_string(path.join("tracks.json")) {
+            Ok(json) => serde_json::from_str::<Vec<serde_json::Value>>(&json)?.len(),
+            Err(_) => 0,
+        };
+        Ok(Self {
+            name: project.name,
+            path: path.to_path_buf(),
+            bpm: project.bpm,
+            num_tracks,
+            author: project.author,
+        })
+    }
+
+    /// Load the whole project, as `load_project` does
+    pub fn load_full(&self) -> Result<LoadedProject, Box<dyn Error>> {
+        load_project(&self.path)
+    }
+}
+
+/// Every project in the projects base directory, sorted by folder
+pub fn list_projects() -> Result<Vec<ProjectSummary>, std::io::Error> {
+    list_projects_in(&get_projects_base_dir())
+}
+
+/// Every subdirectory of `dir` with a `gaucho.toml`, sorted by folder.
+/// Projects whose metadata can't be read are logged and left out.
+pub fn list_projects_in(dir: &Path) -> Result<Vec<ProjectSummary>, std::io::Error> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut folders = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.join("gaucho.toml").is_file() {
+            folders.push(path);
         }
     }
+    folders.sort();
 
-    // Fallback to production path
-    let home = home_dir().unwrap_or_else(|| PathBuf::from("/home/pi"));
-    home.join("gaucho-projects").join(project_name)
+    Ok(folders.iter()
+        .filter_map(|path| match ProjectSummary::read(path) {
+            Ok(summary) => Some(summary),
+            Err(e) => {
+                warn!("Skipping project {:?}: {}", path, e);
+                None
+            }
+        })
+        .collect())
 }
 
-pub fn load_project<P: AsRef<Path>>(folder: P) -> Result<(Project, Vec<Track>, Vec<Pattern>, Vec<PatternMeta>), Box<dyn std::error::Error>> {
+/// Load a project folder. Patterns are read in file name order, and `pattern_metas[i]`
+/// is the metadata of `patterns[i]` (empty if the pattern has no `.meta.json` file).
+pub fn load_project<P: AsRef<Path>>(folder: P) -> Result<LoadedProject, Box<dyn std::error::Error>> {
     let folder = folder.as_ref();
     info!("Loading project from: {:?}", folder);
 
     // Load project metadata
     let gaucho_toml_path = folder.join("gaucho.toml");
     debug!("Loading project metadata from: {:?}", gaucho_toml_path);
-    let proj_toml = fs::read_to_string(&gaucho_toml_path)?;
-    let project: Project = toml::from_str(&proj_toml)?;
+    let mut project = Project::from_toml(&fs::read_to_string(&gaucho_toml_path)?)?;
+    if migration::needs_migration(&project.version) {
+        migration::migrate_project(folder, &project.version, migration::CURRENT_VERSION)?;
+        project = Project::from_toml(&fs::read_to_string(&gaucho_toml_path)?)?;
+    } else if project.version != migration::CURRENT_VERSION {
+        warn!("Unknown project version {}, loading it as {}", project.version, migration::CURRENT_VERSION);
+    }
     info!("Project loaded: {}", project.name);
 
     // Load tracks
     let tracks_json_path = folder.join("tracks.json");
     debug!("Loading tracks from: {:?}", tracks_json_path);
     let tracks_json = fs::read_to_string(&tracks_json_path)?;
-    let tracks: Vec<Track> = serde_json::from_str(&tracks_json)?;
+    let tracks = Track::from_json_array(&tracks_json)?;
     info!("Loaded {} tracks", tracks.len());
 
     // Load patterns
@@ -51,40 +126,436 @@ pub fn load_project<P: AsRef<Path>>(folder: P) -> Result<(Project, Vec<Track>, V
     let mut pattern_metas = Vec::new();
     
     if patterns_dir.exists() && patterns_dir.is_dir() {
-        let entries = fs::read_dir(&patterns_dir)?;
-        
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
-            
-            // Only process JSON files and skip metadata files
+        // Only process JSON files and skip metadata and effect files
+        let mut pattern_paths = Vec::new();
+        for entry in fs::read_dir(&patterns_dir)? {
+            let path = entry?.path();
             if path.is_file() && 
                path.extension().map_or(false, |ext| ext == "json") && 
-               !path.to_string_lossy().contains(".meta.json") {
-                
-                debug!("Loading pattern from: {:?}", path);
-                let pattern_json = fs::read_to_string(&path)?;
-                let pattern: Pattern = serde_json::from_str(&pattern_json)?;
-                patterns.push(pattern);
-                
-                // Optionally load the metadata file
-                let meta_filename = path.file_stem()
-                    .and_then(|stem| Some(format!("{}.meta.json", stem.to_string_lossy())));
-                
-                if let Some(meta_name) = meta_filename {
-                    let meta_path = path.with_file_name(meta_name);
-                    if meta_path.exists() {
-                        debug!("Loading pattern metadata from: {:?}", meta_path);
-                        let meta_json = fs::read_to_string(&meta_path)?;
-                        let meta: PatternMeta = serde_json::from_str(&meta_json)?;
-                        info!("Loaded pattern metadata with {} FX entries", meta.fx.len());
-                        pattern_metas.push(meta);
-                    }
-                }
+               !path.to_string_lossy().contains(".meta.json") &&
+               path.file_name().is_none_or(|name| name != EFFECTS_FILE) {
+                pattern_paths.push(path);
             }
         }
+        // Directory order isn't defined; load patterns by file name
+        pattern_paths.sort();
+        
+        for path in pattern_paths {
+            debug!("Loading pattern from: {:?}", path);
+            let pattern_json = fs::read_to_string(&path)?;
+            let pattern = Pattern::from_json(&pattern_json)?;
+            pattern.validate_length()
+                .map_err(|e| format!("Invalid pattern {:?}: {}", path, e))?;
+            patterns.push(pattern);
+            
+            // Every pattern gets a metadata entry so `pattern_metas[i]` belongs to `patterns[i]`;
+            // patterns without a .meta.json file get an empty one
+            let meta_path = path.with_file_name(format!(
+                "{}.meta.json",
+                path.file_stem().unwrap_or_default().to_string_lossy()
+            ));
+            let meta = if meta_path.exists() {
+                debug!("Loading pattern metadata from: {:?}", meta_path);
+                let meta_json = fs::read_to_string(&meta_path)?;
+                let meta: PatternMeta = serde_json::from_str(&meta_json)
+                    .map_err(|e| format!("Invalid pattern metadata {:?}: {}", meta_path, e))?;
+                info!("Loaded pattern metadata with {} FX entries", meta.fx.len());
+                meta
+            } else {
+                PatternMeta::default()
+            };
+            pattern_metas.push(meta);
+        }
     }
     
     info!("Loaded {} patterns with {} metadata entries", patterns.len(), pattern_metas.len());
     Ok((project, tracks, patterns, pattern_metas))
 }
+
+/// Write a project folder in the layout read by `load_project`.
+/// Patterns are saved as `patterns/NNN.json` by pattern id; other files in the folder are left alone.
+pub fn save_project<P: AsRef<Path>>(folder: P, project: &Project, tracks: &[Track], patterns: &[Pattern]) -> Result<(), Box<dyn Error>> {
+    let folder = folder.as_ref();
+    info!("Saving project '{}' to: {:?}", project.name, folder);
+
+    let patterns_dir = folder.join("patterns");
+    fs::create_dir_all(&patterns_dir)?;
+
+    fs::write(folder.join("gaucho.toml"), project.to_toml()?)?;
+    fs::write(folder.join("tracks.json"), Track::to_json_array(tracks)?)?;
+    for pattern in patterns {
+        let path = patterns_dir.join(format!("{:03}.json", pattern.pattern_id));
+        debug!("Saving pattern to: {:?}", path);
+        fs::write(path, pattern.to_json()?)?;
+    }
+
+    info!("Saved {} tracks and {} patterns", tracks.len(), patterns.len());
+    Ok(())
+}
+
+/// Sample file extensions picked up by `scan_samples_directory`
+const SAMPLE_EXTENSIONS: [&str; 3] = ["wav", "ogg", "flac"];
+
+/// Create a track for every sample file in `dir` and its immediate subdirectories,
+/// sorted by path. Tracks are named after the file stem and point at the path relative to `dir`.
+pub fn scan_samples_directory(dir: &Path, default_volume: f32) -> Result<Vec<Track>, std::io::Error> {
+    info!("Scanning {:?} for samples", dir);
+    let mut samples = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            for nested in fs::read_dir(&path)? {
+                let nested = nested?.path();
+                if is_sample_file(&nested) {
+                    samples.push(nested);
+                }
+            }
+        } else if is_sample_file(&path) {
+            samples.push(path);
+        }
+    }
+    samples.sort();
+
+    let tracks: Vec<Track> = samples.iter()
+        .map(|path| Track {
+            name: path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default(),
+            sample: path.strip_prefix(dir).unwrap_or(path).to_string_lossy().into_owned(),
+            volume: default_volume,
+            pan: 0.0,
+            pitch_semitones: 0.0,
+            muted: false,
+            color: None,
+        })
+        .collect();
+    info!("Found {} samples", tracks.len());
+    Ok(tracks)
+}
+
+fn is_sample_file(path: &Path) -> bool {
+    path.is_file() && path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| SAMPLE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Effect chains saved alongside the patterns
+const EFFECTS_FILE: &str = "effects.json";
+
+/// Save runtime effect state to `patterns/effects.json`.
+/// Generic so the project crate doesn't depend on the audio effect types.
+pub fn save_effects<P: AsRef<Path>, T: Serialize>(folder: P, state: &T) -> Result<(), Box<dyn Error>> {
+    let patterns_dir = folder.as_ref().join("patterns");
+    fs::create_dir_all(&patterns_dir)?;
+    let path = patterns_dir.join(EFFECTS_FILE);
+    debug!("Saving effects to: {:?}", path);
+    fs::write(path, serde_json::to_string_pretty(state)?)?;
+    Ok(())
+}
+
+/// Load effect state saved by `save_effects`, or None if the project has none
+pub fn load_effects<P: AsRef<Path>, T: DeserializeOwned>(folder: P) -> Result<Option<T>, Box<dyn Error>> {
+    let path = folder.as_ref().join("patterns").join(EFFECTS_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    debug!("Loading effects from: {:?}", path);
+    Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+}
+
+/// Suffix of the groove files in a project's `grooves/` folder
+const GROOVE_SUFFIX: &str = ".groove.json";
+
+/// Groove played by a project that doesn't ship one with the same name: no offsets
+pub const STRAIGHT_GROOVE: &str = "straight";
+
+/// Load the `grooves/*.groove.json` files of a project, keyed by file name without the suffix.
+/// Each file is a list of per-step offsets, in fractions of a step.
+/// A `straight` groove with no offsets is always included.
+pub fn load_grooves(project_path: &Path) -> Result<HashMap<String, Vec<f32>>, Box<dyn Error>> {
+    let mut grooves = HashMap::new();
+    let grooves_dir = project_path.join("grooves");
+    if grooves_dir.is_dir() {
+        for entry in fs::read_dir(&grooves_dir)? {
+            let path = entry?.path();
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            let Some(name) = file_name.strip_suffix(GROOVE_SUFFIX) else {
+                continue;
+            };
+            debug!("Loading groove from: {:?}", path);
+            let offsets: Vec<f32> = serde_json::from_str(&fs::read_to_string(&path)?)
+                .map_err(|e| format!("Invalid groove {:?}: {}", path, e))?;
+            grooves.insert(name.to_string(), offsets);
+        }
+    }
+    grooves.entry(STRAIGHT_GROOVE.to_string()).or_insert_with(|| vec![0.0]);
+    info!("Loaded {} grooves", grooves.len());
+    Ok(grooves)
+}
+
+/// Bundle a project folder into a ZIP archive: `gaucho.toml`, `tracks.json`,
+/// and everything below `patterns/`, `grooves/` and `samples/`.
+/// Samples referenced by tracks but missing from the folder are logged and left out.
+pub fn export_project_zip(project_path: &Path, output_zip: &Path) -> Result<(), Box<dyn Error>> {
+    info!("Exporting project {:?} to {:?}", project_path, output_zip);
+    let mut files = vec![PathBuf::from("gaucho.toml"), PathBuf::from("tracks.json")];
+    collect_files(project_path, Path::new("patterns"), &mut files)?;
+    collect_files(project_path, Path::new("grooves"), &mut files)?;
+    collect_files(project_path, Path::new("samples"), &mut files)?;
+
+    // Track samples may be given relative to the project or to its samples folder
+    let tracks = Track::from_json_array(&fs::read_to_string(project_path.join("tracks.json"))?)?;
+    for track in &tracks {
+        let candidates = [PathBuf::from(&track.sample), Path::new("samples").join(&track.sample)];
+        match candidates.into_iter().find(|path| project_path.join(path).is_file()) {
+            Some(path) if !files.contains(&path) => files.push(path),
+            Some(_) => {}
+            None => warn!("Sample {} for track {} not found, leaving it out of the archive", track.sample, track.name),
+        }
+    }
+
+    let mut zip = ZipWriter::new(File::create(output_zip)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for file in &files {
+        let name = file.components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        debug!("Adding {} to archive", name);
+        zip.start_file(name, options)?;
+        zip.write_all(&fs::read(project_path.join(file))?)?;
+    }
+    zip.finish()?;
+
+    info!("Exported {} files to {:?}", files.len(), output_zip);
+    Ok(())
+}
+
+/// Extract a project archive made by `export_project_zip` into `destination`
+pub fn import_project_zip(zip_path: &Path, destination: &Path) -> Result<(), Box<dyn Error>> {
+    info!("Importing project archive {:?} into {:?}", zip_path, destination);
+    extract_archive(File::open(zip_path)?, destination)
+}
+
+/// How long a downloaded project is used before it's downloaded again
+pub const URL_CACHE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// File in a cached download recording when it was made, in seconds since the Unix epoch
+const DOWNLOADED_AT_FILE: &str = ".downloaded";
+
+/// How long a project download may take before it fails
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Folder downloaded projects are cached in: `gaucho-tracker` in the user cache directory
+pub fn url_cache_dir() -> PathBuf {
+    dirs::cache_dir().unwrap_or_else(env::temp_dir).join("gaucho-tracker")
+}
+
+/// Load a project shared as a ZIP archive at an `http://` or `https://` URL. The archive is extracted
+/// to a folder of `cache_dir` named after the URL and reused for `URL_CACHE_MAX_AGE`.
+pub fn load_project_from_url(url: &str, cache_dir: &Path) -> Result<LoadedProject, Box<dyn Error>> {
+    load_project(download_project(url, cache_dir)?)
+}
+
+/// Download and extract the project at `url` unless a recent copy is cached.
+/// Returns the folder it was extracted to.
+pub fn download_project(url: &str, cache_dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let folder = cache_dir.join(format!("{:016x}", fnv1a(url.as_bytes())));
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let downloaded_at = fs::read_to_string(folder.join(DOWNLOADED_AT_FILE))
+        .ok()
+        .and_then(|contents| contents.trim().parse::<u64>().ok());
+
+    match downloaded_at {
+        Some(at) if now.saturating_sub(at) < URL_CACHE_MAX_AGE.as_secs() => {
+            info!("Using cached download of {} in {:?}", url, folder);
+        }
+        _ => {
+            info!("Downloading project from {}", url);
+            let client = reqwest::blocking::Client::builder().timeout(DOWNLOAD_TIMEOUT).build()?;
+            let data = client.get(url).send()?.error_for_status()?.bytes()?;
+            // Extract next to the cache entry so a failed download leaves the old one intact
+            let partial = folder.with_extension("partial");
+            if partial.exists() {
+                fs::remove_dir_all(&partial)?;
+            }
+            extract_archive(Cursor::new(data), &partial)?;
+            fs::write(partial.join(DOWNLOADED_AT_FILE), now.to_string())?;
+            if folder.exists() {
+                fs::remove_dir_all(&folder)?;
+            }
+            fs::rename(&partial, &folder)?;
+        }
+    }
+    Ok(folder)
+}
+
+/// 64-bit FNV-1a hash, stable across runs and platforms
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Write the files of a ZIP archive below `destination`. Directory entries are skipped.
+fn extract_archive(reader: impl Read + Seek, destination: &Path) -> Result<(), Box<dyn Error>> {
+    let mut archive = ZipArchive::new(reader)?;
+    let mut extracted = 0;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+        // Refuse names that would escape the destination folder
+        let relative = entry.enclosed_name()
+            .filter(|path| path.components().all(|component| matches!(component, Component::Normal(_))))
+            .ok_or_else(|| format!("Unsafe path in project archive: {}", entry.name()))?;
+        let path = destination.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        debug!("Extracting {:?}", path);
+        io::copy(&mut entry, &mut File::create(path)?)?;
+        extracted += 1;
+    }
+
+    info!("Extracted {} files to {:?}", extracted, destination);
+    Ok(())
+}
+
+/// Recursively collect files below `root/dir`, as paths relative to `root`
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    let full = root.join(dir);
+    if !full.is_dir() {
+        return Ok(());
+    }
+    let mut entries: Vec<PathBuf> = fs::read_dir(&full)?
+        .map(|entry| entry.map(|e| dir.join(e.file_name())))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+    for path in entries {
+        if root.join(&path).is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Steps per quarter note in imported and exported patterns
+const STEPS_PER_BEAT: u64 = 4;
+
+/// Resolution of exported MIDI files
+const EXPORT_TICKS_PER_QUARTER: u16 = 96;
+
+/// Notes for tracks whose name doesn't identify the drum, in track order
+const FALLBACK_DRUM_NOTES: [u8; 8] = [36, 38, 42, 46, 39, 45, 49, 51];
+
+/// General MIDI percussion notes mapped to the default Kick, Snare and HiHat tracks
+pub fn default_drum_map() -> HashMap<u8, usize> {
+    HashMap::from([
+        (35, 0), (36, 0), // bass drums
+        (38, 1), (40, 1), // snares
+        (42, 2), (44, 2), (46, 2), // hi-hats
+    ])
+}
+
+/// Import a pattern from a MIDI file, quantizing notes to the nearest 16th step.
+/// `note_map` maps MIDI note numbers to track indices; other notes are ignored.
+/// `bpm` is only needed for files with SMPTE timing.
+pub fn import_midi_pattern(path: &Path, bpm: u32, note_map: &HashMap<u8, usize>) -> Result<Pattern, Box<dyn Error>> {
+    info!("Importing MIDI pattern from: {:?}", path);
+    let data = fs::read(path)?;
+    let file = midi::parse(&data)?;
+
+    let ticks_per_step = match file.timing {
+        Timing::Metrical(ticks_per_quarter) => f64::from(ticks_per_quarter) / STEPS_PER_BEAT as f64,
+        Timing::Timecode(fps, ticks_per_frame) => {
+            if bpm == 0 {
+                return Err("BPM must be greater than zero".into());
+            }
+            let ticks_per_second = f64::from(fps) * f64::from(ticks_per_frame);
+            ticks_per_second * 60.0 / (f64::from(bpm) * STEPS_PER_BEAT as f64)
+        }
+    };
+
+    let num_tracks = note_map.values().max().map_or(0, |&max| max + 1);
+    let hits: Vec<(usize, usize)> = file.notes.iter()
+        .filter_map(|note| {
+            let track = *note_map.get(&note.key)?;
+            let step = (note.tick as f64 / ticks_per_step).round() as usize;
+            Some((track, step))
+        })
+        .collect();
+
+    let last_step = hits.iter().map(|&(_, step)| step).max().unwrap_or(0);
+    let num_steps = (last_step / 16 + 1) * 16;
+    let mut steps = vec![vec![false; num_steps]; num_tracks];
+    for (track, step) in hits {
+        steps[track][step] = true;
+    }
+
+    debug!("Imported {} notes into {} tracks of {} steps", file.notes.len(), num_tracks, num_steps);
+    Ok(Pattern::new(0, steps))
+}
+
+/// General MIDI percussion note for a track, guessed from its name
+pub fn drum_note_for_track(track_idx: usize, track: &Track) -> u8 {
+    let name = track.name.to_lowercase();
+    if name.contains("kick") || name.contains("bass drum") {
+        36
+    } else if name.contains("snare") {
+        38
+    } else if name.contains("clap") {
+        39
+    } else if name.contains("open") && name.contains("hat") {
+        46
+    } else if name.contains("hat") {
+        42
+    } else if name.contains("crash") {
+        49
+    } else if name.contains("ride") {
+        51
+    } else {
+        FALLBACK_DRUM_NOTES[track_idx % FALLBACK_DRUM_NOTES.len()]
+    }
+}
+
+/// Export a pattern as a MIDI file, picking each track's note from its name
+pub fn export_midi_pattern(pattern: &Pattern, tracks: &[Track], bpm: u32, output: &Path) -> Result<(), Box<dyn Error>> {
+    let notes: Vec<u8> = (0..pattern.steps.len())
+        .map(|idx| tracks.get(idx).map_or(FALLBACK_DRUM_NOTES[idx % FALLBACK_DRUM_NOTES.len()], |track| drum_note_for_track(idx, track)))
+        .collect();
+    export_midi_pattern_with_notes(pattern, &notes, bpm, output)
+}
+
+/// Export a pattern as a MIDI file, playing track `i` on note `notes[i]`.
+/// Each active step is a note lasting until the next step.
+pub fn export_midi_pattern_with_notes(pattern: &Pattern, notes: &[u8], bpm: u32, output: &Path) -> Result<(), Box<dyn Error>> {
+    if bpm == 0 {
+        return Err("BPM must be greater than zero".into());
+    }
+    if notes.len() < pattern.steps.len() {
+        return Err(format!("Need a MIDI note for each of the {} tracks, got {}", pattern.steps.len(), notes.len()).into());
+    }
+    if let Some(&note) = notes.iter().find(|&&note| note > 127) {
+        return Err(format!("Invalid MIDI note number {}", note).into());
+    }
+
+    let ticks_per_step = u64::from(EXPORT_TICKS_PER_QUARTER) / STEPS_PER_BEAT;
+    let drum_notes: Vec<midi::DrumNote> = pattern.steps.iter().zip(notes)
+        .flat_map(|(row, &key)| {
+            row.iter().enumerate().filter(|(_, &on)| on).map(move |(step, _)| midi::DrumNote {
+                tick: step as u64 * ticks_per_step,
+                length: ticks_per_step,
+                key,
+                velocity: 100,
+            })
+        })
+        .collect();
+
+    let num_steps = pattern.steps.iter().map(Vec::len).max().unwrap_or(0) as u64;
+    let tempo = 60_000_000 / bpm;
+    let data = midi::write(EXPORT_TICKS_PER_QUARTER, tempo, &drum_notes, num_steps * ticks_per_step)?;
+    fs::write(output, data)?;
+    info!("Exported {} notes to {:?}", drum_notes.len(), output);
+    Ok(())
+}
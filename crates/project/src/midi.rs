@@ -0,0 +1,125 @@
+// Standard MIDI File reading and writing for pattern import/export
+// midly does the encoding; this keeps only the timing division and note events
+
+use std::error::Error;
+
+use midly::num::u28;
+use midly::{Format, Header, MetaMessage, MidiMessage, Smf, TrackEvent, TrackEventKind};
+
+/// How delta times in the file map to musical time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Timing {
+    /// Ticks per quarter note
+    Metrical(u16),
+    /// Frames per second and ticks per frame
+    Timecode(u8, u8),
+}
+
+/// A note-on with non-zero velocity, at an absolute tick from the start of its track
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct NoteOn {
+    pub tick: u64,
+    pub key: u8,
+    pub velocity: u8,
+}
+
+/// The parts of a MIDI file used by the importer
+#[derive(Debug, Clone)]
+pub(crate) struct MidiFile {
+    pub timing: Timing,
+    pub notes: Vec<NoteOn>,
+}
+
+/// Parse a Standard MIDI File (format 0 or 1)
+pub(crate) fn parse(data: &[u8]) -> Result<MidiFile, Box<dyn Error>> {
+    let smf = Smf::parse(data)?;
+    let timing = match smf.header.timing {
+        midly::Timing::Metrical(ticks_per_quarter) => {
+            if ticks_per_quarter == 0 {
+                return Err("MIDI file has zero ticks per quarter note".into());
+            }
+            Timing::Metrical(ticks_per_quarter.as_int())
+        }
+        midly::Timing::Timecode(fps, ticks_per_frame) => {
+            if ticks_per_frame == 0 {
+                return Err("Invalid SMPTE division in MIDI file".into());
+            }
+            Timing::Timecode(fps.as_int(), ticks_per_frame)
+        }
+    };
+
+    let mut notes = Vec::new();
+    for track in &smf.tracks {
+        let mut tick = 0u64;
+        for event in track {
+            tick += u64::from(event.delta.as_int());
+            if let TrackEventKind::Midi { message: MidiMessage::NoteOn { key, vel }, .. } = event.kind {
+                if vel > 0 {
+                    notes.push(NoteOn { tick, key: key.as_int(), velocity: vel.as_int() });
+                }
+            }
+        }
+    }
+    notes.sort_by_key(|note| note.tick);
+    Ok(MidiFile { timing, notes })
+}
+
+/// A note on the General MIDI percussion channel, in absolute ticks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct DrumNote {
+    pub tick: u64,
+    pub length: u64,
+    pub key: u8,
+    pub velocity: u8,
+}
+
+/// General MIDI percussion channel (channel 10, zero-based 9)
+const DRUM_CHANNEL: u8 = 9;
+
+/// Serialize a format 1 file with a tempo track and one drum track ending at `end_tick`
+pub(crate) fn write(ticks_per_quarter: u16, tempo: u32, notes: &[DrumNote], end_tick: u64) -> Result<Vec<u8>, Box<dyn Error>> {
+    let header = Header::new(Format::Parallel, midly::Timing::Metrical(ticks_per_quarter.into()));
+    let mut smf = Smf::new(header);
+
+    smf.tracks.push(vec![
+        meta_event(u28::new(0), MetaMessage::Tempo(tempo.into())),
+        meta_event(u28::new(0), MetaMessage::EndOfTrack),
+    ]);
+
+    // (tick, is_note_on, key, velocity); note-offs sort first so back-to-back notes don't overlap
+    let mut events: Vec<(u64, bool, u8, u8)> = notes.iter()
+        .flat_map(|note| [
+            (note.tick, true, note.key, note.velocity.clamp(1, 127)),
+            (note.tick + note.length, false, note.key, 0),
+        ])
+        .collect();
+    events.sort_by_key(|&(tick, on, key, _)| (tick, on, key));
+
+    let mut track = Vec::with_capacity(events.len() + 1);
+    let mut last_tick = 0;
+    for (tick, on, key, velocity) in events {
+        let (key, vel) = (key.into(), velocity.into());
+        let message = if on { MidiMessage::NoteOn { key, vel } } else { MidiMessage::NoteOff { key, vel } };
+        track.push(TrackEvent {
+            delta: delta(tick - last_tick)?,
+            kind: TrackEventKind::Midi { channel: DRUM_CHANNEL.into(), message },
+        });
+        last_tick = tick;
+    }
+    track.push(meta_event(delta(end_tick.saturating_sub(last_tick))?, MetaMessage::EndOfTrack));
+    smf.tracks.push(track);
+
+    let mut out = Vec::new();
+    smf.write_std(&mut out)?;
+    Ok(out)
+}
+
+fn delta(ticks: u64) -> Result<u28, Box<dyn Error>> {
+    u32::try_from(ticks).ok()
+        .and_then(u28::try_from)
+        .ok_or_else(|| format!("MIDI delta time of {} ticks is too long", ticks).into())
+}
+
+fn meta_event(delta: u28, message: MetaMessage<'static>) -> TrackEvent<'static> {
+    TrackEvent { delta, kind: TrackEventKind::Meta(message) }
+}
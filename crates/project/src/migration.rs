@@ -0,0 +1,61 @@
+// Upgrades project folders written by older versions of the tracker
+// Each migration rewrites the files of one version into the layout of the next
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use log::info;
+
+/// Project format version written by this build
+pub const CURRENT_VERSION: &str = "2.0";
+
+type MigrationFn = fn(&Path) -> Result<(), Box<dyn Error>>;
+
+/// Migrations in order, as (from version, to version, migration)
+const MIGRATIONS: [(&str, &str, MigrationFn); 1] = [
+    ("1.0", "2.0", add_track_pan_and_pitch),
+];
+
+/// Whether a project of `version` can be brought up to `CURRENT_VERSION`
+pub fn needs_migration(version: &str) -> bool {
+    version != CURRENT_VERSION && MIGRATIONS.iter().any(|(from, _, _)| *from == version)
+}
+
+/// Apply every migration from `from_version` up to `to_version` to the project folder at `path`,
+/// updating the version in `gaucho.toml` after each one
+pub fn migrate_project(path: &Path, from_version: &str, to_version: &str) -> Result<(), Box<dyn Error>> {
+    let mut version = from_version.to_string();
+    while version != to_version {
+        let (_, next, migrate) = MIGRATIONS.iter()
+            .find(|(from, _, _)| *from == version)
+            .ok_or_else(|| format!("No migration from project version {} to {}", version, to_version))?;
+
+        info!("Migrating project {:?} from version {} to {}", path, version, next);
+        migrate(path)?;
+        set_version(path, next)?;
+        version = next.to_string();
+    }
+    Ok(())
+}
+
+/// Rewrite the version in `gaucho.toml`, leaving the other fields as they are
+fn set_version(path: &Path, version: &str) -> Result<(), Box<dyn Error>> {
+    let toml_path = path.join("gaucho.toml");
+    let mut doc: toml::Table = fs::read_to_string(&toml_path)?.parse()?;
+    doc.insert("version".to_string(), toml::Value::String(version.to_string()));
+    fs::write(toml_path, toml::to_string(&doc)?)?;
+    Ok(())
+}
+
+/// 1.0 -> 2.0: tracks gained `pan` and `pitch_semitones`
+fn add_track_pan_and_pitch(path: &Path) -> Result<(), Box<dyn Error>> {
+    let tracks_path = path.join("tracks.json");
+    let mut tracks: Vec<serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_str(&fs::read_to_string(&tracks_path)?)?;
+    for track in &mut tracks {
+        track.entry("pan").or_insert(serde_json::json!(0.0));
+        track.entry("pitch_semitones").or_insert(serde_json::json!(0.0));
+    }
+    fs::write(tracks_path, serde_json::to_string_pretty(&tracks)?)?;
+    Ok(())
+}
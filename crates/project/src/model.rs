@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Project {
     pub name: String,
     pub version: String,
@@ -9,6 +9,12 @@ pub struct Project {
     pub swing: f32,
     pub author: String,
     pub created: String,
+    /// Start playback as soon as the project is loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_start: Option<bool>,
+    /// Per-step timing offsets, in fractions of a step, played instead of swing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub groove_template: Option<Vec<f32>>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -16,12 +22,194 @@ pub struct Track {
     pub name: String,
     pub sample: String,
     pub volume: f32,
+    /// Stereo position from -1.0 (left) to 1.0 (right)
+    #[serde(default)]
+    pub pan: f32,
+    /// Pitch shift in semitones
+    #[serde(default)]
+    pub pitch_semitones: f32,
+    /// Muted tracks keep their steps but don't play
+    #[serde(default)]
+    pub muted: bool,
+    /// Color of the track in the TUI, as `#RRGGBB` or a color name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(from = "RawPattern")]
 pub struct Pattern {
     pub pattern_id: u32,
     pub steps: Vec<Vec<bool>>,
+    /// Steps per track; zero for an empty pattern
+    pub length: usize,
+}
+
+/// Pattern as stored on disk, where `length` may be missing
+#[derive(Deserialize)]
+struct RawPattern {
+    pattern_id: u32,
+    steps: Vec<Vec<bool>>,
+    #[serde(default)]
+    length: Option<usize>,
+}
+
+impl From<RawPattern> for Pattern {
+    fn from(raw: RawPattern) -> Self {
+        match raw.length {
+            Some(length) => Pattern { pattern_id: raw.pattern_id, steps: raw.steps, length },
+            None => Pattern::new(raw.pattern_id, raw.steps),
+        }
+    }
+}
+
+impl Track {
+    /// Serialize a track list in the format of `tracks.json`
+    pub fn to_json_array(tracks: &[Track]) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(tracks)
+    }
+
+    /// Parse the contents of `tracks.json`
+    pub fn from_json_array(s: &str) -> Result<Vec<Track>, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+}
+
+impl Pattern {
+    /// Create a pattern whose length is taken from its first track
+    pub fn new(pattern_id: u32, steps: Vec<Vec<bool>>) -> Self {
+        let length = steps.first().map_or(0, Vec::len);
+        Pattern { pattern_id, steps, length }
+    }
+
+    /// Check that every track has exactly `length` steps
+    pub fn validate_length(&self) -> Result<(), String> {
+        match self.steps.iter().position(|track| track.len() != self.length) {
+            Some(idx) => Err(format!(
+                "Pattern {} track {} has {} steps, expected {}",
+                self.pattern_id, idx, self.steps[idx].len(), self.length
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Serialize in the format of `patterns/NNN.json`
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+
+    /// A copy of this pattern with every hit turned into a rest and vice versa
+    pub fn invert(&self) -> Pattern {
+        Pattern {
+            pattern_id: self.pattern_id,
+            length: self.length,
+            steps: self.steps
+                .iter()
+                .map(|track| track.iter().map(|step| !step).collect())
+                .collect(),
+        }
+    }
+
+    /// Flip every step of a single track in place
+    pub fn invert_track(&mut self, track_idx: usize) {
+        if let Some(track) = self.steps.get_mut(track_idx) {
+            for step in track.iter_mut() {
+                *step = !*step;
+            }
+        }
+    }
+
+    /// A track's steps in reverse order, so it plays backwards; empty if the track doesn't exist
+    pub fn reversed_track(&self, track_idx: usize) -> Vec<bool> {
+        self.steps.get(track_idx)
+            .map(|track| track.iter().rev().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Cut the pattern into `num_parts` equal sections, e.g. a 32-step pattern into two
+    /// 16-step phrases. Sections are numbered on from this pattern's id.
+    pub fn split(&self, num_parts: usize) -> Result<Vec<Pattern>, &'static str> {
+        if num_parts == 0 {
+            return Err("Cannot split a pattern into zero parts");
+        }
+        if !self.length.is_multiple_of(num_parts) {
+            return Err("Pattern length must divide evenly into the number of parts");
+        }
+        let part_length = self.length / num_parts;
+        Ok((0..num_parts)
+            .map(|part| Pattern {
+                pattern_id: self.pattern_id + part as u32,
+                length: part_length,
+                steps: self.steps
+                    .iter()
+                    .map(|track| track[part * part_length..(part + 1) * part_length].to_vec())
+                    .collect(),
+            })
+            .collect())
+    }
+
+    /// Play patterns one after another as a single pattern, keeping the first one's id.
+    /// All patterns must have the same number of tracks.
+    pub fn join(patterns: &[Pattern]) -> Result<Pattern, &'static str> {
+        let first = patterns.first().ok_or("Cannot join an empty list of patterns")?;
+        if patterns.iter().any(|pattern| pattern.steps.len() != first.steps.len()) {
+            return Err("All patterns must have the same number of tracks");
+        }
+        Ok(Pattern {
+            pattern_id: first.pattern_id,
+            length: patterns.iter().map(|pattern| pattern.length).sum(),
+            steps: (0..first.steps.len())
+                .map(|track_idx| patterns.iter().flat_map(|pattern| pattern.steps[track_idx].iter().copied()).collect())
+                .collect(),
+        })
+    }
+
+    /// Encode the steps as lowercase hex, each track packed into bytes LSB-first.
+    /// Tracks are padded to whole bytes, so 16 steps of 8 tracks take 32 characters.
+    pub fn to_hex_string(&self) -> String {
+        let mut hex = String::new();
+        for track in &self.steps {
+            for byte_idx in 0..self.length.div_ceil(8) {
+                let byte = (0..8)
+                    .filter(|bit| track.get(byte_idx * 8 + bit).copied().unwrap_or(false))
+                    .fold(0u8, |byte, bit| byte | (1 << bit));
+                hex.push_str(&format!("{:02x}", byte));
+            }
+        }
+        hex
+    }
+
+    /// Decode a pattern written by `to_hex_string`; padding bits are ignored
+    pub fn from_hex_string(hex: &str, num_tracks: usize, num_steps: usize) -> Result<Self, String> {
+        let bytes_per_track = num_steps.div_ceil(8);
+        let expected = num_tracks * bytes_per_track * 2;
+        if hex.len() != expected {
+            return Err(format!(
+                "Hex pattern has {} characters, expected {} for {} tracks of {} steps",
+                hex.len(), expected, num_tracks, num_steps
+            ));
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|idx| {
+                hex.get(idx..idx + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| format!("Invalid hex digits at position {}", idx))
+            })
+            .collect::<Result<Vec<u8>, String>>()?;
+
+        let steps = (0..num_tracks)
+            .map(|track_idx| {
+                let track_bytes = &bytes[track_idx * bytes_per_track..(track_idx + 1) * bytes_per_track];
+                (0..num_steps).map(|step| track_bytes[step / 8] & (1 << (step % 8)) != 0).collect()
+            })
+            .collect();
+        Ok(Pattern { pattern_id: 0, steps, length: num_steps })
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -30,14 +218,120 @@ pub struct TrackMapEntry {
     pub sample: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct FxEntry {
     pub retrigger: Option<u32>,
     pub reverse: Option<bool>,
+    /// Filter cutoff frequency, 20-20000 Hz
+    pub filter_cutoff_hz: Option<f32>,
+    /// "low" or "high" pass; low pass if only a cutoff is given
+    pub filter_type: Option<String>,
+    pub pitch_semitones: Option<f32>,
+    /// Stereo position from -1.0 (left) to 1.0 (right)
+    pub pan: Option<f32>,
+    pub delay_ms: Option<f32>,
+    /// Tremolo LFO rate; the effect is only applied when this is set
+    pub tremolo_rate_hz: Option<f32>,
+    /// How far the tremolo dips the volume, 0.0-1.0
+    pub tremolo_depth: Option<f32>,
+    /// Compressor threshold as a fraction of full scale, 0.0-1.0; the compressor is only applied when this is set
+    pub compress_threshold: Option<f32>,
+    /// Compression ratio, at least 1.0
+    pub compress_ratio: Option<f32>,
+    pub compress_attack_samples: Option<usize>,
+    pub compress_release_samples: Option<usize>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct PatternMeta {
     pub track_map: Vec<TrackMapEntry>,
     pub fx: HashMap<String, FxEntry>,
 }
+
+impl FxEntry {
+    /// Check the entry for values the effects can't use.
+    /// Returns every problem found rather than stopping at the first.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if let Some(cutoff) = self.filter_cutoff_hz {
+            if !(20.0..=20000.0).contains(&cutoff) {
+                errors.push(format!("Filter cutoff {} Hz is outside the range 20-20000", cutoff));
+            }
+        }
+        if let Some(filter_type) = &self.filter_type {
+            if filter_type != "low" && filter_type != "high" {
+                errors.push(format!("Filter type '{}' must be \"low\" or \"high\"", filter_type));
+            }
+        }
+        if let Some(pan) = self.pan {
+            if !(-1.0..=1.0).contains(&pan) {
+                errors.push(format!("Pan {} must be between -1.0 and 1.0", pan));
+            }
+        }
+        if let Some(delay) = self.delay_ms {
+            if delay < 0.0 {
+                errors.push(format!("Delay {} ms must not be negative", delay));
+            }
+        }
+        if let Some(rate) = self.tremolo_rate_hz {
+            if rate < 0.0 {
+                errors.push(format!("Tremolo rate {} Hz must not be negative", rate));
+            }
+        }
+        if let Some(depth) = self.tremolo_depth {
+            if !(0.0..=1.0).contains(&depth) {
+                errors.push(format!("Tremolo depth {} must be between 0.0 and 1.0", depth));
+            }
+        }
+        if let Some(threshold) = self.compress_threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                errors.push(format!("Compressor threshold {} must be between 0.0 and 1.0", threshold));
+            }
+        }
+        if let Some(ratio) = self.compress_ratio {
+            if ratio < 1.0 {
+                errors.push(format!("Compressor ratio {} must be at least 1.0", ratio));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Project {
+    /// Serialize in the format of `gaucho.toml`
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(self)
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(s)
+    }
+
+    /// Check project metadata for values the tracker cannot play.
+    /// Returns every problem found rather than stopping at the first.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if self.name.trim().is_empty() {
+            errors.push("Project name is empty".to_string());
+        }
+        if !(20..=300).contains(&self.bpm) {
+            errors.push(format!("BPM {} is outside the supported range 20-300", self.bpm));
+        }
+        if !(0.0..=1.0).contains(&self.swing) {
+            errors.push(format!("Swing {} must be between 0.0 and 1.0", self.swing));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
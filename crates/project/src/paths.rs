@@ -0,0 +1,95 @@
+// Where projects are looked up on disk
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use dirs::{data_dir, home_dir};
+use log::debug;
+use serde::Deserialize;
+
+/// Environment variable naming the folder projects are kept in
+pub const PROJECTS_DIR_ENV_VAR: &str = "GAUCHO_PROJECTS_DIR";
+
+/// Contents of `~/.config/gaucho-tracker/config.toml`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Config {
+    /// Folder projects are kept in
+    #[serde(default)]
+    pub projects_dir: Option<PathBuf>,
+}
+
+impl Config {
+    /// Path of the user configuration file
+    pub fn path() -> Option<PathBuf> {
+        home_dir().map(|home| home.join(".config").join("gaucho-tracker").join("config.toml"))
+    }
+
+    /// Read the user configuration file; a missing or invalid file gives the defaults
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
+                debug!("Failed to parse config {:?}: {}", path, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+/// Folders searched for projects, in order of preference
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectPathConfig {
+    pub search_dirs: Vec<PathBuf>,
+}
+
+impl Default for ProjectPathConfig {
+    /// `GAUCHO_PROJECTS_DIR`, then `projects_dir` from the config file, then `projects/` or
+    /// `gaucho-projects/` in the current directory, then the XDG data dir, then `~/gaucho-projects`
+    fn default() -> Self {
+        let mut search_dirs = Vec::new();
+        if let Some(dir) = env::var_os(PROJECTS_DIR_ENV_VAR).filter(|dir| !dir.is_empty()) {
+            search_dirs.push(PathBuf::from(dir));
+        }
+        if let Some(dir) = Config::load().projects_dir {
+            search_dirs.push(dir);
+        }
+        if let Ok(cwd) = env::current_dir() {
+            search_dirs.push(cwd.join("projects"));
+            search_dirs.push(cwd.join("gaucho-projects"));
+        }
+        if let Some(data) = data_dir() {
+            search_dirs.push(data.join("gaucho-tracker").join("projects"));
+        }
+        if let Some(home) = home_dir() {
+            search_dirs.push(home.join("gaucho-projects"));
+        }
+        Self { search_dirs }
+    }
+}
+
+impl ProjectPathConfig {
+    /// The first search folder that exists, or the last one, where new projects would go
+    pub fn base_dir(&self) -> PathBuf {
+        self.search_dirs.iter()
+            .find(|dir| dir.is_dir())
+            .or(self.search_dirs.last())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Folder of the named project: the first search folder holding it, otherwise
+/// where it would be created in `base_dir`
+pub fn get_project_path_with_config(name: &str, config: &ProjectPathConfig) -> PathBuf {
+    for dir in &config.search_dirs {
+        let path = dir.join(name);
+        debug!("Checking path: {:?}", path);
+        if path.exists() {
+            return path;
+        }
+    }
+    config.base_dir().join(name)
+}
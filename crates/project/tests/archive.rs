@@ -0,0 +1,51 @@
+use project::{export_project_zip, import_project_zip, load_project};
+use std::fs;
+
+#[test]
+fn export_and_import_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let zip_path = dir.path().join("my-song.zip");
+    export_project_zip("tests/fixtures/my-song".as_ref(), &zip_path).unwrap();
+
+    let imported = dir.path().join("imported");
+    import_project_zip(&zip_path, &imported).unwrap();
+
+    let (project, tracks, patterns, metas) = load_project(&imported).unwrap();
+    let (original, original_tracks, original_patterns, _) = load_project("tests/fixtures/my-song").unwrap();
+    assert_eq!(project.name, original.name);
+    assert_eq!(tracks.len(), original_tracks.len());
+    assert_eq!(patterns, original_patterns);
+    assert_eq!(metas.len(), 1);
+
+    // The samples folder comes along byte for byte; files outside the layout don't
+    let sample = "samples/funky-acoustic-snare-shot.wav";
+    assert_eq!(
+        fs::read(imported.join(sample)).unwrap(),
+        fs::read(format!("tests/fixtures/my-song/{}", sample)).unwrap()
+    );
+    assert!(!imported.join("notes.md").exists());
+}
+
+#[test]
+fn import_rejects_non_archives() {
+    let dir = tempfile::tempdir().unwrap();
+    let bogus = dir.path().join("bogus.zip");
+    fs::write(&bogus, b"not a zip file").unwrap();
+    assert!(import_project_zip(&bogus, &dir.path().join("out")).is_err());
+}
+
+#[test]
+fn import_rejects_paths_outside_the_destination() {
+    use std::io::Write;
+
+    let dir = tempfile::tempdir().unwrap();
+    let evil = dir.path().join("evil.zip");
+    let mut zip = zip::ZipWriter::new(fs::File::create(&evil).unwrap());
+    zip.start_file("../escaped.txt", zip::write::SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"outside").unwrap();
+    zip.finish().unwrap();
+
+    let destination = dir.path().join("out");
+    assert!(import_project_zip(&evil, &destination).is_err());
+    assert!(!dir.path().join("escaped.txt").exists());
+}
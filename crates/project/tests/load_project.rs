@@ -10,7 +10,7 @@ fn test_load_project_folder() {
     
     // Test project metadata
     assert_eq!(project.name, "My Song");
-    assert_eq!(project.version, "1.0");
+    assert_eq!(project.version, "2.0");
     assert_eq!(project.bpm, 120);
     assert_eq!(project.swing, 0.0);
     assert_eq!(project.author, "esteban");
@@ -20,6 +20,10 @@ fn test_load_project_folder() {
     assert_eq!(tracks[0].name, "Kick");
     assert_eq!(tracks[0].sample, "samples/kick.wav");
     assert_eq!(tracks[0].volume, 1.0);
+    // Fields added after the fixture was written fall back to their defaults
+    assert_eq!(tracks[0].pan, 0.0);
+    assert_eq!(tracks[0].pitch_semitones, 0.0);
+    assert!(!tracks[0].muted);
     
     // Test patterns
     assert_eq!(patterns.len(), 1);
@@ -27,6 +31,32 @@ fn test_load_project_folder() {
     assert_eq!(patterns[0].steps.len(), 2);
     assert_eq!(patterns[0].steps[0].len(), 8);
     assert!(patterns[0].steps[0][0]); // First step of first track should be true
+    
+    // Metadata from 000.meta.json
+    assert_eq!(pattern_metas.len(), 1);
+    assert_eq!(pattern_metas[0].track_map.len(), 2);
+    assert_eq!(pattern_metas[0].track_map[1].sample, "snare.wav");
+    assert_eq!(pattern_metas[0].fx["1:3"].retrigger, Some(2));
+    assert_eq!(pattern_metas[0].fx["2:7"].reverse, Some(true));
+}
+
+#[test]
+fn pattern_metas_pair_with_patterns() {
+    let dir = tempfile::tempdir().unwrap();
+    let from = std::path::Path::new("tests/fixtures/my-song");
+    std::fs::create_dir_all(dir.path().join("patterns")).unwrap();
+    for file in ["gaucho.toml", "tracks.json", "patterns/000.json"] {
+        std::fs::copy(from.join(file), dir.path().join(file)).unwrap();
+    }
+    // 000 has no metadata; 001 has the fixture's metadata
+    std::fs::copy(from.join("patterns/000.json"), dir.path().join("patterns/001.json")).unwrap();
+    std::fs::copy(from.join("patterns/000.meta.json"), dir.path().join("patterns/001.meta.json")).unwrap();
+
+    let (_, _, patterns, pattern_metas) = load_project(dir.path()).unwrap();
+    assert_eq!(patterns.len(), 2);
+    assert_eq!(pattern_metas.len(), 2);
+    assert!(pattern_metas[0].fx.is_empty() && pattern_metas[0].track_map.is_empty());
+    assert_eq!(pattern_metas[1].fx.len(), 2);
 }
 
 use project::get_project_path;
@@ -35,4 +65,170 @@ use project::get_project_path;
 fn resolves_dev_or_home_path() {
     let path = get_project_path("my-song");
     assert!(path.ends_with("my-song"));
-}
\ No newline at end of file
+}
+#[test]
+fn validate_reports_invalid_metadata() {
+    let (mut project, _, _, _) = load_project("tests/fixtures/my-song").unwrap();
+    assert!(project.validate().is_ok());
+
+    project.name = String::new();
+    project.bpm = 5;
+    project.swing = 1.5;
+    let errors = project.validate().unwrap_err();
+    assert_eq!(errors.len(), 3);
+}
+
+#[test]
+fn save_project_round_trips() {
+    let (project, tracks, patterns, _) = load_project("tests/fixtures/my-song").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    project::save_project(dir.path(), &project, &tracks, &patterns).unwrap();
+    assert!(dir.path().join("patterns/000.json").exists());
+
+    let (saved, saved_tracks, saved_patterns, saved_metas) = load_project(dir.path()).unwrap();
+    assert_eq!(saved.name, project.name);
+    assert_eq!(saved.bpm, project.bpm);
+    assert_eq!(saved.created, project.created);
+    assert_eq!(saved_tracks.len(), tracks.len());
+    assert_eq!(saved_tracks[1].sample, tracks[1].sample);
+    assert_eq!(saved_patterns, patterns);
+    // save_project doesn't write metadata, so the pattern gets an empty entry
+    assert_eq!(saved_metas.len(), 1);
+    assert!(saved_metas[0].fx.is_empty());
+}
+
+#[test]
+fn model_serialization_helpers_round_trip() {
+    use project::model::Track;
+    use project::{Pattern, Project};
+
+    let (project, tracks, patterns, _) = load_project("tests/fixtures/my-song").unwrap();
+
+    let parsed = Project::from_toml(&project.to_toml().unwrap()).unwrap();
+    assert_eq!(parsed.author, project.author);
+    assert_eq!(parsed.swing, project.swing);
+
+    // auto_start is optional and only written when set
+    assert_eq!(project.auto_start, None);
+    assert!(!project.to_toml().unwrap().contains("auto_start"));
+    let mut auto = Project::from_toml(&project.to_toml().unwrap()).unwrap();
+    auto.auto_start = Some(true);
+    assert_eq!(Project::from_toml(&auto.to_toml().unwrap()).unwrap().auto_start, Some(true));
+
+    assert_eq!(project.groove_template, None);
+    let mut grooved = project.clone();
+    grooved.groove_template = Some(vec![0.0, 0.25]);
+    assert_eq!(Project::from_toml(&grooved.to_toml().unwrap()).unwrap().groove_template, Some(vec![0.0, 0.25]));
+
+    let parsed = Track::from_json_array(&Track::to_json_array(&tracks).unwrap()).unwrap();
+    assert_eq!(parsed[0].name, "Kick");
+
+    // color is optional and only written when set
+    assert_eq!(tracks[0].color, None);
+    assert!(!Track::to_json_array(&tracks).unwrap().contains("color"));
+    let mut colored = tracks.clone();
+    colored[0].color = Some("#FF8800".to_string());
+    assert_eq!(Track::from_json_array(&Track::to_json_array(&colored).unwrap()).unwrap()[0].color.as_deref(), Some("#FF8800"));
+
+    assert_eq!(Pattern::from_json(&patterns[0].to_json().unwrap()).unwrap(), patterns[0]);
+    assert!(Project::from_toml("name = 1").is_err());
+}
+
+#[test]
+fn effects_file_is_not_loaded_as_a_pattern() {
+    let (project, tracks, patterns, _) = load_project("tests/fixtures/my-song").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    project::save_project(dir.path(), &project, &tracks, &patterns).unwrap();
+    project::loader::save_effects(dir.path(), &vec![("0", "Reverse")]).unwrap();
+
+    let (_, _, saved_patterns, _) = load_project(dir.path()).unwrap();
+    assert_eq!(saved_patterns, patterns);
+    let effects: Option<Vec<(String, String)>> = project::loader::load_effects(dir.path()).unwrap();
+    assert_eq!(effects.unwrap().len(), 1);
+
+    let empty = tempfile::tempdir().unwrap();
+    assert!(project::loader::load_effects::<_, Vec<String>>(empty.path()).unwrap().is_none());
+}
+
+#[test]
+fn fx_entry_extended_fields() {
+    use project::model::FxEntry;
+
+    let (_, _, _, metas) = load_project("tests/fixtures/my-song").unwrap();
+    let fx = &metas[0].fx["1:3"];
+    assert_eq!(fx.filter_cutoff_hz, None);
+    assert!(fx.validate().is_ok());
+
+    let fx: FxEntry = serde_json::from_str(
+        r#"{ "filter_cutoff_hz": 800.0, "filter_type": "high", "pitch_semitones": -3.0, "pan": 0.5, "delay_ms": 120.0 }"#
+    ).unwrap();
+    assert_eq!(fx.filter_type.as_deref(), Some("high"));
+    assert_eq!(fx.delay_ms, Some(120.0));
+    assert!(fx.validate().is_ok());
+
+    let fx: FxEntry = serde_json::from_str(
+        r#"{ "filter_cutoff_hz": 5.0, "filter_type": "band", "pan": -1.5, "tremolo_rate_hz": -1.0, "tremolo_depth": 1.5, "compress_threshold": 2.0, "compress_ratio": 0.5 }"#
+    ).unwrap();
+    assert_eq!(fx.validate().unwrap_err().len(), 7);
+}
+
+#[test]
+fn scan_samples_directory_creates_tracks() {
+    use std::fs;
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("perc/deep")).unwrap();
+    for file in ["snare.wav", "kick.wav", "perc/clap.WAV", "perc/deep/too-deep.wav", "notes.txt"] {
+        fs::write(dir.path().join(file), b"").unwrap();
+    }
+
+    let tracks = project::scan_samples_directory(dir.path(), 0.8).unwrap();
+    let names: Vec<&str> = tracks.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["kick", "clap", "snare"]);
+    assert_eq!(tracks[1].sample, "perc/clap.WAV");
+    assert!(tracks.iter().all(|t| t.volume == 0.8 && !t.muted));
+}
+
+#[test]
+fn list_projects_reads_summaries() {
+    let (project, tracks, patterns, _) = load_project("tests/fixtures/my-song").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    project::save_project(dir.path().join("b-song"), &project, &tracks, &patterns).unwrap();
+    let other = project::Project { name: "Other".to_string(), bpm: 90, ..project.clone() };
+    project::save_project(dir.path().join("a-song"), &other, &tracks[..1], &patterns).unwrap();
+    // Folders without a gaucho.toml aren't projects
+    std::fs::create_dir(dir.path().join("samples")).unwrap();
+
+    let summaries = project::list_projects_in(dir.path()).unwrap();
+    assert_eq!(summaries.len(), 2);
+    assert_eq!(summaries[0].name, "Other");
+    assert_eq!(summaries[0].bpm, 90);
+    assert_eq!(summaries[0].num_tracks, 1);
+    assert_eq!(summaries[1].name, "My Song");
+    assert_eq!(summaries[1].author, "esteban");
+    assert_eq!(summaries[1].num_tracks, 2);
+    assert_eq!(summaries[1].path, dir.path().join("b-song"));
+
+    let (loaded, loaded_tracks, _, _) = summaries[1].load_full().unwrap();
+    assert_eq!(loaded.name, "My Song");
+    assert_eq!(loaded_tracks.len(), 2);
+
+    assert!(project::list_projects_in(&dir.path().join("missing")).unwrap().is_empty());
+}
+
+#[test]
+fn load_grooves_reads_groove_files() {
+    let grooves = project::load_grooves(std::path::Path::new("tests/fixtures/my-song")).unwrap();
+    assert_eq!(grooves.len(), 2);
+    assert_eq!(grooves["straight"], vec![0.0]);
+    assert_eq!(grooves["shuffle"], vec![0.0, 0.15, 0.0, 0.3]);
+
+    // Projects without grooves still get a straight one
+    let dir = tempfile::tempdir().unwrap();
+    let grooves = project::load_grooves(dir.path()).unwrap();
+    assert_eq!(grooves.keys().collect::<Vec<_>>(), vec!["straight"]);
+
+    std::fs::create_dir(dir.path().join("grooves")).unwrap();
+    std::fs::write(dir.path().join("grooves/bad.groove.json"), "{}").unwrap();
+    assert!(project::load_grooves(dir.path()).is_err());
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use project::model::Track;
+use project::{default_drum_map, export_midi_pattern, export_midi_pattern_with_notes, import_midi_pattern, Pattern};
+
+fn track(name: &str) -> Track {
+    Track { name: name.to_string(), sample: format!("samples/{}.wav", name.to_lowercase()), volume: 1.0, pan: 0.0, pitch_semitones: 0.0, muted: false, color: None }
+}
+
+fn steps_at(row: &[bool]) -> Vec<usize> {
+    row.iter().enumerate().filter(|(_, &on)| on).map(|(i, _)| i).collect()
+}
+
+#[test]
+fn imports_drum_beat_with_default_map() {
+    let pattern = import_midi_pattern(Path::new("tests/fixtures/beat.mid"), 120, &default_drum_map()).unwrap();
+
+    assert_eq!(pattern.pattern_id, 0);
+    assert_eq!(pattern.steps.len(), 3);
+    assert!(pattern.steps.iter().all(|row| row.len() == 16));
+    assert_eq!(steps_at(&pattern.steps[0]), vec![0, 8]);
+    assert_eq!(steps_at(&pattern.steps[1]), vec![4, 12]);
+    // The hi-hat two ticks late on step 2 is quantized back onto the grid
+    assert_eq!(steps_at(&pattern.steps[2]), vec![0, 2, 4, 6, 8, 10, 12, 14]);
+}
+
+#[test]
+fn custom_map_selects_tracks_and_ignores_other_notes() {
+    let note_map = HashMap::from([(38, 0), (36, 3)]);
+    let pattern = import_midi_pattern(Path::new("tests/fixtures/beat.mid"), 120, &note_map).unwrap();
+
+    assert_eq!(pattern.steps.len(), 4);
+    assert_eq!(steps_at(&pattern.steps[0]), vec![4, 12]);
+    assert!(steps_at(&pattern.steps[1]).is_empty());
+    assert!(steps_at(&pattern.steps[2]).is_empty());
+    assert_eq!(steps_at(&pattern.steps[3]), vec![0, 8]);
+}
+
+#[test]
+fn rejects_files_that_are_not_midi() {
+    let result = import_midi_pattern(Path::new("tests/fixtures/my-song/gaucho.toml"), 120, &default_drum_map());
+    assert!(result.is_err());
+}
+
+#[test]
+fn exported_pattern_imports_back_unchanged() {
+    let mut steps = vec![vec![false; 32]; 3];
+    for step in [0, 6, 10, 16, 22, 26] {
+        steps[0][step] = true;
+    }
+    steps[1][8] = true;
+    steps[1][24] = true;
+    // Back-to-back hits on every step
+    steps[2] = vec![true; 32];
+    let original = Pattern::new(0, steps);
+    let tracks = vec![track("Kick"), track("Snare"), track("HiHat")];
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("export.mid");
+    export_midi_pattern(&original, &tracks, 128, &path).unwrap();
+
+    let imported = import_midi_pattern(&path, 128, &default_drum_map()).unwrap();
+    assert_eq!(imported, original);
+}
+
+#[test]
+fn export_with_custom_notes() {
+    let original = Pattern::new(0, vec![[true, false, false, true].repeat(4), vec![false; 16]]);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("custom.mid");
+    export_midi_pattern_with_notes(&original, &[60, 61], 90, &path).unwrap();
+
+    let imported = import_midi_pattern(&path, 90, &HashMap::from([(60, 0), (61, 1)])).unwrap();
+    assert_eq!(imported, original);
+
+    assert!(export_midi_pattern_with_notes(&original, &[60], 90, &path).is_err());
+    assert!(export_midi_pattern_with_notes(&original, &[60, 128], 90, &path).is_err());
+}
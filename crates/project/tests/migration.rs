@@ -0,0 +1,43 @@
+use project::load_project;
+use project::migration::{migrate_project, needs_migration, CURRENT_VERSION};
+use std::fs;
+use std::path::Path;
+
+/// Copy a fixture project so migrations don't rewrite the checked-in files
+fn copy_fixture(name: &str, to: &Path) {
+    let from = Path::new("tests/fixtures").join(name);
+    fs::create_dir_all(to.join("patterns")).unwrap();
+    for file in ["gaucho.toml", "tracks.json", "patterns/000.json"] {
+        fs::copy(from.join(file), to.join(file)).unwrap();
+    }
+}
+
+#[test]
+fn load_project_migrates_legacy_tracks() {
+    let dir = tempfile::tempdir().unwrap();
+    copy_fixture("legacy-1.0", dir.path());
+    assert!(!fs::read_to_string(dir.path().join("tracks.json")).unwrap().contains("pan"));
+
+    let (project, tracks, patterns, _) = load_project(dir.path()).unwrap();
+    assert_eq!(project.version, CURRENT_VERSION);
+    assert_eq!(project.name, "Legacy Song");
+    assert_eq!(tracks.len(), 2);
+    assert_eq!(patterns.len(), 1);
+
+    // Migrated files are written back
+    let tracks_json: Vec<serde_json::Value> =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("tracks.json")).unwrap()).unwrap();
+    assert!(tracks_json.iter().all(|t| t["pan"] == 0.0 && t["pitch_semitones"] == 0.0));
+    assert_eq!(tracks_json[0]["name"], "Kick");
+    assert!(fs::read_to_string(dir.path().join("gaucho.toml")).unwrap().contains("version = \"2.0\""));
+}
+
+#[test]
+fn migrate_project_rejects_unknown_versions() {
+    let dir = tempfile::tempdir().unwrap();
+    copy_fixture("legacy-1.0", dir.path());
+    assert!(needs_migration("1.0"));
+    assert!(!needs_migration(CURRENT_VERSION));
+    assert!(!needs_migration("0.3"));
+    assert!(migrate_project(dir.path(), "0.3", CURRENT_VERSION).is_err());
+}
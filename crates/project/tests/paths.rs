@@ -0,0 +1,41 @@
+use project::{get_project_path, get_project_path_with_config, get_projects_base_dir, Config, ProjectPathConfig, PROJECTS_DIR_ENV_VAR};
+use std::env;
+use std::fs;
+
+#[test]
+fn resolves_from_configured_dirs() {
+    let first = tempfile::tempdir().unwrap();
+    let second = tempfile::tempdir().unwrap();
+    fs::create_dir(second.path().join("my-song")).unwrap();
+    let config = ProjectPathConfig { search_dirs: vec![first.path().to_path_buf(), second.path().to_path_buf()] };
+
+    // An existing project is found wherever it is
+    assert_eq!(get_project_path_with_config("my-song", &config), second.path().join("my-song"));
+    // A new one goes in the first existing folder
+    assert_eq!(get_project_path_with_config("new-song", &config), first.path().join("new-song"));
+
+    let missing = ProjectPathConfig { search_dirs: vec![first.path().join("nope"), second.path().join("nope")] };
+    assert_eq!(missing.base_dir(), second.path().join("nope"));
+}
+
+#[test]
+fn env_var_overrides_default_dirs() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("my-song")).unwrap();
+    env::set_var(PROJECTS_DIR_ENV_VAR, dir.path());
+
+    assert_eq!(ProjectPathConfig::default().search_dirs[0], dir.path());
+    assert_eq!(get_project_path("my-song"), dir.path().join("my-song"));
+    assert_eq!(get_projects_base_dir(), dir.path());
+
+    env::remove_var(PROJECTS_DIR_ENV_VAR);
+    assert_ne!(ProjectPathConfig::default().search_dirs.first().map(|d| d.as_path()), Some(dir.path()));
+}
+
+#[test]
+fn parses_projects_dir_from_config() {
+    let config: Config = toml::from_str("projects_dir = \"/srv/songs\"").unwrap();
+    assert_eq!(config.projects_dir.as_deref(), Some(std::path::Path::new("/srv/songs")));
+    let empty: Config = toml::from_str("").unwrap();
+    assert_eq!(empty, Config::default());
+}
@@ -0,0 +1,165 @@
+use project::Pattern;
+
+fn pattern(steps: Vec<Vec<bool>>) -> Pattern {
+    Pattern::new(3, steps)
+}
+
+#[test]
+fn invert_flips_every_step() {
+    let original = pattern(vec![
+        vec![true, false, false, false],
+        vec![false, false, true, true],
+    ]);
+    let inverted = original.invert();
+
+    assert_eq!(inverted.pattern_id, 3);
+    assert_eq!(inverted.steps[0], vec![false, true, true, true]);
+    assert_eq!(inverted.steps[1], vec![true, true, false, false]);
+}
+
+#[test]
+fn double_inversion_returns_original() {
+    let patterns = vec![
+        pattern(vec![]),
+        pattern(vec![vec![]]),
+        pattern(vec![vec![true; 16]]),
+        pattern(vec![vec![false; 8], vec![true, false, true, false, true, false, true, false]]),
+    ];
+    for p in patterns {
+        assert_eq!(p.invert().invert(), p);
+    }
+}
+
+#[test]
+fn invert_track_only_changes_that_track() {
+    let mut p = pattern(vec![vec![true, false], vec![true, false]]);
+    p.invert_track(1);
+    assert_eq!(p.steps, vec![vec![true, false], vec![false, true]]);
+
+    // Out of range tracks are ignored
+    p.invert_track(5);
+    assert_eq!(p.steps, vec![vec![true, false], vec![false, true]]);
+}
+
+#[test]
+fn reversed_track_plays_backwards() {
+    let p = pattern(vec![vec![true, true, false, false], vec![true, false, false, true]]);
+    assert_eq!(p.reversed_track(0), vec![false, false, true, true]);
+    // Palindromes read the same both ways
+    assert_eq!(p.reversed_track(1), vec![true, false, false, true]);
+    assert!(p.reversed_track(2).is_empty());
+    // The pattern itself is unchanged
+    assert_eq!(p.steps[0], vec![true, true, false, false]);
+}
+
+#[test]
+fn length_defaults_to_first_track() {
+    let parsed = Pattern::from_json(r#"{ "pattern_id": 1, "steps": [[true, false, true], [false, false, false]] }"#).unwrap();
+    assert_eq!(parsed.length, 3);
+    assert!(parsed.validate_length().is_ok());
+
+    let empty = Pattern::from_json(r#"{ "pattern_id": 2, "steps": [[], []], "length": 0 }"#).unwrap();
+    assert_eq!(empty.length, 0);
+    assert!(empty.validate_length().is_ok());
+
+    assert_eq!(Pattern::from_json(&parsed.to_json().unwrap()).unwrap(), parsed);
+}
+
+#[test]
+fn validate_length_reports_mismatched_track() {
+    let mut p = pattern(vec![vec![true; 16], vec![false; 8]]);
+    assert!(p.validate_length().unwrap_err().contains("track 1"));
+
+    p.steps[1].resize(16, false);
+    assert!(p.validate_length().is_ok());
+}
+
+#[test]
+fn loading_mismatched_pattern_fails() {
+    let (project, tracks, patterns, _) = project::load_project("tests/fixtures/my-song").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    project::save_project(dir.path(), &project, &tracks, &patterns).unwrap();
+    std::fs::write(
+        dir.path().join("patterns/000.json"),
+        r#"{ "pattern_id": 0, "steps": [[true, false], [true, false, true]], "length": 2 }"#,
+    ).unwrap();
+
+    let err = project::load_project(dir.path()).unwrap_err();
+    assert!(err.to_string().contains("expected 2"));
+}
+
+#[test]
+fn hex_string_round_trips() {
+    let cases = vec![
+        vec![vec![true, false, false, false]; 1],
+        // 12 steps pad each track to two bytes
+        vec![
+            vec![true, false, true, false, false, false, false, false, true, true, false, true],
+            vec![false; 12],
+            vec![true; 12],
+        ],
+        (0..8).map(|track| (0..16).map(|step| (step + track) % 3 == 0).collect()).collect(),
+        vec![(0..64).map(|step| step % 5 == 0).collect()],
+        vec![vec![]],
+    ];
+    for steps in cases {
+        let original = Pattern::new(0, steps);
+        let hex = original.to_hex_string();
+        let decoded = Pattern::from_hex_string(&hex, original.steps.len(), original.length).unwrap();
+        assert_eq!(decoded, original, "Round trip through {:?}", hex);
+    }
+}
+
+#[test]
+fn hex_string_packs_steps_lsb_first() {
+    let mut steps = vec![vec![false; 16]; 8];
+    for step in [0, 4, 8, 12] {
+        steps[0][step] = true;
+    }
+    steps[1][1] = true;
+    steps[1][15] = true;
+    let hex = Pattern::new(0, steps).to_hex_string();
+    assert_eq!(hex.len(), 32);
+    assert_eq!(&hex[..8], "11110280");
+    assert!(hex[8..].chars().all(|c| c == '0'));
+}
+
+#[test]
+fn from_hex_string_rejects_bad_input() {
+    assert!(Pattern::from_hex_string("1111", 1, 8).unwrap_err().contains("expected 2"));
+    assert!(Pattern::from_hex_string("zz", 1, 8).is_err());
+    assert!(Pattern::from_hex_string("é1", 1, 8).is_err());
+
+    // Bits past the last step are padding
+    let p = Pattern::from_hex_string("ff0f", 1, 12).unwrap();
+    assert_eq!(p.steps, vec![vec![true; 12]]);
+    assert_eq!(Pattern::from_hex_string("ffff", 1, 12).unwrap(), p);
+}
+
+#[test]
+fn split_and_join_round_trip() {
+    let original = pattern(vec![
+        (0..16).map(|step| step % 4 == 0).collect(),
+        (0..16).map(|step| step >= 12).collect(),
+    ]);
+
+    let parts = original.split(2).unwrap();
+    assert_eq!(parts.len(), 2);
+    assert_eq!((parts[0].pattern_id, parts[1].pattern_id), (3, 4));
+    assert_eq!(parts[1].length, 8);
+    assert_eq!(parts[1].steps[0], vec![true, false, false, false, true, false, false, false]);
+    assert_eq!(parts[1].steps[1], vec![false, false, false, false, true, true, true, true]);
+
+    assert_eq!(Pattern::join(&parts).unwrap(), original);
+}
+
+#[test]
+fn split_and_join_reject_mismatches() {
+    let original = pattern(vec![vec![false; 16]]);
+    assert!(original.split(3).is_err());
+    assert!(original.split(0).is_err());
+    assert_eq!(original.split(1).unwrap(), vec![original.clone()]);
+
+    assert!(Pattern::join(&[]).is_err());
+    assert!(Pattern::join(&[original, pattern(vec![vec![true; 8]; 2])]).is_err());
+}
@@ -0,0 +1,60 @@
+use project::{export_project_zip, load_project, load_project_from_url};
+use std::fs;
+use std::path::Path;
+
+fn fixture_zip() -> Vec<u8> {
+    let dir = tempfile::tempdir().unwrap();
+    let zip_path = dir.path().join("my-song.zip");
+    export_project_zip(Path::new("tests/fixtures/my-song"), &zip_path).unwrap();
+    fs::read(zip_path).unwrap()
+}
+
+#[test]
+fn downloads_and_caches_projects() {
+    let zip = fixture_zip();
+    let mut server = mockito::Server::new();
+    let download = server.mock("GET", "/my-song.zip").with_body(&zip).expect(1).create();
+    let url = format!("{}/my-song.zip", server.url());
+    let cache = tempfile::tempdir().unwrap();
+
+    let (project, tracks, patterns, _) = load_project_from_url(&url, cache.path()).unwrap();
+    let (original, original_tracks, original_patterns, _) = load_project("tests/fixtures/my-song").unwrap();
+    assert_eq!(project.name, original.name);
+    assert_eq!(tracks.len(), original_tracks.len());
+    assert_eq!(patterns, original_patterns);
+
+    // A fresh download is reused
+    load_project_from_url(&url, cache.path()).unwrap();
+    download.assert();
+
+    // One older than a day is downloaded again
+    let folders: Vec<_> = fs::read_dir(cache.path()).unwrap().map(|e| e.unwrap().path()).collect();
+    assert_eq!(folders.len(), 1);
+    fs::write(folders[0].join(".downloaded"), "0").unwrap();
+    let redownload = server.mock("GET", "/my-song.zip").with_body(&zip).expect(1).create();
+    load_project_from_url(&url, cache.path()).unwrap();
+    redownload.assert();
+}
+
+#[test]
+fn follows_redirects_and_reads_chunked_bodies() {
+    let zip = fixture_zip();
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/old.zip").with_status(302).with_header("location", "/new.zip").create();
+    server.mock("GET", "/new.zip")
+        .with_chunked_body(move |writer| {
+            let (first, second) = zip.split_at(zip.len() / 2);
+            writer.write_all(first)?;
+            writer.write_all(second)
+        })
+        .create();
+    server.mock("GET", "/missing.zip").with_status(404).create();
+    let cache = tempfile::tempdir().unwrap();
+
+    // Relative redirects resolve against the original host
+    let (project, _, _, _) = load_project_from_url(&format!("{}/old.zip", server.url()), cache.path()).unwrap();
+    assert_eq!(project.name, load_project("tests/fixtures/my-song").unwrap().0.name);
+
+    let missing = load_project_from_url(&format!("{}/missing.zip", server.url()), cache.path());
+    assert!(missing.unwrap_err().to_string().contains("404"));
+}
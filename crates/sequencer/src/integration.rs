@@ -1,5 +1,19 @@
 // This module will contain code for integrating the sequencer with other components
-use log::{debug};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use crossbeam_channel::{unbounded, RecvTimeoutError, Sender};
+use log::{debug, warn};
+use midir::{MidiOutput, MidiOutputConnection};
+use core::{SharedEventBus, TrackerEvent};
+
+use crate::{Sequencer, SequencerError, TriggerEvent};
+
+/// Callback a connection forwards trigger events to
+pub type EventSender = Box<dyn Fn(TriggerEvent) + Send + Sync + 'static>;
 
 /// This enum represents the possible destinations for sequencer events
 #[derive(Debug, Clone, Copy)]
@@ -12,32 +26,197 @@ pub enum EventDestination {
     MIDI,
 }
 
+/// Where a MIDI connection writes its bytes
+type MidiSink = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// MIDI note of track 0; further tracks map to the notes above it (General MIDI kick)
+const BASE_MIDI_NOTE: usize = 36;
+
+/// Velocity used for triggers, which don't carry their own yet
+const TRIGGER_VELOCITY: f32 = 1.0;
+
+/// How long a note sent by `send_midi_note` is held before its Note Off
+const NOTE_LENGTH: Duration = Duration::from_millis(50);
+
 /// Represents a connection to another component
 pub struct Connection {
+    /// Shared with the event bus listener so deactivating also stops forwarding from there
+    outputs: Arc<Outputs>,
+    /// Event bus subscription made by `subscribe_to_sequencer`
+    subscription: Option<(SharedEventBus, usize)>,
+}
+
+/// The parts of a connection that events are forwarded through
+struct Outputs {
     destination: EventDestination,
-    active: bool,
+    active: AtomicBool,
+    sender: Mutex<Option<EventSender>>,
+    midi: Mutex<Option<MidiSink>>,
+    /// Queue of the Note Off thread, started with the first note
+    note_offs: Mutex<Option<Sender<NoteOff>>>,
+}
+
+/// A Note Off due at `due`
+struct NoteOff {
+    due: Instant,
+    channel: u8,
+    note: u8,
+    output: MidiSink,
+}
+
+/// Send Note Offs as they fall due. A note played again before its Note Off
+/// replaces the pending one, so the earlier Note Off can't cut the new note short.
+/// Runs until the connection is dropped, then releases any held notes.
+fn run_note_offs(queue: crossbeam_channel::Receiver<NoteOff>) {
+    let mut pending: HashMap<(u8, u8), NoteOff> = HashMap::new();
+    loop {
+        let next_due = pending.values().map(|off| off.due).min();
+        let received = match next_due {
+            Some(due) => queue.recv_timeout(due.saturating_duration_since(Instant::now())),
+            None => queue.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(off) => {
+                pending.insert((off.channel, off.note), off);
+            },
+            Err(RecvTimeoutError::Timeout) => {},
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        let now = Instant::now();
+        let due: Vec<(u8, u8)> = pending.iter().filter(|(_, off)| off.due <= now).map(|(&key, _)| key).collect();
+        for key in due {
+            if let Some(off) = pending.remove(&key) {
+                send_note_off(&off);
+            }
+        }
+    }
+    for off in pending.values() {
+        send_note_off(off);
+    }
+}
+
+fn send_note_off(off: &NoteOff) {
+    if let Err(e) = write_midi(&off.output, &[0x80 | off.channel, off.note, 0]) {
+        warn!("Failed to send MIDI Note Off for note {}: {}", off.note, e);
+    }
 }
 
 impl Connection {
     pub fn new(destination: EventDestination) -> Self {
         Self {
-            destination,
-            active: false,
+            outputs: Arc::new(Outputs {
+                destination,
+                active: AtomicBool::new(false),
+                sender: Mutex::new(None),
+                midi: Mutex::new(None),
+                note_offs: Mutex::new(None),
+            }),
+            subscription: None,
         }
     }
     
     pub fn activate(&mut self) {
-        self.active = true;
-        debug!("Connection to {:?} activated", self.destination);
+        self.outputs.active.store(true, Ordering::SeqCst);
+        debug!("Connection to {:?} activated", self.outputs.destination);
     }
     
     pub fn deactivate(&mut self) {
-        self.active = false;
-        debug!("Connection to {:?} deactivated", self.destination);
+        self.outputs.active.store(false, Ordering::SeqCst);
+        debug!("Connection to {:?} deactivated", self.outputs.destination);
     }
     
     pub fn is_active(&self) -> bool {
-        self.active
+        self.outputs.active.load(Ordering::SeqCst)
+    }
+    
+    /// Set the callback that receives events sent through this connection
+    pub fn set_event_sender(&mut self, sender: EventSender) {
+        *self.outputs.sender.lock().unwrap() = Some(sender);
+    }
+    
+    /// Write MIDI bytes to `output` instead of a hardware port
+    pub fn set_midi_output(&mut self, output: Box<dyn Write + Send>) {
+        *self.outputs.midi.lock().unwrap() = Some(Arc::new(Mutex::new(output)));
+    }
+    
+    /// Forward an event to the sender, if the connection is active and has one.
+    /// MIDI connections also play the track's note.
+    pub fn send(&self, event: TriggerEvent) {
+        self.outputs.send(event);
+    }
+    
+    /// Send a Note On now and the matching Note Off after `NOTE_LENGTH`
+    pub fn send_midi_note(&self, note: u8, velocity: u8, channel: u8) -> Result<(), SequencerError> {
+        self.outputs.send_midi_note(note, velocity, channel)
+    }
+    
+    /// Route the sequencer's `StepTriggered` events through this connection.
+    /// Replaces any earlier subscription; the listener is removed when the connection is dropped.
+    pub fn subscribe_to_sequencer(&mut self, sequencer: &Sequencer) {
+        let event_bus = Arc::clone(sequencer.get_event_bus());
+        let outputs = Arc::clone(&self.outputs);
+        let id = event_bus.subscribe_immediate(move |event| {
+            if let TrackerEvent::StepTriggered(track_idx, step_idx) = event {
+                outputs.send(TriggerEvent { track_idx: *track_idx, step_idx: *step_idx });
+            }
+        });
+        
+        if let Some((old_bus, old_id)) = self.subscription.replace((event_bus, id)) {
+            old_bus.unsubscribe(old_id);
+        }
+        debug!("Connection to {:?} subscribed to sequencer events", self.outputs.destination);
+    }
+}
+
+impl Outputs {
+    fn send(&self, event: TriggerEvent) {
+        if !self.active.load(Ordering::SeqCst) {
+            return;
+        }
+        if let Some(sender) = self.sender.lock().unwrap().as_ref() {
+            sender(event);
+        }
+        if let EventDestination::MIDI = self.destination {
+            let note = (BASE_MIDI_NOTE + event.track_idx).min(127) as u8;
+            if let Err(e) = self.send_midi_note(note, (TRIGGER_VELOCITY * 127.0) as u8, 0) {
+                warn!("Failed to send MIDI note for track {}: {}", event.track_idx, e);
+            }
+        }
+    }
+    
+    fn send_midi_note(&self, note: u8, velocity: u8, channel: u8) -> Result<(), SequencerError> {
+        let output = self.midi.lock().unwrap().clone()
+            .ok_or_else(|| SequencerError::MidiError("Connection has no MIDI output".to_string()))?;
+        let (note, channel) = (note & 0x7F, channel & 0x0F);
+        write_midi(&output, &[0x90 | channel, note, velocity & 0x7F])?;
+        
+        // Hold the note off the caller's thread, which is usually the event bus dispatcher
+        let mut note_offs = self.note_offs.lock().unwrap();
+        let queue = note_offs.get_or_insert_with(|| {
+            let (sender, receiver) = unbounded();
+            thread::Builder::new()
+                .name("midi-note-off".to_string())
+                .spawn(move || run_note_offs(receiver))
+                .expect("Failed to spawn MIDI Note Off thread");
+            sender
+        });
+        let _ = queue.send(NoteOff { due: Instant::now() + NOTE_LENGTH, channel, note, output });
+        Ok(())
+    }
+}
+
+fn write_midi(output: &MidiSink, message: &[u8]) -> Result<(), SequencerError> {
+    let mut output = output.lock().unwrap();
+    output.write_all(message)?;
+    output.flush()?;
+    Ok(())
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let Some((event_bus, id)) = self.subscription.take() {
+            event_bus.unsubscribe(id);
+        }
     }
 }
 
@@ -50,6 +229,56 @@ pub fn setup_sampler_connection() -> Connection {
     Connection::new(EventDestination::Sampler)
 }
 
+/// Client name other MIDI software sees for this program
+const MIDI_CLIENT_NAME: &str = "gaucho-tracker";
+
+/// Open the named MIDI output port (a name from `list_midi_output_ports`)
+pub fn setup_midi_connection(port_name: &str) -> Result<Connection, SequencerError> {
+    let not_found = || SequencerError::MidiPortNotFound(port_name.to_string());
+    let midi_out = open_midi_output().ok_or_else(not_found)?;
+    let port = midi_out.ports().into_iter()
+        .find(|port| midi_out.port_name(port).is_ok_and(|name| name == port_name))
+        .ok_or_else(not_found)?;
+    let output = midi_out.connect(&port, "gaucho-tracker-out")
+        .map_err(|e| SequencerError::MidiError(format!("Failed to open {}: {}", port_name, e)))?;
+
+    let mut connection = Connection::new(EventDestination::MIDI);
+    connection.set_midi_output(Box::new(MidiPortWriter(output)));
+    debug!("Opened MIDI output port {}", port_name);
+    Ok(connection)
+}
+
+/// Names of the available MIDI output ports
+pub fn list_midi_output_ports() -> Vec<String> {
+    let Some(midi_out) = open_midi_output() else {
+        return Vec::new();
+    };
+    midi_out.ports().iter()
+        .filter_map(|port| midi_out.port_name(port).ok())
+        .collect()
+}
+
+/// A MIDI client, or None when the system has no MIDI support to offer ports from
+fn open_midi_output() -> Option<MidiOutput> {
+    MidiOutput::new(MIDI_CLIENT_NAME)
+        .map_err(|e| warn!("MIDI output is unavailable: {}", e))
+        .ok()
+}
+
+/// Sends each write to a midir connection as one complete MIDI message
+struct MidiPortWriter(MidiOutputConnection);
+
+impl Write for MidiPortWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send(buf).map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -65,4 +294,120 @@ mod tests {
         conn.deactivate();
         assert_eq!(conn.is_active(), false);
     }
+    
+    #[test]
+    fn test_sampler_connection_receives_triggers() {
+        use std::thread::sleep;
+        use std::time::Duration;
+        
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = Arc::clone(&received);
+        let mut conn = setup_sampler_connection();
+        conn.set_event_sender(Box::new(move |event| {
+            received_clone.lock().unwrap().push((event.track_idx, event.step_idx));
+        }));
+        
+        // Inactive connections drop events
+        conn.send(TriggerEvent { track_idx: 0, step_idx: 0 });
+        assert!(received.lock().unwrap().is_empty());
+        
+        let sequencer = Sequencer::new(600, vec![vec![true, false], vec![false, true]]);
+        conn.subscribe_to_sequencer(&sequencer);
+        conn.activate();
+        sequencer.start();
+        sleep(Duration::from_millis(200));
+        sequencer.stop();
+        sleep(Duration::from_millis(50));
+        
+        let received = received.lock().unwrap().clone();
+        assert!(received.contains(&(0, 0)), "Expected track 0 triggers: {:?}", received);
+        assert!(received.contains(&(1, 1)), "Expected track 1 triggers: {:?}", received);
+        
+        let listeners = sequencer.get_event_bus().listener_count();
+        drop(conn);
+        assert_eq!(sequencer.get_event_bus().listener_count(), listeners - 1);
+    }
+    
+    /// Collects written MIDI bytes so tests can inspect them
+    #[derive(Clone, Default)]
+    struct MidiCapture(Arc<Mutex<Vec<u8>>>);
+    
+    impl Write for MidiCapture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_midi_connection_sends_note_on_and_off() {
+        use std::thread::sleep;
+        
+        let capture = MidiCapture::default();
+        let mut conn = Connection::new(EventDestination::MIDI);
+        conn.set_midi_output(Box::new(capture.clone()));
+        
+        // Inactive connections stay silent
+        conn.send(TriggerEvent { track_idx: 2, step_idx: 0 });
+        sleep(NOTE_LENGTH * 2);
+        assert!(capture.0.lock().unwrap().is_empty());
+        
+        conn.activate();
+        conn.send(TriggerEvent { track_idx: 2, step_idx: 0 });
+        assert_eq!(*capture.0.lock().unwrap(), vec![0x90, 38, 127]);
+        sleep(NOTE_LENGTH * 2);
+        assert_eq!(*capture.0.lock().unwrap(), vec![0x90, 38, 127, 0x80, 38, 0]);
+        
+        capture.0.lock().unwrap().clear();
+        conn.send_midi_note(60, 64, 9).unwrap();
+        sleep(NOTE_LENGTH * 2);
+        assert_eq!(*capture.0.lock().unwrap(), vec![0x99, 60, 64, 0x89, 60, 0]);
+    }
+    
+    #[test]
+    fn test_retriggered_note_is_not_cut_short() {
+        use std::thread::sleep;
+        
+        let capture = MidiCapture::default();
+        let mut conn = Connection::new(EventDestination::MIDI);
+        conn.set_midi_output(Box::new(capture.clone()));
+        
+        conn.send_midi_note(36, 100, 0).unwrap();
+        sleep(NOTE_LENGTH / 2);
+        conn.send_midi_note(36, 90, 0).unwrap();
+        conn.send_midi_note(38, 80, 0).unwrap();
+        
+        // The first note's Note Off would have been due by now
+        sleep(NOTE_LENGTH * 3 / 4);
+        assert_eq!(*capture.0.lock().unwrap(), vec![0x90, 36, 100, 0x90, 36, 90, 0x90, 38, 80]);
+        
+        sleep(NOTE_LENGTH);
+        let bytes = capture.0.lock().unwrap().clone();
+        assert_eq!(bytes.len(), 15);
+        assert!(bytes[9..].chunks(3).any(|off| off == [0x80, 36, 0]));
+        assert!(bytes[9..].chunks(3).any(|off| off == [0x80, 38, 0]));
+        
+        // Dropping the connection releases held notes right away
+        capture.0.lock().unwrap().clear();
+        conn.send_midi_note(40, 100, 0).unwrap();
+        drop(conn);
+        sleep(NOTE_LENGTH / 5);
+        assert_eq!(*capture.0.lock().unwrap(), vec![0x90, 40, 100, 0x80, 40, 0]);
+    }
+    
+    #[test]
+    fn test_midi_connection_errors() {
+        assert!(Connection::new(EventDestination::MIDI).send_midi_note(36, 100, 0).is_err());
+        
+        let result = setup_midi_connection("gaucho-tracker missing port");
+        assert!(matches!(result, Err(SequencerError::MidiPortNotFound(_))));
+        
+        // Listing ports must work whether or not any are present
+        let ports = list_midi_output_ports();
+        assert!(!ports.contains(&"gaucho-tracker missing port".to_string()));
+    }
 }
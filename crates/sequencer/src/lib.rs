@@ -1,63 +1,339 @@
 // sequencer module
 
 pub mod integration;
+mod sample_clock;
+#[cfg(test)]
+#[path = "tests.rs"]
+mod component_tests;
 
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
-use std::time::{Duration, Instant};
-use crossbeam_channel::{bounded, Receiver, Sender};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, SendError, Sender};
 use core::{EventBus, TrackerEvent, SharedEventBus};
-use log::{debug, info};
+use log::{debug, info, warn};
+use rodio::OutputStreamHandle;
+
+use sample_clock::SampleClock;
+
+/// Inter-step timing statistics from `Sequencer::measure_timing_accuracy`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TimingStats {
+    pub mean_interval_us: f64,
+    pub std_dev_us: f64,
+    /// Largest distance of a single interval from the mean
+    pub max_jitter_us: f64,
+}
+
+impl TimingStats {
+    /// Statistics of the gaps between consecutive timestamps; all zero with fewer than two
+    pub fn from_timestamps(times: &[Instant]) -> Self {
+        if times.len() < 2 {
+            return Self::default();
+        }
+        let intervals: Vec<f64> = times.windows(2)
+            .map(|pair| pair[1].duration_since(pair[0]).as_secs_f64() * 1_000_000.0)
+            .collect();
+        let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+        let variance = intervals.iter().map(|interval| (interval - mean).powi(2)).sum::<f64>() / intervals.len() as f64;
+        let max_jitter = intervals.iter().map(|interval| (interval - mean).abs()).fold(0.0, f64::max);
+        Self { mean_interval_us: mean, std_dev_us: variance.sqrt(), max_jitter_us: max_jitter }
+    }
+}
 
 /// Represents a trigger event which contains the track index and step index
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TriggerEvent {
     pub track_idx: usize,
     pub step_idx: usize,
 }
 
+/// Error types for the sequencer and its connections
+#[derive(Debug)]
+pub enum SequencerError {
+    /// No MIDI output port has the requested name
+    MidiPortNotFound(String),
+    MidiError(String),
+    IoError(std::io::Error),
+    EmptyPattern,
+    /// A track with no steps
+    EmptyTrack(usize),
+    UnequalTrackLengths { expected: usize, got: usize, track_idx: usize },
+    /// A step length that isn't a positive number
+    InvalidStepLength(f32),
+    /// A command could not reach the sequencer thread
+    ChannelError(String),
+    /// The sequencer thread stopped without being asked to
+    ThreadPanic(String),
+}
+
+impl std::fmt::Display for SequencerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MidiPortNotFound(name) => write!(f, "MIDI output port not found: {}", name),
+            Self::MidiError(msg) => write!(f, "MIDI error: {}", msg),
+            Self::IoError(err) => write!(f, "IO error: {}", err),
+            Self::EmptyPattern => write!(f, "Pattern cannot be empty"),
+            Self::EmptyTrack(track_idx) => write!(f, "Track {} of the pattern is empty", track_idx),
+            Self::UnequalTrackLengths { expected, got, track_idx } => write!(
+                f, "All tracks must have the same length: track {} has {} steps, expected {}", track_idx, got, expected
+            ),
+            Self::InvalidStepLength(length) => write!(f, "Step lengths must be positive, got {}", length),
+            Self::ChannelError(msg) => write!(f, "Failed to send command to the sequencer thread: {}", msg),
+            Self::ThreadPanic(msg) => write!(f, "Sequencer thread panicked: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SequencerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SequencerError {
+    fn from(err: std::io::Error) -> Self {
+        Self::IoError(err)
+    }
+}
+
+/// Steps per bar used unless changed with `set_steps_per_bar` (16th notes in 4/4)
+pub const DEFAULT_STEPS_PER_BAR: usize = 16;
+
+/// MIDI clock pulses per step at the default subdivision: 24 per beat makes 6 per 16th note
+pub const MIDI_CLOCKS_PER_STEP: usize = 6;
+
+/// How long the sequencer thread waits for commands while stopped
+const IDLE_WAIT: Duration = Duration::from_millis(50);
+
+/// Note value a step stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Subdivision {
+    Eighth,
+    #[default]
+    Sixteenth,
+    ThirtySecond,
+}
+
+impl Subdivision {
+    /// Steps per beat
+    pub fn factor(self) -> u32 {
+        match self {
+            Self::Eighth => 2,
+            Self::Sixteenth => 4,
+            Self::ThirtySecond => 8,
+        }
+    }
+    
+    /// Note value of a step: 8, 16 or 32
+    pub fn note_value(self) -> u32 {
+        self.factor() * 4
+    }
+    
+    /// The next finer subdivision, wrapping from 32nds back to 8ths
+    pub fn next(self) -> Self {
+        match self {
+            Self::Eighth => Self::Sixteenth,
+            Self::Sixteenth => Self::ThirtySecond,
+            Self::ThirtySecond => Self::Eighth,
+        }
+    }
+    
+    /// MIDI clock pulses per step at 24 per beat
+    pub fn midi_clocks_per_step(self) -> usize {
+        24 / self.factor() as usize
+    }
+}
+
+/// Length of one step at the given tempo and subdivision
+fn step_interval(bpm: u32, subdivision: Subdivision) -> Duration {
+    Duration::from_secs_f64(60.0 / bpm.max(1) as f64 / subdivision.factor() as f64)
+}
+
+/// Wall-clock time in nanoseconds since the Unix epoch, for sharing timestamps through atomics
+fn now_nanos() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64)
+}
+
+/// Largest groove offset either way, in steps; keeps grooved steps in order
+pub const MAX_GROOVE_OFFSET: f32 = 0.5;
+
+/// How far a step fires from its grid position, in steps. A groove template gives
+/// the offset of every step; without one, odd steps are delayed by the swing amount.
+fn step_offset(pattern_step: u64, swing: f32, groove: &[f32]) -> f64 {
+    if !groove.is_empty() {
+        groove[(pattern_step % groove.len() as u64) as usize] as f64
+    } else if pattern_step % 2 == 1 {
+        swing as f64 * 0.5
+    } else {
+        0.0
+    }
+}
+
+/// When a step at `grid_tick` fires, after swing or groove
+fn swung_tick(grid_tick: Instant, tick_interval: Duration, offset: f64) -> Instant {
+    if offset < 0.0 {
+        grid_tick.checked_sub(tick_interval.mul_f64(-offset)).unwrap_or(grid_tick)
+    } else {
+        grid_tick + tick_interval.mul_f64(offset)
+    }
+}
+
+/// Length multiplier of a step across tracks. Tracks left at 1.0 don't count,
+/// so a single track can stretch or shorten the step; if several do, the longest wins.
+fn effective_step_length(step_lengths: &[Vec<f32>], step_idx: usize) -> f32 {
+    step_lengths.iter()
+        .filter_map(|row| row.get(step_idx).copied())
+        .filter(|&length| length != 1.0)
+        .reduce(f32::max)
+        .unwrap_or(1.0)
+}
+
+/// Step gate conditions: (track, step) -> (required track, step offset)
+pub type StepGates = HashMap<(usize, usize), (usize, i32)>;
+
+/// Whether a gated step may fire: the required track must be active and unmuted
+/// at the offset step, counted around the end of the pattern. Ungated steps always may.
+fn gate_open(pattern: &[Vec<bool>], muted_tracks: &HashSet<usize>, gates: &StepGates, track_idx: usize, step_idx: usize) -> bool {
+    let Some(&(required_track, offset)) = gates.get(&(track_idx, step_idx)) else {
+        return true;
+    };
+    let Some(row) = pattern.get(required_track).filter(|row| !row.is_empty()) else {
+        return false;
+    };
+    let required_step = (step_idx as i64 + offset as i64).rem_euclid(row.len() as i64) as usize;
+    row[required_step] && !muted_tracks.contains(&required_track)
+}
+
+/// Triggers of one step: every active, unmuted track whose gate is open
+fn step_triggers(pattern: &[Vec<bool>], muted_tracks: &HashSet<usize>, gates: &StepGates, step_idx: usize) -> Vec<TriggerEvent> {
+    pattern.iter()
+        .enumerate()
+        .filter(|(track_idx, track)| {
+            !muted_tracks.contains(track_idx)
+                && track.get(step_idx).copied().unwrap_or(false)
+                && gate_open(pattern, muted_tracks, gates, *track_idx, step_idx)
+        })
+        .map(|(track_idx, _)| TriggerEvent { track_idx, step_idx })
+        .collect()
+}
+
+/// Step retriggers: (track, step) -> (trigger count, interval in ms; 0 spreads them over the step)
+pub type StepRetriggers = HashMap<(usize, usize), (u32, f32)>;
+
+/// Delays of a step's follow-up triggers after the first, kept inside the step
+fn retrigger_delays(count: u32, interval_ms: f32, step_duration: Duration) -> Vec<Duration> {
+    if count <= 1 {
+        return Vec::new();
+    }
+    let spread = step_duration / count;
+    let interval = if interval_ms > 0.0 {
+        Duration::from_secs_f64(interval_ms as f64 / 1000.0).min(spread)
+    } else {
+        spread
+    };
+    (1..count).map(|n| interval * n).collect()
+}
+
+/// Callback invoked from the sequencer thread for every trigger
+pub type TriggerListener = Box<dyn Fn(TriggerEvent) + Send + Sync + 'static>;
+
+/// Registered listeners; removed entries are left as None so ids stay stable.
+/// Shared so the thread can call them without holding the list's lock.
+type ListenerList = Arc<Mutex<Vec<Option<Arc<dyn Fn(TriggerEvent) + Send + Sync + 'static>>>>>;
+
+impl From<SendError<SequencerCommand>> for SequencerError {
+    fn from(err: SendError<SequencerCommand>) -> Self {
+        Self::ChannelError(err.to_string())
+    }
+}
+
 /// Commands that can be sent to the sequencer thread
 #[derive(Debug)]
 enum SequencerCommand {
     Start,
     Stop,
     SetBPM(u32),
+    /// The subdivision changed; wakes the thread so the next step is timed with it
+    SetSubdivision(Subdivision),
+    SetPattern(Vec<Vec<bool>>),
+    /// Replace the step length multipliers of one track
+    SetStepLengths(usize, Vec<f32>),
+    /// Stop or resume generating triggers for a track
+    SetTrackMuted(usize, bool),
+    /// Set or clear the gate of one step
+    SetStepGate(usize, usize, Option<(usize, i32)>),
+    /// Set how often one step fires and how far apart (track, step, count, interval ms)
+    SetStepRetrigger(usize, usize, u32, f32),
+    /// The sample clock reached a step boundary; stale generations are ignored
+    ClockTick(u64),
+    /// An external clock pulse arrived; carries its number since playback started
+    ExternalClockTick(usize),
     Quit,
 }
 
 /// The main Sequencer struct that handles timing and step progression
 pub struct Sequencer {
     bpm: Arc<Mutex<u32>>,
-    current_step: Arc<Mutex<usize>>,
+    current_step: Arc<AtomicUsize>,
     is_playing: Arc<Mutex<bool>>,
-    pattern: Vec<Vec<bool>>, // [track][step]
+    /// Pattern last handed to the sequencer thread - [track][step]
+    pattern: Arc<Mutex<Vec<Vec<bool>>>>,
     cmd_sender: Sender<SequencerCommand>,
     event_receiver: Receiver<Vec<TriggerEvent>>,
     thread_handle: Option<JoinHandle<()>>,
     event_bus: SharedEventBus,
+    listeners: ListenerList,
+    /// Bars started since playback began
+    bar_count: Arc<Mutex<u32>>,
+    steps_per_bar: Arc<AtomicUsize>,
+    /// When the current step started, in nanoseconds since the Unix epoch
+    last_tick_nanos: Arc<AtomicU64>,
+    /// Length of the current step in nanoseconds
+    tick_interval_nanos: Arc<AtomicU64>,
+    /// Note value of one step
+    subdivision: Arc<Mutex<Subdivision>>,
+    /// How far odd steps are pushed towards the next step (0.0 = straight, 1.0 = half a step)
+    swing: Arc<Mutex<f32>>,
+    /// Fixed per-step offsets that replace swing when not empty
+    groove: Arc<Mutex<Vec<f32>>>,
+    /// Step length multipliers - [track][step], missing entries count as 1.0
+    step_lengths: Arc<Mutex<Vec<Vec<f32>>>>,
+    /// Tracks last sent to the sequencer thread as muted
+    muted_tracks: Mutex<HashSet<usize>>,
+    /// Gates last sent to the sequencer thread
+    step_gates: Mutex<StepGates>,
+    /// Retriggers last sent to the sequencer thread
+    step_retriggers: Mutex<StepRetriggers>,
+    /// Output the step timing is taken from in sample-accurate mode
+    stream_handle: Option<OutputStreamHandle>,
+    /// When recent steps fired, recorded while a timing measurement runs
+    step_times: Arc<Mutex<Vec<Instant>>>,
+    /// Number of step times still to record; 0 when no measurement runs
+    step_times_wanted: Arc<AtomicUsize>,
+    /// Steps follow `external_clock_tick` pulses instead of the tempo
+    external_clock: Arc<AtomicBool>,
+    /// External clock pulses received since playback started
+    clock_ticks: Arc<AtomicUsize>,
 }
 
 impl Sequencer {
     /// Validate that the pattern is consistent and usable
-    pub fn validate_pattern(pattern: &Vec<Vec<bool>>) -> Result<(), &'static str> {
-        // Check if pattern is empty
-        if pattern.is_empty() {
-            return Err("Pattern cannot be empty");
-        }
-        
-        // Check if any tracks are empty
-        if pattern.iter().any(|track| track.is_empty()) {
-            return Err("Pattern cannot have empty tracks");
-        }
-        
-        // Check if all tracks have the same length
-        let first_track_len = pattern[0].len();
-        for track in pattern.iter().skip(1) {
+    pub fn validate_pattern(pattern: &Vec<Vec<bool>>) -> Result<(), SequencerError> {
+        let first_track_len = pattern.first().ok_or(SequencerError::EmptyPattern)?.len();
+        for (track_idx, track) in pattern.iter().enumerate() {
+            if track.is_empty() {
+                return Err(SequencerError::EmptyTrack(track_idx));
+            }
             if track.len() != first_track_len {
-                return Err("All tracks must have the same length");
+                return Err(SequencerError::UnequalTrackLengths { expected: first_track_len, got: track.len(), track_idx });
             }
         }
-        
         Ok(())
     }
     
@@ -70,6 +346,21 @@ impl Sequencer {
 
     /// Create a new sequencer with the given BPM, pattern data, and event bus
     pub fn new_with_event_bus(bpm: u32, pattern: Vec<Vec<bool>>, event_bus: SharedEventBus) -> Self {
+        Self::spawn(bpm, pattern, event_bus, None)
+    }
+
+    /// Create a sequencer whose steps are timed by silence queued on an audio sink
+    /// rather than by thread sleeps, so step boundaries fall on exact sample positions
+    pub fn new_sample_accurate(bpm: u32, pattern: Vec<Vec<bool>>, stream_handle: OutputStreamHandle) -> Self {
+        Self::spawn(bpm, pattern, Arc::new(EventBus::new()), Some(stream_handle))
+    }
+
+    fn spawn(
+        bpm: u32,
+        pattern: Vec<Vec<bool>>,
+        event_bus: SharedEventBus,
+        stream_handle: Option<OutputStreamHandle>,
+    ) -> Self {
         // In a production implementation, consider validating the pattern here
         // and responding to errors appropriately.
         // For now, we'll assume the pattern is valid.
@@ -77,96 +368,281 @@ impl Sequencer {
         let (cmd_sender, cmd_receiver) = bounded::<SequencerCommand>(32);
         let (event_sender, event_receiver) = bounded::<Vec<TriggerEvent>>(32);
         let bpm = Arc::new(Mutex::new(bpm));
-        let current_step = Arc::new(Mutex::new(0));
+        let current_step = Arc::new(AtomicUsize::new(0));
         let is_playing = Arc::new(Mutex::new(false));
+        let listeners: ListenerList = Arc::new(Mutex::new(Vec::new()));
+        let bar_count = Arc::new(Mutex::new(0));
+        let steps_per_bar = Arc::new(AtomicUsize::new(DEFAULT_STEPS_PER_BAR));
+        let last_tick_nanos = Arc::new(AtomicU64::new(0));
+        let subdivision = Arc::new(Mutex::new(Subdivision::default()));
+        let tick_interval_nanos = Arc::new(AtomicU64::new(step_interval(*bpm.lock().unwrap(), Subdivision::default()).as_nanos() as u64));
+        let swing = Arc::new(Mutex::new(0.0));
+        let groove: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::new()));
+        let step_lengths: Arc<Mutex<Vec<Vec<f32>>>> = Arc::new(Mutex::new(Vec::new()));
+        let external_clock = Arc::new(AtomicBool::new(false));
+        let step_times: Arc<Mutex<Vec<Instant>>> = Arc::new(Mutex::new(Vec::new()));
+        let step_times_wanted = Arc::new(AtomicUsize::new(0));
         
         let bpm_clone = Arc::clone(&bpm);
         let current_step_clone = Arc::clone(&current_step);
         let is_playing_clone = Arc::clone(&is_playing);
-        let pattern_clone = pattern.clone();
+        let mut pattern_clone = pattern.clone();
         let event_bus_clone = Arc::clone(&event_bus);
+        let listeners_clone = Arc::clone(&listeners);
+        let bar_count_clone = Arc::clone(&bar_count);
+        let steps_per_bar_clone = Arc::clone(&steps_per_bar);
+        let last_tick_nanos_clone = Arc::clone(&last_tick_nanos);
+        let tick_interval_nanos_clone = Arc::clone(&tick_interval_nanos);
+        let swing_clone = Arc::clone(&swing);
+        let subdivision_clone = Arc::clone(&subdivision);
+        let groove_clone = Arc::clone(&groove);
+        let step_lengths_clone = Arc::clone(&step_lengths);
+        let external_clock_clone = Arc::clone(&external_clock);
+        let step_times_clone = Arc::clone(&step_times);
+        let step_times_wanted_clone = Arc::clone(&step_times_wanted);
+        let clock_sender = cmd_sender.clone();
+        let mut sample_clock = stream_handle.clone().map(SampleClock::new);
         
         // Spawn the sequencer thread
         let thread_handle = thread::spawn(move || {
-            let mut last_tick = Instant::now();
+            // Step timing is absolute: each step's grid position is the previous one plus its
+            // length, so sleep overshoot on one step doesn't push back the following ones
+            let mut grid_tick = Instant::now();
+            // Steps played since playback started (not wrapped to the pattern length)
+            let mut step_count: u64 = 0;
+            // Tracks whose steps don't trigger
+            let mut muted_tracks: HashSet<usize> = HashSet::new();
+            // Steps that only fire when another track is active
+            let mut step_gates = StepGates::new();
+            // Steps that fire more than once
+            let mut step_retriggers = StepRetriggers::new();
+            // Follow-up triggers of retriggered steps, with when they're due
+            let mut pending_retriggers: Vec<(Instant, TriggerEvent)> = Vec::new();
+            let fire = |trigger: TriggerEvent| {
+                // Listeners may add or remove listeners, so call them from a snapshot
+                let listeners: Vec<_> = listeners_clone.lock().unwrap().iter().flatten().cloned().collect();
+                for listener in listeners {
+                    listener(trigger);
+                }
+                // Slow UI listeners mustn't hold up the clock; forwarders that play the trigger,
+                // like the audio connector, subscribe immediately and still get it in order
+                event_bus_clone.emit_async(TrackerEvent::StepTriggered(trigger.track_idx, trigger.step_idx));
+            };
+            // Bumped on every start/stop so ticks queued by an earlier run are ignored
+            let mut clock_generation: u64 = 0;
             
             loop {
-                // Process incoming commands
-                if let Ok(cmd) = cmd_receiver.try_recv() {
-                    match cmd {
-                        SequencerCommand::Start => {
-                            *is_playing_clone.lock().unwrap() = true;
-                            debug!("Sequencer started");
-                            // Emit event for playback state change
-                            event_bus_clone.emit(TrackerEvent::PlaybackStateChanged(true));
-                        },
-                        SequencerCommand::Stop => {
-                            *is_playing_clone.lock().unwrap() = false;
-                            *current_step_clone.lock().unwrap() = 0;
-                            debug!("Sequencer stopped");
-                            // Emit event for playback state change
-                            event_bus_clone.emit(TrackerEvent::PlaybackStateChanged(false));
-                        },
-                        SequencerCommand::SetBPM(new_bpm) => {
-                            *bpm_clone.lock().unwrap() = new_bpm;
-                            debug!("BPM set to {}", new_bpm);
-                            // Emit event for BPM change
-                            event_bus_clone.emit(TrackerEvent::BpmChanged(new_bpm));
-                        },
-                        SequencerCommand::Quit => {
-                            debug!("Sequencer thread shutting down");
-                            break;
-                        }
+                // Fire retriggers that are due; tracks muted since their step stay silent
+                let now = Instant::now();
+                pending_retriggers.retain(|&(due, trigger)| {
+                    if due > now {
+                        return true;
+                    }
+                    if !muted_tracks.contains(&trigger.track_idx) {
+                        fire(trigger);
                     }
+                    false
+                });
+                
+                let subdivision = *subdivision_clone.lock().unwrap();
+                let tick_interval = step_interval(*bpm_clone.lock().unwrap(), subdivision);
+                // Steps every track has; with none the thread idles until a usable pattern arrives
+                let pattern_len = pattern_clone.iter().map(Vec::len).min().unwrap_or(0) as u64;
+                let next_tick = if pattern_len > 0 {
+                    let offset = step_offset(step_count % pattern_len, *swing_clone.lock().unwrap(), &groove_clone.lock().unwrap());
+                    swung_tick(grid_tick, tick_interval, offset)
+                } else {
+                    grid_tick
+                };
+                
+                // Wait for a command or until the next step is due; the sample clock
+                // and the external clock announce due steps with commands of their own
+                let clocked = sample_clock.is_some() || external_clock_clone.load(Ordering::Relaxed);
+                let timeout = if !clocked && pattern_len > 0 && *is_playing_clone.lock().unwrap() {
+                    next_tick.saturating_duration_since(Instant::now())
+                } else {
+                    IDLE_WAIT
+                };
+                let timeout = pending_retriggers.iter()
+                    .map(|(due, _)| due.saturating_duration_since(Instant::now()))
+                    .fold(timeout, Duration::min);
+                
+                let mut clock_ticked = false;
+                match cmd_receiver.recv_timeout(timeout) {
+                    Ok(cmd) => {
+                        match cmd {
+                            SequencerCommand::Start => {
+                                *is_playing_clone.lock().unwrap() = true;
+                                grid_tick = Instant::now();
+                                step_count = 0;
+                                clock_generation += 1;
+                                pending_retriggers.clear();
+                                if let Some(clock) = &mut sample_clock {
+                                    if let Err(e) = clock.restart(grid_tick) {
+                                        warn!("Sample clock could not start: {}", e);
+                                    }
+                                    let sender = clock_sender.clone();
+                                    let generation = clock_generation;
+                                    clock.schedule(grid_tick, Box::new(move || {
+                                        let _ = sender.try_send(SequencerCommand::ClockTick(generation));
+                                    }));
+                                }
+                                debug!("Sequencer started");
+                                // Emit event for playback state change
+                                event_bus_clone.emit(TrackerEvent::PlaybackStateChanged(true));
+                            },
+                            SequencerCommand::Stop => {
+                                *is_playing_clone.lock().unwrap() = false;
+                                current_step_clone.store(0, Ordering::SeqCst);
+                                *bar_count_clone.lock().unwrap() = 0;
+                                step_count = 0;
+                                clock_generation += 1;
+                                pending_retriggers.clear();
+                                if let Some(clock) = &mut sample_clock {
+                                    clock.stop();
+                                }
+                                debug!("Sequencer stopped");
+                                // Emit event for playback state change
+                                event_bus_clone.emit(TrackerEvent::PlaybackStateChanged(false));
+                            },
+                            SequencerCommand::SetBPM(new_bpm) => {
+                                // The next step keeps its grid position; later steps follow the new rate
+                                *bpm_clone.lock().unwrap() = new_bpm;
+                                debug!("BPM set to {}", new_bpm);
+                                // Emit event for BPM change
+                                event_bus_clone.emit(TrackerEvent::BpmChanged(new_bpm));
+                            },
+                            SequencerCommand::SetSubdivision(subdivision) => {
+                                // Like a tempo change, the next step keeps its grid position
+                                debug!("Subdivision set to 1/{}", subdivision.note_value());
+                            },
+                            SequencerCommand::SetPattern(pattern) => {
+                                // Playback continues from the same position, wrapped to the new length
+                                pattern_clone = pattern;
+                                debug!("Pattern set to {} steps", pattern_clone.first().map_or(0, |t| t.len()));
+                            },
+                            SequencerCommand::SetStepLengths(track_idx, lengths) => {
+                                let mut step_lengths = step_lengths_clone.lock().unwrap();
+                                if step_lengths.len() <= track_idx {
+                                    step_lengths.resize(track_idx + 1, Vec::new());
+                                }
+                                step_lengths[track_idx] = lengths;
+                                debug!("Step lengths set for track {}", track_idx);
+                            },
+                            SequencerCommand::SetTrackMuted(track_idx, muted) => {
+                                if muted {
+                                    muted_tracks.insert(track_idx);
+                                } else {
+                                    muted_tracks.remove(&track_idx);
+                                }
+                                debug!("Track {} {}", track_idx, if muted { "muted" } else { "unmuted" });
+                            },
+                            SequencerCommand::SetStepGate(track_idx, step_idx, gate) => {
+                                match gate {
+                                    Some(gate) => step_gates.insert((track_idx, step_idx), gate),
+                                    None => step_gates.remove(&(track_idx, step_idx)),
+                                };
+                                debug!("Gate on track {} step {} set to {:?}", track_idx, step_idx, gate);
+                            },
+                            SequencerCommand::SetStepRetrigger(track_idx, step_idx, count, interval_ms) => {
+                                if count > 1 {
+                                    step_retriggers.insert((track_idx, step_idx), (count, interval_ms));
+                                } else {
+                                    step_retriggers.remove(&(track_idx, step_idx));
+                                }
+                                debug!("Retrigger on track {} step {} set to {} x {} ms", track_idx, step_idx, count, interval_ms);
+                            },
+                            SequencerCommand::ClockTick(generation) => {
+                                clock_ticked = generation == clock_generation
+                                    && !external_clock_clone.load(Ordering::Relaxed);
+                            },
+                            SequencerCommand::ExternalClockTick(tick) => {
+                                clock_ticked = tick.is_multiple_of(subdivision.midi_clocks_per_step());
+                            },
+                            SequencerCommand::Quit => {
+                                debug!("Sequencer thread shutting down");
+                                break;
+                            }
+                        }
+                        if !clock_ticked {
+                            continue;
+                        }
+                    },
+                    Err(RecvTimeoutError::Disconnected) => break,
+                    Err(RecvTimeoutError::Timeout) => {},
                 }
                 
-                if *is_playing_clone.lock().unwrap() {
-                    // Calculate tick interval based on BPM
-                    let bpm = *bpm_clone.lock().unwrap();
-                    let tick_interval = Duration::from_millis((60_000 / bpm / 4) as u64); // 16th notes
+                // Hold the playing flag while the step is advanced so stop() can't interleave with it
+                let playing = is_playing_clone.lock().unwrap();
+                let step_due = if clocked || external_clock_clone.load(Ordering::Relaxed) {
+                    clock_ticked
+                } else {
+                    Instant::now() >= next_tick
+                };
+                if *playing && step_due && pattern_len > 0 {
+                    let current_step_idx = (step_count % pattern_len) as usize;
+                    current_step_clone.store(current_step_idx, Ordering::SeqCst);
+                    let step_length = effective_step_length(&step_lengths_clone.lock().unwrap(), current_step_idx);
+                    let step_duration = tick_interval.mul_f64(step_length as f64);
+                    grid_tick += step_duration;
+                    last_tick_nanos_clone.store(now_nanos(), Ordering::Relaxed);
+                    if step_times_wanted_clone.load(Ordering::Relaxed) > 0 {
+                        step_times_clone.lock().unwrap().push(Instant::now());
+                        step_times_wanted_clone.fetch_sub(1, Ordering::Relaxed);
+                    }
+                    tick_interval_nanos_clone.store(step_duration.as_nanos() as u64, Ordering::Relaxed);
+                    
+                    let steps_per_bar = steps_per_bar_clone.load(Ordering::Relaxed).max(1) as u64;
+                    let bar_started = step_count.is_multiple_of(steps_per_bar).then(|| {
+                        let mut bars = bar_count_clone.lock().unwrap();
+                        *bars += 1;
+                        *bars - 1
+                    });
+                    step_count += 1;
+                    
+                    // Queue the silence up to the next step's swung position
+                    if let Some(clock) = &mut sample_clock {
+                        let next_step = step_count % pattern_len;
+                        let offset = step_offset(next_step, *swing_clone.lock().unwrap(), &groove_clone.lock().unwrap());
+                        let target = swung_tick(grid_tick, tick_interval, offset);
+                        let sender = clock_sender.clone();
+                        let generation = clock_generation;
+                        clock.schedule(target, Box::new(move || {
+                            let _ = sender.try_send(SequencerCommand::ClockTick(generation));
+                        }));
+                    }
+                    
+                    // Subscribers and listeners may call back into the sequencer,
+                    // so no lock is held while they run
+                    drop(playing);
                     
-                    let now = Instant::now();
-                    let elapsed = now.duration_since(last_tick);
+                    // Announce the downbeat before any of its triggers
+                    if let Some(bar) = bar_started {
+                        event_bus_clone.emit(TrackerEvent::BarStarted(bar));
+                    }
                     
-                    // If it's time for a new step
-                    if elapsed >= tick_interval {
-                        last_tick = now;
+                    // Calculate triggers for current step
+                    let triggers = step_triggers(&pattern_clone, &muted_tracks, &step_gates, current_step_idx);
+                    let step_start = Instant::now();
+                    for &trigger in &triggers {
+                        debug!("Trigger track {} on step {}", trigger.track_idx, current_step_idx);
                         
-                        // Get current step
-                        let mut step = current_step_clone.lock().unwrap();
-                        let current_step_idx = *step;
+                        // Notify listeners directly from this thread
+                        fire(trigger);
                         
-                        // Calculate triggers for current step
-                        let mut triggers = Vec::new();
-                        for (track_idx, track) in pattern_clone.iter().enumerate() {
-                            if current_step_idx < track.len() && track[current_step_idx] {
-                                debug!("Trigger track {} on step {}", track_idx, current_step_idx);
-                                // Create a trigger event
-                                let trigger = TriggerEvent {
-                                    track_idx,
-                                    step_idx: current_step_idx,
-                                };
-                                
-                                // Add to trigger list
-                                triggers.push(trigger);
-                                
-                                // Emit event through event bus
-                                event_bus_clone.emit(TrackerEvent::StepTriggered(track_idx, current_step_idx));
+                        if let Some(&(count, interval_ms)) = step_retriggers.get(&(trigger.track_idx, current_step_idx)) {
+                            for delay in retrigger_delays(count, interval_ms, step_duration) {
+                                pending_retriggers.push((step_start + delay, trigger));
                             }
                         }
-                        
-                        // Send trigger events if any through the channel (legacy method)
-                        if !triggers.is_empty() {
-                            let _ = event_sender.send(triggers);
-                        }
-                        
-                        // Advance to next step
-                        *step = (current_step_idx + 1) % pattern_clone[0].len();
+                    }
+                    
+                    // Send trigger events if any through the channel (legacy method).
+                    // Nobody may be polling tick(), so drop batches rather than block when it's full.
+                    if !triggers.is_empty() {
+                        let _ = event_sender.try_send(triggers);
                     }
                 }
-                
-                // Sleep a bit to avoid maxing out CPU
-                thread::sleep(Duration::from_millis(1));
             }
         });
         
@@ -174,50 +650,87 @@ impl Sequencer {
             bpm,
             current_step,
             is_playing,
-            pattern,
+            pattern: Arc::new(Mutex::new(pattern)),
             cmd_sender,
             event_receiver,
             thread_handle: Some(thread_handle),
             event_bus,
+            listeners,
+            bar_count,
+            steps_per_bar,
+            last_tick_nanos,
+            tick_interval_nanos,
+            subdivision,
+            swing,
+            groove,
+            step_lengths,
+            muted_tracks: Mutex::new(HashSet::new()),
+            step_gates: Mutex::new(StepGates::new()),
+            step_retriggers: Mutex::new(StepRetriggers::new()),
+            stream_handle,
+            step_times,
+            step_times_wanted,
+            external_clock,
+            clock_ticks: Arc::new(AtomicUsize::new(0)),
         }
     }
     
     /// Start the sequencer playback
     pub fn start(&self) {
-        let bpm = Arc::clone(&self.bpm); // Clone Arc for thread
-        let event_bus = self.event_bus.clone();
-        let pattern = self.pattern.clone();
-    
-        let steps_per_beat = 4;
-    
-        std::thread::spawn(move || {
-            let mut current_step = 0;
+        self.clock_ticks.store(0, Ordering::SeqCst);
+        let _ = self.cmd_sender.send(SequencerCommand::Start);
+    }
+        
+    /// Stop the sequencer playback and rewind to the first step
+    pub fn stop(&self) {
+        // Update the shared state right away so callers see the stop immediately;
+        // the thread still handles the command to emit the state change event
+        {
+            let mut playing = self.is_playing.lock().unwrap();
+            *playing = false;
+            self.current_step.store(0, Ordering::SeqCst);
+            *self.bar_count.lock().unwrap() = 0;
+        }
+        let _ = self.cmd_sender.send(SequencerCommand::Stop);
+    }
     
-            loop {
-                let beats_per_minute = *bpm.lock().unwrap() as f64; // <-- HERE!
-                let step_interval = std::time::Duration::from_secs_f64(60.0 / (beats_per_minute * steps_per_beat as f64));
+    /// Number of bars started since playback began
+    pub fn bar_count(&self) -> u32 {
+        *self.bar_count.lock().unwrap()
+    }
     
-                let start_time = std::time::Instant::now();
+    /// Set how many steps make up one bar for `BarStarted` events
+    pub fn set_steps_per_bar(&self, steps: usize) {
+        self.steps_per_bar.store(steps.max(1), Ordering::Relaxed);
+    }
     
-                for (track_idx, steps) in pattern.iter().enumerate() {
-                    if steps[current_step] {
-                        event_bus.emit(TrackerEvent::StepTriggered(track_idx, current_step));
-                    }
-                }
+    /// Change the note value of a step. Steps per bar are scaled to match,
+    /// so 16 steps per bar of 16ths become 8 of 8ths.
+    pub fn set_subdivision(&self, subdivision: Subdivision) {
+        let previous = std::mem::replace(&mut *self.subdivision.lock().unwrap(), subdivision);
+        let steps_per_bar = self.steps_per_bar.load(Ordering::Relaxed) * subdivision.factor() as usize / previous.factor() as usize;
+        self.steps_per_bar.store(steps_per_bar.max(1), Ordering::Relaxed);
+        let _ = self.cmd_sender.send(SequencerCommand::SetSubdivision(subdivision));
+    }
     
-                current_step = (current_step + 1) % pattern[0].len();
+    /// Note value of one step
+    pub fn get_subdivision(&self) -> Subdivision {
+        *self.subdivision.lock().unwrap()
+    }
     
-                let elapsed = start_time.elapsed();
-                if elapsed < step_interval {
-                    std::thread::sleep(step_interval - elapsed);
-                }
-            }
-        });
+    /// Register a callback that is called from the sequencer thread for every trigger.
+    /// Returns an id for `remove_listener`.
+    pub fn add_listener(&self, listener: TriggerListener) -> usize {
+        let mut listeners = self.listeners.lock().unwrap();
+        listeners.push(Some(Arc::from(listener)));
+        listeners.len() - 1
     }
-        
-    /// Stop the sequencer playback
-    pub fn stop(&self) {
-        let _ = self.cmd_sender.send(SequencerCommand::Stop);
+    
+    /// Unregister a callback added with `add_listener`
+    pub fn remove_listener(&self, id: usize) {
+        if let Some(slot) = self.listeners.lock().unwrap().get_mut(id) {
+            *slot = None;
+        }
     }
     
     /// Check if the sequencer is currently playing
@@ -225,17 +738,181 @@ impl Sequencer {
         *self.is_playing.lock().unwrap()
     }
     
-    /// Get the current step of the sequencer
+    /// Get the step currently playing (0 while stopped)
     pub fn current_step(&self) -> usize {
-        *self.current_step.lock().unwrap()
+        self.current_step.load(Ordering::SeqCst)
+    }
+    
+    /// Fraction of the current step that has elapsed, from 0.0 up to (but not including) 1.0.
+    /// Always 0.0 while stopped.
+    pub fn step_progress(&self) -> f32 {
+        let last_tick = self.last_tick_nanos.load(Ordering::Relaxed);
+        let interval = self.tick_interval_nanos.load(Ordering::Relaxed);
+        if !self.is_playing() || last_tick == 0 || interval == 0 {
+            return 0.0;
+        }
+        let elapsed = now_nanos().saturating_sub(last_tick);
+        (elapsed as f64 / interval as f64).min(0.999) as f32
+    }
+    
+    /// Record when each of the next `max_steps` steps fires, discarding earlier measurements
+    pub fn start_timing_measurement(&self, max_steps: usize) {
+        self.step_times.lock().unwrap().clear();
+        self.step_times_wanted.store(max_steps, Ordering::Relaxed);
+    }
+    
+    /// Statistics of the intervals between the steps recorded since `start_timing_measurement`
+    pub fn measure_timing_accuracy(&self) -> TimingStats {
+        TimingStats::from_timestamps(&self.step_times.lock().unwrap())
+    }
+    
+    /// Number of steps recorded since `start_timing_measurement`
+    pub fn timing_sample_count(&self) -> usize {
+        self.step_times.lock().unwrap().len()
+    }
+    
+    /// Advance steps from `external_clock_tick` pulses (MIDI clock, 0xF8) instead of the tempo
+    pub fn enable_external_clock_mode(&self) {
+        self.external_clock.store(true, Ordering::Relaxed);
+    }
+    
+    /// Whether steps follow an external clock
+    pub fn is_external_clock(&self) -> bool {
+        self.external_clock.load(Ordering::Relaxed)
+    }
+    
+    /// Feed one external clock pulse; every `Subdivision::midi_clocks_per_step`th pulse plays a step,
+    /// starting with the first pulse after `start`. Ignored unless in external clock mode.
+    pub fn external_clock_tick(&self) {
+        if !self.is_external_clock() {
+            return;
+        }
+        let tick = self.clock_ticks.fetch_add(1, Ordering::SeqCst);
+        let _ = self.cmd_sender.send(SequencerCommand::ExternalClockTick(tick));
     }
     
     /// Set the BPM (tempo) of the sequencer
     pub fn set_bpm(&self, bpm: u32) {
+        if self.is_external_clock() {
+            debug!("Ignoring BPM change while following an external clock");
+            return;
+        }
         let _ = self.cmd_sender.send(SequencerCommand::SetBPM(bpm));
     }
     
+    /// Replace the pattern without stopping playback
+    pub fn set_pattern(&mut self, pattern: Vec<Vec<bool>>) -> Result<(), SequencerError> {
+        Self::validate_pattern(&pattern)?;
+        *self.pattern.lock().unwrap() = pattern.clone();
+        self.cmd_sender.send(SequencerCommand::SetPattern(pattern))?;
+        Ok(())
+    }
+    
+    /// Set the swing amount, clamped to 0.0 (straight) ..= 1.0 (odd steps half a step late)
+    pub fn set_swing(&self, swing: f32) {
+        *self.swing.lock().unwrap() = swing.clamp(0.0, 1.0);
+    }
+    
+    /// Get the current swing amount
+    pub fn get_swing(&self) -> f32 {
+        *self.swing.lock().unwrap()
+    }
+    
+    /// Play steps at fixed offsets from the grid, in fractions of a step clamped to
+    /// `MAX_GROOVE_OFFSET` either way. The template repeats if shorter than the pattern;
+    /// an empty template goes back to swing.
+    pub fn set_groove_template(&self, offsets: Vec<f32>) {
+        *self.groove.lock().unwrap() = offsets.into_iter()
+            .map(|offset| offset.clamp(-MAX_GROOVE_OFFSET, MAX_GROOVE_OFFSET))
+            .collect();
+    }
+    
+    /// Get the groove template in use, empty when steps follow swing
+    pub fn get_groove_template(&self) -> Vec<f32> {
+        self.groove.lock().unwrap().clone()
+    }
+    
+    /// Set the length multipliers of one track's steps (0.5 = half a step, 2.0 = two steps).
+    /// Takes effect once the sequencer thread handles the command.
+    pub fn set_step_lengths(&self, track_idx: usize, lengths: Vec<f32>) -> Result<(), SequencerError> {
+        if let Some(&length) = lengths.iter().find(|length| !length.is_finite() || **length <= 0.0) {
+            return Err(SequencerError::InvalidStepLength(length));
+        }
+        self.cmd_sender.send(SequencerCommand::SetStepLengths(track_idx, lengths))?;
+        Ok(())
+    }
+    
+    /// Mute or unmute a track. Muted tracks produce no triggers or `StepTriggered` events.
+    /// Takes effect once the sequencer thread handles the command.
+    pub fn set_track_muted(&self, track_idx: usize, muted: bool) {
+        let mut muted_tracks = self.muted_tracks.lock().unwrap();
+        if muted {
+            muted_tracks.insert(track_idx);
+        } else {
+            muted_tracks.remove(&track_idx);
+        }
+        let _ = self.cmd_sender.send(SequencerCommand::SetTrackMuted(track_idx, muted));
+    }
+    
+    /// Make a step fire only when `required_track` is active `offset` steps away
+    /// (wrapping around the pattern), or clear its gate with None.
+    /// Takes effect once the sequencer thread handles the command.
+    pub fn set_step_gate(&self, track_idx: usize, step_idx: usize, gate: Option<(usize, i32)>) {
+        let mut step_gates = self.step_gates.lock().unwrap();
+        match gate {
+            Some(gate) => step_gates.insert((track_idx, step_idx), gate),
+            None => step_gates.remove(&(track_idx, step_idx)),
+        };
+        let _ = self.cmd_sender.send(SequencerCommand::SetStepGate(track_idx, step_idx, gate));
+    }
+    
+    /// Fire a step `count` times, `interval_ms` apart (0 spreads them evenly over the
+    /// step). Repeats never run past the step; a count of 1 or less clears the retrigger.
+    pub fn set_step_retrigger(&self, track_idx: usize, step_idx: usize, count: u32, interval_ms: f32) {
+        let mut step_retriggers = self.step_retriggers.lock().unwrap();
+        if count > 1 {
+            step_retriggers.insert((track_idx, step_idx), (count, interval_ms));
+        } else {
+            step_retriggers.remove(&(track_idx, step_idx));
+        }
+        let _ = self.cmd_sender.send(SequencerCommand::SetStepRetrigger(track_idx, step_idx, count, interval_ms));
+    }
+    
+    /// Trigger count and interval of a step; (1, 0.0) when it fires once
+    pub fn get_step_retrigger(&self, track_idx: usize, step_idx: usize) -> (u32, f32) {
+        self.step_retriggers.lock().unwrap().get(&(track_idx, step_idx)).copied().unwrap_or((1, 0.0))
+    }
+    
+    /// Whether a track is muted
+    pub fn is_track_muted(&self, track_idx: usize) -> bool {
+        self.muted_tracks.lock().unwrap().contains(&track_idx)
+    }
+    
+    /// Length multiplier the sequencer applies to a step after combining all tracks
+    pub fn get_step_length(&self, step_idx: usize) -> f32 {
+        effective_step_length(&self.step_lengths.lock().unwrap(), step_idx)
+    }
+    
+    /// Time in milliseconds from the start of the pattern at which `step_idx` fires.
+    /// Odd steps are delayed by `swing * 0.5` of a step, unless a groove template is set.
+    pub fn get_step_time_ms(&self, step_idx: usize) -> f64 {
+        let base_interval_ms = step_interval(self.get_bpm(), self.get_subdivision()).as_secs_f64() * 1000.0;
+        let step_lengths = self.step_lengths.lock().unwrap();
+        let grid_steps: f64 = (0..step_idx).map(|step| effective_step_length(&step_lengths, step) as f64).sum();
+        let offset = step_offset(step_idx as u64, self.get_swing(), &self.groove.lock().unwrap());
+        (grid_steps + offset) * base_interval_ms
+    }
+    
+    /// Length of one loop of the pattern in milliseconds
+    pub fn get_pattern_duration_ms(&self) -> f64 {
+        let steps = self.pattern.lock().unwrap().first().map_or(0, |track| track.len());
+        let step_lengths = self.step_lengths.lock().unwrap();
+        let grid_steps: f64 = (0..steps).map(|step| effective_step_length(&step_lengths, step) as f64).sum();
+        grid_steps * step_interval(self.get_bpm(), self.get_subdivision()).as_secs_f64() * 1000.0
+    }
+    
     /// Process any trigger events that have occurred since the last call
+    #[deprecated(note = "register a callback with `add_listener` instead of polling")]
     pub fn tick(&self) -> Vec<TriggerEvent> {
         match self.event_receiver.try_recv() {
             Ok(events) => events,
@@ -243,14 +920,96 @@ impl Sequencer {
         }
     }
     
+    /// Compute the next `steps` steps from the start of the pattern in the calling thread,
+    /// without timing, listeners or events. Uses the pattern, mutes and gates last set on
+    /// this sequencer. Every step is listed, with an empty Vec when nothing triggers.
+    pub fn run_headless(&self, steps: usize) -> Vec<(usize, Vec<TriggerEvent>)> {
+        let pattern = self.get_pattern();
+        let pattern_len = pattern.first().map_or(0, Vec::len);
+        if pattern_len == 0 {
+            return Vec::new();
+        }
+        let muted_tracks = self.muted_tracks.lock().unwrap().clone();
+        let step_gates = self.step_gates.lock().unwrap().clone();
+        (0..steps)
+            .map(|step| {
+                let step_idx = step % pattern_len;
+                (step_idx, step_triggers(&pattern, &muted_tracks, &step_gates, step_idx))
+            })
+            .collect()
+    }
+    
+    /// Triggers of the next `num_steps` steps as (time in ms from now, track, step),
+    /// with swing or groove, step lengths and retriggers applied. While stopped the
+    /// preview starts at the first step; while playing, at the step after the current one.
+    pub fn generate_trigger_preview(&self, num_steps: usize) -> Vec<(f64, usize, usize)> {
+        let pattern = self.get_pattern();
+        let pattern_len = pattern.first().map_or(0, Vec::len);
+        if pattern_len == 0 {
+            return Vec::new();
+        }
+        let base_ms = step_interval(self.get_bpm(), self.get_subdivision()).as_secs_f64() * 1000.0;
+        let swing = self.get_swing();
+        let groove = self.groove.lock().unwrap().clone();
+        let step_lengths = self.step_lengths.lock().unwrap().clone();
+        let muted_tracks = self.muted_tracks.lock().unwrap().clone();
+        let step_gates = self.step_gates.lock().unwrap().clone();
+        let step_retriggers = self.step_retriggers.lock().unwrap().clone();
+        
+        // Grid position of the first previewed step, in ms from now
+        let (first_step, mut grid_ms) = if self.is_playing() {
+            let current = self.current_step() % pattern_len;
+            let current_ms = effective_step_length(&step_lengths, current) as f64 * base_ms;
+            let fired_late_ms = step_offset(current as u64, swing, &groove) * base_ms;
+            let remaining_ms = (1.0 - self.step_progress() as f64) * current_ms - fired_late_ms;
+            ((current + 1) % pattern_len, remaining_ms.max(0.0))
+        } else {
+            (0, 0.0)
+        };
+        
+        let mut preview = Vec::new();
+        for n in 0..num_steps {
+            let step_idx = (first_step + n) % pattern_len;
+            let step_ms = effective_step_length(&step_lengths, step_idx) as f64 * base_ms;
+            let time_ms = (grid_ms + step_offset(step_idx as u64, swing, &groove) * base_ms).max(0.0);
+            for trigger in step_triggers(&pattern, &muted_tracks, &step_gates, step_idx) {
+                preview.push((time_ms, trigger.track_idx, step_idx));
+                if let Some(&(count, interval_ms)) = step_retriggers.get(&(trigger.track_idx, step_idx)) {
+                    for delay in retrigger_delays(count, interval_ms, Duration::from_secs_f64(step_ms / 1000.0)) {
+                        preview.push((time_ms + delay.as_secs_f64() * 1000.0, trigger.track_idx, step_idx));
+                    }
+                }
+            }
+            grid_ms += step_ms;
+        }
+        preview
+    }
+    
     /// Get the current BPM
     pub fn get_bpm(&self) -> u32 {
         *self.bpm.lock().unwrap()
     }
     
-    /// Get a reference to the current pattern
-    pub fn get_pattern(&self) -> &Vec<Vec<bool>> {
-        &self.pattern
+    /// Snapshot of the current pattern, including changes made with `set_pattern`
+    pub fn get_pattern(&self) -> Vec<Vec<bool>> {
+        self.pattern.lock().unwrap().clone()
+    }
+    
+    /// Whether a step is on in the current pattern; false if it's out of range
+    pub fn get_step(&self, track_idx: usize, step_idx: usize) -> bool {
+        self.pattern.lock().unwrap()
+            .get(track_idx)
+            .and_then(|track| track.get(step_idx))
+            .copied()
+            .unwrap_or(false)
+    }
+    
+    /// Fails with `ThreadPanic` if the sequencer thread has died
+    pub fn check_thread(&self) -> Result<(), SequencerError> {
+        match &self.thread_handle {
+            Some(handle) if handle.is_finished() => Err(SequencerError::ThreadPanic("thread exited unexpectedly".to_string())),
+            _ => Ok(()),
+        }
     }
     
     /// Get a reference to the event bus
@@ -277,14 +1036,35 @@ impl Clone for Sequencer {
         // When cloning, we'll create a new instance that shares
         // the same state but has separate channels
         let bpm = self.get_bpm();
-        let pattern = self.get_pattern().clone();
+        let pattern = self.get_pattern();
         
         // Share the same event bus when cloning
-        Sequencer::new_with_event_bus(bpm, pattern, Arc::clone(&self.event_bus))
+        let sequencer = Sequencer::spawn(bpm, pattern, Arc::clone(&self.event_bus), self.stream_handle.clone());
+        sequencer.set_swing(self.get_swing());
+        sequencer.set_subdivision(self.get_subdivision());
+        sequencer.set_steps_per_bar(self.steps_per_bar.load(Ordering::Relaxed));
+        sequencer.set_groove_template(self.get_groove_template());
+        if self.is_external_clock() {
+            sequencer.enable_external_clock_mode();
+        }
+        for (track_idx, lengths) in self.step_lengths.lock().unwrap().iter().enumerate() {
+            let _ = sequencer.set_step_lengths(track_idx, lengths.clone());
+        }
+        for &track_idx in self.muted_tracks.lock().unwrap().iter() {
+            sequencer.set_track_muted(track_idx, true);
+        }
+        for (&(track_idx, step_idx), &gate) in self.step_gates.lock().unwrap().iter() {
+            sequencer.set_step_gate(track_idx, step_idx, Some(gate));
+        }
+        for (&(track_idx, step_idx), &(count, interval_ms)) in self.step_retriggers.lock().unwrap().iter() {
+            sequencer.set_step_retrigger(track_idx, step_idx, count, interval_ms);
+        }
+        sequencer
     }
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
     use std::thread::sleep;
@@ -435,6 +1215,568 @@ mod tests {
         sequencer.stop();
     }
     
+    #[test]
+    fn test_listeners() {
+        let pattern = vec![vec![true, true, true, true]];
+        let sequencer = Sequencer::new(240, pattern);
+        
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = Arc::clone(&received);
+        let id = sequencer.add_listener(Box::new(move |event| {
+            received_clone.lock().unwrap().push(event.step_idx);
+        }));
+        
+        sequencer.start();
+        sleep(Duration::from_millis(200));
+        assert!(!received.lock().unwrap().is_empty(), "Listener should receive triggers");
+        
+        // No more calls after removal
+        sequencer.remove_listener(id);
+        sleep(Duration::from_millis(10));
+        let count = received.lock().unwrap().len();
+        sleep(Duration::from_millis(200));
+        assert_eq!(received.lock().unwrap().len(), count);
+        
+        sequencer.stop();
+    }
+    
+    #[test]
+    fn test_bar_started_events() {
+        let pattern = vec![vec![true, true, true, true, true, true]];
+        let sequencer = Sequencer::new(240, pattern);
+        sequencer.set_steps_per_bar(4);
+        
+        let bars = Arc::new(Mutex::new(Vec::new()));
+        let bars_clone = Arc::clone(&bars);
+        sequencer.get_event_bus().subscribe(move |event| {
+            if let TrackerEvent::BarStarted(bar) = event {
+                bars_clone.lock().unwrap().push(*bar);
+            }
+        });
+        let triggers = Arc::new(AtomicUsize::new(0));
+        let triggers_clone = Arc::clone(&triggers);
+        sequencer.add_listener(Box::new(move |_| {
+            triggers_clone.fetch_add(1, Ordering::SeqCst);
+        }));
+        
+        sequencer.start();
+        sleep(Duration::from_millis(700));
+        sequencer.stop();
+        
+        // One bar per 4 steps, counted independently of the 6-step pattern loop
+        let triggers = triggers.load(Ordering::SeqCst);
+        let bars = bars.lock().unwrap().clone();
+        assert!(triggers >= 5, "Expected several steps, got {}", triggers);
+        assert_eq!(bars.len(), triggers.div_ceil(4));
+        assert_eq!(bars, (0..bars.len() as u32).collect::<Vec<_>>());
+        assert_eq!(sequencer.bar_count(), 0, "Bar count resets on stop");
+    }
+    
+    #[test]
+    fn test_step_events_arrive_in_order_with_playback_events() {
+        let sequencer = Sequencer::new(600, vec![vec![true; 4]]);
+        // A slow UI listener doesn't hold up immediate listeners
+        sequencer.get_event_bus().subscribe(|_| sleep(Duration::from_millis(20)));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = Arc::clone(&events);
+        sequencer.get_event_bus().subscribe_immediate(move |event| {
+            if matches!(event, TrackerEvent::StepTriggered(_, _) | TrackerEvent::PlaybackStateChanged(_)) {
+                events_clone.lock().unwrap().push(event.clone());
+            }
+        });
+        
+        sequencer.start();
+        sleep(Duration::from_millis(150));
+        sequencer.stop();
+        sleep(Duration::from_millis(50));
+        
+        let events = events.lock().unwrap();
+        assert!(matches!(events.first(), Some(TrackerEvent::PlaybackStateChanged(true))));
+        assert!(matches!(events.last(), Some(TrackerEvent::PlaybackStateChanged(false))));
+        assert!(events.len() > 3, "Expected step triggers between start and stop");
+    }
+    
+    #[test]
+    fn test_callbacks_can_call_back_into_the_sequencer() {
+        let sequencer = Arc::new(Sequencer::new(600, vec![vec![true; 4]]));
+        
+        // A bar subscriber that checks the transport
+        let handle = Arc::downgrade(&sequencer);
+        let bar_playing = Arc::new(AtomicBool::new(false));
+        let bar_playing_clone = Arc::clone(&bar_playing);
+        sequencer.get_event_bus().subscribe(move |event| {
+            if let (TrackerEvent::BarStarted(_), Some(sequencer)) = (event, handle.upgrade()) {
+                bar_playing_clone.store(sequencer.is_playing(), Ordering::SeqCst);
+            }
+        });
+        
+        // A one-shot listener that removes itself
+        let handle = Arc::downgrade(&sequencer);
+        let own_id = Arc::new(AtomicUsize::new(usize::MAX));
+        let own_id_clone = Arc::clone(&own_id);
+        let one_shot_calls = Arc::new(AtomicUsize::new(0));
+        let one_shot_calls_clone = Arc::clone(&one_shot_calls);
+        let id = sequencer.add_listener(Box::new(move |_| {
+            one_shot_calls_clone.fetch_add(1, Ordering::SeqCst);
+            if let Some(sequencer) = handle.upgrade() {
+                sequencer.remove_listener(own_id_clone.load(Ordering::SeqCst));
+            }
+        }));
+        own_id.store(id, Ordering::SeqCst);
+        
+        // A listener that stops playback after a few hits
+        let handle = Arc::downgrade(&sequencer);
+        let hits = Arc::new(AtomicUsize::new(0));
+        let hits_clone = Arc::clone(&hits);
+        sequencer.add_listener(Box::new(move |_| {
+            if hits_clone.fetch_add(1, Ordering::SeqCst) + 1 == 3 {
+                if let Some(sequencer) = handle.upgrade() {
+                    sequencer.stop();
+                }
+            }
+        }));
+        
+        sequencer.start();
+        sleep(Duration::from_millis(300));
+        assert!(!sequencer.is_playing());
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert_eq!(one_shot_calls.load(Ordering::SeqCst), 1);
+        assert!(bar_playing.load(Ordering::SeqCst));
+        assert!(sequencer.check_thread().is_ok());
+    }
+    
+    #[test]
+    fn test_stop_during_step_resets_position() {
+        let pattern = vec![vec![true; 8]];
+        let sequencer = Sequencer::new(600, pattern);
+        
+        // A slow listener keeps the thread busy inside step computation
+        sequencer.add_listener(Box::new(|_| sleep(Duration::from_millis(5))));
+        
+        for _ in 0..10 {
+            sequencer.start();
+            sleep(Duration::from_millis(60));
+            sequencer.stop();
+            assert_eq!(sequencer.current_step(), 0);
+            sleep(Duration::from_millis(10));
+            assert_eq!(sequencer.current_step(), 0, "A step in progress must not advance after stop");
+        }
+    }
+    
+    #[test]
+    fn test_timing_does_not_drift() {
+        // 1200 BPM gives 12.5 ms steps, so 100 steps should take 1.25 s
+        let pattern = vec![vec![true; 16]];
+        let sequencer = Sequencer::new(1200, pattern);
+        
+        let times = Arc::new(Mutex::new(Vec::new()));
+        let times_clone = Arc::clone(&times);
+        sequencer.add_listener(Box::new(move |_| {
+            times_clone.lock().unwrap().push(Instant::now());
+        }));
+        
+        sequencer.start();
+        sleep(Duration::from_millis(1400));
+        sequencer.stop();
+        
+        let times = times.lock().unwrap();
+        assert!(times.len() > 100, "Expected more than 100 steps, got {}", times.len());
+        let elapsed = times[100].duration_since(times[0]).as_secs_f64();
+        let expected = 100.0 * step_interval(1200, Subdivision::Sixteenth).as_secs_f64();
+        assert!(
+            (elapsed - expected).abs() <= expected * 0.01,
+            "100 steps took {:.4}s, expected {:.4}s", elapsed, expected
+        );
+    }
+    
+    #[test]
+    fn test_sample_accurate_timing_matches_timer() {
+        // Needs an output device; skip quietly where there is none
+        if let Ok((_stream, stream_handle)) = rodio::OutputStream::try_default() {
+            let timer = Sequencer::new(120, vec![vec![true; 16]]);
+            let sample_accurate = Sequencer::new_sample_accurate(120, vec![vec![true; 16]], stream_handle);
+            
+            for sequencer in [&timer, &sample_accurate] {
+                sequencer.start_timing_measurement(32);
+                sequencer.start();
+            }
+            
+            // 32 steps of 125 ms, plus some slack for the output buffer
+            sleep(Duration::from_millis(4200));
+            timer.stop();
+            sample_accurate.stop();
+            
+            let expected = step_interval(120, Subdivision::Sixteenth).as_secs_f64() * 1_000_000.0;
+            for (mode, sequencer) in [("timer", &timer), ("sample-accurate", &sample_accurate)] {
+                assert_eq!(sequencer.timing_sample_count(), 32, "{} mode", mode);
+                let stats = sequencer.measure_timing_accuracy();
+                assert!((stats.mean_interval_us - expected).abs() <= expected * 0.02, "{} mode: {:?}", mode, stats);
+                assert!(stats.std_dev_us < expected / 4.0, "{} mode: {:?}", mode, stats);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_measure_timing_accuracy() {
+        // 120 BPM gives 125 ms steps
+        let sequencer = Sequencer::new(120, vec![vec![true; 16]]);
+        assert_eq!(sequencer.measure_timing_accuracy(), TimingStats::default());
+        
+        sequencer.start_timing_measurement(5);
+        sequencer.start();
+        sleep(Duration::from_millis(800));
+        sequencer.stop();
+        
+        // Recording stops at the requested number of steps
+        assert_eq!(sequencer.timing_sample_count(), 5);
+        let stats = sequencer.measure_timing_accuracy();
+        let expected = step_interval(120, Subdivision::Sixteenth).as_secs_f64() * 1_000_000.0;
+        assert!((stats.mean_interval_us - expected).abs() <= expected * 0.05, "{:?}", stats);
+        assert!(stats.max_jitter_us >= stats.std_dev_us);
+    }
+    
+    #[test]
+    fn test_timing_stats_from_timestamps() {
+        let start = Instant::now();
+        let times: Vec<Instant> = [0, 100, 200, 400].iter().map(|&ms| start + Duration::from_millis(ms)).collect();
+        let stats = TimingStats::from_timestamps(&times);
+        assert!((stats.mean_interval_us - 133_333.333).abs() < 0.01);
+        assert!((stats.max_jitter_us - 66_666.667).abs() < 0.01);
+        assert!((stats.std_dev_us - 47_140.452).abs() < 0.01);
+        assert_eq!(TimingStats::from_timestamps(&times[..1]), TimingStats::default());
+    }
+    
+    #[test]
+    fn test_step_times_with_swing() {
+        // 150 BPM gives 100 ms steps
+        let sequencer = Sequencer::new(150, vec![vec![true; 8]]);
+        assert_eq!(sequencer.get_step_time_ms(3), 300.0);
+        assert_eq!(sequencer.get_pattern_duration_ms(), 800.0);
+        
+        sequencer.set_swing(0.5);
+        let base = 100.0;
+        for even in [0, 2, 4, 6] {
+            assert_eq!(sequencer.get_step_time_ms(even), even as f64 * base);
+            let gap = sequencer.get_step_time_ms(even + 1) - sequencer.get_step_time_ms(even);
+            assert!((gap - 1.25 * base).abs() < 1e-9, "Odd step {} is {} ms after its even step", even + 1, gap);
+        }
+        assert_eq!(sequencer.get_pattern_duration_ms(), 800.0);
+        
+        sequencer.set_swing(3.0);
+        assert_eq!(sequencer.get_swing(), 1.0);
+    }
+    
+    #[test]
+    fn test_step_times_with_groove_template() {
+        // 150 BPM gives 100 ms steps
+        let sequencer = Sequencer::new(150, vec![vec![true; 4]]);
+        sequencer.set_swing(1.0);
+        sequencer.set_groove_template(vec![0.0, 0.25, -0.1, 2.0]);
+        assert_eq!(sequencer.get_groove_template(), vec![0.0, 0.25, -0.1, 0.5]);
+        
+        assert!((sequencer.get_step_time_ms(1) - 125.0).abs() < 1e-6);
+        assert!((sequencer.get_step_time_ms(2) - 190.0).abs() < 1e-6);
+        assert!((sequencer.get_step_time_ms(3) - 350.0).abs() < 1e-6);
+        
+        // An empty template goes back to swing
+        sequencer.set_groove_template(Vec::new());
+        assert!((sequencer.get_step_time_ms(1) - 150.0).abs() < 1e-6);
+    }
+    
+    #[test]
+    fn test_swing_delays_odd_steps() {
+        // 300 BPM gives 50 ms steps; with 0.5 swing odd steps land 62.5 ms after even ones
+        let sequencer = Sequencer::new(300, vec![vec![true; 4]]);
+        sequencer.set_swing(0.5);
+        
+        let times = Arc::new(Mutex::new(Vec::new()));
+        let times_clone = Arc::clone(&times);
+        sequencer.add_listener(Box::new(move |event| {
+            times_clone.lock().unwrap().push((event.step_idx, Instant::now()));
+        }));
+        
+        sequencer.start();
+        sleep(Duration::from_millis(1050));
+        sequencer.stop();
+        
+        let times = times.lock().unwrap();
+        let (mut long, mut short) = (Vec::new(), Vec::new());
+        for pair in times.windows(2) {
+            let gap = pair[1].1.duration_since(pair[0].1).as_secs_f64() * 1000.0;
+            if pair[0].0 % 2 == 0 { long.push(gap) } else { short.push(gap) }
+        }
+        let average = |gaps: &[f64]| gaps.iter().sum::<f64>() / gaps.len() as f64;
+        assert!(long.len() >= 5 && short.len() >= 5);
+        assert!((average(&long) - 62.5).abs() < 5.0, "Even to odd gap {:.1} ms", average(&long));
+        assert!((average(&short) - 37.5).abs() < 5.0, "Odd to even gap {:.1} ms", average(&short));
+    }
+    
+    #[test]
+    fn test_step_length_multiplier_delays_next_step() {
+        // 300 BPM gives 50 ms steps; step 1 is stretched to 100 ms
+        let sequencer = Sequencer::new(300, vec![vec![true; 4], vec![false; 4]]);
+        sequencer.set_step_lengths(1, vec![1.0, 2.0, 1.0, 1.0]).unwrap();
+        assert!(sequencer.set_step_lengths(0, vec![0.0]).is_err());
+        
+        let times = Arc::new(Mutex::new(Vec::new()));
+        let times_clone = Arc::clone(&times);
+        sequencer.add_listener(Box::new(move |event| {
+            times_clone.lock().unwrap().push((event.step_idx, Instant::now()));
+        }));
+        
+        sequencer.start();
+        sleep(Duration::from_millis(1050));
+        sequencer.stop();
+        assert_eq!(sequencer.get_step_length(1), 2.0);
+        assert_eq!(sequencer.get_step_time_ms(2), 150.0);
+        assert_eq!(sequencer.get_pattern_duration_ms(), 250.0);
+        
+        let times = times.lock().unwrap();
+        let (mut stretched, mut normal) = (Vec::new(), Vec::new());
+        for pair in times.windows(2) {
+            let gap = pair[1].1.duration_since(pair[0].1).as_secs_f64() * 1000.0;
+            if pair[0].0 == 1 { stretched.push(gap) } else { normal.push(gap) }
+        }
+        let average = |gaps: &[f64]| gaps.iter().sum::<f64>() / gaps.len() as f64;
+        assert!(stretched.len() >= 3 && normal.len() >= 9);
+        assert!((average(&stretched) - 100.0).abs() < 5.0, "Stretched step gap {:.1} ms", average(&stretched));
+        assert!((average(&normal) - 50.0).abs() < 5.0, "Normal step gap {:.1} ms", average(&normal));
+    }
+    
+    #[test]
+    fn test_step_progress() {
+        // 60 BPM gives 250 ms steps
+        let sequencer = Sequencer::new(60, vec![vec![true; 4]]);
+        assert_eq!(sequencer.step_progress(), 0.0);
+
+        sequencer.start();
+        sleep(Duration::from_millis(20));
+        let early = sequencer.step_progress();
+        sleep(Duration::from_millis(100));
+        let later = sequencer.step_progress();
+        assert!((0.0..1.0).contains(&early));
+        assert!(later > early, "Progress should advance within a step ({} -> {})", early, later);
+        assert!(later < 1.0);
+
+        sequencer.stop();
+        assert_eq!(sequencer.step_progress(), 0.0);
+    }
+
+    #[test]
+    fn test_set_pattern_while_playing() {
+        let mut sequencer = Sequencer::new(600, vec![vec![false; 4]]);
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = Arc::clone(&received);
+        sequencer.add_listener(Box::new(move |event| {
+            received_clone.lock().unwrap().push(event.step_idx);
+        }));
+
+        sequencer.start();
+        sleep(Duration::from_millis(100));
+        assert!(received.lock().unwrap().is_empty());
+
+        assert!(sequencer.set_pattern(vec![vec![true; 2], vec![false]]).is_err());
+        assert!(sequencer.check_thread().is_ok());
+        sequencer.set_pattern(vec![vec![true, false]]).unwrap();
+        assert_eq!(sequencer.get_pattern(), vec![vec![true, false]]);
+        assert!(sequencer.get_step(0, 0));
+        assert!(!sequencer.get_step(0, 1));
+        assert!(!sequencer.get_step(3, 0));
+        sleep(Duration::from_millis(200));
+        sequencer.stop();
+
+        let received = received.lock().unwrap();
+        assert!(!received.is_empty(), "New pattern should play without restarting");
+        assert!(received.iter().all(|&step| step == 0));
+    }
+
+    #[test]
+    fn test_muted_track_does_not_trigger() {
+        let sequencer = Sequencer::new(600, vec![vec![true; 4], vec![true; 4]]);
+        
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = Arc::clone(&received);
+        sequencer.add_listener(Box::new(move |event| {
+            received_clone.lock().unwrap().push(event.track_idx);
+        }));
+        let bus_events = Arc::new(Mutex::new(Vec::new()));
+        let bus_events_clone = Arc::clone(&bus_events);
+        sequencer.get_event_bus().subscribe(move |event| {
+            if let TrackerEvent::StepTriggered(track_idx, _) = event {
+                bus_events_clone.lock().unwrap().push(*track_idx);
+            }
+        });
+        
+        sequencer.set_track_muted(1, true);
+        assert!(sequencer.is_track_muted(1));
+        sequencer.start();
+        sleep(Duration::from_millis(200));
+        sequencer.stop();
+        sleep(Duration::from_millis(50));
+        
+        let received = received.lock().unwrap();
+        assert!(!received.is_empty());
+        assert!(received.iter().all(|&track| track == 0), "Muted track triggered: {:?}", received);
+        assert!(bus_events.lock().unwrap().iter().all(|&track| track == 0));
+        
+        sequencer.set_track_muted(1, false);
+        assert!(!sequencer.is_track_muted(1));
+    }
+    
+    #[test]
+    fn test_external_clock_advances_every_six_ticks() {
+        // At 1200 BPM the internal timer would play a step every 12.5 ms
+        let sequencer = Sequencer::new(1200, vec![vec![true; 16]]);
+        sequencer.enable_external_clock_mode();
+        
+        let steps = Arc::new(Mutex::new(Vec::new()));
+        let steps_clone = Arc::clone(&steps);
+        sequencer.add_listener(Box::new(move |trigger| {
+            steps_clone.lock().unwrap().push(trigger.step_idx);
+        }));
+        
+        sequencer.start();
+        for _ in 0..24 {
+            sequencer.external_clock_tick();
+        }
+        sleep(Duration::from_millis(100));
+        assert_eq!(*steps.lock().unwrap(), vec![0, 1, 2, 3]);
+        
+        // Tempo changes don't apply to an external clock
+        sequencer.set_bpm(60);
+        assert_eq!(sequencer.get_bpm(), 1200);
+        sequencer.stop();
+    }
+    
+    #[test]
+    fn test_gated_steps_follow_required_track() {
+        let sequencer = Sequencer::new(120, vec![
+            vec![true, false, true, false],
+            vec![true, true, true, true],
+        ]);
+        sequencer.enable_external_clock_mode();
+        for step in 0..4 {
+            sequencer.set_step_gate(1, step, Some((0, 0)));
+        }
+        // Step 3 of track 1 follows track 0 one step earlier instead
+        sequencer.set_step_gate(1, 3, Some((0, -1)));
+        
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = Arc::clone(&received);
+        sequencer.add_listener(Box::new(move |event| {
+            if event.track_idx == 1 {
+                received_clone.lock().unwrap().push(event.step_idx);
+            }
+        }));
+        
+        sequencer.start();
+        for _ in 0..4 * MIDI_CLOCKS_PER_STEP {
+            sequencer.external_clock_tick();
+        }
+        sleep(Duration::from_millis(100));
+        assert_eq!(*received.lock().unwrap(), vec![0, 2, 3]);
+        sequencer.stop();
+    }
+    
+    #[test]
+    fn test_gate_open_wraps_and_respects_mutes() {
+        let pattern = vec![vec![true, false, false, false], vec![true; 4]];
+        let mut gates = StepGates::new();
+        gates.insert((1, 3), (0, 1));
+        assert!(gate_open(&pattern, &HashSet::new(), &gates, 1, 3));
+        assert!(!gate_open(&pattern, &HashSet::from([0]), &gates, 1, 3));
+        assert!(gate_open(&pattern, &HashSet::new(), &gates, 1, 2));
+        
+        gates.insert((1, 2), (5, 0));
+        assert!(!gate_open(&pattern, &HashSet::new(), &gates, 1, 2));
+    }
+    
+    #[test]
+    fn test_step_retrigger_fires_within_step() {
+        let sequencer = Sequencer::new(120, vec![vec![true, false, false, false], vec![true; 4]]);
+        sequencer.enable_external_clock_mode();
+        sequencer.set_step_retrigger(0, 0, 3, 10.0);
+        assert_eq!(sequencer.get_step_retrigger(0, 0), (3, 10.0));
+        assert_eq!(sequencer.get_step_retrigger(1, 0), (1, 0.0));
+        
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = Arc::clone(&received);
+        sequencer.get_event_bus().subscribe(move |event| {
+            if let TrackerEvent::StepTriggered(track_idx, step_idx) = event {
+                received_clone.lock().unwrap().push((*track_idx, *step_idx));
+            }
+        });
+        
+        sequencer.start();
+        for _ in 0..MIDI_CLOCKS_PER_STEP {
+            sequencer.external_clock_tick();
+        }
+        sleep(Duration::from_millis(150));
+        sequencer.stop();
+        
+        let received = received.lock().unwrap();
+        assert_eq!(received.iter().filter(|&&event| event == (0, 0)).count(), 3);
+        assert_eq!(received.iter().filter(|&&event| event == (1, 0)).count(), 1);
+    }
+    
+    #[test]
+    fn test_retrigger_delays_stay_inside_step() {
+        let step = Duration::from_millis(120);
+        assert!(retrigger_delays(1, 10.0, step).is_empty());
+        assert_eq!(retrigger_delays(3, 10.0, step), vec![Duration::from_millis(10), Duration::from_millis(20)]);
+        assert_eq!(retrigger_delays(3, 0.0, step), vec![Duration::from_millis(40), Duration::from_millis(80)]);
+        assert_eq!(retrigger_delays(4, 100.0, step).last(), Some(&Duration::from_millis(90)));
+    }
+    
+    #[test]
+    fn test_subdivision_sets_step_length() {
+        let sequencer = Sequencer::new(120, vec![vec![true; 16]]);
+        assert_eq!(sequencer.get_subdivision(), Subdivision::Sixteenth);
+        assert!((sequencer.get_pattern_duration_ms() - 2000.0).abs() < 1e-6);
+        
+        sequencer.set_subdivision(Subdivision::Eighth);
+        assert!((sequencer.get_step_time_ms(1) - 250.0).abs() < 1e-6);
+        assert_eq!(sequencer.steps_per_bar.load(Ordering::Relaxed), 8);
+        
+        sequencer.set_subdivision(Subdivision::ThirtySecond);
+        assert!((sequencer.get_step_time_ms(1) - 62.5).abs() < 1e-6);
+        assert_eq!(sequencer.steps_per_bar.load(Ordering::Relaxed), 32);
+        assert_eq!(Subdivision::ThirtySecond.midi_clocks_per_step(), 3);
+        assert_eq!(Subdivision::ThirtySecond.next(), Subdivision::Eighth);
+    }
+    
+    #[test]
+    fn test_trigger_preview_includes_swing_and_lengths() {
+        let sequencer = Sequencer::new(120, vec![vec![true, true, false, true], vec![false, false, true, false]]);
+        sequencer.set_swing(1.0);
+        sequencer.set_step_lengths(0, vec![1.0, 1.0, 2.0, 1.0]).unwrap();
+        sequencer.set_track_muted(1, true);
+        // Step lengths reach the shared state through the sequencer thread
+        sleep(Duration::from_millis(20));
+        
+        // 125 ms steps; odd steps are half a step late and step 2 lasts two steps
+        let preview = sequencer.generate_trigger_preview(5);
+        assert_eq!(preview, vec![
+            (0.0, 0, 0),
+            (187.5, 0, 1),
+            (562.5, 0, 3),
+            (625.0, 0, 0),
+        ]);
+        assert!(!sequencer.is_playing());
+    }
+    
+    #[test]
+    fn test_unusable_pattern_leaves_thread_idle() {
+        for pattern in [vec![], vec![vec![true, false], vec![]]] {
+            let sequencer = Sequencer::new(600, pattern);
+            sequencer.start();
+            sleep(Duration::from_millis(50));
+            assert!(sequencer.check_thread().is_ok());
+            assert_eq!(sequencer.current_step(), 0);
+        }
+    }
+    
     #[test]
     fn test_pattern_validation() {
         // Valid pattern
@@ -446,13 +1788,16 @@ mod tests {
         
         // Empty pattern
         let pattern_empty: Vec<Vec<bool>> = vec![];
-        assert_eq!(Sequencer::validate_pattern(&pattern_empty), Err("Pattern cannot be empty"));
+        assert!(matches!(Sequencer::validate_pattern(&pattern_empty), Err(SequencerError::EmptyPattern)));
+        assert!(matches!(Sequencer::validate_pattern(&vec![vec![true], vec![]]), Err(SequencerError::EmptyTrack(1))));
         
         // Track with different lengths
         let pattern_diff_lengths = vec![
             vec![true, false, true],
             vec![false, true],
         ];
-        assert_eq!(Sequencer::validate_pattern(&pattern_diff_lengths), Err("All tracks must have the same length"));
+        let err = Sequencer::validate_pattern(&pattern_diff_lengths).unwrap_err();
+        assert!(matches!(err, SequencerError::UnequalTrackLengths { expected: 3, got: 2, track_idx: 1 }));
+        assert_eq!(err.to_string(), "All tracks must have the same length: track 1 has 2 steps, expected 3");
     }
 }
@@ -0,0 +1,78 @@
+// Step timing driven by the audio output instead of thread sleeps
+use std::time::Instant;
+use rodio::buffer::SamplesBuffer;
+use rodio::source::EmptyCallback;
+use rodio::{OutputStreamHandle, PlayError, Sink};
+
+/// Rate of the silence queued on the timing sink
+pub const CLOCK_SAMPLE_RATE: u32 = 44100;
+
+/// Frames from `start` to `target` on the clock's sample grid
+fn frames_between(start: Instant, target: Instant) -> u64 {
+    (target.saturating_duration_since(start).as_secs_f64() * CLOCK_SAMPLE_RATE as f64).round() as u64
+}
+
+/// Queues silence on a dedicated sink and fires a callback when playback reaches
+/// the end of it, so step boundaries land on exact sample positions
+pub struct SampleClock {
+    stream_handle: OutputStreamHandle,
+    sink: Option<Sink>,
+    /// Grid position that frame 0 of the current run corresponds to
+    start: Instant,
+    /// Frames queued since `start`
+    frames_queued: u64,
+}
+
+impl SampleClock {
+    pub fn new(stream_handle: OutputStreamHandle) -> Self {
+        Self {
+            stream_handle,
+            sink: None,
+            start: Instant::now(),
+            frames_queued: 0,
+        }
+    }
+
+    /// Drop anything queued and start counting frames from `start`
+    pub fn restart(&mut self, start: Instant) -> Result<(), PlayError> {
+        self.sink = Some(Sink::try_new(&self.stream_handle)?);
+        self.start = start;
+        self.frames_queued = 0;
+        Ok(())
+    }
+
+    /// Drop anything queued; pending callbacks never fire
+    pub fn stop(&mut self) {
+        self.sink = None;
+    }
+
+    /// Run `callback` from the audio thread once playback reaches `target`.
+    /// Frame counts are rounded against the run's start, so rounding never accumulates.
+    pub fn schedule(&mut self, target: Instant, callback: Box<dyn Fn() + Send>) {
+        let Some(sink) = &self.sink else {
+            return;
+        };
+        let target_frames = frames_between(self.start, target).max(self.frames_queued);
+        let frames = (target_frames - self.frames_queued) as usize;
+        if frames > 0 {
+            sink.append(SamplesBuffer::new(1, CLOCK_SAMPLE_RATE, vec![0.0f32; frames]));
+        }
+        sink.append(EmptyCallback::<f32>::new(callback));
+        self.frames_queued = target_frames;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_frames_between_rounds_to_grid() {
+        let start = Instant::now();
+        // One 16th at 120 BPM is exactly 5512.5 frames
+        assert_eq!(frames_between(start, start + Duration::from_millis(125)), 5513);
+        assert_eq!(frames_between(start, start + Duration::from_millis(250)), 11025);
+        assert_eq!(frames_between(start + Duration::from_millis(1), start), 0);
+    }
+}
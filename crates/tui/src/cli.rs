@@ -0,0 +1,178 @@
+// Command line parsing for the gaucho-tracker binary
+use std::path::PathBuf;
+
+use std::ffi::OsString;
+
+use clap::builder::RangedU64ValueParser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+
+/// Project loaded when `--project` is not given
+pub const DEFAULT_PROJECT: &str = "my-song";
+
+/// Parsed command line
+#[derive(Debug, Clone, PartialEq, Parser)]
+#[command(name = "gaucho-tracker", version = env!("CARGO_PKG_VERSION"), about = "A step sequencer for the terminal")]
+pub struct Cli {
+    /// Project used by play and render
+    #[arg(long, value_name = "NAME", default_value = DEFAULT_PROJECT)]
+    pub project: String,
+    /// Output device used by play, or the system default
+    #[arg(long, value_name = "NAME")]
+    pub audio_device: Option<String>,
+    /// Play without opening an audio device
+    #[arg(long)]
+    pub headless: bool,
+    /// Accept OSC remote control messages on a UDP port
+    #[arg(long, value_name = "PORT")]
+    pub osc_port: Option<u16>,
+    /// Start playback as soon as the project is loaded
+    #[arg(long)]
+    pub auto_start: bool,
+    /// Run the commands in FILE before play opens the tracker
+    #[arg(long, value_name = "FILE")]
+    pub script: Option<PathBuf>,
+    /// Download the project from a ZIP archive at URL instead of using --project
+    #[arg(long, value_name = "URL")]
+    pub url: Option<String>,
+    /// Run the sequencer for 64 steps and print its timing statistics
+    #[arg(long)]
+    pub benchmark: bool,
+    /// What to do; launches the tracker when omitted
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Subcommand selected on the command line
+#[derive(Debug, Clone, PartialEq, Subcommand)]
+pub enum Command {
+    /// Launch the interactive tracker (default)
+    Play,
+    /// Render the first pattern to a WAV file
+    Render {
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+        #[arg(long, value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        bars: usize,
+    },
+    /// Check a project for errors
+    Validate {
+        #[arg(value_name = "PROJECT_NAME")]
+        project: String,
+    },
+    /// Print project metadata as JSON
+    Info {
+        #[arg(value_name = "PROJECT_NAME")]
+        project: String,
+    },
+    /// Write a tracks.json with a track per sample in DIR
+    AutoPopulate {
+        #[arg(long, value_name = "DIR")]
+        samples: PathBuf,
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+    },
+    /// Move the first pattern of --project in and out as JSON
+    #[command(subcommand)]
+    Pattern(PatternCommand),
+}
+
+/// `pattern` subcommands
+#[derive(Debug, Clone, PartialEq, Subcommand)]
+pub enum PatternCommand {
+    /// Print the first pattern as JSON
+    Export,
+    /// Replace the first pattern with JSON from stdin
+    Import,
+}
+
+/// Parse command line arguments, including the program name.
+/// `--help` and `--version` come back as errors of their own kind for `Error::exit` to print.
+pub fn parse_args<I, T>(args: I) -> Result<Cli, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let cli = Cli::try_parse_from(args)?;
+    // clap can't declare a conflict between a flag and the subcommands
+    if cli.benchmark && cli.command.is_some() {
+        return Err(Cli::command().error(ErrorKind::ArgumentConflict, "--benchmark can't be combined with a command"));
+    }
+    Ok(cli)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        parse_args(std::iter::once("gaucho-tracker").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn test_parse_subcommands() {
+        assert_eq!(parse(&[]).unwrap().command, None);
+        assert_eq!(parse(&[]).unwrap().project, DEFAULT_PROJECT);
+
+        let cli = parse(&["--project", "demo", "render", "--output", "out.wav", "--bars", "4"]).unwrap();
+        assert_eq!(cli.project, "demo");
+        assert_eq!(cli.command, Some(Command::Render { output: PathBuf::from("out.wav"), bars: 4 }));
+
+        assert_eq!(parse(&["validate", "demo"]).unwrap().command, Some(Command::Validate { project: "demo".to_string() }));
+        assert_eq!(parse(&["info", "demo"]).unwrap().command, Some(Command::Info { project: "demo".to_string() }));
+
+        let cli = parse(&["--audio-device", "USB Audio", "play"]).unwrap();
+        assert_eq!(cli.audio_device.as_deref(), Some("USB Audio"));
+        assert_eq!(cli.command, Some(Command::Play));
+        assert_eq!(parse(&[]).unwrap().audio_device, None);
+
+        assert_eq!(
+            parse(&["auto-populate", "--samples", "kit", "--output", "tracks.json"]).unwrap().command,
+            Some(Command::AutoPopulate { samples: PathBuf::from("kit"), output: PathBuf::from("tracks.json") })
+        );
+
+        assert_eq!(parse(&["pattern", "export"]).unwrap().command, Some(Command::Pattern(PatternCommand::Export)));
+        let cli = parse(&["--project", "demo", "pattern", "import"]).unwrap();
+        assert_eq!((cli.project.as_str(), cli.command), ("demo", Some(Command::Pattern(PatternCommand::Import))));
+
+        assert!(parse(&["--headless", "play"]).unwrap().headless);
+        assert!(!parse(&["play"]).unwrap().headless);
+
+        assert_eq!(parse(&["--osc-port", "9000", "play"]).unwrap().osc_port, Some(9000));
+        assert_eq!(parse(&["play"]).unwrap().osc_port, None);
+
+        assert!(parse(&["--auto-start"]).unwrap().auto_start);
+        assert!(!parse(&["play"]).unwrap().auto_start);
+
+        assert!(parse(&["--benchmark"]).unwrap().benchmark);
+
+        assert_eq!(parse(&["--script", "setup.txt"]).unwrap().script, Some(PathBuf::from("setup.txt")));
+        assert_eq!(parse(&["play"]).unwrap().script, None);
+
+        assert_eq!(parse(&["--url", "http://example.com/song.zip"]).unwrap().url.as_deref(), Some("http://example.com/song.zip"));
+        assert_eq!(parse(&["play"]).unwrap().url, None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse(&["--help"]).unwrap_err().kind(), ErrorKind::DisplayHelp);
+        assert_eq!(parse(&["--version"]).unwrap_err().kind(), ErrorKind::DisplayVersion);
+
+        assert!(parse(&["render", "--output", "out.wav"]).is_err());
+        assert!(parse(&["render", "--output", "out.wav", "--bars", "zero"]).is_err());
+        assert!(parse(&["render", "--output", "out.wav", "--bars", "0"]).is_err());
+        assert!(parse(&["validate"]).is_err());
+        assert!(parse(&["pattern"]).is_err());
+        assert!(parse(&["pattern", "share"]).is_err());
+        assert!(parse(&["auto-populate", "--output", "tracks.json"]).is_err());
+        assert!(parse(&["info", "a", "b"]).is_err());
+        assert!(parse(&["dance"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+        assert!(parse(&["--audio-device"]).is_err());
+        assert!(parse(&["--osc-port", "70000"]).is_err());
+        assert!(parse(&["--osc-port"]).is_err());
+        assert!(parse(&["--benchmark", "play"]).is_err());
+        assert!(parse(&["--script"]).is_err());
+        assert!(parse(&["--url"]).is_err());
+    }
+}
@@ -0,0 +1,32 @@
+// Step activity heat map colors
+use ratatui::style::Color;
+
+/// Dimmest color a step that has fired can get, so it stays distinct from one that never did
+const MIN_HEAT: u8 = 40;
+
+/// Background for a step that fired `hits` times when the busiest step fired `max_hits` times.
+/// Brightness scales with the ratio, from dark blue through red to yellow.
+pub fn heat_color(hits: u32, max_hits: u32) -> Color {
+    if hits == 0 || max_hits == 0 {
+        return Color::Rgb(0, 0, 0);
+    }
+    let heat = (hits.min(max_hits) as f32 / max_hits as f32).clamp(0.0, 1.0);
+    let red = MIN_HEAT as f32 + heat * (255 - MIN_HEAT) as f32;
+    let green = (heat - 0.5).max(0.0) * 2.0 * 255.0;
+    let blue = (1.0 - heat) * MIN_HEAT as f32;
+    Color::Rgb(red.round() as u8, green.round() as u8, blue.round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heat_color_scales_with_hits() {
+        assert_eq!(heat_color(0, 10), Color::Rgb(0, 0, 0));
+        assert_eq!(heat_color(3, 0), Color::Rgb(0, 0, 0));
+        assert_eq!(heat_color(10, 10), Color::Rgb(255, 255, 0));
+        assert_eq!(heat_color(5, 10), Color::Rgb(148, 0, 20));
+        assert_eq!(heat_color(1, 10), Color::Rgb(62, 0, 36));
+    }
+}
@@ -1,54 +1,100 @@
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Paragraph, Row, Table},
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, LineGauge, List, ListItem, ListState, Paragraph, Row, Table},
     Terminal,
 };
+use std::env;
 use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use project::{load_project, get_project_path};
+use project::{load_project, get_project_path, export_project_zip, import_project_zip, download_project, url_cache_dir, Project};
+use project::model::{PatternMeta, Track};
+use sequencer::Sequencer;
 use log::{debug, error, info};
 use env_logger;
-use app_state::AppState;
+use app_state::{AppState, AudioMode};
 use core::{TrackerEvent, EventBus};
 
+mod cli;
+mod heatmap;
+mod path_prompt;
+mod preview;
+mod sample_browser;
+mod stats;
+mod theme;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use cli::{Command, PatternCommand};
+use path_prompt::{PathPromptState, PromptAction};
+use sample_browser::SampleBrowserState;
+use stats::CpuMonitor;
+use theme::Theme;
+
 // AppState has been moved to the app_state crate
 
+/// How long the header border stays highlighted when a bar starts
+const BAR_FLASH_DURATION: Duration = Duration::from_millis(120);
+
+/// How long the REC indicator stays on, then off, while recording
+const REC_BLINK_MS: u128 = 250;
+
 fn main() -> Result<(), io::Error> {
     // Initialize the logger
     env_logger::init();
 
-    std::panic::set_hook(Box::new(|info| {
-        error!("Application panicked: {:?}", info);
-        let _ = crossterm::terminal::disable_raw_mode();
-        let _ = crossterm::execute!(
-            std::io::stdout(),
-            crossterm::terminal::LeaveAlternateScreen,
-            crossterm::event::DisableMouseCapture
-        );
-    }));
+    let mut cli = cli::parse_args(env::args_os()).unwrap_or_else(|e| e.exit());
 
-    info!("Starting TUI application");
+    // A downloaded project is opened from its cache folder, which project lookup accepts as a path
+    if let Some(url) = &cli.url {
+        match download_project(url, &url_cache_dir()) {
+            Ok(folder) => cli.project = folder.to_string_lossy().into_owned(),
+            Err(e) => {
+                eprintln!("Failed to download project from {}: {}", url, e);
+                process::exit(1);
+            }
+        }
+    }
 
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    if cli.benchmark {
+        return run_benchmark();
+    }
 
-    let project_path = get_project_path("my-song");
+    match cli.command.unwrap_or(Command::Play) {
+        Command::Play => {
+            let audio_mode = if cli.headless { AudioMode::Headless } else { AudioMode::Hardware };
+            run_tui(&cli.project, cli.audio_device, audio_mode, cli.osc_port, cli.auto_start, cli.script.as_deref())
+        }
+        Command::Render { output, bars } => run_render(&cli.project, &output, bars),
+        Command::Validate { project } => run_validate(&project),
+        Command::Info { project } => run_info(&project),
+        Command::AutoPopulate { samples, output } => run_auto_populate(&samples, &output),
+        Command::Pattern(PatternCommand::Export) => run_pattern_export(&cli.project),
+        Command::Pattern(PatternCommand::Import) => run_pattern_import(&cli.project),
+    }
+}
+
+/// Load a project by name and build an AppState from its first pattern
+fn load_app_state(project_name: &str) -> io::Result<(AppState, Project, Vec<PatternMeta>)> {
+    let project_path = get_project_path(project_name);
     
-    let (_project, tracks, patterns, pattern_metas) = match load_project(project_path.clone()) {
+    let (project, tracks, patterns, pattern_metas) = match load_project(project_path.clone()) {
         Ok((proj, trks, pats, metas)) => {
             info!("Project loaded successfully: {}", proj.name);
             (proj, trks, pats, metas)
         }
         Err(e) => {
             error!("Failed to load project: {}", e);
-            return Err(io::Error::new(io::ErrorKind::Other, "Failed to load project"));
+            return Err(io::Error::other(format!("Failed to load project: {}", e)));
         }
     };
 
@@ -62,27 +108,197 @@ fn main() -> Result<(), io::Error> {
     } else {
         16 // Default number of steps
     };
-    let mut app = AppState::new(num_tracks, num_steps);
-
-    // Populate AppState with pattern steps and track names
-    app.steps = if !patterns.is_empty() {
-        patterns[0].steps.clone()
-    } else {
-        vec![vec![false; num_steps]; num_tracks]
-    };
-    
-    // Initialize track names from loaded tracks
-    app.track_names = tracks.iter().map(|t| t.name.clone()).collect();
+    // Edit the project's first pattern, with its tracks and samples
+    let mut app = AppState::new(num_tracks, num_steps)
+        .with_patterns(patterns)
+        .with_sample_dir(project_path.join("samples"))
+        .with_tracks(tracks)
+        .with_bpm(project.bpm);
     if app.track_names.is_empty() {
         app.track_names = (0..num_tracks).map(|i| format!("tr-{:<2}", i)).collect();
     }
-    
-    // Configure the app state with tracks and sample directory
-    let sample_dir = project_path.join("samples");
-    app = app.with_sample_dir(sample_dir).with_tracks(tracks);
-    
+    app.swing = project.swing;
+    if let Some(offsets) = project.groove_template.clone() {
+        app.apply_groove_template(offsets);
+    }
+    if let Err(e) = app.load_effects(&project_path) {
+        error!("Failed to load saved effects: {}", e);
+    }
+    if let Err(e) = app.load_grooves(&project_path) {
+        error!("Failed to load grooves: {}", e);
+    }
+
+    debug!("AppState initialized with {} tracks and {} steps", num_tracks, num_steps);
+    Ok((app, project, pattern_metas))
+}
+
+/// Render the project's first pattern to a WAV file without opening the TUI
+fn run_render(project_name: &str, output: &Path, bars: usize) -> io::Result<()> {
+    let (app, project, _) = load_app_state(project_name)?;
+    app.render_to_wav(output, bars)
+        .map_err(|e| io::Error::other(format!("Render failed: {}", e)))?;
+    println!("Rendered {} bars of '{}' to {}", bars, project.name, output.display());
+    Ok(())
+}
+
+/// Check a project for errors, exiting with status 1 if any are found
+fn run_validate(project_name: &str) -> io::Result<()> {
+    let (project, _tracks, patterns, _metas) = load_project(get_project_path(project_name))
+        .map_err(|e| io::Error::other(format!("Failed to load project: {}", e)))?;
+
+    let mut errors = project.validate().err().unwrap_or_default();
+    for pattern in &patterns {
+        if let Err(e) = Sequencer::validate_pattern(&pattern.steps) {
+            errors.push(format!("Pattern {}: {}", pattern.pattern_id, e));
+        }
+    }
+
+    if errors.is_empty() {
+        println!("Project '{}' is valid", project.name);
+        return Ok(());
+    }
+
+    for e in &errors {
+        eprintln!("error: {}", e);
+    }
+    process::exit(1);
+}
+
+/// Print project metadata as JSON
+fn run_info(project_name: &str) -> io::Result<()> {
+    let (project, _tracks, _patterns, _metas) = load_project(get_project_path(project_name))
+        .map_err(|e| io::Error::other(format!("Failed to load project: {}", e)))?;
+    let json = serde_json::to_string_pretty(&project)
+        .map_err(io::Error::other)?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// Play steps at this tempo for `--benchmark`
+const BENCHMARK_BPM: u32 = 120;
+/// Steps timed by `--benchmark`
+const BENCHMARK_STEPS: usize = 64;
+
+/// Run the sequencer on its own and print how evenly its steps are spaced
+fn run_benchmark() -> io::Result<()> {
+    let sequencer = Sequencer::new(BENCHMARK_BPM, vec![vec![true; 16]]);
+    let step = Duration::from_secs_f64(60.0 / BENCHMARK_BPM as f64 / 4.0);
+    println!("Timing {} steps at {} BPM...", BENCHMARK_STEPS, BENCHMARK_BPM);
+
+    sequencer.start_timing_measurement(BENCHMARK_STEPS);
+    sequencer.start();
+    // Give up after twice the expected time rather than waiting forever
+    let deadline = Instant::now() + step * (BENCHMARK_STEPS as u32 * 2);
+    while sequencer.timing_sample_count() < BENCHMARK_STEPS && Instant::now() < deadline {
+        std::thread::sleep(step);
+    }
+    sequencer.stop();
+
+    let stats = sequencer.measure_timing_accuracy();
+    println!("Steps:         {}", sequencer.timing_sample_count());
+    println!("Expected:      {:.1}us", step.as_secs_f64() * 1_000_000.0);
+    println!("Mean interval: {:.1}us", stats.mean_interval_us);
+    println!("Std deviation: {:.1}us", stats.std_dev_us);
+    println!("Max jitter:    {:.1}us", stats.max_jitter_us);
+    Ok(())
+}
+
+/// Print the project's first pattern as JSON, for sharing as text
+fn run_pattern_export(project_name: &str) -> io::Result<()> {
+    let (app, _, _) = load_app_state(project_name)?;
+    println!("{}", app.get_pattern_as_json().map_err(io::Error::other)?);
+    Ok(())
+}
+
+/// Replace the project's first pattern with JSON read from stdin and save the project
+fn run_pattern_import(project_name: &str) -> io::Result<()> {
+    let (mut app, project, _) = load_app_state(project_name)?;
+    let json = io::read_to_string(io::stdin())?;
+    app.set_pattern_from_json(&json)
+        .map_err(|e| io::Error::other(format!("Invalid pattern: {}", e)))?;
+    app.save_project(&get_project_path(project_name), &project)
+        .map_err(|e| io::Error::other(format!("Failed to save project: {}", e)))?;
+    println!("Imported pattern into '{}'", project.name);
+    Ok(())
+}
+
+/// Write a tracks.json with one track per sample file in `samples`
+fn run_auto_populate(samples: &Path, output: &Path) -> io::Result<()> {
+    let tracks = project::scan_samples_directory(samples, 1.0)?;
+    let json = Track::to_json_array(&tracks).map_err(io::Error::other)?;
+    std::fs::write(output, json)?;
+    println!("Wrote {} tracks from {} to {}", tracks.len(), samples.display(), output.display());
+    Ok(())
+}
+
+/// Export the open project to, or import a new project from, the archive at `path`
+fn run_path_prompt(action: PromptAction, path: &Path, project_path: &Path) {
+    match action {
+        PromptAction::ExportZip => match export_project_zip(project_path, path) {
+            Ok(()) => info!("Exported project to {}", path.display()),
+            Err(e) => error!("Failed to export project: {}", e),
+        },
+        PromptAction::ImportZip => {
+            let Some(name) = path_prompt::imported_project_name(path) else {
+                error!("Cannot name a project after {}", path.display());
+                return;
+            };
+            let destination = get_project_path(&name);
+            if destination.exists() {
+                error!("Not importing {}: project '{}' already exists", path.display(), name);
+                return;
+            }
+            match import_project_zip(path, &destination) {
+                Ok(()) => info!("Imported project '{}' to {}", name, destination.display()),
+                Err(e) => error!("Failed to import project: {}", e),
+            }
+        },
+        // Replaces the app state, so the caller handles it with open_project
+        PromptAction::OpenProject => {},
+    }
+}
+
+/// Load the project named `name`, or the folder at that path, into the running app.
+/// Returns the project's metadata and folder.
+fn open_project(app: &mut AppState, name: &Path) -> io::Result<(Project, PathBuf)> {
+    let folder = if name.is_dir() {
+        name.to_path_buf()
+    } else {
+        get_project_path(&name.to_string_lossy())
+    };
+    app.load_project_from_path(&folder)
+        .map_err(|e| io::Error::other(format!("Failed to load project: {}", e)))?;
+    let (project, _, _, _) = load_project(&folder)
+        .map_err(|e| io::Error::other(format!("Failed to load project: {}", e)))?;
+    Ok((project, folder))
+}
+
+/// Launch the interactive tracker
+fn run_tui(project_name: &str, audio_device: Option<String>, audio_mode: AudioMode, osc_port: Option<u16>, auto_start: bool, script: Option<&Path>) -> io::Result<()> {
+    std::panic::set_hook(Box::new(|info| {
+        error!("Application panicked: {:?}", info);
+        let _ = crossterm::terminal::disable_raw_mode();
+        let _ = crossterm::execute!(
+            std::io::stdout(),
+            crossterm::terminal::LeaveAlternateScreen,
+            crossterm::event::DisableMouseCapture
+        );
+    }));
+
+    info!("Starting TUI application");
+
+    let (app, mut project, pattern_metas) = load_app_state(project_name)?;
+    let mut app = app.with_audio_device(audio_device)
+        .with_audio_mode(audio_mode)
+        .with_auto_start(auto_start || project.auto_start == Some(true));
+    // Read the script before taking over the terminal so a bad path is reported
+    let script = script.map(std::fs::read_to_string).transpose()?;
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+
     // Initialize the sequencer with the pattern and BPM, and connect to audio
-    app.bpm = _project.bpm as u32;
     match app.initialize_sequencer(true) {
         Ok(_) => {
             info!("Sequencer and audio initialized successfully");
@@ -101,17 +317,40 @@ fn main() -> Result<(), io::Error> {
         Err(e) => {
             // Fall back to sequencer-only operation if audio fails
             error!("Failed to initialize audio: {}, continuing without audio", e);
-            let event_bus_clone = app.get_event_bus().clone();
-            app.sequencer = Some(sequencer::Sequencer::new_with_event_bus(app.bpm, app.steps.clone(), event_bus_clone));
+            if let Err(e) = app.initialize_sequencer(false) {
+                error!("Failed to initialize sequencer: {}", e);
+            }
         }
     }
-    
-    debug!("AppState initialized with {} tracks and {} steps", num_tracks, num_steps);
+
+    if let Some(port) = osc_port {
+        if let Err(e) = app.enable_osc(port) {
+            error!("Failed to start OSC listener on port {}: {}", port, e);
+        }
+    }
+
+    if let Some(script) = script {
+        for result in app.execute_script(&script) {
+            match result {
+                Ok(status) => info!("Script: {}", status),
+                Err(e) => error!("Script command failed: {}", e),
+            }
+        }
+    }
+
+    // Resolve the color theme once; the draw closure borrows it each frame
+    let theme = Theme::load();
+    // Time base for blinking indicators
+    let started_at = Instant::now();
 
     // Ensure `terminal` is properly initialized
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
+    // Time the current bar started, for flashing the header border
+    let bar_started_at: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+    let bar_started_at_listener = Arc::clone(&bar_started_at);
+
     // Event handler for tracker events
     app.subscribe_to_events(move |event| {
         match event {
@@ -130,10 +369,94 @@ fn main() -> Result<(), io::Error> {
             TrackerEvent::TrackVolumeChanged(track_idx, volume) => {
                 debug!("TUI received volume changed event for track {}: {:.1}", track_idx, volume);
             },
+            TrackerEvent::BarStarted(bar) => {
+                debug!("TUI received bar started event: {}", bar);
+                *bar_started_at_listener.lock().unwrap() = Some(Instant::now());
+            },
+            TrackerEvent::TrackAdded(track_idx) => {
+                debug!("TUI received track added event: {}", track_idx);
+            },
+            TrackerEvent::TrackRemoved(track_idx) => {
+                debug!("TUI received track removed event: {}", track_idx);
+            },
+            TrackerEvent::PatternLoaded(pattern_idx) => {
+                debug!("TUI received pattern loaded event: {}", pattern_idx);
+            },
+            TrackerEvent::ProjectLoaded(name) => {
+                info!("TUI received project loaded event: {}", name);
+            },
+            TrackerEvent::MasterVolumeChanged(volume) => {
+                debug!("TUI received master volume change: {:.2}", volume);
+            },
+            TrackerEvent::TrackSoloChanged(track_idx, soloed) => {
+                debug!("TUI received track solo change: {} -> {}", track_idx, soloed);
+            },
+            TrackerEvent::SubdivisionChanged(note_value) => {
+                info!("TUI received subdivision change: 1/{}", note_value);
+            },
         }
     });
 
+    // Track whose name flashes for a frame, set when a newly enabled step first plays
+    let flash_track: Arc<Mutex<Option<usize>>> = Arc::new(Mutex::new(None));
+    // Listener waiting to flash the last toggled step; only the latest toggle flashes
+    let mut pending_flash: Option<usize> = None;
+
+    // Sample browser popup, open while choosing a new sample for a track
+    let mut sample_browser: Option<SampleBrowserState> = None;
+
+    // Path prompt popup, open while entering an archive path or a project to open
+    let mut path_prompt: Option<PathPromptState> = None;
+    let mut project_name = project_name.to_string();
+    let mut project_path = get_project_path(&project_name);
+
+    // Heat map view, coloring steps by how often they have fired
+    let mut show_heat_map = false;
+    let mut show_lookahead = false;
+
+    // Footer statistics are refreshed on an interval rather than every frame
+    let cpu_monitor = CpuMonitor::start();
+    let mut stats_text = String::new();
+    let mut last_stats_refresh: Option<Instant> = None;
+
     loop {
+        if last_stats_refresh.is_none_or(|t| t.elapsed() >= stats::SAMPLE_INTERVAL) {
+            let queue_depth = app.audio.as_ref().map(|audio| audio.queue_depth());
+            let sample_memory = app.audio.as_ref().and_then(|audio| audio.memory_usage(Duration::from_millis(50)));
+            let audio_metrics = app.audio.as_ref().map(|audio| {
+                let metrics = audio.metrics();
+                (metrics.avg_dispatch_latency_us, metrics.errors)
+            });
+            stats_text = stats::format_stats(cpu_monitor.usage_percent(), queue_depth, sample_memory, audio_metrics);
+            last_stats_refresh = Some(Instant::now());
+
+            // Bring the audio thread back if it stopped responding
+            if let Some(audio) = app.audio.as_ref() {
+                if let Err(e) = audio.ensure_thread_alive() {
+                    error!("Failed to restart audio thread: {}", e);
+                }
+            }
+        }
+
+        let header_border = if bar_started_at.lock().unwrap().is_some_and(|t| t.elapsed() < BAR_FLASH_DURATION) {
+            theme.playing_step
+        } else {
+            theme.border
+        };
+
+        // Shown for this frame only
+        let flashed_track = flash_track.lock().unwrap().take();
+
+        let heat_counts = show_heat_map.then(|| app.step_hit_counts());
+        let max_hits = heat_counts.iter().flatten().flatten().copied().max().unwrap_or(0);
+        let lookahead = show_lookahead.then(|| {
+            let upcoming = app.sequencer.as_ref()
+                .map(|sequencer| sequencer.generate_trigger_preview(preview::LOOKAHEAD_STEPS))
+                .unwrap_or_default();
+            let step_ms = 60_000.0 / app.bpm.max(1) as f64 / app.subdivision.factor() as f64;
+            preview::piano_roll_lines(&upcoming, app.num_tracks(), step_ms)
+        });
+
         terminal.draw(|f| {
             debug!("Drawing UI");
             let size = f.area();
@@ -141,6 +464,7 @@ fn main() -> Result<(), io::Error> {
                 .direction(Direction::Vertical)
                 .constraints([
                     Constraint::Length(3),
+                    Constraint::Length(1),
                     Constraint::Length(6),
                     Constraint::Length(4),
                 ])
@@ -149,12 +473,42 @@ fn main() -> Result<(), io::Error> {
             let status = if app.is_playing { "PLAYING" } else { "PAUSED" };
             let step_display = if app.is_playing { app.current_step + 1 } else { app.selected_step + 1 };
             
+            let groove = app.active_groove().unwrap_or("-");
+            // Blink the record indicator twice a second
+            let rec = if app.record_mode && (started_at.elapsed().as_millis() / REC_BLINK_MS).is_multiple_of(2) { " REC" } else { "" };
             let header = Paragraph::new(format!(
-                "SONG: {} | BPM:{} STEP:{:02}/{} | {}", 
-                _project.name, _project.bpm, step_display, num_steps, status
-            )).block(Block::default().borders(Borders::ALL));
+                "SONG: {} | PAT:{:02}/{:02} | BPM:{} STEP:{:02}/{} GRID:{} | GROOVE:{} ({}) | {}{}", 
+                project.name, app.current_pattern() + 1, app.pattern_count(), project.bpm, step_display, app.num_steps(),
+                app.subdivision.note_value(), groove, app.groove_names().len(), status, rec
+            ))
+            .style(Style::default().fg(theme.header_text))
+            .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(header_border)));
             f.render_widget(header, chunks[0]);
 
+            // Playback position, moving smoothly between steps
+            let position = if app.num_steps() == 0 {
+                0.0
+            } else {
+                (app.get_playback_progress() / app.num_steps() as f32) as f64
+            };
+            let position_gauge = LineGauge::default()
+                .filled_style(Style::default().fg(theme.playing_step))
+                .unfilled_style(Style::default().fg(theme.inactive_step))
+                .label("")
+                .ratio(position.clamp(0.0, 1.0));
+            let gauge_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(24)])
+                .split(chunks[1]);
+            f.render_widget(position_gauge, gauge_chunks[0]);
+
+            let master_gauge = LineGauge::default()
+                .filled_style(Style::default().fg(theme.active_step))
+                .unfilled_style(Style::default().fg(theme.inactive_step))
+                .label(format!(" MST {:>3}%", (app.master_volume() * 100.0).round()))
+                .ratio((app.master_volume() / app_state::MAX_MASTER_VOLUME) as f64);
+            f.render_widget(master_gauge, gauge_chunks[1]);
+
             use ratatui::widgets::Cell;
 
             let rows: Vec<Row> = app
@@ -166,22 +520,33 @@ fn main() -> Result<(), io::Error> {
                     let is_playing = app.is_playing && 
                         app.trigger_events.iter().any(|e| e.track_idx == track_idx);
                     
+                    let info = app.get_track_info(track_idx);
+                    let color = theme::track_color(info.as_ref().and_then(|info| info.color.as_deref()), track_idx);
+                    
                     // Show visual feedback for playing tracks
-                    let track_style = if is_playing {
-                        Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK)
+                    let track_style = if flashed_track == Some(track_idx) {
+                        Style::default().fg(theme.background).bg(theme.playing_step).add_modifier(Modifier::BOLD)
+                    } else if app.is_track_muted(track_idx) {
+                        Style::default().fg(theme.inactive_step).add_modifier(Modifier::CROSSED_OUT)
+                    } else if is_playing {
+                        Style::default().fg(theme.playing_track).add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK)
                     } else {
-                        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+                        Style::default().fg(color).add_modifier(Modifier::BOLD)
                     };
                     
                     // Add track name and volume info
-                    let volume_str = if let Some(vol) = app.get_track_volume(track_idx) {
-                        format!("{:.1}", vol)
-                    } else {
-                        "?".to_string()
+                    let volume_str = match &info {
+                        Some(info) => format!("{:.1}", info.volume),
+                        None => "?".to_string(),
+                    };
+
+                    let tuning_str = match &info {
+                        Some(info) if info.tuning_semitones != 0.0 => format!(" {:+}st", info.tuning_semitones),
+                        _ => String::new(),
                     };
 
                     let mut cells: Vec<Cell> = vec![
-                        Cell::from(format!("{} v{}", app.track_names[track_idx], volume_str))
+                        Cell::from(format!("{} v{}{}", app.track_names[track_idx], volume_str, tuning_str))
                             .style(track_style),
                     ];
                     cells.extend(
@@ -189,15 +554,26 @@ fn main() -> Result<(), io::Error> {
                             .iter()
                             .enumerate()
                             .map(|(i, &on)| {
-                                let symbol = if on { "X" } else { "." };
+                                // Steps with a length multiplier are marked; color still shows on/off
+                                let stretched = app.step_lengths.get(track_idx)
+                                    .and_then(|row| row.get(i))
+                                    .is_some_and(|&length| length != 1.0);
+                                let symbol = if stretched { "!" } else if on { "X" } else { "." };
                                 let style = if app.is_playing && app.current_step == i {
                                     // Highlight current playing step
-                                    Style::default().fg(Color::Black).bg(Color::Green).add_modifier(Modifier::BOLD)
+                                    Style::default().fg(theme.background).bg(theme.playing_step).add_modifier(Modifier::BOLD)
                                 } else if app.selected_track == track_idx && app.selected_step == i {
                                     // Highlight selected cell
-                                    Style::default().fg(Color::Yellow).bg(Color::Black).add_modifier(Modifier::BOLD)
+                                    Style::default().fg(theme.selected_cell).bg(theme.background).add_modifier(Modifier::BOLD)
+                                } else if app.is_selected(track_idx, i) {
+                                    // Highlight cells inside the visual selection
+                                    Style::default().bg(theme.selection)
+                                } else if let Some(counts) = &heat_counts {
+                                    Style::default().fg(theme.active_step).bg(heatmap::heat_color(counts[track_idx][i], max_hits))
+                                } else if on {
+                                    Style::default().fg(color)
                                 } else {
-                                    Style::default()
+                                    Style::default().fg(theme.inactive_step)
                                 };
                                 Cell::from(symbol).style(style)
                             })
@@ -207,32 +583,241 @@ fn main() -> Result<(), io::Error> {
                 })
                 .collect();
             let mut widths = vec![Constraint::Length(10)]; // Increased width for track names + volume
-            widths.extend(std::iter::repeat(Constraint::Length(1)).take(16));
-            let table = Table::new(rows, vec![Constraint::Length(1); 16])
-                .block(Block::default().title("PATTERN VIEW").borders(Borders::ALL))
+            widths.extend(std::iter::repeat_n(Constraint::Length(1), app.num_steps()));
+            let table = Table::new(rows, vec![Constraint::Length(1); app.num_steps()])
+                .block(Block::default().title(if show_heat_map { "HEAT MAP" } else { "PATTERN VIEW" }).borders(Borders::ALL).border_style(Style::default().fg(theme.border)))
                 .widths(widths);
-            f.render_widget(table, chunks[1]);
+            if let Some(lines) = &lookahead {
+                let roll: Vec<Line> = lines.iter()
+                    .zip(&app.track_names)
+                    .map(|(line, name)| Line::from(format!("{:<10}{}", name, line)))
+                    .collect();
+                let roll = Paragraph::new(roll)
+                    .style(Style::default().fg(theme.active_step))
+                    .block(Block::default().title("LOOKAHEAD").borders(Borders::ALL).border_style(Style::default().fg(theme.border)));
+                f.render_widget(roll, chunks[2]);
+            } else {
+                f.render_widget(table, chunks[2]);
+            }
+
+            let footer_text = if app.selection_mode {
+                "-- SELECT -- [Arrows] Extend [C] Copy [Esc] Cancel"
+            } else {
+                "[Space] Toggle Step [P] Play/Pause [T] Test Sound [+/-] Volume [Ctrl+/-] Master [Shift+</>] Tune [Arrows] Move [V] Select [Shift+P] Paste [Shift+S] Sample [Shift+I] Invert Track [I] Invert All [Shift+D] Duplicate [U] Undo [M] Mute [R] Record [1-8] Hit [L] Length [Shift+L] Log [Shift+H] Heat Map [Shift+A] Lookahead [Shift+G] Groove [Shift+N] Grid [Ctrl+R] Reset Heat [Ctrl+E/I] Export/Import [Ctrl+O] Open [Q] Quit"
+            };
+            let footer_block = Block::default().borders(Borders::ALL).border_style(Style::default().fg(theme.border));
+            let footer_area = footer_block.inner(chunks[3]);
+            f.render_widget(footer_block, chunks[3]);
+            let footer_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(stats_text.len() as u16 + 1)])
+                .split(footer_area);
+            let help = Paragraph::new(Line::from(footer_text).alignment(Alignment::Left))
+                .wrap(ratatui::widgets::Wrap { trim: true });
+            f.render_widget(help, footer_chunks[0]);
+            f.render_widget(Line::from(stats_text.as_str()).alignment(Alignment::Right), footer_chunks[1]);
 
-            let footer = Paragraph::new("[Space] Toggle Step [P] Play/Pause [T] Test Sound [+/-] Volume [Arrows] Move [Q] Quit")
-                .block(Block::default().borders(Borders::ALL));
-            f.render_widget(footer, chunks[2]);
+            if let Some(browser) = &sample_browser {
+                let area = size.inner(ratatui::layout::Margin { horizontal: 4, vertical: 1 });
+                let items: Vec<ListItem> = browser
+                    .filtered()
+                    .iter()
+                    .map(|path| ListItem::new(path.to_string_lossy().into_owned()))
+                    .collect();
+                let track_name = app.track_names.get(browser.track_idx).map(String::as_str).unwrap_or("?");
+                let list = List::new(items)
+                    .block(
+                        Block::default()
+                            .title(format!("SAMPLE FOR {} | FILTER: {}", track_name, browser.filter))
+                            .borders(Borders::ALL)
+                            .border_style(Style::default().fg(theme.border)),
+                    )
+                    .highlight_style(Style::default().fg(theme.selected_cell).add_modifier(Modifier::BOLD))
+                    .highlight_symbol("> ");
+                let mut list_state = ListState::default().with_selected(Some(browser.cursor));
+                f.render_widget(Clear, area);
+                f.render_stateful_widget(list, area, &mut list_state);
+            }
+
+            if let Some(prompt) = &path_prompt {
+                let area = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(0), Constraint::Length(3), Constraint::Min(0)])
+                    .split(size.inner(ratatui::layout::Margin { horizontal: 4, vertical: 0 }))[1];
+                let input = Paragraph::new(format!("{}_", prompt.input))
+                    .style(Style::default().fg(theme.header_text))
+                    .block(
+                        Block::default()
+                            .title(format!("{} | [Enter] OK [Esc] Cancel", prompt.title()))
+                            .borders(Borders::ALL)
+                            .border_style(Style::default().fg(theme.border)),
+                    );
+                f.render_widget(Clear, area);
+                f.render_widget(input, area);
+            }
         })?;
 
         // Process sequencer events if it's playing
         if app.is_playing {
             app.process_sequencer_events();
         }
+        app.process_osc_messages();
         
-        if event::poll(std::time::Duration::from_millis(10))? {
+        if event::poll(Duration::from_millis(10))? {
             if let Event::Key(key) = event::read()? {
+                // While the sample browser is open it receives all key presses
+                if let Some(browser) = sample_browser.as_mut() {
+                    match key.code {
+                        KeyCode::Esc => sample_browser = None,
+                        KeyCode::Enter => {
+                            if let Some(path) = browser.selected() {
+                                let sample = path.to_string_lossy().into_owned();
+                                if let Err(e) = app.set_track_sample(browser.track_idx, &sample) {
+                                    error!("Failed to change sample: {}", e);
+                                }
+                            }
+                            sample_browser = None;
+                        },
+                        KeyCode::Up => browser.move_up(),
+                        KeyCode::Down => browser.move_down(),
+                        KeyCode::Backspace => browser.pop_filter(),
+                        KeyCode::Char(c) => browser.push_filter(c),
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                // While the path prompt is open it receives all key presses
+                if let Some(prompt) = path_prompt.as_mut() {
+                    match key.code {
+                        KeyCode::Esc => path_prompt = None,
+                        KeyCode::Enter => {
+                            if let Some(path) = prompt.path() {
+                                if prompt.action == PromptAction::OpenProject {
+                                    match open_project(&mut app, &path) {
+                                        Ok((loaded, folder)) => {
+                                            info!("Opened project '{}'", loaded.name);
+                                            project_name = folder.file_name()
+                                                .map_or_else(|| loaded.name.clone(), |name| name.to_string_lossy().into_owned());
+                                            project = loaded;
+                                            project_path = folder;
+                                        },
+                                        Err(e) => error!("{}", e),
+                                    }
+                                } else {
+                                    run_path_prompt(prompt.action, &path, &project_path);
+                                }
+                            }
+                            path_prompt = None;
+                        },
+                        KeyCode::Backspace => prompt.pop(),
+                        KeyCode::Char(c) => prompt.push(c),
+                        _ => {}
+                    }
+                    continue;
+                }
+
                 match key.code {
+                    KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        let default_path = format!("{}.zip", project_name);
+                        path_prompt = Some(PathPromptState::new(PromptAction::ExportZip, &default_path));
+                    },
+                    KeyCode::Char('i') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        path_prompt = Some(PathPromptState::new(PromptAction::ImportZip, ""));
+                    },
+                    KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        path_prompt = Some(PathPromptState::new(PromptAction::OpenProject, ""));
+                    },
+                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.reset_step_hit_counts(),
+                    KeyCode::Char('r') => app.toggle_record_mode(),
+                    KeyCode::Char(c @ '1'..='8') if app.record_mode => {
+                        // Record a hit on the track and play it so the take can be heard
+                        let track_idx = c as usize - '1' as usize;
+                        match app.record_step(track_idx) {
+                            Ok(step_idx) => {
+                                debug!("Recorded track {} at step {}", track_idx, step_idx);
+                                let _ = app.test_track_sound(track_idx);
+                            },
+                            Err(e) => debug!("Not recording track {}: {}", track_idx, e),
+                        }
+                    },
                     KeyCode::Char('q') => break,
-                    KeyCode::Char(' ') => app.toggle_step(),
+                    KeyCode::Char(' ') => {
+                        app.toggle_step();
+                        if let Some(id) = pending_flash.take() {
+                            app.unsubscribe_from_events(id);
+                        }
+                        let (track_idx, step_idx) = (app.selected_track, app.selected_step);
+                        if app.is_playing && app.steps[track_idx][step_idx] {
+                            let flash_track = Arc::clone(&flash_track);
+                            pending_flash = Some(app.subscribe_once(move |event| {
+                                let TrackerEvent::StepTriggered(track, step) = event else { return false };
+                                if (*track, *step) != (track_idx, step_idx) {
+                                    return false;
+                                }
+                                *flash_track.lock().unwrap() = Some(track_idx);
+                                true
+                            }));
+                        }
+                    },
                     KeyCode::Char('p') => app.toggle_playback(),
                     KeyCode::Left => app.move_cursor_left(),
                     KeyCode::Right => app.move_cursor_right(),
                     KeyCode::Up => app.move_cursor_up(),
                     KeyCode::Down => app.move_cursor_down(),
+                    KeyCode::Char('V') => app.enter_selection_mode(),
+                    KeyCode::Char('C') => app.copy_selection(),
+                    KeyCode::Char('P') => app.paste_clipboard(),
+                    KeyCode::Char('I') => app.invert_track(app.selected_track),
+                    KeyCode::Char('i') => app.invert_pattern(),
+                    KeyCode::Char('D') => {
+                        // Duplicate the current pattern into a new slot and edit the copy
+                        let copy = app.pattern_count() as u32;
+                        if let Err(e) = app.clone_pattern(app.current_pattern(), copy).and_then(|_| app.select_pattern(copy)) {
+                            error!("Failed to duplicate pattern: {}", e);
+                        }
+                    },
+                    KeyCode::Char('u') => {
+                        app.undo();
+                    },
+                    KeyCode::Char('m') => {
+                        if let Err(e) = app.toggle_track_mute(app.selected_track) {
+                            error!("Failed to toggle mute: {}", e);
+                        }
+                    },
+                    KeyCode::Esc => app.exit_selection_mode(),
+                    KeyCode::Char('l') => {
+                        // Cycle the pattern length through the common sizes
+                        let next = match app.num_steps() {
+                            8 => 16,
+                            16 => 32,
+                            32 => 64,
+                            _ => 8,
+                        };
+                        if let Err(e) = app.set_step_count(next) {
+                            error!("Failed to change step count: {}", e);
+                        }
+                    },
+                    KeyCode::Char('L') => app.log_pattern(),
+                    KeyCode::Char('H') => show_heat_map = !show_heat_map,
+                    KeyCode::Char('A') => show_lookahead = !show_lookahead,
+                    KeyCode::Char('G') => {
+                        if let Some(name) = app.cycle_groove() {
+                            info!("Playing groove '{}'", name);
+                        }
+                    },
+                    KeyCode::Char('N') => {
+                        if let Err(e) = app.set_subdivision(app.subdivision.next()) {
+                            error!("Failed to change subdivision: {}", e);
+                        }
+                    },
+                    KeyCode::Char('S') => {
+                        // Browse for a new sample for the selected track
+                        match SampleBrowserState::open(app.get_sample_dir(), app.selected_track) {
+                            Ok(browser) => sample_browser = Some(browser),
+                            Err(e) => error!("Failed to read sample directory: {}", e),
+                        }
+                    },
                     KeyCode::Char('t') => {
                         // Test sound of the currently selected track
                         if let Err(e) = app.test_track_sound(app.selected_track) {
@@ -241,6 +826,12 @@ fn main() -> Result<(), io::Error> {
                             info!("Testing sound for track {}", app.selected_track);
                         }
                     },
+                    KeyCode::Char('+') | KeyCode::Char('=') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.set_master_volume(app.master_volume() + 0.1);
+                    },
+                    KeyCode::Char('-') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.set_master_volume(app.master_volume() - 0.1);
+                    },
                     KeyCode::Char('+') | KeyCode::Char('=') => {
                         // Increase volume of the currently selected track
                         if let Some(current_volume) = app.get_track_volume(app.selected_track) {
@@ -250,6 +841,14 @@ fn main() -> Result<(), io::Error> {
                             }
                         }
                     },
+                    KeyCode::Char('<') | KeyCode::Char('>') => {
+                        // Tune the selected track down or up a semitone
+                        let step = if key.code == KeyCode::Char('>') { 1.0 } else { -1.0 };
+                        let tuning = app.get_track_tuning(app.selected_track).unwrap_or(0.0);
+                        if let Err(e) = app.set_track_tuning(app.selected_track, tuning + step) {
+                            error!("Failed to tune track: {}", e);
+                        }
+                    },
                     KeyCode::Char('-') => {
                         // Decrease volume of the currently selected track
                         if let Some(current_volume) = app.get_track_volume(app.selected_track) {
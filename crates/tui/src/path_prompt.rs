@@ -0,0 +1,78 @@
+// Single-line file path prompt for opening projects and archive export and import
+use std::path::{Path, PathBuf};
+
+/// What the entered path is used for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PromptAction {
+    /// Write the current project to a ZIP archive
+    ExportZip,
+    /// Extract a ZIP archive as a new project
+    ImportZip,
+    /// Replace the running project with another one, by name or folder
+    OpenProject,
+}
+
+/// State of the path prompt popup
+#[derive(Debug, Clone)]
+pub struct PathPromptState {
+    pub action: PromptAction,
+    /// Path typed so far
+    pub input: String,
+}
+
+impl PathPromptState {
+    pub fn new(action: PromptAction, initial: &str) -> Self {
+        Self { action, input: initial.to_string() }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self.action {
+            PromptAction::ExportZip => "EXPORT PROJECT TO ZIP",
+            PromptAction::ImportZip => "IMPORT PROJECT FROM ZIP",
+            PromptAction::OpenProject => "OPEN PROJECT",
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.input.push(c);
+    }
+
+    pub fn pop(&mut self) {
+        self.input.pop();
+    }
+
+    /// The entered path, or None if only whitespace was typed
+    pub fn path(&self) -> Option<PathBuf> {
+        let trimmed = self.input.trim();
+        (!trimmed.is_empty()).then(|| PathBuf::from(trimmed))
+    }
+}
+
+/// Name for a project imported from `zip_path`: the archive's file name without extension
+pub fn imported_project_name(zip_path: &Path) -> Option<String> {
+    zip_path.file_stem().map(|stem| stem.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editing_and_path() {
+        let mut prompt = PathPromptState::new(PromptAction::ExportZip, "song.zi");
+        prompt.push('p');
+        assert_eq!(prompt.path(), Some(PathBuf::from("song.zip")));
+
+        for _ in 0..10 {
+            prompt.pop();
+        }
+        prompt.push(' ');
+        assert_eq!(prompt.path(), None);
+    }
+
+    #[test]
+    fn test_imported_project_name() {
+        assert_eq!(imported_project_name(Path::new("/tmp/my-song.zip")).as_deref(), Some("my-song"));
+        assert_eq!(imported_project_name(Path::new("/")), None);
+    }
+}
@@ -0,0 +1,42 @@
+// Lookahead piano roll of upcoming triggers
+
+/// Steps shown in the lookahead view
+pub const LOOKAHEAD_STEPS: usize = 16;
+
+/// Columns per step, enough to show swing and retriggers between grid positions
+pub const COLUMNS_PER_STEP: usize = 4;
+
+/// One line per track with a mark in the column of every upcoming trigger.
+/// `preview` holds (time in ms from now, track, step) as made by
+/// `Sequencer::generate_trigger_preview`; triggers past the view are left out.
+pub fn piano_roll_lines(preview: &[(f64, usize, usize)], num_tracks: usize, step_ms: f64) -> Vec<String> {
+    let columns = LOOKAHEAD_STEPS * COLUMNS_PER_STEP;
+    let mut grid: Vec<Vec<char>> = (0..num_tracks)
+        .map(|_| (0..columns).map(|col| if col % COLUMNS_PER_STEP == 0 { '|' } else { '.' }).collect())
+        .collect();
+    if step_ms > 0.0 {
+        for &(time_ms, track_idx, _) in preview {
+            let col = (time_ms / step_ms * COLUMNS_PER_STEP as f64).round() as usize;
+            if let Some(cell) = grid.get_mut(track_idx).and_then(|row| row.get_mut(col)) {
+                *cell = '#';
+            }
+        }
+    }
+    grid.into_iter().map(|row| row.into_iter().collect()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_piano_roll_places_triggers_by_time() {
+        let preview = [(0.0, 0, 0), (187.5, 0, 1), (250.0, 1, 2), (10_000.0, 1, 3)];
+        let lines = piano_roll_lines(&preview, 2, 125.0);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("#...|.#.|"));
+        assert!(lines[1].starts_with("|...|...#..."));
+        assert_eq!(lines[1].chars().filter(|&c| c == '#').count(), 1);
+        assert_eq!(lines[0].len(), LOOKAHEAD_STEPS * COLUMNS_PER_STEP);
+    }
+}
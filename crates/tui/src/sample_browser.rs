@@ -0,0 +1,144 @@
+// Sample file browser for swapping a track's sample at runtime
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File extensions the browser lists
+const SAMPLE_EXTENSIONS: [&str; 3] = ["wav", "ogg", "flac"];
+
+/// State of the sample browser popup
+#[derive(Debug, Clone)]
+pub struct SampleBrowserState {
+    /// Sample files relative to the sample directory, sorted
+    pub entries: Vec<PathBuf>,
+    /// Case-insensitive substring typed by the user
+    pub filter: String,
+    /// Index into the filtered entries
+    pub cursor: usize,
+    /// Track whose sample will be replaced
+    pub track_idx: usize,
+}
+
+impl SampleBrowserState {
+    /// Open a browser listing every sample file below `sample_dir`
+    pub fn open(sample_dir: &Path, track_idx: usize) -> io::Result<Self> {
+        let mut entries = Vec::new();
+        collect_samples(sample_dir, sample_dir, &mut entries)?;
+        entries.sort();
+
+        Ok(Self {
+            entries,
+            filter: String::new(),
+            cursor: 0,
+            track_idx,
+        })
+    }
+
+    /// Entries matching the current filter
+    pub fn filtered(&self) -> Vec<&PathBuf> {
+        let filter = self.filter.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|entry| entry.to_string_lossy().to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    /// The entry under the cursor, if any
+    pub fn selected(&self) -> Option<&PathBuf> {
+        self.filtered().get(self.cursor).copied()
+    }
+
+    pub fn move_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.cursor + 1 < self.filtered().len() {
+            self.cursor += 1;
+        }
+    }
+
+    /// Append a character to the filter
+    pub fn push_filter(&mut self, c: char) {
+        self.filter.push(c);
+        self.cursor = 0;
+    }
+
+    /// Remove the last character of the filter
+    pub fn pop_filter(&mut self) {
+        self.filter.pop();
+        self.cursor = 0;
+    }
+}
+
+/// Recursively collect sample files below `dir`, relative to `root`
+fn collect_samples(root: &Path, dir: &Path, entries: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_samples(root, &path, entries)?;
+        } else if is_sample_file(&path) {
+            if let Ok(relative) = path.strip_prefix(root) {
+                entries.push(relative.to_path_buf());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_sample_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| SAMPLE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn browser(entries: &[&str]) -> SampleBrowserState {
+        SampleBrowserState {
+            entries: entries.iter().map(PathBuf::from).collect(),
+            filter: String::new(),
+            cursor: 0,
+            track_idx: 0,
+        }
+    }
+
+    #[test]
+    fn test_open_lists_samples_recursively() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("drums")).unwrap();
+        fs::write(dir.path().join("kick.wav"), b"").unwrap();
+        fs::write(dir.path().join("drums").join("snare.FLAC"), b"").unwrap();
+        fs::write(dir.path().join("pad.ogg"), b"").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"").unwrap();
+
+        let state = SampleBrowserState::open(dir.path(), 2).unwrap();
+        assert_eq!(state.track_idx, 2);
+        assert_eq!(
+            state.entries,
+            vec![PathBuf::from("drums/snare.FLAC"), PathBuf::from("kick.wav"), PathBuf::from("pad.ogg")]
+        );
+    }
+
+    #[test]
+    fn test_filter_and_cursor() {
+        let mut state = browser(&["hihat.wav", "kick.wav", "open-hihat.wav"]);
+        state.move_down();
+        state.move_down();
+        state.move_down();
+        assert_eq!(state.selected(), Some(&PathBuf::from("open-hihat.wav")));
+
+        for c in "HAT".chars() {
+            state.push_filter(c);
+        }
+        assert_eq!(state.filtered().len(), 2);
+        assert_eq!(state.selected(), Some(&PathBuf::from("hihat.wav")));
+
+        state.push_filter('x');
+        assert_eq!(state.selected(), None);
+        state.pop_filter();
+        assert_eq!(state.filtered().len(), 2);
+    }
+}
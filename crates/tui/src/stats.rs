@@ -0,0 +1,121 @@
+// Runtime statistics shown in the TUI footer
+use std::fs;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::debug;
+
+/// How often the CPU monitor samples `/proc/self/stat`
+pub const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Kernel clock ticks per second used by `/proc` (USER_HZ, fixed at 100 on Linux)
+const CLOCK_TICKS_PER_SEC: f32 = 100.0;
+
+/// Marker stored while no CPU reading is available
+const NO_READING: u32 = u32::MAX;
+
+/// Background monitor for the process CPU usage
+pub struct CpuMonitor {
+    /// Last reading as percent * 10, or `NO_READING`
+    usage_tenths: Arc<AtomicU32>,
+}
+
+impl CpuMonitor {
+    /// Spawn the monitor thread. Readings are only available on Linux.
+    pub fn start() -> Self {
+        let usage_tenths = Arc::new(AtomicU32::new(NO_READING));
+        let thread_usage = Arc::clone(&usage_tenths);
+
+        let spawned = thread::Builder::new()
+            .name("cpu-monitor".to_string())
+            .spawn(move || {
+                let mut last = match read_cpu_ticks() {
+                    Some(ticks) => (ticks, Instant::now()),
+                    None => {
+                        debug!("CPU usage is not available on this platform");
+                        return;
+                    }
+                };
+
+                loop {
+                    thread::sleep(SAMPLE_INTERVAL);
+                    let Some(ticks) = read_cpu_ticks() else { return };
+                    let now = Instant::now();
+                    let busy_secs = ticks.saturating_sub(last.0) as f32 / CLOCK_TICKS_PER_SEC;
+                    let wall_secs = now.duration_since(last.1).as_secs_f32();
+                    if wall_secs > 0.0 {
+                        let percent = busy_secs / wall_secs * 100.0;
+                        thread_usage.store((percent * 10.0) as u32, Ordering::Relaxed);
+                    }
+                    last = (ticks, now);
+                }
+            });
+
+        if let Err(e) = spawned {
+            debug!("Failed to spawn CPU monitor: {}", e);
+        }
+
+        Self { usage_tenths }
+    }
+
+    /// Latest CPU usage in percent, if a reading is available
+    pub fn usage_percent(&self) -> Option<f32> {
+        match self.usage_tenths.load(Ordering::Relaxed) {
+            NO_READING => None,
+            tenths => Some(tenths as f32 / 10.0),
+        }
+    }
+}
+
+/// Total user + system clock ticks consumed by this process
+fn read_cpu_ticks() -> Option<u64> {
+    let stat = fs::read_to_string("/proc/self/stat").ok()?;
+    parse_cpu_ticks(&stat)
+}
+
+/// Extract utime + stime from the contents of `/proc/<pid>/stat`
+fn parse_cpu_ticks(stat: &str) -> Option<u64> {
+    // The command name may contain spaces, so split after its closing paren.
+    // Remaining fields start at field 3 (state); utime and stime are fields 14 and 15.
+    let rest = &stat[stat.rfind(')')? + 1..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+/// Format the right-aligned footer statistics
+/// `audio_metrics` is the average trigger dispatch latency in microseconds and the error count
+pub fn format_stats(cpu_percent: Option<f32>, queue_depth: Option<usize>, sample_memory: Option<usize>, audio_metrics: Option<(f64, u64)>) -> String {
+    let cpu = cpu_percent.map_or_else(|| "--".to_string(), |p| format!("{:.0}%", p));
+    let queue = queue_depth.map_or_else(|| "--".to_string(), |q| q.to_string());
+    let memory = sample_memory.map_or_else(|| "--".to_string(), |bytes| format!("{:.1}MB", bytes as f64 / (1024.0 * 1024.0)));
+    let (latency, errors) = audio_metrics.map_or_else(
+        || ("--".to_string(), "--".to_string()),
+        |(latency_us, errors)| (format!("{:.0}us", latency_us), errors.to_string()),
+    );
+    format!("CPU: {} | Q: {} | MEM: {} | LAT: {} | ERR: {}", cpu, queue, memory, latency, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_ticks() {
+        let stat = "1234 (gaucho (tui)) S 1 1234 1234 0 -1 4194304 500 0 0 0 250 75 0 0 20 0 4 0 100 0 0";
+        assert_eq!(parse_cpu_ticks(stat), Some(325));
+        assert_eq!(parse_cpu_ticks("garbage"), None);
+    }
+
+    #[test]
+    fn test_format_stats() {
+        assert_eq!(
+            format_stats(Some(12.4), Some(3), Some(3 * 1024 * 1024 / 2), Some((84.6, 2))),
+            "CPU: 12% | Q: 3 | MEM: 1.5MB | LAT: 85us | ERR: 2"
+        );
+        assert_eq!(format_stats(None, None, None, None), "CPU: -- | Q: -- | MEM: -- | LAT: -- | ERR: --");
+    }
+}
@@ -0,0 +1,236 @@
+// Color themes for the TUI
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use log::{debug, info};
+use ratatui::style::Color;
+use serde::Deserialize;
+
+/// Environment variable used to select a theme by name
+pub const THEME_ENV_VAR: &str = "GAUCHO_THEME";
+
+/// Semantic colors used when drawing the tracker
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// Steps that are switched on
+    pub active_step: Color,
+    /// Steps that are switched off
+    pub inactive_step: Color,
+    /// The cell under the cursor
+    pub selected_cell: Color,
+    /// Background of the step currently being played
+    pub playing_step: Color,
+    /// Track name while the track is triggering
+    pub playing_track: Color,
+    /// Track name while idle
+    pub track_name: Color,
+    /// Background of cells inside a visual selection
+    pub selection: Color,
+    /// Block borders
+    pub border: Color,
+    /// Header text
+    pub header_text: Color,
+    /// Background behind highlighted cells
+    pub background: Color,
+}
+
+/// Colors given to tracks without one of their own, in track order
+pub const TRACK_PALETTE: [Color; 6] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Yellow,
+    Color::Green,
+    Color::LightBlue,
+    Color::LightRed,
+];
+
+/// Parse a `#RRGGBB` hex string or a color name; anything else is `Color::Reset`
+pub fn parse_color(s: &str) -> Color {
+    let s = s.trim();
+    if let Some(hex) = s.strip_prefix('#') {
+        let channel = |range: std::ops::Range<usize>| hex.get(range).and_then(|c| u8::from_str_radix(c, 16).ok());
+        return match (hex.len(), channel(0..2), channel(2..4), channel(4..6)) {
+            (6, Some(r), Some(g), Some(b)) => Color::Rgb(r, g, b),
+            _ => Color::Reset,
+        };
+    }
+    match s.to_lowercase().replace(['_', '-', ' '], "").as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" | "purple" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        _ => Color::Reset,
+    }
+}
+
+/// Color of a track: its own if it has one, otherwise the palette entry for its index
+pub fn track_color(color: Option<&str>, track_idx: usize) -> Color {
+    match color {
+        Some(color) => parse_color(color),
+        None => TRACK_PALETTE[track_idx % TRACK_PALETTE.len()],
+    }
+}
+
+/// Contents of `~/.config/gaucho-tracker/theme.toml`
+#[derive(Debug, Deserialize)]
+struct ThemeConfig {
+    name: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            active_step: Color::Reset,
+            inactive_step: Color::Reset,
+            selected_cell: Color::Yellow,
+            playing_step: Color::Green,
+            playing_track: Color::Magenta,
+            track_name: Color::Cyan,
+            selection: Color::Blue,
+            border: Color::Reset,
+            header_text: Color::Reset,
+            background: Color::Black,
+        }
+    }
+}
+
+impl Theme {
+    /// A theme for displays without color support
+    pub fn monochrome() -> Self {
+        Self {
+            active_step: Color::White,
+            inactive_step: Color::Gray,
+            selected_cell: Color::White,
+            playing_step: Color::White,
+            playing_track: Color::White,
+            track_name: Color::Gray,
+            selection: Color::Gray,
+            border: Color::Gray,
+            header_text: Color::White,
+            background: Color::Gray,
+        }
+    }
+
+    /// Ethan Schoonover's Solarized (dark) palette
+    pub fn solarized() -> Self {
+        Self {
+            active_step: Color::Rgb(147, 161, 161),   // base1
+            inactive_step: Color::Rgb(88, 110, 117),  // base01
+            selected_cell: Color::Rgb(181, 137, 0),   // yellow
+            playing_step: Color::Rgb(133, 153, 0),    // green
+            playing_track: Color::Rgb(211, 54, 130),  // magenta
+            track_name: Color::Rgb(42, 161, 152),     // cyan
+            selection: Color::Rgb(38, 139, 210),      // blue
+            border: Color::Rgb(88, 110, 117),         // base01
+            header_text: Color::Rgb(131, 148, 150),   // base0
+            background: Color::Rgb(0, 43, 54),        // base03
+        }
+    }
+
+    /// Look up a pre-defined theme by name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "default" => Some(Self::default()),
+            "monochrome" | "mono" => Some(Self::monochrome()),
+            "solarized" => Some(Self::solarized()),
+            _ => None,
+        }
+    }
+
+    /// Path of the user theme configuration file
+    pub fn config_path() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".config").join("gaucho-tracker").join("theme.toml"))
+    }
+
+    /// Load the theme selected by `GAUCHO_THEME` or the user config file,
+    /// falling back to the default theme
+    pub fn load() -> Self {
+        if let Ok(name) = env::var(THEME_ENV_VAR) {
+            if let Some(theme) = Self::from_name(&name) {
+                info!("Using theme '{}' from {}", name, THEME_ENV_VAR);
+                return theme;
+            }
+            debug!("Unknown theme '{}' in {}", name, THEME_ENV_VAR);
+        }
+
+        if let Some(path) = Self::config_path() {
+            if let Ok(contents) = fs::read_to_string(&path) {
+                match toml::from_str::<ThemeConfig>(&contents) {
+                    Ok(config) => {
+                        if let Some(theme) = Self::from_name(&config.name) {
+                            info!("Using theme '{}' from {:?}", config.name, path);
+                            return theme;
+                        }
+                        debug!("Unknown theme '{}' in {:?}", config.name, path);
+                    }
+                    Err(e) => debug!("Failed to parse theme config {:?}: {}", path, e),
+                }
+            }
+        }
+
+        Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monochrome_uses_only_white_and_gray() {
+        let theme = Theme::monochrome();
+        let colors = [
+            theme.active_step,
+            theme.inactive_step,
+            theme.selected_cell,
+            theme.playing_step,
+            theme.playing_track,
+            theme.track_name,
+            theme.selection,
+            theme.border,
+            theme.header_text,
+            theme.background,
+        ];
+        assert!(colors.iter().all(|c| *c == Color::White || *c == Color::Gray));
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#FF8800"), Color::Rgb(255, 136, 0));
+        assert_eq!(parse_color("#ff8800"), Color::Rgb(255, 136, 0));
+        assert_eq!(parse_color("red"), Color::Red);
+        assert_eq!(parse_color("Light Blue"), Color::LightBlue);
+        assert_eq!(parse_color("grey"), Color::Gray);
+        assert_eq!(parse_color("#FF88"), Color::Reset);
+        assert_eq!(parse_color("#GG0000"), Color::Reset);
+        assert_eq!(parse_color("chartreuse"), Color::Reset);
+    }
+
+    #[test]
+    fn test_track_color_falls_back_to_palette() {
+        assert_eq!(track_color(Some("#010203"), 0), Color::Rgb(1, 2, 3));
+        assert_eq!(track_color(None, 1), TRACK_PALETTE[1]);
+        assert_eq!(track_color(None, TRACK_PALETTE.len()), TRACK_PALETTE[0]);
+    }
+
+    #[test]
+    fn test_theme_from_name() {
+        assert_eq!(Theme::from_name("solarized"), Some(Theme::solarized()));
+        assert_eq!(Theme::from_name("Monochrome"), Some(Theme::monochrome()));
+        assert_eq!(Theme::from_name("default"), Some(Theme::default()));
+        assert_eq!(Theme::from_name("neon"), None);
+    }
+}